@@ -0,0 +1,137 @@
+//! # Derive macros for axum-valid
+//!
+//! These macros are re-exported by `axum-valid`, use them from there:
+//!
+//! * `SensitiveFields`, with the `#[redact]` field attribute
+//! * `Warnings`, with the `#[warning("code")]` container and field attribute
+//! * `HasRules`, reading the `#[validate]` attributes of validator
+
+#![deny(unsafe_code, missing_docs, clippy::unwrap_used)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr, Token};
+
+/// Implement `SensitiveFields` for a struct, listing its fields marked with `#[redact]`
+#[proc_macro_derive(SensitiveFields, attributes(redact))]
+pub fn derive_sensitive_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    sensitive_fields(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implement `Warnings` for a struct, listing the error codes marked with `#[warning("code")]`
+#[proc_macro_derive(Warnings, attributes(warning))]
+pub fn derive_warnings(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    warnings(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implement `HasRules` for a struct, checking if it or one of its fields has a `#[validate]` attribute
+#[proc_macro_derive(HasRules)]
+pub fn derive_has_rules(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    has_rules(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Named fields of a struct, the only kind of type whose errors have field paths
+fn named_fields(input: &DeriveInput) -> syn::Result<&Fields> {
+    match &input.data {
+        Data::Struct(data) if matches!(data.fields, Fields::Named(_)) => Ok(&data.fields),
+        _ => Err(syn::Error::new_spanned(
+            &input.ident,
+            "only structs with named fields are supported",
+        )),
+    }
+}
+
+fn sensitive_fields(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let mut paths = Vec::new();
+    for field in named_fields(input)? {
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("redact"))
+        {
+            attr.meta.require_path_only()?;
+            if let Some(ident) = &field.ident {
+                paths.push(ident.unraw().to_string());
+            }
+        }
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::axum_valid::error_body::SensitiveFields for #ident #ty_generics #where_clause {
+            const SENSITIVE_FIELDS: &'static [&'static str] = &[#(#paths),*];
+        }
+    })
+}
+
+/// Codes listed by a `#[warning("code", ...)]` attribute
+fn warning_codes(attrs: &[syn::Attribute]) -> syn::Result<Vec<LitStr>> {
+    let mut codes = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("warning")) {
+        codes.extend(attr.parse_args_with(Punctuated::<LitStr, Token![,]>::parse_terminated)?);
+    }
+    Ok(codes)
+}
+
+fn warnings(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = named_fields(input)?;
+    let mut warnings = warning_codes(&input.attrs)?
+        .into_iter()
+        .map(|code| quote!((::core::option::Option::None, #code)))
+        .collect::<Vec<_>>();
+    for field in fields {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let path = ident.unraw().to_string();
+        warnings.extend(
+            warning_codes(&field.attrs)?
+                .into_iter()
+                .map(|code| quote!((::core::option::Option::Some(#path), #code))),
+        );
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::axum_valid::severity::Warnings for #ident #ty_generics #where_clause {
+            const WARNINGS: &'static [(::core::option::Option<&'static str>, &'static str)] = &[#(#warnings),*];
+        }
+    })
+}
+
+fn has_rules(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "only structs are supported",
+        ));
+    };
+    // validator only validates what a `#[validate]` attribute asks for, including nested fields
+    let is_rule = |attr: &syn::Attribute| attr.path().is_ident("validate");
+    let has_rules = input.attrs.iter().any(is_rule)
+        || data
+            .fields
+            .iter()
+            .any(|field| field.attrs.iter().any(is_rule));
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::axum_valid::rule_cache::HasRules for #ident #ty_generics #where_clause {
+            fn has_rules() -> bool {
+                #has_rules
+            }
+        }
+    })
+}
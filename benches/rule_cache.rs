@@ -0,0 +1,53 @@
+//! # Cached rule detection benchmark
+//!
+//! Compares `Valid<E>` and `ValidCached<E>` on a type without rules whose `validate` is costly,
+//! e.g. because it walks a schema. Run it with `cargo bench --bench rule_cache --features rule_cache`.
+//!
+
+use axum::extract::{FromRequestParts, Query};
+use axum::http::Request;
+use axum_valid::rule_cache::{HasRules, ValidCached};
+use axum_valid::Valid;
+use serde::Deserialize;
+use std::hint::black_box;
+use std::time::Instant;
+use validator::{Validate, ValidationErrors};
+
+const ITERATIONS: u32 = 100_000;
+
+#[derive(Deserialize, HasRules)]
+struct Note {
+    text: String,
+}
+
+/// A schema-driven validation, finding out on every call that no field is constrained
+impl Validate for Note {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let constrained = (0..64).filter(|field| black_box(*field) < 0).count();
+        black_box((constrained, self.text.len()));
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let (parts, _) = Request::builder().uri("/?text=note").body(())?.into_parts();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let result = Valid::<Query<Note>>::from_request_parts(&mut parts.clone(), &()).await;
+        assert!(result.is_ok());
+    }
+    let valid = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let result = ValidCached::<Query<Note>>::from_request_parts(&mut parts.clone(), &()).await;
+        assert!(result.is_ok());
+    }
+    let cached = start.elapsed();
+
+    println!("Valid:       {:?} per request", valid / ITERATIONS);
+    println!("ValidCached: {:?} per request", cached / ITERATIONS);
+    Ok(())
+}
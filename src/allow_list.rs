@@ -0,0 +1,212 @@
+//! # Runtime allow-lists of values
+//!
+//! ## Feature
+//!
+//! Enable the `allow_list` feature to use `AllowList`.
+//!
+//! Some enumerations are data rather than code, e.g. categories stored in a database,
+//! so they can't be validated with a Rust `enum` or a rule known at compile time.
+//! `AllowList` is a set of allowed values shared across requests, which can be updated at runtime,
+//! e.g. when a category is added or when the set is reloaded from the database.
+//! Put it in the state and pass it as the validation arguments of `ValidEx`.
+//!
+//! A value missing from the set is reported with the code `allow_list` and the value as its `value` parameter.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::extract::FromRef;
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::allow_list::AllowList;
+//! use axum_valid::ValidEx;
+//! use serde::Deserialize;
+//! use validator::{Validate, ValidationError};
+//!
+//! #[derive(Clone, FromRef)]
+//! struct AppState {
+//!     categories: AllowList,
+//! }
+//!
+//! #[derive(Validate, Deserialize)]
+//! #[validate(context = AllowList)]
+//! pub struct Product {
+//!     #[validate(custom(function = "validate_category", use_context))]
+//!     pub category: String,
+//! }
+//!
+//! fn validate_category(category: &str, categories: &AllowList) -> Result<(), ValidationError> {
+//!     categories.validate(category)
+//! }
+//!
+//! async fn handler(ValidEx(Json(product)): ValidEx<Json<Product>>) {
+//!     println!("category = {}", product.category);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! // e.g. loaded from the database
+//! let categories = AllowList::from_iter(["books", "games"]);
+//! let router = Router::new()
+//!     .route("/json", post(handler))
+//!     .with_state(AppState { categories });
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use validator::ValidationError;
+
+/// Set of allowed values, cheap to clone and shared between clones
+#[derive(Debug, Clone, Default)]
+pub struct AllowList(Arc<RwLock<HashSet<String>>>);
+
+impl AllowList {
+    /// Create an empty allow-list, which rejects every value
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HashSet<String>> {
+        self.0
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashSet<String>> {
+        self.0
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Allow a value, returns `false` if it was already allowed
+    pub fn insert(&self, value: impl Into<String>) -> bool {
+        self.write().insert(value.into())
+    }
+
+    /// Disallow a value, returns `false` if it wasn't allowed
+    pub fn remove(&self, value: &str) -> bool {
+        self.write().remove(value)
+    }
+
+    /// Replace all the allowed values, e.g. after reloading them from the database
+    pub fn replace<I, S>(&self, values: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let values = values.into_iter().map(Into::into).collect();
+        *self.write() = values;
+    }
+
+    /// Check if a value is allowed
+    pub fn contains(&self, value: &str) -> bool {
+        self.read().contains(value)
+    }
+
+    /// Number of allowed values
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Check if no value is allowed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Validator rule checking that `value` is allowed
+    ///
+    /// A value missing from the set is reported with the code `allow_list` and the value as its `value` parameter.
+    pub fn validate(&self, value: &str) -> Result<(), ValidationError> {
+        if self.contains(value) {
+            return Ok(());
+        }
+        let mut error = ValidationError::new("allow_list");
+        error.message = Some(Cow::from(format!("`{value}` is not an allowed value")));
+        error.add_param(Cow::from("value"), &value);
+        Err(error)
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for AllowList {
+    fn from_iter<I: IntoIterator<Item = S>>(values: I) -> Self {
+        AllowList(Arc::new(RwLock::new(
+            values.into_iter().map(Into::into).collect(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_at_runtime() {
+        let list = AllowList::from_iter(["a", "b"]);
+        let shared = list.clone();
+        assert!(list.validate("a").is_ok());
+        assert!(list.insert("c"));
+        assert!(shared.contains("c"));
+        assert!(list.remove("a"));
+        let error = shared.validate("a").expect_err("a was removed");
+        assert_eq!(error.code, "allow_list");
+        assert_eq!(error.params["value"], serde_json::json!("a"));
+        list.replace(["x"]);
+        assert_eq!(shared.len(), 1);
+        assert!(AllowList::new().validate("x").is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn allow_value_at_runtime() -> anyhow::Result<()> {
+        use crate::{ValidEx, ValidRejection};
+        use axum::body::Body;
+        use axum::extract::{FromRequest, Request};
+        use axum::http::header::CONTENT_TYPE;
+        use axum::Json;
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(context = AllowList)]
+        struct Product {
+            #[validate(custom(function = "validate_category", use_context))]
+            category: String,
+        }
+
+        fn validate_category(
+            category: &str,
+            categories: &AllowList,
+        ) -> Result<(), ValidationError> {
+            categories.validate(category)
+        }
+
+        let request = || {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"category": "music"}"#))
+        };
+
+        let categories = AllowList::from_iter(["books", "games"]);
+        assert!(matches!(
+            ValidEx::<Json<Product>>::from_request(request()?, &categories).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["category"][0].code == "allow_list"
+        ));
+
+        // e.g. a category inserted in the database
+        categories.insert("music");
+        let ValidEx(Json(product)) =
+            ValidEx::<Json<Product>>::from_request(request()?, &categories)
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(product.category, "music");
+        Ok(())
+    }
+}
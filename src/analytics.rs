@@ -0,0 +1,273 @@
+//! # Validation failure analytics
+//!
+//! ## Feature
+//!
+//! Enable the `analytics` feature to use `WithAnalytics<E>`.
+//!
+//! `WithAnalytics<E>` wraps a validation extractor such as `Valid<E>`. When validation fails,
+//! every field error is reported to the `AnalyticsSink` of the `ValidationAnalytics` provided by the state,
+//! as a `(route, field, code)` tuple, so that failures can be aggregated per route and field in dashboards.
+//! The route is the path template matched by the router, such as `/users/:id`, or the request path if there's none.
+//!
+//! The rejection is returned unchanged.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::extract::Query;
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::analytics::{ValidationAnalytics, WithAnalytics};
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         let analytics = ValidationAnalytics::new(|route: &str, field: &str, code: &str| {
+//!             println!("{route} {field} {code}");
+//!         });
+//!         Router::new()
+//!             .route("/query", get(handler))
+//!             .with_state(analytics)
+//!     }
+//!
+//!     async fn handler(WithAnalytics(Valid(Query(parameter))): WithAnalytics<Valid<Query<Parameter>>>) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(range(min = 5, max = 10))]
+//!         pub v0: i32,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::{ErrorBody, ValidationRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, FromRequestParts, MatchedPath, Request};
+use axum::http::request::Parts;
+use axum::http::{Extensions, Uri};
+use axum::response::IntoResponse;
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// Receiver of validation failures
+pub trait AnalyticsSink: Send + Sync {
+    /// Record a field error of a failed validation
+    fn record(&self, route: &str, field: &str, code: &str);
+}
+
+impl<F> AnalyticsSink for F
+where
+    F: Fn(&str, &str, &str) + Send + Sync,
+{
+    fn record(&self, route: &str, field: &str, code: &str) {
+        self(route, field, code)
+    }
+}
+
+/// Analytics sink provided by the state
+#[derive(Clone)]
+pub struct ValidationAnalytics(Arc<dyn AnalyticsSink>);
+
+impl ValidationAnalytics {
+    /// Create the analytics from a sink
+    pub fn new(sink: impl AnalyticsSink + 'static) -> Self {
+        ValidationAnalytics(Arc::new(sink))
+    }
+
+    /// Record the field errors of `body` for `route`
+    pub fn record(&self, route: &str, body: &ErrorBody) {
+        for error in &body.errors {
+            self.0.record(
+                route,
+                &error.path.to_string(),
+                error.code.as_deref().unwrap_or_default(),
+            );
+        }
+    }
+}
+
+impl Debug for ValidationAnalytics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ValidationAnalytics").finish_non_exhaustive()
+    }
+}
+
+/// # `WithAnalytics` validation extractor
+///
+/// See the [module documentation](crate::analytics) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WithAnalytics<E>(pub E);
+
+impl<E> Deref for WithAnalytics<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for WithAnalytics<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> WithAnalytics<E> {
+    /// Consumes the `WithAnalytics` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+fn route(extensions: &Extensions, uri: &Uri) -> String {
+    extensions
+        .get::<MatchedPath>()
+        .map_or_else(|| uri.path(), MatchedPath::as_str)
+        .to_owned()
+}
+
+fn record<V, E>(rejection: &ValidationRejection<V, E>, analytics: &ValidationAnalytics, route: &str)
+where
+    for<'v> ErrorBody: From<&'v V>,
+{
+    if let ValidationRejection::Valid(errors) = rejection {
+        analytics.record(route, &ErrorBody::from(errors));
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequest<State> for WithAnalytics<Extractor>
+where
+    State: Send + Sync,
+    ValidationAnalytics: FromRef<State>,
+    Extractor: FromRequest<State, Rejection = ValidationRejection<V, E>>,
+    for<'v> ErrorBody: From<&'v V>,
+    ValidationRejection<V, E>: IntoResponse,
+{
+    type Rejection = ValidationRejection<V, E>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let analytics = ValidationAnalytics::from_ref(state);
+        let route = route(req.extensions(), req.uri());
+        Extractor::from_request(req, state)
+            .await
+            .map(WithAnalytics)
+            .inspect_err(|rejection| record(rejection, &analytics, &route))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequestParts<State> for WithAnalytics<Extractor>
+where
+    State: Send + Sync,
+    ValidationAnalytics: FromRef<State>,
+    Extractor: FromRequestParts<State, Rejection = ValidationRejection<V, E>>,
+    for<'v> ErrorBody: From<&'v V>,
+    ValidationRejection<V, E>: IntoResponse,
+{
+    type Rejection = ValidationRejection<V, E>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let analytics = ValidationAnalytics::from_ref(state);
+        let route = route(&parts.extensions, &parts.uri);
+        Extractor::from_request_parts(parts, state)
+            .await
+            .map(WithAnalytics)
+            .inspect_err(|rejection| record(rejection, &analytics, &route))
+    }
+}
+
+#[cfg(all(test, feature = "validator", feature = "query"))]
+mod tests {
+    use super::*;
+    use crate::Valid;
+    use axum::extract::Query;
+    use axum::routing::get;
+    use axum::Router;
+    use std::net::SocketAddr;
+    use std::sync::Mutex;
+    use tokio::net::TcpListener;
+    use validator::Validate;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Parameters {
+        #[validate(range(min = 5, max = 10))]
+        v0: i32,
+        #[validate(length(min = 1, max = 3))]
+        v1: String,
+    }
+
+    #[tokio::test]
+    async fn sink_receives_field_failures() -> anyhow::Result<()> {
+        let records = Arc::new(Mutex::new(Vec::<(String, String, String)>::new()));
+        let sink = {
+            let records = records.clone();
+            move |route: &str, field: &str, code: &str| {
+                if let Ok(mut records) = records.lock() {
+                    records.push((route.to_owned(), field.to_owned(), code.to_owned()));
+                }
+            }
+        };
+        async fn handler(_: WithAnalytics<Valid<Query<Parameters>>>) {}
+
+        let router = Router::new()
+            .route("/users/:id", get(handler))
+            .with_state(ValidationAnalytics::new(sink));
+        let listener = TcpListener::bind(&SocketAddr::from(([127u8, 0, 0, 1], 0u16))).await?;
+        let address = listener.local_addr()?;
+        let server =
+            tokio::spawn(async move { axum::serve(listener, router.into_make_service()).await });
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://{address}/users/1?v0=5&v1=abc"))
+            .send()
+            .await?;
+        assert!(response.status().is_success());
+        assert!(records
+            .lock()
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .is_empty());
+
+        let response = client
+            .get(format!("http://{address}/users/1?v0=11&v1=abcd"))
+            .send()
+            .await?;
+        assert_eq!(
+            response.status().as_u16(),
+            crate::VALIDATION_ERROR_STATUS.as_u16()
+        );
+        let mut records = records.lock().map_err(|e| anyhow::anyhow!("{e}"))?.clone();
+        records.sort();
+        assert_eq!(
+            records,
+            [
+                ("/users/:id".into(), "v0".into(), "range".into()),
+                ("/users/:id".into(), "v1".into(), "length".into()),
+            ]
+        );
+        server.abort();
+        Ok(())
+    }
+}
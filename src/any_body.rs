@@ -0,0 +1,323 @@
+//! # Support for `AnyBody<T>`
+//!
+//! ## Feature
+//!
+//! Enable the `any_body` feature to use `Valid<AnyBody<T>>`, or its alias `ValidAny<T>`.
+//!
+//! `AnyBody<T>` lets one handler accept several body formats. It dispatches on the `Content-Type` header:
+//!
+//! * `application/json` (and `+json` types) is extracted with `Json<T>`,
+//! * `application/x-www-form-urlencoded` is extracted with `Form<T>`,
+//! * `application/msgpack` is extracted with `MsgPack<T>`.
+//!
+//! Other content types are rejected with `415 Unsupported Media Type`.
+//! The extracted data is then validated like with any other extractor.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::Router;
+//!     use axum_valid::any_body::{AnyBody, ValidAny};
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/any", post(handler))
+//!     }
+//!
+//!     async fn handler(Valid(AnyBody(parameter)): ValidAny<Parameter>) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(range(min = 5, max = 10))]
+//!         pub v0: i32,
+//!         #[validate(length(min = 1, max = 10))]
+//!         pub v1: String,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::extract::rejection::{FormRejection, JsonRejection};
+use axum::extract::{FromRequest, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Form, Json};
+use axum_serde::MsgPack;
+use serde::de::DeserializeOwned;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "validator")]
+use validator::ValidateArgs;
+
+/// Validated `AnyBody<T>`
+#[cfg(feature = "validator")]
+pub type ValidAny<T> = crate::Valid<AnyBody<T>>;
+
+/// # `AnyBody` extractor
+///
+/// See the [module documentation](crate::any_body) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnyBody<T>(pub T);
+
+impl<T> Deref for AnyBody<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for AnyBody<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// `AnyBodyRejection` is returned when the `AnyBody` extractor fails.
+#[derive(Debug)]
+pub enum AnyBodyRejection {
+    /// The content type is missing or not supported
+    UnsupportedMediaType,
+    /// The JSON body is rejected by `Json`
+    Json(JsonRejection),
+    /// The form body is rejected by `Form`
+    Form(FormRejection),
+    /// The MessagePack body is rejected by `MsgPack`
+    MsgPack(axum_serde::Rejection<rmp_serde::decode::Error>),
+}
+
+impl Display for AnyBodyRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnyBodyRejection::UnsupportedMediaType => write!(
+                f,
+                "Expected request with `Content-Type: application/json`, \
+                 `application/x-www-form-urlencoded` or `application/msgpack`"
+            ),
+            AnyBodyRejection::Json(e) => write!(f, "{e}"),
+            AnyBodyRejection::Form(e) => write!(f, "{e}"),
+            AnyBodyRejection::MsgPack(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for AnyBodyRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnyBodyRejection::UnsupportedMediaType => None,
+            AnyBodyRejection::Json(e) => Some(e),
+            AnyBodyRejection::Form(e) => Some(e),
+            AnyBodyRejection::MsgPack(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for AnyBodyRejection {
+    fn into_response(self) -> Response {
+        match self {
+            e @ AnyBodyRejection::UnsupportedMediaType => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, e.to_string()).into_response()
+            }
+            AnyBodyRejection::Json(e) => e.into_response(),
+            AnyBodyRejection::Form(e) => e.into_response(),
+            AnyBodyRejection::MsgPack(e) => e.into_response(),
+        }
+    }
+}
+
+enum BodyFormat {
+    Json,
+    Form,
+    MsgPack,
+}
+
+fn body_format(req: &Request) -> Option<BodyFormat> {
+    let content_type = req.headers().get(CONTENT_TYPE)?.to_str().ok()?;
+    let essence = content_type.split(';').next()?.trim();
+    let (kind, subtype) = essence.split_once('/')?;
+    if !kind.eq_ignore_ascii_case("application") {
+        return None;
+    }
+    let subtype = subtype.to_ascii_lowercase();
+    match subtype.as_str() {
+        "json" => Some(BodyFormat::Json),
+        "x-www-form-urlencoded" => Some(BodyFormat::Form),
+        "msgpack" => Some(BodyFormat::MsgPack),
+        _ if subtype.ends_with("+json") => Some(BodyFormat::Json),
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for AnyBody<T>
+where
+    T: DeserializeOwned + 'static,
+    S: Send + Sync,
+{
+    type Rejection = AnyBodyRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match body_format(&req) {
+            Some(BodyFormat::Json) => Json::from_request(req, state)
+                .await
+                .map(|Json(value)| AnyBody(value))
+                .map_err(AnyBodyRejection::Json),
+            Some(BodyFormat::Form) => Form::from_request(req, state)
+                .await
+                .map(|Form(value)| AnyBody(value))
+                .map_err(AnyBodyRejection::Form),
+            Some(BodyFormat::MsgPack) => MsgPack::from_request(req, state)
+                .await
+                .map(|MsgPack(value)| AnyBody(value))
+                .map_err(AnyBodyRejection::MsgPack),
+            None => Err(AnyBodyRejection::UnsupportedMediaType),
+        }
+    }
+}
+
+impl<T> HasValidate for AnyBody<T> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>> HasValidateArgs<'v> for AnyBody<T> {
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify> crate::HasModify for AnyBody<T> {
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T> crate::PayloadExtractor for AnyBody<T> {
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload> crate::HasValidify for AnyBody<T> {
+    type Validify = T;
+    type PayloadExtractor = AnyBody<T::Payload>;
+    fn from_validify(v: Self::Validify) -> Self {
+        AnyBody(v)
+    }
+}
+
+#[cfg(all(test, feature = "validator"))]
+mod tests {
+    use super::*;
+    use crate::{Valid, ValidRejection};
+    use axum::body::Body;
+    use serde::{Deserialize, Serialize};
+    use validator::Validate;
+
+    #[derive(Debug, Serialize, Deserialize, Validate)]
+    struct Parameters {
+        #[validate(range(min = 5, max = 10))]
+        v0: i32,
+        #[validate(length(min = 1, max = 10))]
+        v1: String,
+    }
+
+    fn request(content_type: &str, body: Vec<u8>) -> anyhow::Result<Request> {
+        Ok(Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body))?)
+    }
+
+    #[tokio::test]
+    async fn dispatch_on_content_type() -> anyhow::Result<()> {
+        let valid = Parameters {
+            v0: 5,
+            v1: String::from("abc"),
+        };
+        let invalid = Parameters {
+            v0: 11,
+            v1: String::new(),
+        };
+        let bodies = |parameters: &Parameters| -> anyhow::Result<[(&str, Vec<u8>); 3]> {
+            Ok([
+                ("application/json", serde_json::to_vec(parameters)?),
+                (
+                    "application/x-www-form-urlencoded",
+                    format!("v0={}&v1={}", parameters.v0, parameters.v1).into_bytes(),
+                ),
+                ("application/msgpack", rmp_serde::to_vec_named(parameters)?),
+            ])
+        };
+
+        for (content_type, body) in bodies(&valid)? {
+            let Valid(AnyBody(parameters)) =
+                ValidAny::<Parameters>::from_request(request(content_type, body)?, &())
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{content_type}: {e}"))?;
+            assert_eq!(parameters.v0, valid.v0, "{content_type}");
+            assert_eq!(parameters.v1, valid.v1, "{content_type}");
+        }
+
+        for (content_type, body) in bodies(&invalid)? {
+            let rejection = ValidAny::<Parameters>::from_request(request(content_type, body)?, &())
+                .await
+                .expect_err("parameters are invalid");
+            assert!(
+                matches!(&rejection, ValidRejection::Valid(errors) if errors.field_errors().len() == 2),
+                "{content_type}: {rejection}"
+            );
+        }
+
+        let rejection =
+            ValidAny::<Parameters>::from_request(request("text/plain", Vec::new())?, &())
+                .await
+                .expect_err("text is not supported");
+        assert!(matches!(
+            rejection,
+            ValidRejection::Inner(AnyBodyRejection::UnsupportedMediaType)
+        ));
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        Ok(())
+    }
+}
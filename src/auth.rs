@@ -0,0 +1,324 @@
+//! # Authentication challenges for validation errors
+//!
+//! ## Feature
+//!
+//! Enable the `auth` feature to use `WithAuthChallenge<E>`.
+//!
+//! Some validation rules check credentials, e.g. the format of a token or an API key.
+//! Their failures are authentication failures, which should be answered with `401 Unauthorized`
+//! and a `WWW-Authenticate` header (RFC 9110) rather than with the usual validation error status.
+//!
+//! `WithAuthChallenge<E>` wraps a validation extractor such as `Valid<E>`. When validation fails,
+//! the codes of the errors are looked up in the `AuthChallenges` provided by the state.
+//! If any error code is mapped to a challenge, the errors are returned as an `ErrorBody` with the `401` status code
+//! and one `WWW-Authenticate` header per distinct challenge, otherwise they are returned as a usual `ErrorBody`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::extract::Query;
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::auth::{AuthChallenges, WithAuthChallenge};
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::{Validate, ValidationError};
+//!
+//!     pub fn router() -> Router {
+//!         let challenges = AuthChallenges::new()
+//!             .challenge("invalid_token", r#"Bearer realm="api", error="invalid_token""#);
+//!         Router::new()
+//!             .route("/query", get(handler))
+//!             .with_state(challenges)
+//!     }
+//!
+//!     async fn handler(WithAuthChallenge(Valid(Query(parameter))): WithAuthChallenge<Valid<Query<Parameter>>>) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(custom(function = "validate_token"))]
+//!         pub token: String,
+//!     }
+//!
+//!     fn validate_token(token: &str) -> Result<(), ValidationError> {
+//!         if token.len() == 32 && token.bytes().all(|b| b.is_ascii_hexdigit()) {
+//!             Ok(())
+//!         } else {
+//!             Err(ValidationError::new("invalid_token"))
+//!         }
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::{ErrorBody, ValidationRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, FromRequestParts, Request};
+use axum::http::header::WWW_AUTHENTICATE;
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// Challenges of the `WWW-Authenticate` header, indexed by error code
+#[derive(Debug, Clone, Default)]
+pub struct AuthChallenges(Arc<HashMap<String, String>>);
+
+impl AuthChallenges {
+    /// Create an empty mapping
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map an error code to a challenge, e.g. `Bearer error="invalid_token"`
+    pub fn challenge(mut self, code: impl Into<String>, challenge: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.0).insert(code.into(), challenge.into());
+        self
+    }
+
+    /// Get the challenge of an error code
+    pub fn get(&self, code: &str) -> Option<&str> {
+        self.0.get(code).map(String::as_str)
+    }
+
+    /// Get the distinct challenges of the errors in `body`, in the order of the errors
+    pub fn challenges_of(&self, body: &ErrorBody) -> Vec<String> {
+        let mut challenges = Vec::<String>::new();
+        for challenge in body
+            .errors
+            .iter()
+            .filter_map(|error| error.code.as_deref())
+            .filter_map(|code| self.get(code))
+        {
+            if !challenges.iter().any(|c| c == challenge) {
+                challenges.push(challenge.to_owned());
+            }
+        }
+        challenges
+    }
+}
+
+/// Validation errors, returned with `WWW-Authenticate` headers if they have challenges
+#[derive(Debug, Clone)]
+pub struct ChallengedErrors {
+    /// The challenges of the errors, empty if no error is an authentication failure
+    pub challenges: Vec<String>,
+    /// The errors
+    pub body: ErrorBody,
+}
+
+impl Display for ChallengedErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.body, f)
+    }
+}
+
+impl IntoResponse for ChallengedErrors {
+    fn into_response(self) -> Response {
+        let mut response = self.body.into_response();
+        if self.challenges.is_empty() {
+            return response;
+        }
+        *response.status_mut() = StatusCode::UNAUTHORIZED;
+        for challenge in self.challenges {
+            if let Ok(challenge) = HeaderValue::try_from(challenge) {
+                response.headers_mut().append(WWW_AUTHENTICATE, challenge);
+            }
+        }
+        response
+    }
+}
+
+/// `AuthChallengeRejection` is returned when the `WithAuthChallenge` extractor fails.
+#[derive(Debug)]
+pub enum AuthChallengeRejection<E> {
+    /// The data is invalid
+    Valid(ChallengedErrors),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for AuthChallengeRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthChallengeRejection::Valid(errors) => write!(f, "{errors}"),
+            AuthChallengeRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for AuthChallengeRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AuthChallengeRejection::Valid(_) => None,
+            AuthChallengeRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for AuthChallengeRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            AuthChallengeRejection::Valid(errors) => errors.into_response(),
+            AuthChallengeRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+/// # `WithAuthChallenge` validation extractor
+///
+/// See the [module documentation](crate::auth) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WithAuthChallenge<E>(pub E);
+
+impl<E> Deref for WithAuthChallenge<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for WithAuthChallenge<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> WithAuthChallenge<E> {
+    /// Consumes the `WithAuthChallenge` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+fn challenge<V, E>(
+    rejection: ValidationRejection<V, E>,
+    challenges: &AuthChallenges,
+) -> AuthChallengeRejection<E>
+where
+    ErrorBody: From<V>,
+{
+    match rejection {
+        ValidationRejection::Valid(errors) => {
+            let body = ErrorBody::from(errors);
+            AuthChallengeRejection::Valid(ChallengedErrors {
+                challenges: challenges.challenges_of(&body),
+                body,
+            })
+        }
+        ValidationRejection::Inner(error) => AuthChallengeRejection::Inner(error),
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequest<State> for WithAuthChallenge<Extractor>
+where
+    State: Send + Sync,
+    AuthChallenges: FromRef<State>,
+    Extractor: FromRequest<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = AuthChallengeRejection<E>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let challenges = AuthChallenges::from_ref(state);
+        Extractor::from_request(req, state)
+            .await
+            .map(WithAuthChallenge)
+            .map_err(|rejection| challenge(rejection, &challenges))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequestParts<State> for WithAuthChallenge<Extractor>
+where
+    State: Send + Sync,
+    AuthChallenges: FromRef<State>,
+    Extractor: FromRequestParts<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = AuthChallengeRejection<E>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let challenges = AuthChallenges::from_ref(state);
+        Extractor::from_request_parts(parts, state)
+            .await
+            .map(WithAuthChallenge)
+            .map_err(|rejection| challenge(rejection, &challenges))
+    }
+}
+
+#[cfg(all(test, feature = "validator", feature = "query"))]
+mod tests {
+    use super::*;
+    use crate::Valid;
+    use axum::extract::Query;
+    use axum::http::Request;
+    use validator::{Validate, ValidationError};
+
+    const CHALLENGE: &str = r#"Bearer realm="api", error="invalid_token""#;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Parameters {
+        #[validate(custom(function = "validate_token"))]
+        token: String,
+        #[validate(range(max = 100))]
+        limit: u32,
+    }
+
+    fn validate_token(token: &str) -> Result<(), ValidationError> {
+        if token.starts_with("tk_") {
+            Ok(())
+        } else {
+            Err(ValidationError::new("invalid_token"))
+        }
+    }
+
+    async fn extract(uri: &str) -> anyhow::Result<Response> {
+        let challenges = AuthChallenges::new().challenge("invalid_token", CHALLENGE);
+        let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+        let rejection = WithAuthChallenge::<Valid<Query<Parameters>>>::from_request_parts(
+            &mut parts,
+            &challenges,
+        )
+        .await
+        .expect_err("invalid parameters");
+        Ok(rejection.into_response())
+    }
+
+    #[tokio::test]
+    async fn auth_failure_has_challenge() -> anyhow::Result<()> {
+        let response = extract("/?token=abc&limit=10").await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[WWW_AUTHENTICATE], CHALLENGE);
+
+        let response = extract("/?token=tk_abc&limit=1000").await?;
+        assert_eq!(response.status(), crate::VALIDATION_ERROR_STATUS);
+        assert!(!response.headers().contains_key(WWW_AUTHENTICATE));
+        Ok(())
+    }
+}
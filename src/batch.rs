@@ -0,0 +1,401 @@
+//! # Per-item validation of batches
+//!
+//! ## Feature
+//!
+//! Enable the `batch` feature to use `ValidBatch<T>`.
+//!
+//! Batch endpoints process many items in one request. Rejecting the whole batch because of one invalid item
+//! forces clients to resubmit the valid ones. `ValidBatch<T>` extracts a JSON array, deserializes and validates
+//! each item separately, and hands all of them to the handler as `BatchItem`s, with the index of the item
+//! and its `BatchResult`: either the valid item, or its errors as `FieldError`s.
+//! An item that can't be deserialized has a single error with the code `parse`.
+//! Only a body that isn't a JSON array is rejected, as `JsonRejection`.
+//!
+//! `BatchItem` serializes as `{"index": 0, "result": {"ok": ...}}` or `{"index": 1, "result": {"errors": [...]}}`.
+//! Process the valid items with `ValidBatch::map` and return the results with a `200 OK` response.
+//!
+//! For bulk upserts, `ValidBulkUpsert<T>` extracts and validates the items like `ValidBatch<T>`, and
+//! `ValidBulkUpsert::apply` calls the handler's callback on the valid items only, in order. The returned `MultiStatus`
+//! responds with `207 Multi-Status` and the per-item outcomes, e.g.
+//! `{"index": 0, "status": "applied", "result": ...}` or `{"index": 1, "status": "validation_failed", "errors": [...]}`,
+//! so that clients only resubmit the failed items, which is safe as long as the upserts are idempotent.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::batch::{BatchItem, ValidBatch};
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct User {
+//!     #[validate(length(min = 1, max = 32))]
+//!     pub name: String,
+//! }
+//!
+//! async fn handler(batch: ValidBatch<User>) -> Json<Vec<BatchItem<u64>>> {
+//!     // e.g. the identifiers of the created users
+//!     Json(batch.map(|user| user.name.len() as u64))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/users", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! ```no_run
+//! use axum::routing::put;
+//! use axum::Router;
+//! use axum_valid::batch::{MultiStatus, ValidBulkUpsert};
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Product {
+//!     #[validate(length(min = 1, max = 16))]
+//!     pub sku: String,
+//!     #[validate(range(min = 0))]
+//!     pub stock: i64,
+//! }
+//!
+//! async fn handler(upsert: ValidBulkUpsert<Product>) -> MultiStatus<String> {
+//!     // e.g. upsert the product in a database, by its SKU
+//!     upsert.apply(|product| async move { product.sku }).await
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/products", put(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::{FieldError, FieldPath};
+use crate::validator::validate_redacted;
+use crate::ErrorBody;
+use axum::async_trait;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use validator::Validate;
+
+/// Outcome of an item of a batch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchResult<T> {
+    /// The item is valid
+    Ok(T),
+    /// The item is invalid
+    Errors(Vec<FieldError>),
+}
+
+impl<T> BatchResult<T> {
+    /// Check if the item is valid
+    pub fn is_ok(&self) -> bool {
+        matches!(self, BatchResult::Ok(_))
+    }
+
+    /// Convert into a `Result`
+    pub fn into_result(self) -> Result<T, Vec<FieldError>> {
+        match self {
+            BatchResult::Ok(item) => Ok(item),
+            BatchResult::Errors(errors) => Err(errors),
+        }
+    }
+}
+
+/// An item of a batch, with its index in the batch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchItem<T> {
+    /// Index of the item in the batch
+    pub index: usize,
+    /// Outcome of the item
+    pub result: BatchResult<T>,
+}
+
+/// # `ValidBatch` extractor
+///
+/// See the [module documentation](crate::batch) for details.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ValidBatch<T>(pub Vec<BatchItem<T>>);
+
+impl<T> Deref for ValidBatch<T> {
+    type Target = Vec<BatchItem<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ValidBatch<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> ValidBatch<T> {
+    /// Consumes the `ValidBatch` and returns the items within.
+    pub fn into_inner(self) -> Vec<BatchItem<T>> {
+        self.0
+    }
+
+    /// Process the valid items with `f`, invalid items keep their errors
+    pub fn map<R, F>(self, mut f: F) -> Vec<BatchItem<R>>
+    where
+        F: FnMut(T) -> R,
+    {
+        self.0
+            .into_iter()
+            .map(|BatchItem { index, result }| BatchItem {
+                index,
+                result: match result {
+                    BatchResult::Ok(item) => BatchResult::Ok(f(item)),
+                    BatchResult::Errors(errors) => BatchResult::Errors(errors),
+                },
+            })
+            .collect()
+    }
+}
+
+fn check<T: DeserializeOwned + Validate>(value: Value) -> BatchResult<T> {
+    let item = match serde_json::from_value::<T>(value) {
+        Ok(item) => item,
+        Err(e) => {
+            let mut error = FieldError::new(FieldPath::new(), "parse");
+            error.message = Some(e.to_string());
+            return BatchResult::Errors(vec![error]);
+        }
+    };
+    match validate_redacted(&item) {
+        Ok(()) => BatchResult::Ok(item),
+        Err(errors) => BatchResult::Errors(ErrorBody::from(errors).errors),
+    }
+}
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidBatch<T>
+where
+    State: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = JsonRejection;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let Json(values) = Json::<Vec<Value>>::from_request(req, state).await?;
+        Ok(ValidBatch(
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(index, value)| BatchItem {
+                    index,
+                    result: check(value),
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// Status of an item of a bulk upsert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpsertStatus {
+    /// The item is valid and was applied
+    Applied,
+    /// The item is invalid and wasn't applied
+    ValidationFailed,
+}
+
+/// Outcome of an item of a bulk upsert
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UpsertOutcome<R> {
+    /// Index of the item in the batch
+    pub index: usize,
+    /// Status of the item
+    pub status: UpsertStatus,
+    /// Result of the callback, for applied items
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<R>,
+    /// Validation errors, for invalid items
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// Per-item outcomes of a bulk upsert, responding with `207 Multi-Status`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MultiStatus<R>(pub Vec<UpsertOutcome<R>>);
+
+impl<R> MultiStatus<R> {
+    /// Count the applied items
+    pub fn applied(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|outcome| outcome.status == UpsertStatus::Applied)
+            .count()
+    }
+}
+
+impl<R: Serialize> IntoResponse for MultiStatus<R> {
+    fn into_response(self) -> Response {
+        (StatusCode::MULTI_STATUS, Json(self.0)).into_response()
+    }
+}
+
+/// # `ValidBulkUpsert` extractor
+///
+/// See the [module documentation](crate::batch) for details.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ValidBulkUpsert<T>(pub Vec<BatchItem<T>>);
+
+impl<T> ValidBulkUpsert<T> {
+    /// Consumes the `ValidBulkUpsert` and returns the items within.
+    pub fn into_inner(self) -> Vec<BatchItem<T>> {
+        self.0
+    }
+
+    /// Apply the valid items with `f`, one after the other, and collect the outcomes of all items
+    pub async fn apply<R, F, Fut>(self, mut f: F) -> MultiStatus<R>
+    where
+        F: FnMut(T) -> Fut,
+        Fut: Future<Output = R>,
+    {
+        let mut outcomes = Vec::with_capacity(self.0.len());
+        for BatchItem { index, result } in self.0 {
+            outcomes.push(match result {
+                BatchResult::Ok(item) => UpsertOutcome {
+                    index,
+                    status: UpsertStatus::Applied,
+                    result: Some(f(item).await),
+                    errors: Vec::new(),
+                },
+                BatchResult::Errors(errors) => UpsertOutcome {
+                    index,
+                    status: UpsertStatus::ValidationFailed,
+                    result: None,
+                    errors,
+                },
+            });
+        }
+        MultiStatus(outcomes)
+    }
+}
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidBulkUpsert<T>
+where
+    State: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = JsonRejection;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let ValidBatch(items) = ValidBatch::from_request(req, state).await?;
+        Ok(ValidBulkUpsert(items))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct User {
+        #[validate(length(min = 1, max = 5))]
+        name: String,
+    }
+
+    fn request(body: &'static str) -> anyhow::Result<Request> {
+        Ok(Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?)
+    }
+
+    #[tokio::test]
+    async fn per_item_results() -> anyhow::Result<()> {
+        let batch = ValidBatch::<User>::from_request(
+            request(r#"[{"name": "gteng"}, {"name": ""}, {"name": 1}, {"name": "a"}]"#)?,
+            &(),
+        )
+        .await?;
+        let results = serde_json::to_value(batch.map(|user| user.name))?;
+        assert_eq!(
+            results[0],
+            serde_json::json!({"index": 0, "result": {"ok": "gteng"}})
+        );
+        assert_eq!(results[1]["index"], 1);
+        assert_eq!(results[1]["result"]["errors"][0]["path"], "name");
+        assert_eq!(results[1]["result"]["errors"][0]["code"], "length");
+        assert_eq!(results[2]["result"]["errors"][0]["code"], "parse");
+        assert_eq!(
+            results[3],
+            serde_json::json!({"index": 3, "result": {"ok": "a"}})
+        );
+
+        assert!(
+            ValidBatch::<User>::from_request(request(r#"{"name": "gteng"}"#)?, &())
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bulk_upsert_multi_status() -> anyhow::Result<()> {
+        let upsert = ValidBulkUpsert::<User>::from_request(
+            request(r#"[{"name": "gteng"}, {"name": ""}, {"name": "a"}]"#)?,
+            &(),
+        )
+        .await?;
+        let mut applied = Vec::new();
+        let status = upsert
+            .apply(|user| {
+                applied.push(user.name.clone());
+                async move { user.name.len() }
+            })
+            .await;
+        assert_eq!(applied, ["gteng", "a"]);
+        assert_eq!(status.applied(), 2);
+
+        let response = status.into_response();
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let outcomes: Value = serde_json::from_slice(&body)?;
+        assert_eq!(
+            outcomes[0],
+            serde_json::json!({"index": 0, "status": "applied", "result": 5})
+        );
+        assert_eq!(outcomes[1]["status"], "validation_failed");
+        assert_eq!(outcomes[1]["errors"][0]["path"], "name");
+        assert_eq!(outcomes[1].get("result"), None);
+        assert_eq!(
+            outcomes[2],
+            serde_json::json!({"index": 2, "status": "applied", "result": 1})
+        );
+        Ok(())
+    }
+}
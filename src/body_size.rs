@@ -0,0 +1,201 @@
+//! # Request body size in validation errors
+//!
+//! ## Feature
+//!
+//! Enable the `body_size` feature to use `WithBodySize<E>`.
+//!
+//! `WithBodySize<E>` wraps a validation extractor that consumes the request body, such as `Valid<Json<T>>`.
+//! When validation fails, the errors are returned as an `ErrorBody` including the size of the request body
+//! in bytes as `body_size`, which helps correlating failures with payload sizes while debugging.
+//! As it exposes details of the request, it's meant to be enabled in debug builds only.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::{Json, Router};
+//!     use axum_valid::body_size::WithBodySize;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/json", post(handler))
+//!     }
+//!
+//!     async fn handler(WithBodySize(Valid(Json(parameter))): WithBodySize<Valid<Json<Parameter>>>) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(length(max = 1000))]
+//!         pub items: Vec<u32>,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::buffered::BufferedRequest;
+use crate::{ErrorBody, ValidationRejection};
+use axum::async_trait;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRequest, Request};
+use axum::response::{IntoResponse, Response};
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+
+/// # `WithBodySize` extractor
+///
+/// See the [module documentation](crate::body_size) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WithBodySize<E>(pub E);
+
+impl<E> Deref for WithBodySize<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for WithBodySize<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> WithBodySize<E> {
+    /// Consumes the `WithBodySize` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+/// `BodySizeRejection` is returned when the `WithBodySize` extractor fails.
+#[derive(Debug)]
+pub enum BodySizeRejection<E> {
+    /// The body can't be read
+    Bytes(BytesRejection),
+    /// The data is invalid, the errors include the body size
+    Valid(ErrorBody),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for BodySizeRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodySizeRejection::Bytes(e) => write!(f, "{e}"),
+            BodySizeRejection::Valid(body) => write!(
+                f,
+                "{} validation errors in a body of {} bytes",
+                body.errors.len(),
+                body.body_size.unwrap_or_default()
+            ),
+            BodySizeRejection::Inner(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for BodySizeRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BodySizeRejection::Bytes(e) => Some(e),
+            BodySizeRejection::Valid(_) => None,
+            BodySizeRejection::Inner(e) => Some(e),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for BodySizeRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            BodySizeRejection::Bytes(e) => e.into_response(),
+            BodySizeRejection::Valid(body) => body.into_response(),
+            BodySizeRejection::Inner(e) => e.into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequest<State> for WithBodySize<Extractor>
+where
+    State: Send + Sync,
+    Extractor: FromRequest<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = BodySizeRejection<E>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let buffered = BufferedRequest::read(req, state)
+            .await
+            .map_err(BodySizeRejection::Bytes)?;
+        let body_size = buffered.bytes.len() as u64;
+        Extractor::from_request(buffered.into_request(), state)
+            .await
+            .map(WithBodySize)
+            .map_err(|rejection| match rejection {
+                ValidationRejection::Valid(errors) => {
+                    BodySizeRejection::Valid(ErrorBody::from(errors).with_body_size(body_size))
+                }
+                ValidationRejection::Inner(e) => BodySizeRejection::Inner(e),
+            })
+    }
+}
+
+#[cfg(all(test, feature = "validator"))]
+mod tests {
+    use super::*;
+    use crate::Valid;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+    use validator::Validate;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Parameters {
+        #[validate(length(max = 3))]
+        items: Vec<u32>,
+    }
+
+    #[tokio::test]
+    async fn body_size_matches_request() -> anyhow::Result<()> {
+        const BODY: &str = r#"{"items": [1, 2, 3, 4, 5]}"#;
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(BODY))?;
+        let rejection = WithBodySize::<Valid<Json<Parameters>>>::from_request(request, &())
+            .await
+            .expect_err("too many items");
+        let BodySizeRejection::Valid(body) = &rejection else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(body.body_size, Some(BODY.len() as u64));
+        assert_eq!(body.errors[0].path.to_string(), "items");
+
+        let response = rejection.into_response();
+        let json = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let json = serde_json::from_slice::<serde_json::Value>(&json)?;
+        assert_eq!(json["body_size"], BODY.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+//! Buffered request bodies, for extractors inspecting the body before the inner extractor reads it
+
+use axum::body::{Body, Bytes};
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use std::fmt::{Display, Formatter};
+
+/// A request whose body was read into memory, within `DefaultBodyLimit`
+pub(crate) struct BufferedRequest {
+    pub(crate) parts: Parts,
+    pub(crate) bytes: Bytes,
+}
+
+impl BufferedRequest {
+    /// Read the body of a request like `Bytes::from_request`
+    pub(crate) async fn read<S: Send + Sync>(
+        req: Request,
+        state: &S,
+    ) -> Result<Self, BytesRejection> {
+        let (parts, body) = req.into_parts();
+        let bytes = Bytes::from_request(Request::from_parts(parts.clone(), body), state).await?;
+        Ok(Self { parts, bytes })
+    }
+
+    /// Rebuild the request with the buffered body, to be read by the inner extractor
+    pub(crate) fn into_request(self) -> Request {
+        Request::from_parts(self.parts, Body::from(self.bytes))
+    }
+}
+
+/// `BufferedRejection` is returned when an extractor buffering the body fails.
+#[derive(Debug)]
+pub enum BufferedRejection<E> {
+    /// The body can't be read
+    Bytes(BytesRejection),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for BufferedRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BufferedRejection::Bytes(e) => write!(f, "{e}"),
+            BufferedRejection::Inner(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for BufferedRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BufferedRejection::Bytes(e) => Some(e),
+            BufferedRejection::Inner(e) => Some(e),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for BufferedRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            BufferedRejection::Bytes(e) => e.into_response(),
+            BufferedRejection::Inner(e) => e.into_response(),
+        }
+    }
+}
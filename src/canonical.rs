@@ -0,0 +1,28 @@
+//! Canonical JSON serialization, for hashes, signatures and cache keys of data that don't depend on its formatting
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Sort the keys of all objects in a value
+///
+/// They are sorted explicitly, as the order of `serde_json::Map` depends on its features.
+pub(crate) fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(name, value)| (name, sort_keys(value)))
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Serialize a value into compact JSON, with the keys of objects sorted
+#[cfg(any(feature = "hashed", feature = "signed"))]
+pub(crate) fn to_vec<T: serde::Serialize>(value: &T) -> Result<Vec<u8>, serde_json::Error> {
+    serde_json::to_vec(&sort_keys(serde_json::to_value(value)?))
+}
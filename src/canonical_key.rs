@@ -0,0 +1,170 @@
+//! # Canonical keys of validated data
+//!
+//! ## Feature
+//!
+//! Enable the `canonical_key` feature to use `ValidCanonicalKey<T>`.
+//!
+//! Caches of responses keyed by request payloads miss when equivalent payloads are spelled differently.
+//! `ValidCanonicalKey<T>` extracts a JSON body like `Json<T>`, validates it, and returns the canonical key
+//! of the validated data, a string that is the same for equivalent payloads.
+//!
+//! The key is the compact JSON serialization of the data, with the keys of objects sorted
+//! and the leading and trailing whitespace of strings trimmed, e.g. `{"a":"x","b":[2,1]}`
+//! for both `{"b": [2, 1], "a": " x"}` and `{"a": "x ", "b": [2, 1]}`.
+//! The order of arrays is kept, as it's usually meaningful. Unlike the `ContentHash` of `ValidHashed`,
+//! the key is readable and can't collide, but it's as long as the data.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::Router;
+//! use axum_valid::canonical_key::ValidCanonicalKey;
+//! use serde::{Deserialize, Serialize};
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Serialize, Deserialize)]
+//! pub struct Search {
+//!     #[validate(length(min = 1, max = 64))]
+//!     pub query: String,
+//!     pub page: u32,
+//! }
+//!
+//! async fn handler(ValidCanonicalKey(search, key): ValidCanonicalKey<Search>) {
+//!     // e.g. look up the results cached under `key` before searching
+//!     println!("search {} cached as {key}", search.query);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/search", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::canonical;
+use crate::validator::validate_redacted;
+use crate::{SerializedRejection, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, Request};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use validator::Validate;
+
+/// Get the canonical key of a value: its compact JSON serialization with sorted object keys and trimmed strings
+pub fn canonical_key<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    fn trim(value: Value) -> Value {
+        match value {
+            Value::String(string) => Value::String(string.trim().to_owned()),
+            Value::Array(items) => Value::Array(items.into_iter().map(trim).collect()),
+            Value::Object(object) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(name, value)| (name, trim(value)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+
+    serde_json::to_string(&canonical::sort_keys(trim(serde_json::to_value(value)?)))
+}
+
+/// # `ValidCanonicalKey` data extractor
+///
+/// See the [module documentation](crate::canonical_key) for details.
+///
+#[derive(Debug, Clone)]
+pub struct ValidCanonicalKey<T>(pub T, pub String);
+
+impl<T> ValidCanonicalKey<T> {
+    /// Consumes the `ValidCanonicalKey` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidCanonicalKey`
+pub type CanonicalKeyRejection = SerializedRejection;
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidCanonicalKey<T>
+where
+    State: Send + Sync,
+    T: DeserializeOwned + Serialize + Validate,
+{
+    type Rejection = ValidRejection<CanonicalKeyRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let Json(data) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Json(e)))?;
+        validate_redacted(&data).map_err(ValidRejection::Valid)?;
+        let key = canonical_key(&data)
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Serialize(e)))?;
+        Ok(ValidCanonicalKey(data, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use std::collections::HashMap;
+
+    #[derive(Debug, serde::Deserialize, Serialize, Validate)]
+    struct Search {
+        #[validate(length(min = 1, max = 64))]
+        query: String,
+        page: u32,
+        #[serde(default)]
+        filters: HashMap<String, String>,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    async fn key(body: &'static str) -> anyhow::Result<String> {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        let ValidCanonicalKey(_, key) = ValidCanonicalKey::<Search>::from_request(request, &())
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        Ok(key)
+    }
+
+    #[tokio::test]
+    async fn equivalent_payloads_have_identical_keys() -> anyhow::Result<()> {
+        let key0 = key(
+            r#"{"query": "rust", "page": 1, "filters": {"lang": "en", "year": "2024"}, "tags": ["a", "b"]}"#,
+        )
+        .await?;
+        let key1 = key(
+            r#"{ "tags": [" a", "b "], "filters": {"year": " 2024", "lang": "en"}, "page": 1, "query": "  rust " }"#,
+        )
+        .await?;
+        assert_eq!(key0, key1);
+        assert_eq!(
+            key0,
+            r#"{"filters":{"lang":"en","year":"2024"},"page":1,"query":"rust","tags":["a","b"]}"#
+        );
+
+        // the order of arrays is meaningful
+        let key2 = key(r#"{"query": "rust", "page": 1, "filters": {"lang": "en", "year": "2024"}, "tags": ["b", "a"]}"#).await?;
+        assert_ne!(key0, key2);
+        let key3 = key(r#"{"query": "rust", "page": 2}"#).await?;
+        assert_ne!(key0, key3);
+
+        assert!(key(r#"{"query": "", "page": 1}"#).await.is_err());
+        Ok(())
+    }
+}
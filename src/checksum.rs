@@ -0,0 +1,387 @@
+//! # Request body checksums
+//!
+//! ## Feature
+//!
+//! Enable the `checksum` feature to use `Checksummed<E>`.
+//!
+//! Clients may send a checksum of the request body to detect corruption in transit.
+//! `Checksummed<E>` reads the body and checks it against the checksum headers before the inner extractor
+//! deserializes it. Like the inner extractor, it can be validated with `Valid`, `Garde` or `Validated`,
+//! e.g. `Valid<Checksummed<Json<T>>>`. Two headers are supported:
+//!
+//! * `Content-MD5`: the base64-encoded MD5 digest of the body (RFC 1864).
+//! * `X-Content-SHA256`: the hex-encoded SHA-256 digest of the body, in lowercase or uppercase.
+//!
+//! If both are sent, both must match. A request without any of them is rejected with `400 Bad Request`,
+//! like a request with a malformed checksum. A body that doesn't match its checksum is rejected
+//! with `400 Bad Request` too, but as a distinct `ChecksumRejection::Mismatch`, naming the header.
+//! The checksums only guard against accidental corruption: they aren't signatures, and anyone
+//! who can alter the body can alter them as well.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::checksum::Checksummed;
+//! use axum_valid::Valid;
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Upload {
+//!     #[validate(length(min = 1, max = 64))]
+//!     pub name: String,
+//! }
+//!
+//! async fn handler(Valid(Checksummed(Json(upload))): Valid<Checksummed<Json<Upload>>>) {
+//!     println!("name = {}", upload.name);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/uploads", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::buffered::BufferedRequest;
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+
+/// Name of the header carrying the base64-encoded MD5 digest of the body
+pub const CONTENT_MD5_HEADER: &str = "content-md5";
+
+/// Name of the header carrying the hex-encoded SHA-256 digest of the body
+pub const CONTENT_SHA256_HEADER: &str = "x-content-sha256";
+
+/// Check `body` against the checksum headers, returns whether any checksum header is present
+fn verify<E>(headers: &HeaderMap, body: &[u8]) -> Result<bool, ChecksumRejection<E>> {
+    let mut checked = false;
+    if let Some(value) = headers.get(CONTENT_MD5_HEADER) {
+        let expected = base64::engine::general_purpose::STANDARD
+            .decode(value.as_bytes())
+            .map_err(|_| ChecksumRejection::Malformed(CONTENT_MD5_HEADER))?;
+        if expected.len() != 16 {
+            return Err(ChecksumRejection::Malformed(CONTENT_MD5_HEADER));
+        }
+        if expected[..] != Md5::digest(body)[..] {
+            return Err(ChecksumRejection::Mismatch(CONTENT_MD5_HEADER));
+        }
+        checked = true;
+    }
+    if let Some(value) = headers.get(CONTENT_SHA256_HEADER) {
+        let expected = value
+            .to_str()
+            .ok()
+            .filter(|value| value.len() == 64)
+            .and_then(|value| {
+                (0..64)
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or(ChecksumRejection::Malformed(CONTENT_SHA256_HEADER))?;
+        if expected[..] != Sha256::digest(body)[..] {
+            return Err(ChecksumRejection::Mismatch(CONTENT_SHA256_HEADER));
+        }
+        checked = true;
+    }
+    Ok(checked)
+}
+
+/// # `Checksummed` extractor
+///
+/// See the [module documentation](crate::checksum) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Checksummed<E>(pub E);
+
+impl<E> Deref for Checksummed<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for Checksummed<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> Checksummed<E> {
+    /// Consumes the `Checksummed` and returns the extracted data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+/// `ChecksumRejection` is returned when the `Checksummed` extractor fails.
+#[derive(Debug)]
+pub enum ChecksumRejection<E> {
+    /// No checksum header is sent
+    Missing,
+    /// The checksum header is malformed
+    Malformed(&'static str),
+    /// The body doesn't match the checksum header
+    Mismatch(&'static str),
+    /// The body can't be read
+    Bytes(BytesRejection),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for ChecksumRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChecksumRejection::Missing => write!(
+                f,
+                "Missing `{CONTENT_MD5_HEADER}` or `{CONTENT_SHA256_HEADER}` header"
+            ),
+            ChecksumRejection::Malformed(header) => write!(f, "Malformed `{header}` header"),
+            ChecksumRejection::Mismatch(header) => {
+                write!(f, "Body doesn't match the `{header}` header")
+            }
+            ChecksumRejection::Bytes(e) => write!(f, "{e}"),
+            ChecksumRejection::Inner(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for ChecksumRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ChecksumRejection::Bytes(e) => Some(e),
+            ChecksumRejection::Inner(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for ChecksumRejection<E> {
+    fn into_response(self) -> Response {
+        let message = match self {
+            ChecksumRejection::Bytes(e) => return e.into_response(),
+            ChecksumRejection::Inner(e) => return e.into_response(),
+            ChecksumRejection::Missing => {
+                format!("Missing `{CONTENT_MD5_HEADER}` or `{CONTENT_SHA256_HEADER}` header")
+            }
+            ChecksumRejection::Malformed(header) => format!("Malformed `{header}` header"),
+            ChecksumRejection::Mismatch(header) => {
+                format!("Body doesn't match the `{header}` header")
+            }
+        };
+        (StatusCode::BAD_REQUEST, message).into_response()
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for Checksummed<Extractor>
+where
+    State: Send + Sync,
+    Extractor: FromRequest<State>,
+{
+    type Rejection = ChecksumRejection<Extractor::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let buffered = BufferedRequest::read(req, state)
+            .await
+            .map_err(ChecksumRejection::Bytes)?;
+        if !verify(&buffered.parts.headers, &buffered.bytes)? {
+            return Err(ChecksumRejection::Missing);
+        }
+        Extractor::from_request(buffered.into_request(), state)
+            .await
+            .map(Checksummed)
+            .map_err(ChecksumRejection::Inner)
+    }
+}
+
+impl<E: HasValidate> HasValidate for Checksummed<E> {
+    type Validate = E::Validate;
+    fn get_validate(&self) -> &E::Validate {
+        self.0.get_validate()
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, E: HasValidateArgs<'v>> HasValidateArgs<'v> for Checksummed<E> {
+    type ValidateArgs = E::ValidateArgs;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        self.0.get_validate_args()
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<E: crate::HasModify> crate::HasModify for Checksummed<E> {
+    type Modify = E::Modify;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        self.0.get_modify()
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<E: crate::PayloadExtractor> crate::PayloadExtractor for Checksummed<E> {
+    type Payload = E::Payload;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0.get_payload()
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<E: crate::HasValidify> crate::HasValidify for Checksummed<E> {
+    type Validify = E::Validify;
+    type PayloadExtractor = Checksummed<E::PayloadExtractor>;
+    fn from_validify(v: Self::Validify) -> Self {
+        Checksummed(E::from_validify(v))
+    }
+}
+
+#[cfg(all(test, feature = "json", feature = "validator"))]
+mod tests {
+    use super::*;
+    use crate::{Valid, ValidRejection};
+    use axum::body::Body;
+    use axum::extract::rejection::JsonRejection;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+
+    #[derive(Debug, serde::Deserialize, validator::Validate)]
+    #[cfg_attr(feature = "garde", derive(garde::Validate))]
+    struct Upload {
+        #[cfg_attr(feature = "garde", garde(length(min = 1, max = 10)))]
+        #[validate(length(min = 1, max = 10))]
+        name: String,
+    }
+
+    const BODY: &str = r#"{"name": "report"}"#;
+
+    fn request(headers: &[(&str, &str)], body: &'static str) -> anyhow::Result<Request> {
+        let mut request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        Ok(request.body(Body::from(body))?)
+    }
+
+    async fn extract(
+        headers: &[(&str, &str)],
+        body: &'static str,
+    ) -> anyhow::Result<Result<Upload, ValidRejection<ChecksumRejection<JsonRejection>>>> {
+        Ok(
+            Valid::<Checksummed<Json<Upload>>>::from_request(request(headers, body)?, &())
+                .await
+                .map(|Valid(Checksummed(Json(upload)))| upload),
+        )
+    }
+
+    #[tokio::test]
+    async fn matching_and_mismatching_checksums() -> anyhow::Result<()> {
+        let md5 = base64::engine::general_purpose::STANDARD.encode(Md5::digest(BODY.as_bytes()));
+        let sha256 = Sha256::digest(BODY.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<String>();
+
+        let upload = extract(&[(CONTENT_MD5_HEADER, &md5)], BODY).await??;
+        assert_eq!(upload.name, "report");
+        assert!(extract(&[(CONTENT_SHA256_HEADER, &sha256)], BODY)
+            .await?
+            .is_ok());
+        assert!(extract(
+            &[(CONTENT_MD5_HEADER, &md5), (CONTENT_SHA256_HEADER, &sha256)],
+            BODY
+        )
+        .await?
+        .is_ok());
+
+        // the body was altered in transit
+        let tampered = r#"{"name": "rep0rt"}"#;
+        for header in [
+            (CONTENT_MD5_HEADER, md5.as_str()),
+            (CONTENT_SHA256_HEADER, sha256.as_str()),
+        ] {
+            let rejection = extract(&[header], tampered)
+                .await?
+                .expect_err("checksum mismatch");
+            assert!(matches!(
+                &rejection,
+                ValidRejection::Inner(ChecksumRejection::Mismatch(name)) if *name == header.0
+            ));
+            assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+        }
+
+        assert!(matches!(
+            extract(&[], BODY).await?,
+            Err(ValidRejection::Inner(ChecksumRejection::Missing))
+        ));
+        assert!(matches!(
+            extract(&[(CONTENT_MD5_HEADER, "not base64!")], BODY).await?,
+            Err(ValidRejection::Inner(ChecksumRejection::Malformed(
+                CONTENT_MD5_HEADER
+            )))
+        ));
+
+        // matching checksums don't skip validation
+        let body = r#"{"name": ""}"#;
+        let md5 = base64::engine::general_purpose::STANDARD.encode(Md5::digest(body.as_bytes()));
+        assert!(matches!(
+            extract(&[(CONTENT_MD5_HEADER, &md5)], body).await?,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("name")
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "garde")]
+    #[tokio::test]
+    async fn garde_checksum() -> anyhow::Result<()> {
+        use crate::{Garde, GardeRejection};
+
+        let md5 = base64::engine::general_purpose::STANDARD.encode(Md5::digest(BODY.as_bytes()));
+        let Garde(Checksummed(Json(upload))) = Garde::<Checksummed<Json<Upload>>>::from_request(
+            request(&[(CONTENT_MD5_HEADER, &md5)], BODY)?,
+            &(),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(upload.name, "report");
+
+        assert!(matches!(
+            Garde::<Checksummed<Json<Upload>>>::from_request(request(&[], BODY)?, &()).await,
+            Err(GardeRejection::Inner(ChecksumRejection::Missing))
+        ));
+        let body = r#"{"name": ""}"#;
+        let md5 = base64::engine::general_purpose::STANDARD.encode(Md5::digest(body.as_bytes()));
+        assert!(matches!(
+            Garde::<Checksummed<Json<Upload>>>::from_request(
+                request(&[(CONTENT_MD5_HEADER, &md5)], body)?,
+                &()
+            )
+            .await,
+            Err(GardeRejection::Valid(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+//! # Query complexity budget
+//!
+//! ## Feature
+//!
+//! Enable the `complexity` feature to use `ComplexityBudget`.
+//!
+//! APIs accepting a serialized query, such as a GraphQL-style selection passed in a query parameter,
+//! let clients decide how much work a request costs. `query_complexity` scores such a query,
+//! one point per selected field, so that nested selections add up, and `ComplexityBudget` checks the score
+//! against a budget provided by the state as the validation arguments of `ValidEx`, before the query is processed.
+//!
+//! Apply it with `#[validate(custom(function = "axum_valid::complexity::within_budget", use_context))]`
+//! on a string field of a type with `#[validate(context = ComplexityBudget)]`. An over-budget query fails validation
+//! with the code `complexity_exceeded` (`COMPLEXITY_EXCEEDED`) and its `complexity` and the `max` budget as parameters,
+//! a query with unbalanced braces or parentheses with the code `query_syntax` (`QUERY_SYNTAX`).
+//!
+//! The score is computed without parsing the query fully: arguments, directives, aliases, comments and strings
+//! aren't counted, nor are fragment spreads, whose fields are counted in the fragment definition.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::extract::Query;
+//! use axum::routing::get;
+//! use axum::Router;
+//! use axum_valid::complexity::ComplexityBudget;
+//! use axum_valid::ValidEx;
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! #[validate(context = ComplexityBudget)]
+//! pub struct Search {
+//!     #[validate(custom(function = "axum_valid::complexity::within_budget", use_context))]
+//!     pub query: String,
+//! }
+//!
+//! async fn handler(ValidEx(Query(search)): ValidEx<Query<Search>>) {
+//!     println!("running {}", search.query);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new()
+//!     .route("/search", get(handler))
+//!     .with_state(ComplexityBudget(100));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use std::borrow::Cow;
+use std::iter::Peekable;
+use std::str::Chars;
+use validator::ValidationError;
+
+/// Error code of queries over the complexity budget
+pub const COMPLEXITY_EXCEEDED: &str = "complexity_exceeded";
+
+/// Error code of queries with unbalanced braces or parentheses
+pub const QUERY_SYNTAX: &str = "query_syntax";
+
+/// Maximum complexity of a query, provided by the state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ComplexityBudget(pub usize);
+
+impl ComplexityBudget {
+    /// Check that the complexity of a query is within the budget
+    pub fn check(&self, query: &str) -> Result<(), ValidationError> {
+        let Some(complexity) = query_complexity(query) else {
+            let mut error = ValidationError::new(QUERY_SYNTAX);
+            error.message = Some(Cow::from("unbalanced braces or parentheses"));
+            return Err(error);
+        };
+        if complexity <= self.0 {
+            return Ok(());
+        }
+        let mut error = ValidationError::new(COMPLEXITY_EXCEEDED);
+        error.add_param(Cow::from("complexity"), &complexity);
+        error.add_param(Cow::from("max"), &self.0);
+        error.message = Some(Cow::from(format!(
+            "query complexity {complexity} exceeds the budget of {}",
+            self.0
+        )));
+        Err(error)
+    }
+}
+
+/// Check that the complexity of a query is within the budget, for `#[validate(custom(..., use_context))]`
+pub fn within_budget(query: &str, budget: &ComplexityBudget) -> Result<(), ValidationError> {
+    budget.check(query)
+}
+
+/// Score a query, one point per selected field
+///
+/// Returns `None` if its braces or parentheses are unbalanced.
+pub fn query_complexity(query: &str) -> Option<usize> {
+    let mut chars = query.chars().peekable();
+    let mut depth = 0usize;
+    let mut parentheses = 0usize;
+    let mut complexity = 0;
+    // the next name isn't a field: a fragment spread, a directive or the type condition of an inline fragment
+    let mut skip_name = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            '"' => skip_string(&mut chars),
+            '(' => parentheses += 1,
+            ')' => parentheses = parentheses.checked_sub(1)?,
+            // braces within arguments are input objects, not selection sets
+            '{' if parentheses == 0 => depth += 1,
+            '}' if parentheses == 0 => depth = depth.checked_sub(1)?,
+            '.' | '@' => skip_name = true,
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let mut name = String::from(c);
+                while let Some(&c) = chars
+                    .peek()
+                    .filter(|c| **c == '_' || c.is_ascii_alphanumeric())
+                {
+                    name.push(c);
+                    chars.next();
+                }
+                if parentheses > 0 || depth == 0 {
+                    continue;
+                }
+                if skip_name {
+                    // `... on Type`
+                    skip_name = name == "on";
+                    continue;
+                }
+                // an alias is followed by the name of the field
+                let alias = chars
+                    .clone()
+                    .find(|&c| !c.is_whitespace() && c != ',')
+                    .is_some_and(|c| c == ':');
+                if !alias {
+                    complexity += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    (depth == 0 && parentheses == 0).then_some(complexity)
+}
+
+/// Skip a string or a block string, after its opening quote
+fn skip_string(chars: &mut Peekable<Chars<'_>>) {
+    let mut block = chars.clone();
+    if block.next() == Some('"') && block.next() == Some('"') {
+        *chars = block;
+        let mut quotes = 0;
+        for c in chars.by_ref() {
+            quotes = if c == '"' { quotes + 1 } else { 0 };
+            if quotes == 3 {
+                return;
+            }
+        }
+        return;
+    }
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complexity_scores() {
+        assert_eq!(query_complexity("{ user { name email } }"), Some(3));
+        assert_eq!(
+            query_complexity(
+                r#"query Users($first: Int = 10) {
+                    # the first users
+                    users(first: $first, filter: { name: "a { b" }) @include(if: true) {
+                        id
+                        login: name
+                        ... on Admin { permissions }
+                        ...Details
+                        description(format: """block "quoted" }""")
+                    }
+                }"#
+            ),
+            Some(5)
+        );
+        assert_eq!(query_complexity("{ user { name }"), None);
+        assert_eq!(query_complexity("{ user(id: 1 { name } }"), None);
+    }
+
+    #[cfg(feature = "query")]
+    #[tokio::test]
+    async fn over_budget_query_is_rejected() -> anyhow::Result<()> {
+        use crate::{ValidEx, ValidRejection};
+        use axum::extract::rejection::QueryRejection;
+        use axum::extract::{FromRequestParts, Query};
+        use axum::http::Request;
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(context = ComplexityBudget)]
+        struct Search {
+            #[validate(custom(function = "within_budget", use_context))]
+            query: String,
+        }
+
+        async fn extract(
+            uri: &str,
+            budget: ComplexityBudget,
+        ) -> anyhow::Result<Option<ValidRejection<QueryRejection>>> {
+            let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+            Ok(
+                ValidEx::<Query<Search>>::from_request_parts(&mut parts, &budget)
+                    .await
+                    .err(),
+            )
+        }
+
+        let uri = "/?query=%7B%20user%20%7B%20name%20friends%20%7B%20name%20%7D%20%7D%20%7D";
+        assert!(extract(uri, ComplexityBudget(4)).await?.is_none());
+
+        let Some(ValidRejection::Valid(errors)) = extract(uri, ComplexityBudget(3)).await? else {
+            panic!("expected an over-budget query");
+        };
+        let error = &errors.field_errors()["query"][0];
+        assert_eq!(error.code, COMPLEXITY_EXCEEDED);
+        assert_eq!(error.params["complexity"], serde_json::json!(4));
+        assert_eq!(error.params["max"], serde_json::json!(3));
+
+        let Some(ValidRejection::Valid(errors)) =
+            extract("/?query=%7B", ComplexityBudget(3)).await?
+        else {
+            panic!("expected a malformed query");
+        };
+        assert_eq!(errors.field_errors()["query"][0].code, QUERY_SYNTAX);
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+//! # Validation of compressed request bodies
+//!
+//! ## Feature
+//!
+//! Enable the `compressed` feature to use `Decompressed<E, MAX_SIZE>`.
+//!
+//! Clients may compress large request bodies with gzip and send them with `Content-Encoding: gzip`.
+//! `Decompressed<E, MAX_SIZE>` reads the body and decompresses it before the inner extractor deserializes it.
+//! Like the inner extractor, it can be validated with `Valid`, `Garde` or `Validated`, e.g. `Valid<Decompressed<Json<T>>>`.
+//! Bodies without `Content-Encoding`, or with `Content-Encoding: identity`, are passed as they are.
+//!
+//! A small gzip body can decompress to a huge one, so decompression stops after `MAX_SIZE` bytes,
+//! `DEFAULT_MAX_DECOMPRESSED_SIZE` by default, and the request is rejected with `413 Payload Too Large`.
+//! The compressed body is limited by `DefaultBodyLimit` like any other body.
+//! Malformed gzip bodies are rejected with `400 Bad Request`, and other encodings with `415 Unsupported Media Type`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::compressed::Decompressed;
+//! use axum_valid::Valid;
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Report {
+//!     #[validate(length(min = 1, max = 10000))]
+//!     pub entries: Vec<String>,
+//! }
+//!
+//! // at most 10 MiB once decompressed
+//! async fn handler(
+//!     Valid(Decompressed(Json(report))): Valid<Decompressed<Json<Report>, { 10 * 1024 * 1024 }>>,
+//! ) {
+//!     println!("{} entries", report.entries.len());
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/reports", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::buffered::BufferedRequest;
+use crate::gzip::{decompress, GzipError};
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::header::{CONTENT_ENCODING, CONTENT_LENGTH};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+
+/// Default maximum size of decompressed bodies of `Decompressed`, in bytes
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 2 * 1024 * 1024;
+
+/// # `Decompressed` extractor
+///
+/// See the [module documentation](crate::compressed) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Decompressed<E, const MAX_SIZE: usize = DEFAULT_MAX_DECOMPRESSED_SIZE>(pub E);
+
+impl<E, const MAX_SIZE: usize> Deref for Decompressed<E, MAX_SIZE> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E, const MAX_SIZE: usize> DerefMut for Decompressed<E, MAX_SIZE> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E, const MAX_SIZE: usize> Decompressed<E, MAX_SIZE> {
+    /// Consumes the `Decompressed` and returns the extracted data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+/// `CompressedRejection` is returned when the `Decompressed` extractor fails.
+#[derive(Debug)]
+pub enum CompressedRejection<E> {
+    /// The `Content-Encoding` isn't supported
+    UnsupportedEncoding(String),
+    /// The body isn't valid gzip
+    Malformed,
+    /// The decompressed body is larger than the limit
+    TooLarge(usize),
+    /// The body can't be read
+    Bytes(BytesRejection),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for CompressedRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompressedRejection::UnsupportedEncoding(encoding) => {
+                write!(f, "Unsupported content encoding: {encoding}")
+            }
+            CompressedRejection::Malformed => write!(f, "Malformed gzip body"),
+            CompressedRejection::TooLarge(max) => {
+                write!(f, "The decompressed body is larger than {max} bytes")
+            }
+            CompressedRejection::Bytes(e) => write!(f, "{e}"),
+            CompressedRejection::Inner(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for CompressedRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CompressedRejection::Bytes(e) => Some(e),
+            CompressedRejection::Inner(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for CompressedRejection<E> {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            CompressedRejection::Bytes(e) => return e.into_response(),
+            CompressedRejection::Inner(e) => return e.into_response(),
+            CompressedRejection::UnsupportedEncoding(encoding) => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Unsupported content encoding: {encoding}"),
+            ),
+            CompressedRejection::Malformed => {
+                (StatusCode::BAD_REQUEST, String::from("Malformed gzip body"))
+            }
+            CompressedRejection::TooLarge(max) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("The decompressed body is larger than {max} bytes"),
+            ),
+        };
+        (status, message).into_response()
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, const MAX_SIZE: usize> FromRequest<State>
+    for Decompressed<Extractor, MAX_SIZE>
+where
+    State: Send + Sync,
+    Extractor: FromRequest<State>,
+{
+    type Rejection = CompressedRejection<Extractor::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let encoding = req
+            .headers()
+            .get(CONTENT_ENCODING)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).trim().to_owned());
+        let req = match encoding {
+            None => req,
+            Some(encoding) if encoding.eq_ignore_ascii_case("identity") => req,
+            Some(encoding)
+                if encoding.eq_ignore_ascii_case("gzip")
+                    || encoding.eq_ignore_ascii_case("x-gzip") =>
+            {
+                let mut buffered = BufferedRequest::read(req, state)
+                    .await
+                    .map_err(CompressedRejection::Bytes)?;
+                let decompressed = decompress(&buffered.bytes, MAX_SIZE).map_err(|e| match e {
+                    GzipError::Malformed => CompressedRejection::Malformed,
+                    GzipError::TooLarge => CompressedRejection::TooLarge(MAX_SIZE),
+                })?;
+                // the inner extractor sees the decompressed body
+                buffered.parts.headers.remove(CONTENT_ENCODING);
+                buffered.parts.headers.remove(CONTENT_LENGTH);
+                buffered.bytes = Bytes::from(decompressed);
+                buffered.into_request()
+            }
+            Some(encoding) => return Err(CompressedRejection::UnsupportedEncoding(encoding)),
+        };
+        Extractor::from_request(req, state)
+            .await
+            .map(Decompressed)
+            .map_err(CompressedRejection::Inner)
+    }
+}
+
+impl<E: HasValidate, const MAX_SIZE: usize> HasValidate for Decompressed<E, MAX_SIZE> {
+    type Validate = E::Validate;
+    fn get_validate(&self) -> &E::Validate {
+        self.0.get_validate()
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, E: HasValidateArgs<'v>, const MAX_SIZE: usize> HasValidateArgs<'v>
+    for Decompressed<E, MAX_SIZE>
+{
+    type ValidateArgs = E::ValidateArgs;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        self.0.get_validate_args()
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<E: crate::HasModify, const MAX_SIZE: usize> crate::HasModify for Decompressed<E, MAX_SIZE> {
+    type Modify = E::Modify;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        self.0.get_modify()
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<E: crate::PayloadExtractor, const MAX_SIZE: usize> crate::PayloadExtractor
+    for Decompressed<E, MAX_SIZE>
+{
+    type Payload = E::Payload;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0.get_payload()
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<E: crate::HasValidify, const MAX_SIZE: usize> crate::HasValidify
+    for Decompressed<E, MAX_SIZE>
+{
+    type Validify = E::Validify;
+    type PayloadExtractor = Decompressed<E::PayloadExtractor, MAX_SIZE>;
+    fn from_validify(v: Self::Validify) -> Self {
+        Decompressed(E::from_validify(v))
+    }
+}
+
+#[cfg(all(test, feature = "json", feature = "validator"))]
+mod tests {
+    use super::*;
+    use crate::{Valid, ValidRejection};
+    use axum::body::Body;
+    use axum::extract::rejection::JsonRejection;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+
+    #[derive(Debug, serde::Deserialize, validator::Validate)]
+    struct Upload {
+        #[validate(length(min = 1, max = 10))]
+        name: String,
+    }
+
+    /// `gzip.compress(b'{"name": "report"}', mtime=0)`
+    const REPORT: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xab, 0x56, 0xca, 0x4b, 0xcc,
+        0x4d, 0x55, 0xb2, 0x52, 0x50, 0x2a, 0x4a, 0x2d, 0xc8, 0x2f, 0x2a, 0x51, 0xaa, 0x05, 0x00,
+        0x8c, 0xfd, 0x20, 0x82, 0x12, 0x00, 0x00, 0x00,
+    ];
+
+    /// `gzip.compress(b'{"name": "' + b'x' * 5000 + b'"}', mtime=0)`, 5012 bytes once decompressed
+    const LARGE: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xed, 0xc1, 0x31, 0x0d, 0x00,
+        0x20, 0x10, 0x04, 0x30, 0x2b, 0xe4, 0x64, 0xe0, 0x86, 0x81, 0x11, 0xe6, 0x4f, 0x08, 0xde,
+        0x51, 0xc1, 0xd6, 0xf6, 0x64, 0x8f, 0x35, 0xd3, 0x5b, 0x0a, 0x00, 0x00, 0x00, 0x00, 0xf8,
+        0x2e, 0xf7, 0x01, 0xd8, 0x7a, 0x6a, 0xf5, 0x94, 0x13, 0x00, 0x00,
+    ];
+
+    async fn extract<const MAX_SIZE: usize>(
+        encoding: Option<&str>,
+        body: &'static [u8],
+    ) -> anyhow::Result<Result<Upload, ValidRejection<CompressedRejection<JsonRejection>>>> {
+        let mut request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .header(CONTENT_LENGTH, body.len());
+        if let Some(encoding) = encoding {
+            request = request.header(CONTENT_ENCODING, encoding);
+        }
+        Ok(Valid::<Decompressed<Json<Upload>, MAX_SIZE>>::from_request(
+            request.body(Body::from(body))?,
+            &(),
+        )
+        .await
+        .map(|Valid(Decompressed(Json(upload)))| upload))
+    }
+
+    #[tokio::test]
+    async fn decompress_before_validation() -> anyhow::Result<()> {
+        let upload = extract::<1024>(Some("gzip"), REPORT).await??;
+        assert_eq!(upload.name, "report");
+        // uncompressed bodies are passed as they are
+        let upload = extract::<1024>(None, br#"{"name": "report"}"#).await??;
+        assert_eq!(upload.name, "report");
+
+        // the decompressed body is deserialized and validated
+        assert!(matches!(
+            extract::<8192>(Some("gzip"), LARGE).await?,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("name")
+        ));
+
+        // a small compressed body decompressing beyond the limit
+        let rejection = extract::<1024>(Some("gzip"), LARGE)
+            .await?
+            .expect_err("too large");
+        assert!(matches!(
+            rejection,
+            ValidRejection::Inner(CompressedRejection::TooLarge(1024))
+        ));
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let rejection = extract::<1024>(Some("gzip"), br#"{"name": "report"}"#)
+            .await?
+            .expect_err("not gzip");
+        assert!(matches!(
+            rejection,
+            ValidRejection::Inner(CompressedRejection::Malformed)
+        ));
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+
+        let rejection = extract::<1024>(Some("br"), REPORT)
+            .await?
+            .expect_err("unsupported encoding");
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        Ok(())
+    }
+}
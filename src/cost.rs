@@ -0,0 +1,331 @@
+//! # Validation cost
+//!
+//! ## Feature
+//!
+//! Enable the `cost` feature to use `ValidCosted<E>`.
+//!
+//! Some requests are much more expensive to validate than others, e.g. when custom rules look up a database
+//! or hash a password, so an adaptive rate limiter should weight requests by their validation cost.
+//! `ValidCosted<E>` validates the inner data like `ValidEx<E>`, with a fresh `CostMeter` as the validation arguments,
+//! and returns the `ValidationCost` of the request: the number of rules evaluated through `CostMeter::rule`,
+//! the time spent in them, and the total duration of the validation.
+//!
+//! Rules are counted when they are wrapped with `CostMeter::rule` in functions applied with
+//! `#[validate(custom(function = "...", use_context))]` on a type with `#[validate(context = CostMeter)]`.
+//! Built-in rules such as `length` are cheap, they are not counted, but their time is part of the total duration.
+//!
+//! The cost is added to the extensions of the response, so that a middleware can read it after the handler:
+//! as a response part on success, and by the `CostRejection` on failure.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::cost::{CostMeter, ValidCosted, ValidationCost};
+//! use serde::Deserialize;
+//! use validator::{Validate, ValidationError};
+//!
+//! #[derive(Validate, Deserialize)]
+//! #[validate(context = CostMeter)]
+//! pub struct SignUp {
+//!     #[validate(length(min = 1, max = 32), custom(function = "available", use_context))]
+//!     pub username: String,
+//! }
+//!
+//! fn available(username: &str, meter: &CostMeter) -> Result<(), ValidationError> {
+//!     meter.rule(|| {
+//!         // look up the username
+//!         Ok(())
+//!     })
+//! }
+//!
+//! async fn handler(ValidCosted(Json(sign_up), cost): ValidCosted<Json<SignUp>>) -> (ValidationCost, String) {
+//!     (cost, sign_up.username)
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/sign_up", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::redacting;
+use crate::{HasValidateArgs, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
+use validator::{ValidateArgs, ValidationError, ValidationErrors};
+
+/// Meter of the rules evaluated while validating a request
+///
+/// A meter is created for each request, and passed as the validation arguments.
+#[derive(Debug, Default)]
+pub struct CostMeter {
+    rules: Cell<usize>,
+    time: Cell<Duration>,
+}
+
+impl CostMeter {
+    /// Evaluate a rule, counting it and measuring its duration
+    pub fn rule<F>(&self, rule: F) -> Result<(), ValidationError>
+    where
+        F: FnOnce() -> Result<(), ValidationError>,
+    {
+        let start = Instant::now();
+        let result = rule();
+        self.rules.set(self.rules.get() + 1);
+        self.time.set(self.time.get() + start.elapsed());
+        result
+    }
+
+    /// Number of rules evaluated so far
+    pub fn rules(&self) -> usize {
+        self.rules.get()
+    }
+
+    /// Time spent in the rules evaluated so far
+    pub fn time(&self) -> Duration {
+        self.time.get()
+    }
+}
+
+/// Cost of the validation of a request
+///
+/// As a response part, it's added to the extensions of the response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidationCost {
+    /// Number of rules evaluated through the `CostMeter`
+    pub rules: usize,
+    /// Time spent in the rules evaluated through the `CostMeter`
+    pub rule_time: Duration,
+    /// Total duration of the validation
+    pub total: Duration,
+}
+
+impl IntoResponseParts for ValidationCost {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        res.extensions_mut().insert(self);
+        Ok(res)
+    }
+}
+
+/// # `ValidCosted` data extractor
+///
+/// See the [module documentation](crate::cost) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidCosted<E>(pub E, pub ValidationCost);
+
+impl<E> Deref for ValidCosted<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidCosted<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidCosted<E> {
+    /// Consumes the `ValidCosted` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+
+    /// The cost of the validation
+    pub fn cost(&self) -> ValidationCost {
+        self.1
+    }
+}
+
+/// `CostRejection` is returned when the `ValidCosted` extractor fails.
+#[derive(Debug)]
+pub enum CostRejection<E> {
+    /// The data is invalid, with the cost of its validation
+    Valid(ValidationErrors, ValidationCost),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for CostRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CostRejection::Valid(errors, _) => write!(f, "{errors}"),
+            CostRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for CostRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CostRejection::Valid(errors, _) => Some(errors),
+            CostRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for CostRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            CostRejection::Valid(errors, cost) => {
+                (cost, ValidRejection::<E>::Valid(errors)).into_response()
+            }
+            CostRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+fn validate<E>(inner: &E) -> Result<ValidationCost, (ValidationErrors, ValidationCost)>
+where
+    E: for<'v> HasValidateArgs<'v>,
+    for<'v> <E as HasValidateArgs<'v>>::ValidateArgs: ValidateArgs<'v, Args = &'v CostMeter>,
+{
+    let meter = CostMeter::default();
+    let start = Instant::now();
+    let result = redacting(|| inner.get_validate_args().validate_with_args(&meter));
+    let cost = ValidationCost {
+        rules: meter.rules(),
+        rule_time: meter.time(),
+        total: start.elapsed(),
+    };
+    result.map(|_| cost).map_err(|errors| (errors, cost))
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidCosted<Extractor>
+where
+    State: Send + Sync,
+    Extractor: for<'v> HasValidateArgs<'v> + FromRequest<State>,
+    for<'v> <Extractor as HasValidateArgs<'v>>::ValidateArgs:
+        ValidateArgs<'v, Args = &'v CostMeter>,
+{
+    type Rejection = CostRejection<<Extractor as FromRequest<State>>::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(CostRejection::Inner)?;
+        let cost = validate(&inner).map_err(|(errors, cost)| CostRejection::Valid(errors, cost))?;
+        Ok(ValidCosted(inner, cost))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequestParts<State> for ValidCosted<Extractor>
+where
+    State: Send + Sync,
+    Extractor: for<'v> HasValidateArgs<'v> + FromRequestParts<State>,
+    for<'v> <Extractor as HasValidateArgs<'v>>::ValidateArgs:
+        ValidateArgs<'v, Args = &'v CostMeter>,
+{
+    type Rejection = CostRejection<<Extractor as FromRequestParts<State>>::Rejection>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request_parts(parts, state)
+            .await
+            .map_err(CostRejection::Inner)?;
+        let cost = validate(&inner).map_err(|(errors, cost)| CostRejection::Valid(errors, cost))?;
+        Ok(ValidCosted(inner, cost))
+    }
+}
+
+#[cfg(all(test, feature = "query"))]
+mod tests {
+    use super::*;
+    use axum::extract::rejection::QueryRejection;
+    use axum::extract::Query;
+    use axum::http::Request;
+    use validator::Validate;
+
+    const DELAY: Duration = Duration::from_millis(2);
+
+    fn cheap(_: &str, meter: &CostMeter) -> Result<(), ValidationError> {
+        meter.rule(|| Ok(()))
+    }
+
+    fn available(username: &str, meter: &CostMeter) -> Result<(), ValidationError> {
+        cheap(username, meter)?;
+        meter.rule(|| {
+            std::thread::sleep(DELAY);
+            match username {
+                "taken" => Err(ValidationError::new("taken")),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    fn known_referrer(referrer: &Option<String>, meter: &CostMeter) -> Result<(), ValidationError> {
+        referrer
+            .as_deref()
+            .map_or(Ok(()), |referrer| cheap(referrer, meter))
+    }
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    #[validate(context = CostMeter)]
+    struct SignUp {
+        #[validate(length(min = 1, max = 32), custom(function = "available", use_context))]
+        username: String,
+        #[validate(custom(function = "known_referrer", use_context))]
+        referrer: Option<String>,
+    }
+
+    async fn extract(
+        uri: &str,
+    ) -> anyhow::Result<Result<ValidCosted<Query<SignUp>>, CostRejection<QueryRejection>>> {
+        let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+        Ok(ValidCosted::<Query<SignUp>>::from_request_parts(&mut parts, &()).await)
+    }
+
+    #[tokio::test]
+    async fn cost_reflects_rules_evaluated() -> anyhow::Result<()> {
+        let ValidCosted(Query(sign_up), cost) = extract("/?username=alice")
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(sign_up.username, "alice");
+        assert_eq!(cost.rules, 2);
+        assert!(cost.rule_time >= DELAY);
+        assert!(cost.total >= cost.rule_time);
+
+        // the rules of the optional field are only evaluated if it's provided
+        let ValidCosted(_, cost) = extract("/?username=alice&referrer=bob")
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(cost.rules, 3);
+
+        let response = (cost, "ok").into_response();
+        assert_eq!(response.extensions().get::<ValidationCost>(), Some(&cost));
+
+        let Err(rejection) = extract("/?username=taken&referrer=bob").await? else {
+            panic!("expected the username to be taken");
+        };
+        let CostRejection::Valid(_, cost) = &rejection else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(cost.rules, 3);
+        let cost = *cost;
+        let response = rejection.into_response();
+        assert_eq!(response.status(), crate::VALIDATION_ERROR_STATUS);
+        assert_eq!(response.extensions().get::<ValidationCost>(), Some(&cost));
+        Ok(())
+    }
+}
@@ -0,0 +1,1001 @@
+//! # Field adapters for deserialization
+//!
+//! ## Feature
+//!
+//! Enable the `de` feature to use the field adapters in this module.
+//!
+//! These functions are meant to be used with `#[serde(deserialize_with = "...")]`,
+//! to accept common wire formats that serde doesn't support out of the box.
+//! The `seconds` module is meant to be used with `#[serde(with = "...")]`, to serialize the field the same way.
+//! The adapted fields are validated like any other field afterwards.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "garde")]
+//! mod garde_example {
+//!     use axum::extract::Query;
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::Garde;
+//!     use garde::Validate;
+//!     use serde::Deserialize;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/query", get(handler))
+//!     }
+//!
+//!     // `/query?tags=a,b,c`
+//!     async fn handler(Garde(Query(parameter)): Garde<Query<Parameter>>) {
+//!         assert!(parameter.validate(&()).is_ok());
+//!         println!("tags = {:?}", parameter.tags);
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[serde(deserialize_with = "axum_valid::de::comma_separated")]
+//!         #[garde(length(min = 1, max = 10), inner(length(min = 1, max = 20)))]
+//!         pub tags: Vec<String>,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "garde")]
+//! #     let router = router.nest("/garde", garde_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use serde::de::{Error, IntoDeserializer, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/// Deserialize a comma-separated string (e.g. `a,b,c`) into a `Vec<T>`
+///
+/// Each element is parsed with `T::from_str`. An empty string results in an empty `Vec`.
+pub fn comma_separated<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = Cow::<'de, str>::deserialize(deserializer)?;
+    if value.is_empty() {
+        return Ok(Vec::new());
+    }
+    value
+        .split(',')
+        .map(|element| element.parse().map_err(D::Error::custom))
+        .collect()
+}
+
+/// Deserialize an optional comma-separated string into an `Option<Vec<T>>`
+///
+/// Use it with `#[serde(default)]`, so that an absent field is `None`,
+/// while an empty value (e.g. `?tags=`) is `Some(vec![])`.
+pub fn optional_comma_separated<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    comma_separated(deserializer).map(Some)
+}
+
+/// Deserialize a flag into a `bool`, where a present but empty value (e.g. `?active` or `?active=`) is `true`
+///
+/// `true`, `false`, `1`, `0`, `yes`, `no`, `on` and `off` are accepted too, ignoring case, and booleans, e.g. in JSON.
+/// An absent flag is configured with serde: `#[serde(default)]` makes it `false`,
+/// and `#[serde(default = "...")]` makes it the value returned by a function, e.g. `true` for opt-out flags.
+pub fn flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct Flag;
+
+    impl<'de> Visitor<'de> for Flag {
+        type Value = bool;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a boolean or an empty value")
+        }
+
+        fn visit_bool<E: Error>(self, value: bool) -> Result<bool, E> {
+            Ok(value)
+        }
+
+        fn visit_str<E: Error>(self, value: &str) -> Result<bool, E> {
+            match value.to_ascii_lowercase().as_str() {
+                "" | "true" | "1" | "yes" | "on" => Ok(true),
+                "false" | "0" | "no" | "off" => Ok(false),
+                _ => Err(E::invalid_value(serde::de::Unexpected::Str(value), &self)),
+            }
+        }
+    }
+
+    deserializer.deserialize_any(Flag)
+}
+
+/// Deserialize an optional flag into an `Option<bool>`, where a present but empty value is `Some(true)`
+///
+/// Use it with `#[serde(default)]`, so that an absent flag is `None`, e.g. to apply a default
+/// that depends on other fields, or to require the flag in validation.
+pub fn optional_flag<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    flag(deserializer).map(Some)
+}
+
+/// Deserialize an enum from either its integer discriminant (e.g. `1`) or its serde name (e.g. `active`)
+///
+/// Integers are converted with `T::try_from`, names are deserialized with the `Deserialize` implementation
+/// of `T`, so `#[serde(rename_all = "...")]` applies. Integers are accepted as numbers, e.g. in JSON,
+/// and as strings made of digits, e.g. in queries and forms.
+pub fn int_or_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + TryFrom<i64>,
+    <T as TryFrom<i64>>::Error: Display,
+{
+    struct IntOrString<T>(PhantomData<T>);
+
+    impl<'de, T> Visitor<'de> for IntOrString<T>
+    where
+        T: Deserialize<'de> + TryFrom<i64>,
+        <T as TryFrom<i64>>::Error: Display,
+    {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("an integer or a string")
+        }
+
+        fn visit_i64<E: Error>(self, value: i64) -> Result<T, E> {
+            T::try_from(value).map_err(E::custom)
+        }
+
+        fn visit_u64<E: Error>(self, value: u64) -> Result<T, E> {
+            let value = i64::try_from(value).map_err(E::custom)?;
+            self.visit_i64(value)
+        }
+
+        fn visit_str<E: Error>(self, value: &str) -> Result<T, E> {
+            match value.parse::<i64>() {
+                Ok(value) => self.visit_i64(value),
+                Err(_) => T::deserialize(value.into_deserializer()),
+            }
+        }
+    }
+
+    deserializer.deserialize_any(IntOrString(PhantomData))
+}
+
+/// Deserialize an optional enum from either its integer discriminant or its serde name
+///
+/// Use it with `#[serde(default)]`, so that an absent field is `None`.
+pub fn optional_int_or_string<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + TryFrom<i64>,
+    <T as TryFrom<i64>>::Error: Display,
+{
+    int_or_string(deserializer).map(Some)
+}
+
+/// Deserialize an enum from its serde name in any case, e.g. `ACTIVE` or `Active` for `active`
+///
+/// The value is matched against the variant names of `T` ignoring ASCII case, and deserialized with
+/// the canonical name, so `#[serde(rename_all = "...")]` applies and the field is validated with the canonical variant.
+/// Values matching no variant are rejected like unknown variants.
+pub fn case_insensitive<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    T::deserialize(CaseInsensitive(value)).map_err(D::Error::custom)
+}
+
+/// Deserialize an optional enum from its serde name in any case
+///
+/// Use it with `#[serde(default)]`, so that an absent field is `None`.
+pub fn optional_case_insensitive<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    case_insensitive(deserializer).map(Some)
+}
+
+/// Name of a variant, matched against the variant names of the enum ignoring ASCII case
+struct CaseInsensitive(String);
+
+impl<'de> Deserializer<'de> for CaseInsensitive {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let variant = variants
+            .iter()
+            .find(|variant| variant.eq_ignore_ascii_case(&self.0))
+            .map_or(self.0, |variant| (*variant).to_owned());
+        visitor.visit_enum(variant.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Deserialize a mass with a unit (e.g. `1kg`, `2.5 lb`) into grams
+///
+/// The units `mg`, `g`, `kg`, `t`, `oz` and `lb` are accepted, ignoring case and whitespace between the number and the unit.
+/// Numbers without a unit are grams, so that range rules are written in grams, e.g. `#[validate(range(max = 1000.0))]`
+/// accepts `1kg` but rejects `1.5kg`.
+pub fn grams<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    const UNITS: &[(&str, f64)] = &[
+        ("mg", 0.001),
+        ("g", 1.0),
+        ("kg", 1000.0),
+        ("t", 1_000_000.0),
+        ("oz", 28.349_523_125),
+        ("lb", 453.592_37),
+    ];
+    deserializer.deserialize_any(WithUnit(UNITS))
+}
+
+/// Deserialize an optional mass with a unit into grams
+///
+/// Use it with `#[serde(default)]`, so that an absent field is `None`.
+pub fn optional_grams<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    grams(deserializer).map(Some)
+}
+
+/// Deserialize a length with a unit (e.g. `1.8m`, `12 in`) into millimeters
+///
+/// The units `mm`, `cm`, `m`, `km`, `in` and `ft` are accepted, ignoring case and whitespace between the number and the unit.
+/// Numbers without a unit are millimeters.
+pub fn millimeters<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    const UNITS: &[(&str, f64)] = &[
+        ("mm", 1.0),
+        ("cm", 10.0),
+        ("m", 1000.0),
+        ("km", 1_000_000.0),
+        ("in", 25.4),
+        ("ft", 304.8),
+    ];
+    deserializer.deserialize_any(WithUnit(UNITS))
+}
+
+/// Deserialize an optional length with a unit into millimeters
+///
+/// Use it with `#[serde(default)]`, so that an absent field is `None`.
+pub fn optional_millimeters<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    millimeters(deserializer).map(Some)
+}
+
+/// Durations with a unit (e.g. `90s`, `5m`, `2h`), in whole seconds
+///
+/// It's a module for `#[serde(with = "axum_valid::de::seconds")]`, so that a field is deserialized and serialized
+/// the same way: the units `s`, `m`, `h` and `d` are accepted, ignoring case and whitespace between the number
+/// and the unit, numbers without a unit are seconds, and durations are serialized like `90s`.
+/// Fractional durations, e.g. `1.5s`, are rejected, `1.5m` is `90s`.
+/// The field is validated in seconds, e.g. `#[validate(range(max = 3600))]` accepts `1h` but rejects `61m`.
+pub mod seconds {
+    use super::WithUnit;
+    use serde::de::{Error, Unexpected};
+    use serde::{Deserializer, Serializer};
+
+    const UNITS: &[(&str, f64)] = &[("s", 1.0), ("m", 60.0), ("h", 3600.0), ("d", 86400.0)];
+
+    /// Deserialize a duration with a unit into seconds
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let seconds = deserializer.deserialize_any(WithUnit(UNITS))?;
+        if seconds < 0.0 || seconds.fract() != 0.0 || seconds > u64::MAX as f64 {
+            return Err(D::Error::invalid_value(
+                Unexpected::Float(seconds),
+                &"a whole number of seconds",
+            ));
+        }
+        Ok(seconds as u64)
+    }
+
+    /// Serialize seconds as a duration with the `s` unit, e.g. `90s`
+    pub fn serialize<S>(seconds: &u64, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&format_args!("{seconds}s"))
+    }
+}
+
+/// Quantity with one of the units, converted to the canonical unit with its factor
+struct WithUnit(&'static [(&'static str, f64)]);
+
+impl<'de> Visitor<'de> for WithUnit {
+    type Value = f64;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a number with one of the units ")?;
+        let units = self.0.iter().map(|(unit, _)| *unit).collect::<Vec<_>>();
+        formatter.write_str(&units.join(", "))
+    }
+
+    fn visit_f64<E: Error>(self, value: f64) -> Result<f64, E> {
+        Ok(value)
+    }
+
+    fn visit_i64<E: Error>(self, value: i64) -> Result<f64, E> {
+        Ok(value as f64)
+    }
+
+    fn visit_u64<E: Error>(self, value: u64) -> Result<f64, E> {
+        Ok(value as f64)
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<f64, E> {
+        let value = value.trim();
+        let split = value
+            .find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(split);
+        let factor = if unit.is_empty() {
+            1.0
+        } else {
+            self.0
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(unit))
+                .map(|(_, factor)| *factor)
+                .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(value), &self))?
+        };
+        let number = number
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite())
+            .ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(value), &self))?;
+        Ok(number * factor)
+    }
+}
+
+/// Deserialize a human-friendly duration (e.g. `30s`, `1h 30m`) into a `std::time::Duration`
+///
+/// Requires the `humantime` feature, durations are parsed with [`humantime`](https://crates.io/crates/humantime).
+#[cfg(feature = "humantime")]
+pub fn humantime<'de, D>(deserializer: D) -> Result<std::time::Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Cow::<'de, str>::deserialize(deserializer)?;
+    ::humantime::parse_duration(&value).map_err(D::Error::custom)
+}
+
+/// Deserialize an ISO 8601 timestamp in the RFC 3339 profile (e.g. `2024-03-05T12:00:00Z`) into a `DateTime<Utc>`
+///
+/// Requires the `chrono` feature. The timestamp must have an offset, which is converted to UTC.
+/// Malformed timestamps are rejected by the extractor with a deserialization error,
+/// use `rules::timestamp_window` to validate that well-formed timestamps are within a window.
+#[cfg(feature = "chrono")]
+pub fn rfc3339<'de, D>(deserializer: D) -> Result<::chrono::DateTime<::chrono::Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Cow::<'de, str>::deserialize(deserializer)?;
+    ::chrono::DateTime::parse_from_rfc3339(&value)
+        .map(|timestamp| timestamp.to_utc())
+        .map_err(|e| D::Error::custom(format_args!("invalid RFC 3339 timestamp `{value}`: {e}")))
+}
+
+/// Deserialize an optional ISO 8601 timestamp into an `Option<DateTime<Utc>>`
+///
+/// Use it with `#[serde(default)]`, so that an absent field is `None`.
+#[cfg(feature = "chrono")]
+pub fn optional_rfc3339<'de, D>(
+    deserializer: D,
+) -> Result<Option<::chrono::DateTime<::chrono::Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    rfc3339(deserializer).map(Some)
+}
+
+/// Deserialize base64-encoded binary data (e.g. `aGVsbG8=`) into a `Vec<u8>`
+///
+/// Requires the `base64` feature. The standard alphabet with padding (RFC 4648) is expected.
+/// Malformed values are rejected by the extractor with a deserialization error,
+/// validate the decoded data afterwards, e.g. its size with `#[validate(length(max = ...))]`.
+#[cfg(feature = "base64")]
+pub fn base64<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use ::base64::Engine;
+
+    let value = Cow::<'de, str>::deserialize(deserializer)?;
+    ::base64::engine::general_purpose::STANDARD
+        .decode(value.as_bytes())
+        .map_err(|e| D::Error::custom(format_args!("invalid base64: {e}")))
+}
+
+/// Deserialize optional base64-encoded binary data into an `Option<Vec<u8>>`
+///
+/// Use it with `#[serde(default)]`, so that an absent field is `None`.
+#[cfg(feature = "base64")]
+pub fn optional_base64<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    base64(deserializer).map(Some)
+}
+
+#[cfg(all(test, feature = "query"))]
+mod tests {
+    use super::*;
+    use axum::extract::{FromRequestParts, Query};
+    use axum::http::request::Parts;
+    use axum::http::Request;
+
+    fn parts(uri: &str) -> anyhow::Result<Parts> {
+        Ok(Request::builder().uri(uri).body(())?.into_parts().0)
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[cfg_attr(feature = "validator", derive(validator::Validate))]
+    #[cfg_attr(feature = "garde", derive(garde::Validate))]
+    struct Parameters {
+        #[serde(deserialize_with = "comma_separated")]
+        #[cfg_attr(
+            feature = "validator",
+            validate(length(min = 1, max = 3), custom(function = "validate_tags"))
+        )]
+        #[cfg_attr(
+            feature = "garde",
+            garde(length(min = 1, max = 3), inner(length(min = 1, max = 5)))
+        )]
+        tags: Vec<String>,
+        #[serde(default, deserialize_with = "comma_separated")]
+        #[cfg_attr(feature = "garde", garde(skip))]
+        ids: Vec<u32>,
+    }
+
+    #[cfg(feature = "validator")]
+    fn validate_tags(tags: &[String]) -> Result<(), validator::ValidationError> {
+        if tags.iter().all(|tag| (1..=5).contains(&tag.len())) {
+            Ok(())
+        } else {
+            Err(validator::ValidationError::new("tag_length"))
+        }
+    }
+
+    #[tokio::test]
+    async fn comma_separated_query() -> anyhow::Result<()> {
+        let Query(parameters) =
+            Query::<Parameters>::from_request_parts(&mut parts("/?tags=a,bc,def&ids=1,2")?, &())
+                .await?;
+        assert_eq!(parameters.tags, ["a", "bc", "def"]);
+        assert_eq!(parameters.ids, [1, 2]);
+
+        let Query(parameters) =
+            Query::<Parameters>::from_request_parts(&mut parts("/?tags=")?, &()).await?;
+        assert!(parameters.tags.is_empty());
+        assert!(parameters.ids.is_empty());
+
+        assert!(
+            Query::<Parameters>::from_request_parts(&mut parts("/?tags=a&ids=1,x")?, &())
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn validate_comma_separated_elements() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+
+        assert!(
+            Valid::<Query<Parameters>>::from_request_parts(&mut parts("/?tags=a,b")?, &())
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            Valid::<Query<Parameters>>::from_request_parts(&mut parts("/?tags=a,toolong")?, &()).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["tags"][0].code == "tag_length"
+        ));
+        assert!(matches!(
+            Valid::<Query<Parameters>>::from_request_parts(&mut parts("/?tags=a,b,c,d")?, &()).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["tags"][0].code == "length"
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn empty_and_absent_are_distinguished() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::Validate;
+
+        #[derive(Debug, Deserialize, Validate)]
+        struct Filter {
+            #[serde(default, deserialize_with = "optional_comma_separated")]
+            #[validate(length(min = 1))]
+            tags: Option<Vec<String>>,
+        }
+
+        let Valid(Query(filter)) =
+            Valid::<Query<Filter>>::from_request_parts(&mut parts("/")?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(filter.tags, None);
+
+        let Valid(Query(filter)) =
+            Valid::<Query<Filter>>::from_request_parts(&mut parts("/?tags=a,b")?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(
+            filter.tags,
+            Some(vec![String::from("a"), String::from("b")])
+        );
+
+        // present but empty
+        let Query(filter) =
+            Query::<Filter>::from_request_parts(&mut parts("/?tags=")?, &()).await?;
+        assert_eq!(filter.tags, Some(vec![]));
+        assert!(matches!(
+            Valid::<Query<Filter>>::from_request_parts(&mut parts("/?tags=")?, &()).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["tags"][0].code == "length"
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn empty_and_absent_flags() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::{Validate, ValidationError};
+
+        fn yes() -> bool {
+            true
+        }
+
+        #[derive(Debug, Deserialize, Validate)]
+        #[validate(schema(function = "validate_flags"))]
+        struct Filter {
+            // absent means `false`
+            #[serde(default, deserialize_with = "flag")]
+            archived: bool,
+            // absent means `true`
+            #[serde(default = "yes", deserialize_with = "flag")]
+            notify: bool,
+            // absent is distinguished
+            #[serde(default, deserialize_with = "optional_flag")]
+            active: Option<bool>,
+        }
+
+        fn validate_flags(filter: &Filter) -> Result<(), ValidationError> {
+            if filter.archived && filter.active == Some(true) {
+                Err(ValidationError::new("archived_and_active"))
+            } else {
+                Ok(())
+            }
+        }
+
+        async fn extract(uri: &str) -> anyhow::Result<Filter> {
+            let Valid(Query(filter)) =
+                Valid::<Query<Filter>>::from_request_parts(&mut parts(uri)?, &())
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+            Ok(filter)
+        }
+
+        let filter = extract("/").await?;
+        assert_eq!(
+            (filter.archived, filter.notify, filter.active),
+            (false, true, None)
+        );
+        // present but empty
+        let filter = extract("/?archived&notify").await?;
+        assert_eq!(
+            (filter.archived, filter.notify, filter.active),
+            (true, true, None)
+        );
+        let filter = extract("/?active").await?;
+        assert_eq!(filter.active, Some(true));
+        let filter = extract("/?archived=&notify=off&active=0").await?;
+        assert_eq!(
+            (filter.archived, filter.notify, filter.active),
+            (true, false, Some(false))
+        );
+        let filter = extract("/?archived=No&notify=YES").await?;
+        assert_eq!((filter.archived, filter.notify), (false, true));
+
+        // flags are validated after deserialization
+        assert!(matches!(
+            Valid::<Query<Filter>>::from_request_parts(&mut parts("/?archived&active")?, &()).await,
+            Err(ValidRejection::Valid(errors)) if errors.errors().contains_key("__all__")
+        ));
+        assert!(matches!(
+            Valid::<Query<Filter>>::from_request_parts(&mut parts("/?archived=maybe")?, &()).await,
+            Err(ValidRejection::Inner(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(all(feature = "humantime", feature = "rules"))]
+    #[tokio::test]
+    async fn humantime_duration() -> anyhow::Result<()> {
+        use crate::{rules, Valid, ValidRejection};
+        use std::time::Duration;
+        use validator::{Validate, ValidationError};
+
+        #[derive(Debug, Deserialize, Validate)]
+        struct Timeout {
+            #[serde(deserialize_with = "humantime")]
+            #[validate(custom(function = "timeout_range"))]
+            timeout: Duration,
+        }
+
+        fn timeout_range(timeout: &Duration) -> Result<(), ValidationError> {
+            rules::duration_range(timeout, Duration::from_secs(1), Duration::from_secs(60))
+        }
+
+        let Valid(Query(timeout)) =
+            Valid::<Query<Timeout>>::from_request_parts(&mut parts("/?timeout=30s")?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(timeout.timeout, Duration::from_secs(30));
+
+        let Err(ValidRejection::Valid(errors)) =
+            Valid::<Query<Timeout>>::from_request_parts(&mut parts("/?timeout=2m")?, &()).await
+        else {
+            panic!("expected validation errors");
+        };
+        let error = &errors.field_errors()["timeout"][0];
+        assert_eq!(error.code, "range");
+        // validator reports the field itself as the `value` of a `custom` rule
+        assert_eq!(
+            error.params["value"],
+            serde_json::json!({"secs": 120, "nanos": 0})
+        );
+        assert!(matches!(
+            Valid::<Query<Timeout>>::from_request_parts(&mut parts("/?timeout=soon")?, &()).await,
+            Err(ValidRejection::Inner(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(all(feature = "base64", feature = "validator"))]
+    #[tokio::test]
+    async fn base64_binary() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::Validate;
+
+        #[derive(Debug, Deserialize, Validate)]
+        struct Upload {
+            #[serde(deserialize_with = "base64")]
+            #[validate(length(min = 1, max = 8))]
+            data: Vec<u8>,
+            #[serde(default, deserialize_with = "optional_base64")]
+            #[validate(length(max = 4))]
+            thumbnail: Option<Vec<u8>>,
+        }
+
+        let Valid(Query(upload)) =
+            Valid::<Query<Upload>>::from_request_parts(&mut parts("/?data=aGVsbG8%3D")?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(upload.data, b"hello");
+        assert_eq!(upload.thumbnail, None);
+
+        // malformed base64 is a deserialization error
+        let rejection =
+            Valid::<Query<Upload>>::from_request_parts(&mut parts("/?data=aGVsbG8*")?, &())
+                .await
+                .expect_err("malformed base64");
+        assert!(
+            matches!(rejection, ValidRejection::Inner(ref e) if e.body_text().contains("invalid base64"))
+        );
+
+        // oversized decoded data is a validation error
+        assert!(matches!(
+            Valid::<Query<Upload>>::from_request_parts(
+                &mut parts("/?data=aGVsbG8gd29ybGQ%3D")?,
+                &()
+            )
+            .await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["data"][0].code == "length"
+        ));
+        assert!(matches!(
+            Valid::<Query<Upload>>::from_request_parts(
+                &mut parts("/?data=aGVsbG8%3D&thumbnail=aGVsbG8%3D")?,
+                &()
+            )
+            .await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("thumbnail")
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn int_or_string_enum() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::{Validate, ValidationError};
+
+        #[derive(Debug, PartialEq, Deserialize, serde::Serialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Status {
+            Active,
+            Suspended,
+            Archived,
+        }
+
+        impl TryFrom<i64> for Status {
+            type Error = String;
+
+            fn try_from(value: i64) -> Result<Self, Self::Error> {
+                match value {
+                    1 => Ok(Status::Active),
+                    2 => Ok(Status::Suspended),
+                    3 => Ok(Status::Archived),
+                    _ => Err(format!("unknown status {value}")),
+                }
+            }
+        }
+
+        #[derive(Debug, Deserialize, Validate)]
+        struct Filter {
+            #[serde(deserialize_with = "int_or_string")]
+            #[validate(custom(function = "not_archived"))]
+            status: Status,
+        }
+
+        fn not_archived(status: &Status) -> Result<(), ValidationError> {
+            if *status == Status::Archived {
+                Err(ValidationError::new("archived"))
+            } else {
+                Ok(())
+            }
+        }
+
+        for uri in ["/?status=2", "/?status=suspended"] {
+            let Valid(Query(filter)) =
+                Valid::<Query<Filter>>::from_request_parts(&mut parts(uri)?, &())
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+            assert_eq!(filter.status, Status::Suspended, "{uri}");
+        }
+        for uri in ["/?status=3", "/?status=archived"] {
+            assert!(
+                matches!(
+                    Valid::<Query<Filter>>::from_request_parts(&mut parts(uri)?, &()).await,
+                    Err(ValidRejection::Valid(errors)) if errors.field_errors()["status"][0].code == "archived"
+                ),
+                "{uri}"
+            );
+        }
+        for uri in ["/?status=4", "/?status=deleted"] {
+            assert!(
+                matches!(
+                    Valid::<Query<Filter>>::from_request_parts(&mut parts(uri)?, &()).await,
+                    Err(ValidRejection::Inner(_))
+                ),
+                "{uri}"
+            );
+        }
+
+        // integers are also accepted as numbers
+        let filter = serde_json::from_str::<Filter>(r#"{"status": 1}"#)?;
+        assert_eq!(filter.status, Status::Active);
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn case_insensitive_enum() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::{Validate, ValidationError};
+
+        #[derive(Debug, PartialEq, Deserialize, serde::Serialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Status {
+            Active,
+            Suspended,
+            Archived,
+        }
+
+        #[derive(Debug, Deserialize, Validate)]
+        struct Filter {
+            #[serde(deserialize_with = "case_insensitive")]
+            #[validate(custom(function = "not_archived"))]
+            status: Status,
+            #[serde(default, deserialize_with = "optional_case_insensitive")]
+            previous: Option<Status>,
+        }
+
+        fn not_archived(status: &Status) -> Result<(), ValidationError> {
+            if *status == Status::Archived {
+                Err(ValidationError::new("archived"))
+            } else {
+                Ok(())
+            }
+        }
+
+        for uri in ["/?status=ACTIVE", "/?status=Active", "/?status=active"] {
+            let Valid(Query(filter)) =
+                Valid::<Query<Filter>>::from_request_parts(&mut parts(uri)?, &())
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+            assert_eq!(filter.status, Status::Active, "{uri}");
+            assert_eq!(filter.previous, None, "{uri}");
+        }
+        let Valid(Query(filter)) = Valid::<Query<Filter>>::from_request_parts(
+            &mut parts("/?status=active&previous=SUSPENDED")?,
+            &(),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(filter.previous, Some(Status::Suspended));
+
+        assert!(matches!(
+            Valid::<Query<Filter>>::from_request_parts(&mut parts("/?status=ARCHIVED")?, &()).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["status"][0].code == "archived"
+        ));
+        assert!(matches!(
+            Valid::<Query<Filter>>::from_request_parts(&mut parts("/?status=DELETED")?, &()).await,
+            Err(ValidRejection::Inner(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "garde")]
+    #[tokio::test]
+    async fn garde_comma_separated_elements() -> anyhow::Result<()> {
+        use crate::{Garde, GardeRejection};
+
+        assert!(
+            Garde::<Query<Parameters>>::from_request_parts(&mut parts("/?tags=a,b")?, &())
+                .await
+                .is_ok()
+        );
+        assert!(matches!(
+            Garde::<Query<Parameters>>::from_request_parts(&mut parts("/?tags=a,toolong")?, &()).await,
+            Err(GardeRejection::Valid(report)) if report.to_string().contains("tags[1]")
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn canonical_units() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::Validate;
+
+        #[derive(Debug, Deserialize, Validate)]
+        struct Parcel {
+            #[serde(deserialize_with = "grams")]
+            #[validate(range(min = 1.0, max = 1000.0))]
+            weight: f64,
+            #[serde(default, deserialize_with = "optional_millimeters")]
+            #[validate(range(max = 500.0))]
+            width: Option<f64>,
+        }
+
+        let Valid(Query(parcel)) =
+            Valid::<Query<Parcel>>::from_request_parts(&mut parts("/?weight=1kg")?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parcel.weight, 1000.0);
+        assert_eq!(parcel.width, None);
+
+        let Valid(Query(parcel)) = Valid::<Query<Parcel>>::from_request_parts(
+            &mut parts("/?weight=250%20G&width=12.5cm")?,
+            &(),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parcel.weight, 250.0);
+        assert_eq!(parcel.width, Some(125.0));
+
+        // converted before validation, `1.5kg` is out of the gram-based range
+        let Err(ValidRejection::Valid(errors)) =
+            Valid::<Query<Parcel>>::from_request_parts(&mut parts("/?weight=1.5kg")?, &()).await
+        else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(errors.field_errors()["weight"][0].code, "range");
+
+        for uri in ["/?weight=1furlong", "/?weight=kg", "/?weight=1kg&width=1lb"] {
+            assert!(matches!(
+                Valid::<Query<Parcel>>::from_request_parts(&mut parts(uri)?, &()).await,
+                Err(ValidRejection::Inner(_))
+            ));
+        }
+
+        let parcel = serde_json::from_str::<Parcel>(r#"{"weight": 2.5}"#)?;
+        assert_eq!(parcel.weight, 2.5);
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn seconds_with_module() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::Validate;
+
+        #[derive(Debug, Deserialize, serde::Serialize, Validate)]
+        struct Timeout {
+            #[serde(with = "seconds")]
+            #[validate(range(min = 1, max = 3600))]
+            timeout: u64,
+        }
+
+        for (uri, expected) in [
+            ("/?timeout=90s", 90),
+            ("/?timeout=1h", 3600),
+            ("/?timeout=1.5M", 90),
+        ] {
+            let Valid(Query(timeout)) =
+                Valid::<Query<Timeout>>::from_request_parts(&mut parts(uri)?, &())
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+            assert_eq!(timeout.timeout, expected);
+        }
+
+        // converted before validation, `61m` is out of the second-based range
+        let Err(ValidRejection::Valid(errors)) =
+            Valid::<Query<Timeout>>::from_request_parts(&mut parts("/?timeout=61m")?, &()).await
+        else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(errors.field_errors()["timeout"][0].code, "range");
+
+        for uri in ["/?timeout=1.5s", "/?timeout=-1s", "/?timeout=1w"] {
+            assert!(matches!(
+                Valid::<Query<Timeout>>::from_request_parts(&mut parts(uri)?, &()).await,
+                Err(ValidRejection::Inner(_))
+            ));
+        }
+
+        // serialized the same way, so that it round-trips
+        let timeout = serde_json::from_str::<Timeout>(r#"{"timeout": 120}"#)?;
+        assert_eq!(serde_json::to_string(&timeout)?, r#"{"timeout":"120s"}"#);
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+//! # Defaulted fields
+//!
+//! ## Feature
+//!
+//! Enable the `defaults` feature to use `ValidWithDefaults<T>`.
+//!
+//! Fields with a `#[serde(default)]` can be omitted by clients, but handlers sometimes need to tell
+//! an omitted field from one explicitly set to its default value, e.g. to only overwrite the settings
+//! chosen by the user. `ValidWithDefaults<T>` extracts a JSON body like `Json<T>`, validates it,
+//! and returns the fields of the data that were filled by defaults as `DefaultedFields`.
+//!
+//! The defaulted fields are those of the serialized data that are missing from the body.
+//! Nested objects sent by the client are compared too, and their defaulted fields are reported
+//! with dotted paths such as `theme.color`. The data is serialized to find its fields,
+//! so fields skipped by `#[serde(skip_serializing)]` are never reported.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::Router;
+//! use axum_valid::defaults::ValidWithDefaults;
+//! use serde::{Deserialize, Serialize};
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Serialize, Deserialize)]
+//! pub struct Settings {
+//!     #[validate(length(min = 1, max = 32))]
+//!     pub name: String,
+//!     #[serde(default)]
+//!     #[validate(range(max = 100))]
+//!     pub volume: u8,
+//! }
+//!
+//! async fn handler(ValidWithDefaults(settings, defaulted): ValidWithDefaults<Settings>) {
+//!     if !defaulted.contains("volume") {
+//!         println!("volume set to {}", settings.volume);
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/settings", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::{SerializedRejection, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, Request};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use validator::Validate;
+
+/// Paths of the fields filled by defaults, because they were missing from the body
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DefaultedFields(BTreeSet<String>);
+
+impl DefaultedFields {
+    /// Get the defaulted fields of the serialized `data`, deserialized from `body`
+    pub fn from_json(body: &Value, data: &Value) -> Self {
+        fn collect(body: &Value, data: &Value, prefix: &str, fields: &mut BTreeSet<String>) {
+            let (Value::Object(body), Value::Object(data)) = (body, data) else {
+                return;
+            };
+            for (name, value) in data {
+                let path = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{prefix}.{name}")
+                };
+                match body.get(name) {
+                    Some(sent) => collect(sent, value, &path, fields),
+                    None => {
+                        fields.insert(path);
+                    }
+                }
+            }
+        }
+
+        let mut fields = BTreeSet::new();
+        collect(body, data, "", &mut fields);
+        DefaultedFields(fields)
+    }
+
+    /// Check if a field was filled by its default
+    pub fn contains(&self, field: &str) -> bool {
+        self.0.contains(field)
+    }
+
+    /// Check if all the fields were provided by the client
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the defaulted fields, in order
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+/// # `ValidWithDefaults` data extractor
+///
+/// See the [module documentation](crate::defaults) for details.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ValidWithDefaults<T>(pub T, pub DefaultedFields);
+
+impl<T> ValidWithDefaults<T> {
+    /// Consumes the `ValidWithDefaults` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidWithDefaults`
+pub type DefaultsRejection = SerializedRejection;
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidWithDefaults<T>
+where
+    State: Send + Sync,
+    T: DeserializeOwned + Serialize + Validate,
+{
+    type Rejection = ValidRejection<DefaultsRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Json(e)))?;
+        let data = T::deserialize(&body)
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Deserialize(e)))?;
+        validate_redacted(&data).map_err(ValidRejection::Valid)?;
+        let serialized = serde_json::to_value(&data)
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Serialize(e)))?;
+        let defaulted = DefaultedFields::from_json(&body, &serialized);
+        Ok(ValidWithDefaults(data, defaulted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[derive(Debug, Default, serde::Deserialize, Serialize, Validate)]
+    struct Theme {
+        #[serde(default)]
+        dark: bool,
+        #[serde(default)]
+        color: String,
+    }
+
+    #[derive(Debug, serde::Deserialize, Serialize, Validate)]
+    struct Settings {
+        #[validate(length(min = 1, max = 32))]
+        name: String,
+        #[serde(default)]
+        #[validate(range(max = 100))]
+        volume: u8,
+        #[serde(default)]
+        theme: Theme,
+    }
+
+    async fn extract(
+        body: &'static str,
+    ) -> anyhow::Result<Result<ValidWithDefaults<Settings>, ValidRejection<DefaultsRejection>>>
+    {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        Ok(ValidWithDefaults::<Settings>::from_request(request, &()).await)
+    }
+
+    #[tokio::test]
+    async fn defaulted_fields_are_identified() -> anyhow::Result<()> {
+        let ValidWithDefaults(settings, defaulted) = extract(r#"{"name": "home"}"#)
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(settings.volume, 0);
+        assert_eq!(defaulted.iter().collect::<Vec<_>>(), ["theme", "volume"]);
+
+        // a field explicitly set to its default value is provided
+        let ValidWithDefaults(_, defaulted) =
+            extract(r#"{"name": "home", "volume": 0, "theme": {"dark": true}}"#)
+                .await?
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert!(!defaulted.contains("volume"));
+        assert!(!defaulted.contains("theme"));
+        assert!(!defaulted.contains("theme.dark"));
+        assert_eq!(defaulted.iter().collect::<Vec<_>>(), ["theme.color"]);
+
+        let ValidWithDefaults(_, defaulted) =
+            extract(r#"{"name": "home", "volume": 50, "theme": {"dark": false, "color": "blue"}}"#)
+                .await?
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert!(defaulted.is_empty());
+
+        assert!(matches!(
+            extract(r#"{"name": "home", "volume": 101}"#).await?,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("volume")
+        ));
+        assert!(matches!(
+            extract(r#"{"volume": 10}"#).await?,
+            Err(ValidRejection::Inner(SerializedRejection::Deserialize(_)))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+//! # Mutually dependent nested objects
+//!
+//! ## Feature
+//!
+//! Enable the `dependent` feature to use `ValidDependent<E>`.
+//!
+//! With `#[validate(nested)]`, a nested object is validated on its own, or with the validation arguments
+//! of its parent, so its rules can't depend on the data of its siblings, e.g. the billing details
+//! of an order requiring a VAT id only if the shipping address is in another country.
+//!
+//! Such nested objects declare the type of the sibling they depend on as their context,
+//! e.g. `#[validate(context = Shipping)]`, and are left without `#[validate(nested)]` in their parent.
+//! The parent implements `ValidateDependent`, passing each sibling to the nested object that depends on it
+//! with `nested_with`, which reports the errors of the nested object under its field, like `#[validate(nested)]`.
+//!
+//! `ValidDependent<E>` validates the inner data like `Valid<E>`, then validates its dependent nested objects,
+//! and rejects the request with the errors of both.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::dependent::{nested_with, ValidDependent, ValidateDependent};
+//! use serde::Deserialize;
+//! use validator::{Validate, ValidationError, ValidationErrors};
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Order {
+//!     #[validate(nested)]
+//!     pub shipping: Shipping,
+//!     pub billing: Billing,
+//! }
+//!
+//! #[derive(Validate, Deserialize)]
+//! #[validate(nested)]
+//! pub struct Shipping {
+//!     #[validate(length(equal = 2))]
+//!     pub country: String,
+//! }
+//!
+//! #[derive(Validate, Deserialize)]
+//! #[validate(context = Shipping, schema(function = "vat_id_abroad", use_context))]
+//! pub struct Billing {
+//!     pub country: String,
+//!     pub vat_id: Option<String>,
+//! }
+//!
+//! fn vat_id_abroad(billing: &Billing, shipping: &Shipping) -> Result<(), ValidationError> {
+//!     if billing.country != shipping.country && billing.vat_id.is_none() {
+//!         return Err(ValidationError::new("vat_id_required"));
+//!     }
+//!     Ok(())
+//! }
+//!
+//! impl ValidateDependent for Order {
+//!     fn validate_dependent(&self) -> Result<(), ValidationErrors> {
+//!         nested_with(Ok(()), "billing", &self.billing, &self.shipping)
+//!     }
+//! }
+//!
+//! async fn handler(ValidDependent(Json(order)): ValidDependent<Json<Order>>) {
+//!     println!("ship to {}", order.shipping.country);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/orders", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::redacting;
+use crate::{HasValidate, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use std::ops::{Deref, DerefMut};
+use validator::{Validate, ValidateArgs, ValidationErrors};
+
+/// Validation of nested objects depending on the data of their siblings
+pub trait ValidateDependent {
+    /// Validate the dependent nested objects, with `nested_with`
+    fn validate_dependent(&self) -> Result<(), ValidationErrors>;
+}
+
+/// Validate a nested object with the data of a sibling as its validation arguments
+///
+/// The errors of the nested object are merged into `parent` under `field`, like with `#[validate(nested)]`,
+/// so calls can be chained to validate several dependent nested objects.
+pub fn nested_with<'v, T>(
+    parent: Result<(), ValidationErrors>,
+    field: &'static str,
+    value: &'v T,
+    sibling: T::Args,
+) -> Result<(), ValidationErrors>
+where
+    T: ValidateArgs<'v>,
+{
+    ValidationErrors::merge(parent, field, value.validate_with_args(sibling))
+}
+
+/// # `ValidDependent` data extractor
+///
+/// See the [module documentation](crate::dependent) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidDependent<E>(pub E);
+
+impl<E> Deref for ValidDependent<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidDependent<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidDependent<E> {
+    /// Consumes the `ValidDependent` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+fn validate<T: Validate + ValidateDependent>(data: &T) -> Result<(), ValidationErrors> {
+    match redacting(|| (data.validate(), data.validate_dependent())) {
+        (Ok(()), Ok(())) => Ok(()),
+        (Err(errors), Ok(())) | (Ok(()), Err(errors)) => Err(errors),
+        (Err(mut errors), Err(dependent)) => {
+            errors.errors_mut().extend(dependent.into_errors());
+            Err(errors)
+        }
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidDependent<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequest<State>,
+    Extractor::Validate: Validate + ValidateDependent,
+{
+    type Rejection = ValidRejection<<Extractor as FromRequest<State>>::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(ValidRejection::Inner)?;
+        validate(inner.get_validate())?;
+        Ok(ValidDependent(inner))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequestParts<State> for ValidDependent<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequestParts<State>,
+    Extractor::Validate: Validate + ValidateDependent,
+{
+    type Rejection = ValidRejection<<Extractor as FromRequestParts<State>>::Rejection>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request_parts(parts, state)
+            .await
+            .map_err(ValidRejection::Inner)?;
+        validate(inner.get_validate())?;
+        Ok(ValidDependent(inner))
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::rejection::JsonRejection;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+    use validator::ValidationError;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Order {
+        #[validate(nested)]
+        shipping: Shipping,
+        billing: Billing,
+    }
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    #[validate(nested)]
+    struct Shipping {
+        #[validate(length(equal = 2))]
+        country: String,
+    }
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    #[validate(context = Shipping, schema(function = "vat_id_abroad", use_context))]
+    struct Billing {
+        country: String,
+        vat_id: Option<String>,
+    }
+
+    fn vat_id_abroad(billing: &Billing, shipping: &Shipping) -> Result<(), ValidationError> {
+        if billing.country != shipping.country && billing.vat_id.is_none() {
+            return Err(ValidationError::new("vat_id_required"));
+        }
+        Ok(())
+    }
+
+    impl ValidateDependent for Order {
+        fn validate_dependent(&self) -> Result<(), ValidationErrors> {
+            nested_with(Ok(()), "billing", &self.billing, &self.shipping)
+        }
+    }
+
+    async fn extract(body: &'static str) -> anyhow::Result<Option<ValidRejection<JsonRejection>>> {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        Ok(ValidDependent::<Json<Order>>::from_request(request, &())
+            .await
+            .err())
+    }
+
+    #[tokio::test]
+    async fn nested_rule_depends_on_sibling() -> anyhow::Result<()> {
+        // same country, no VAT id required
+        assert!(
+            extract(r#"{"shipping": {"country": "FR"}, "billing": {"country": "FR"}}"#)
+                .await?
+                .is_none()
+        );
+        assert!(extract(
+            r#"{"shipping": {"country": "FR"}, "billing": {"country": "DE", "vat_id": "DE123"}}"#
+        )
+        .await?
+        .is_none());
+
+        let Some(ValidRejection::Valid(errors)) =
+            extract(r#"{"shipping": {"country": "FR"}, "billing": {"country": "DE"}}"#).await?
+        else {
+            panic!("expected a missing VAT id");
+        };
+        let validator::ValidationErrorsKind::Struct(billing) = &errors.errors()["billing"] else {
+            panic!("expected nested errors");
+        };
+        assert_eq!(billing.errors().len(), 1);
+        let validator::ValidationErrorsKind::Field(schema) = &billing.errors()["__all__"] else {
+            panic!("expected a schema error");
+        };
+        assert_eq!(schema[0].code, "vat_id_required");
+
+        // the errors of the data and of the dependent nested objects are reported together
+        let Some(ValidRejection::Valid(errors)) =
+            extract(r#"{"shipping": {"country": "FRA"}, "billing": {"country": "DE"}}"#).await?
+        else {
+            panic!("expected validation errors");
+        };
+        assert!(errors.errors().contains_key("shipping"));
+        assert!(errors.errors().contains_key("billing"));
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+//! # Validation depth budget
+//!
+//! ## Feature
+//!
+//! Enable the `depth` feature to use `DepthGuarded<T, MAX_DEPTH>`.
+//!
+//! Recursive types such as trees are validated recursively, so deeply nested data can exhaust the stack,
+//! even if the JSON parser accepted it. `DepthGuarded<T, MAX_DEPTH>` wraps the recursive fields of such types,
+//! `Vec<T>` or `Box<T>` fields validated with `#[validate(nested)]`, and counts how many of them are nested
+//! in each other while validating. Beyond `MAX_DEPTH` levels, the nested data is not validated,
+//! and an error with the code `depth_exceeded` and the limit as its `max` parameter is reported for the field instead.
+//!
+//! `DepthGuarded<T, MAX_DEPTH>` is deserialized and serialized like `T`, and may be wrapped in an `Option`.
+//! The limit defaults to `DEFAULT_MAX_DEPTH`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::depth::DepthGuarded;
+//! use axum_valid::Valid;
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Node {
+//!     #[validate(length(min = 1, max = 32))]
+//!     pub name: String,
+//!     #[validate(nested)]
+//!     #[serde(default)]
+//!     pub children: DepthGuarded<Vec<Node>, 16>,
+//! }
+//!
+//! async fn handler(Valid(Json(tree)): Valid<Json<Node>>) {
+//!     assert!(tree.validate().is_ok());
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/tree", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{Deref, DerefMut};
+use validator::{
+    ValidateArgs, ValidateNested, ValidationError, ValidationErrors, ValidationErrorsKind,
+};
+
+/// Error code of data nested deeper than the validation depth budget
+pub const DEPTH_EXCEEDED: &str = "depth_exceeded";
+
+/// Default maximum validation depth of `DepthGuarded`
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+thread_local! {
+    // Validation is synchronous, so the depth is back to zero once the outermost `validate` returns
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Decrements the validation depth when dropped
+struct DepthGuard;
+
+impl DepthGuard {
+    /// Increment the validation depth, and return the guard with the new depth
+    fn enter() -> (Self, usize) {
+        let depth = DEPTH.with(|depth| {
+            depth.set(depth.get() + 1);
+            depth.get()
+        });
+        (DepthGuard, depth)
+    }
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+    }
+}
+
+/// # `DepthGuarded` field wrapper
+///
+/// See the [module documentation](crate::depth) for details.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DepthGuarded<T, const MAX_DEPTH: usize = DEFAULT_MAX_DEPTH>(pub T);
+
+impl<T, const MAX_DEPTH: usize> Deref for DepthGuarded<T, MAX_DEPTH> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, const MAX_DEPTH: usize> DerefMut for DepthGuarded<T, MAX_DEPTH> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T, const MAX_DEPTH: usize> DepthGuarded<T, MAX_DEPTH> {
+    /// Consumes the `DepthGuarded` and returns the data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    fn depth_exceeded(field_name: &'static str) -> ValidationErrors {
+        let mut error = ValidationError::new(DEPTH_EXCEEDED);
+        error.message = Some(Cow::from(format!(
+            "data is nested more than {MAX_DEPTH} levels deep"
+        )));
+        error.add_param(Cow::from("max"), &MAX_DEPTH);
+        let mut errors = ValidationErrors::new();
+        errors.add(field_name, error);
+        errors
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const MAX_DEPTH: usize> Deserialize<'de>
+    for DepthGuarded<T, MAX_DEPTH>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(DepthGuarded)
+    }
+}
+
+impl<T: Serialize, const MAX_DEPTH: usize> Serialize for DepthGuarded<T, MAX_DEPTH> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'v, T, const MAX_DEPTH: usize> ValidateNested<'v> for DepthGuarded<Vec<T>, MAX_DEPTH>
+where
+    T: ValidateArgs<'v>,
+    T::Args: Clone,
+{
+    type Args = T::Args;
+
+    fn validate_nested(
+        &self,
+        field_name: &'static str,
+        args: Self::Args,
+    ) -> Result<(), ValidationErrors> {
+        // empty sequences don't nest any data
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        let (_guard, depth) = DepthGuard::enter();
+        if depth > MAX_DEPTH {
+            return Err(Self::depth_exceeded(field_name));
+        }
+        let errors = self
+            .0
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let errors = item.validate_with_args(args.clone()).err()?;
+                Some((index, Box::new(errors)))
+            })
+            .collect::<BTreeMap<_, _>>();
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(ValidationErrors(HashMap::from([(
+            field_name,
+            ValidationErrorsKind::List(errors),
+        )])))
+    }
+}
+
+impl<'v, T, const MAX_DEPTH: usize> ValidateNested<'v> for DepthGuarded<Box<T>, MAX_DEPTH>
+where
+    T: ValidateArgs<'v>,
+{
+    type Args = T::Args;
+
+    fn validate_nested(
+        &self,
+        field_name: &'static str,
+        args: Self::Args,
+    ) -> Result<(), ValidationErrors> {
+        let (_guard, depth) = DepthGuard::enter();
+        if depth > MAX_DEPTH {
+            return Err(Self::depth_exceeded(field_name));
+        }
+        self.0.validate_with_args(args).map_err(|errors| {
+            ValidationErrors(HashMap::from([(
+                field_name,
+                ValidationErrorsKind::Struct(Box::new(errors)),
+            )]))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validator::Validate;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Node {
+        #[validate(length(min = 1))]
+        name: String,
+        #[validate(nested)]
+        #[serde(default)]
+        children: DepthGuarded<Vec<Node>, 3>,
+        #[validate(nested)]
+        #[serde(default)]
+        next: Option<DepthGuarded<Box<Node>, 3>>,
+    }
+
+    fn chain(depth: usize, nested: impl Fn(String) -> String) -> String {
+        (0..depth).fold(String::from(r#"{"name": "leaf"}"#), |child, _| {
+            nested(child)
+        })
+    }
+
+    #[test]
+    fn depth_budget() -> anyhow::Result<()> {
+        let children = |child| format!(r#"{{"name": "n", "children": [{child}]}}"#);
+        let next = |child| format!(r#"{{"name": "n", "next": {child}}}"#);
+
+        for nested in [&children as &dyn Fn(String) -> String, &next] {
+            let tree = serde_json::from_str::<Node>(&chain(3, nested))?;
+            assert!(tree.validate().is_ok());
+
+            let tree = serde_json::from_str::<Node>(&chain(4, nested))?;
+            let errors = tree.validate().expect_err("4 levels deep");
+            let json = serde_json::to_string(&errors)?;
+            assert!(json.contains(DEPTH_EXCEEDED), "{json}");
+            assert_eq!(DEPTH.with(Cell::get), 0);
+        }
+
+        // invalid data within the budget is still reported
+        let tree = serde_json::from_str::<Node>(&chain(2, |_| {
+            String::from(r#"{"name": "n", "children": [{"name": ""}]}"#)
+        }))?;
+        assert!(tree.validate().is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn reject_deep_json_tree() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use axum::body::Body;
+        use axum::extract::{FromRequest, Request};
+        use axum::http::header::CONTENT_TYPE;
+        use axum::Json;
+
+        let body = chain(10, |child| {
+            format!(r#"{{"name": "n", "children": [{{"name": "sibling"}}, {child}]}}"#)
+        });
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        let rejection = Valid::<Json<Node>>::from_request(request, &())
+            .await
+            .expect_err("too deep");
+        let ValidRejection::Valid(errors) = rejection else {
+            panic!("expected validation errors");
+        };
+        assert!(serde_json::to_string(&errors)?.contains(DEPTH_EXCEEDED));
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+//! # Diffs from a baseline
+//!
+//! ## Feature
+//!
+//! Enable the `diff` feature to use `ValidDiff<T>`.
+//!
+//! For updates of a whole resource, such as a configuration, handlers often need to know what changed.
+//! `ValidDiff<T>` extracts a JSON body like `Json<T>`, validates it, and compares it with the `Baseline<T>`
+//! provided by the state. The `Diff` lists the top-level fields whose serialized values differ from the baseline.
+//!
+//! The baseline is not validated, and no diff is computed if the submitted data is invalid.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::put;
+//! use axum::Router;
+//! use axum_valid::diff::{Baseline, ValidDiff};
+//! use serde::{Deserialize, Serialize};
+//! use validator::Validate;
+//!
+//! #[derive(Clone, Validate, Serialize, Deserialize)]
+//! pub struct Config {
+//!     #[validate(length(min = 1, max = 10))]
+//!     pub name: String,
+//!     #[validate(range(min = 1, max = 64))]
+//!     pub workers: u32,
+//! }
+//!
+//! async fn handler(ValidDiff(config, diff): ValidDiff<Config>) {
+//!     if let Some(change) = diff.get("workers") {
+//!         println!("workers: {} -> {}", change.old, change.new);
+//!     }
+//!     assert!(config.validate().is_ok());
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let baseline = Baseline(Config {
+//!     name: String::from("default"),
+//!     workers: 4,
+//! });
+//! let router = Router::new()
+//!     .route("/config", put(handler))
+//!     .with_state(baseline);
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::{SerializedRejection, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use validator::Validate;
+
+/// Baseline the data of `ValidDiff<T>` is compared with
+///
+/// It's provided by the state with `FromRef`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Baseline<T>(pub T);
+
+/// Change of a field
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// Serialized value of the baseline, `null` if the field is absent
+    pub old: Value,
+    /// Serialized value of the submitted data, `null` if the field is absent
+    pub new: Value,
+}
+
+/// Changed top-level fields, ordered by name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diff(BTreeMap<String, Change>);
+
+impl Diff {
+    /// Compare the serialized fields of two JSON values
+    ///
+    /// Values that are not objects are compared as a whole, under the empty field name.
+    pub fn from_json(old: &Value, new: &Value) -> Self {
+        let (Value::Object(old), Value::Object(new)) = (old, new) else {
+            let mut changes = BTreeMap::new();
+            if old != new {
+                changes.insert(
+                    String::new(),
+                    Change {
+                        old: old.clone(),
+                        new: new.clone(),
+                    },
+                );
+            }
+            return Diff(changes);
+        };
+        let changes = old
+            .keys()
+            .chain(new.keys())
+            .filter_map(|field| {
+                let old = old.get(field).unwrap_or(&Value::Null);
+                let new = new.get(field).unwrap_or(&Value::Null);
+                (old != new).then(|| {
+                    let change = Change {
+                        old: old.clone(),
+                        new: new.clone(),
+                    };
+                    (field.clone(), change)
+                })
+            })
+            .collect();
+        Diff(changes)
+    }
+
+    /// Get the change of a field
+    pub fn get(&self, field: &str) -> Option<&Change> {
+        self.0.get(field)
+    }
+
+    /// Check if a field has changed
+    pub fn contains(&self, field: &str) -> bool {
+        self.0.contains_key(field)
+    }
+
+    /// Check if nothing has changed
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the changed fields
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Change)> {
+        self.0
+            .iter()
+            .map(|(field, change)| (field.as_str(), change))
+    }
+}
+
+/// # `ValidDiff` data extractor
+///
+/// See the [module documentation](crate::diff) for details.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ValidDiff<T>(pub T, pub Diff);
+
+impl<T> ValidDiff<T> {
+    /// Consumes the `ValidDiff` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidDiff`
+pub type DiffRejection = SerializedRejection;
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidDiff<T>
+where
+    State: Send + Sync,
+    T: DeserializeOwned + Serialize + Validate,
+    Baseline<T>: FromRef<State>,
+{
+    type Rejection = ValidRejection<DiffRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let Json(data) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Json(e)))?;
+        validate_redacted(&data).map_err(ValidRejection::Valid)?;
+        let Baseline(baseline) = Baseline::<T>::from_ref(state);
+        let to_value = |value: &T| {
+            serde_json::to_value(value)
+                .map_err(|e| ValidRejection::Inner(SerializedRejection::Serialize(e)))
+        };
+        let diff = Diff::from_json(&to_value(&baseline)?, &to_value(&data)?);
+        Ok(ValidDiff(data, diff))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use serde_json::json;
+
+    #[derive(Debug, Clone, serde::Deserialize, Serialize, Validate)]
+    struct Config {
+        #[validate(length(min = 1, max = 10))]
+        name: String,
+        #[validate(range(min = 1, max = 64))]
+        workers: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        comment: Option<String>,
+    }
+
+    fn baseline() -> Baseline<Config> {
+        Baseline(Config {
+            name: String::from("default"),
+            workers: 4,
+            comment: Some(String::from("initial")),
+        })
+    }
+
+    fn request(body: &'static str) -> anyhow::Result<Request> {
+        Ok(Request::builder()
+            .method("PUT")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?)
+    }
+
+    #[tokio::test]
+    async fn diff_changed_fields() -> anyhow::Result<()> {
+        let ValidDiff(config, diff) = ValidDiff::<Config>::from_request(
+            request(r#"{"name": "default", "workers": 8}"#)?,
+            &baseline(),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(config.workers, 8);
+        assert!(!diff.contains("name"));
+        assert_eq!(
+            diff.get("workers"),
+            Some(&Change {
+                old: json!(4),
+                new: json!(8)
+            })
+        );
+        assert_eq!(
+            diff.get("comment"),
+            Some(&Change {
+                old: json!("initial"),
+                new: Value::Null
+            })
+        );
+        assert_eq!(
+            diff.iter().map(|(field, _)| field).collect::<Vec<_>>(),
+            ["comment", "workers"]
+        );
+
+        let ValidDiff(_, diff) = ValidDiff::<Config>::from_request(
+            request(r#"{"name": "default", "workers": 4, "comment": "initial"}"#)?,
+            &baseline(),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert!(diff.is_empty());
+
+        assert!(matches!(
+            ValidDiff::<Config>::from_request(
+                request(r#"{"name": "default", "workers": 0}"#)?,
+                &baseline()
+            )
+            .await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("workers")
+        ));
+        Ok(())
+    }
+}
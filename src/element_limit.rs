@@ -0,0 +1,290 @@
+//! # Support for `ElementLimitedQuery<T, MAX_ELEMENTS>`
+//!
+//! ## Feature
+//!
+//! Enable the `element_limit` feature to use `Valid<ElementLimitedQuery<T, MAX_ELEMENTS>>`.
+//!
+//! `length` rules bound each array field separately, and only after the whole query string was deserialized,
+//! so a query with many array fields, or with arrays of thousands of elements, is still parsed and allocated.
+//! `ElementLimitedQuery<T, MAX_ELEMENTS>` counts the elements of the query string before deserializing it,
+//! across all fields, and rejects queries with more than `MAX_ELEMENTS` elements with an `ErrorBody`
+//! with the code `too_many_elements`, the limit as its `max` parameter and the count as its `count` parameter.
+//! The limit defaults to `DEFAULT_MAX_ELEMENTS`.
+//!
+//! Each parameter counts as one element, or as one element per comma-separated value, e.g. `?tags=a,b,c&ids=1&ids=2`
+//! counts 5 elements, so both repeated keys and values deserialized with `de::comma_separated` are bounded.
+//! Otherwise, `ElementLimitedQuery<T, MAX_ELEMENTS>` works like `Query<T>`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::element_limit::ElementLimitedQuery;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/element_limit", get(handler))
+//!     }
+//!
+//!     async fn handler(
+//!         Valid(ElementLimitedQuery(parameter)): Valid<ElementLimitedQuery<Parameter, 100>>,
+//!     ) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(length(min = 1, max = 10))]
+//!         pub name: String,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::{ErrorBody, FieldError, FieldPath};
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "validator")]
+use validator::ValidateArgs;
+
+/// Error code of query strings with too many elements
+pub const TOO_MANY_ELEMENTS: &str = "too_many_elements";
+
+/// Default maximum number of elements of `ElementLimitedQuery`
+pub const DEFAULT_MAX_ELEMENTS: usize = 1000;
+
+/// # `ElementLimitedQuery` extractor
+///
+/// See the [module documentation](crate::element_limit) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ElementLimitedQuery<T, const MAX_ELEMENTS: usize = DEFAULT_MAX_ELEMENTS>(pub T);
+
+impl<T, const MAX_ELEMENTS: usize> Deref for ElementLimitedQuery<T, MAX_ELEMENTS> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, const MAX_ELEMENTS: usize> DerefMut for ElementLimitedQuery<T, MAX_ELEMENTS> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// `ElementLimitedQueryRejection` is returned when the `ElementLimitedQuery` extractor fails.
+#[derive(Debug)]
+pub enum ElementLimitedQueryRejection {
+    /// The query string contains too many elements
+    TooManyElements(ErrorBody),
+    /// The query string is rejected by `Query`
+    Query(QueryRejection),
+}
+
+impl Display for ElementLimitedQueryRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElementLimitedQueryRejection::TooManyElements(body) => {
+                let limit = body
+                    .errors
+                    .first()
+                    .and_then(|error| error.params.get("max"))
+                    .unwrap_or(&Value::Null);
+                write!(f, "The query string contains more than {limit} elements")
+            }
+            ElementLimitedQueryRejection::Query(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ElementLimitedQueryRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ElementLimitedQueryRejection::TooManyElements(_) => None,
+            ElementLimitedQueryRejection::Query(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for ElementLimitedQueryRejection {
+    fn into_response(self) -> Response {
+        match self {
+            ElementLimitedQueryRejection::TooManyElements(body) => body.into_response(),
+            ElementLimitedQueryRejection::Query(e) => e.into_response(),
+        }
+    }
+}
+
+/// Count the elements of a query string, one per parameter or per comma-separated value
+fn count_elements(query: &str) -> usize {
+    form_urlencoded::parse(query.as_bytes())
+        .map(|(_, value)| value.split(',').count())
+        .sum()
+}
+
+#[async_trait]
+impl<T, S, const MAX_ELEMENTS: usize> FromRequestParts<S> for ElementLimitedQuery<T, MAX_ELEMENTS>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ElementLimitedQueryRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let count = count_elements(parts.uri.query().unwrap_or_default());
+        if count > MAX_ELEMENTS {
+            let mut error = FieldError::new(FieldPath::new(), TOO_MANY_ELEMENTS);
+            error
+                .params
+                .insert(String::from("max"), Value::from(MAX_ELEMENTS));
+            error
+                .params
+                .insert(String::from("count"), Value::from(count));
+            return Err(ElementLimitedQueryRejection::TooManyElements(
+                ErrorBody::new(vec![error]),
+            ));
+        }
+        let Query(value) = Query::from_request_parts(parts, state)
+            .await
+            .map_err(ElementLimitedQueryRejection::Query)?;
+        Ok(ElementLimitedQuery(value))
+    }
+}
+
+impl<T, const MAX_ELEMENTS: usize> HasValidate for ElementLimitedQuery<T, MAX_ELEMENTS> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>, const MAX_ELEMENTS: usize> HasValidateArgs<'v>
+    for ElementLimitedQuery<T, MAX_ELEMENTS>
+{
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify, const MAX_ELEMENTS: usize> crate::HasModify
+    for ElementLimitedQuery<T, MAX_ELEMENTS>
+{
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T, const MAX_ELEMENTS: usize> crate::PayloadExtractor
+    for ElementLimitedQuery<T, MAX_ELEMENTS>
+{
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload, const MAX_ELEMENTS: usize>
+    crate::HasValidify for ElementLimitedQuery<T, MAX_ELEMENTS>
+{
+    type Validify = T;
+    type PayloadExtractor = ElementLimitedQuery<T::Payload, MAX_ELEMENTS>;
+    fn from_validify(v: Self::Validify) -> Self {
+        ElementLimitedQuery(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    #[test]
+    fn count_query_elements() {
+        assert_eq!(count_elements(""), 0);
+        assert_eq!(count_elements("name=a"), 1);
+        assert_eq!(count_elements("tags=a,b,c&ids=1&ids=2"), 5);
+        assert_eq!(count_elements("tags=a%2Cb"), 2);
+    }
+
+    #[cfg(feature = "de")]
+    #[tokio::test]
+    async fn reject_too_many_elements() -> anyhow::Result<()> {
+        #[derive(Debug, serde::Deserialize)]
+        struct Parameters {
+            #[serde(deserialize_with = "crate::de::comma_separated")]
+            tags: Vec<String>,
+            #[serde(deserialize_with = "crate::de::comma_separated")]
+            ids: Vec<u32>,
+        }
+
+        async fn extract(
+            uri: &str,
+        ) -> anyhow::Result<Result<Parameters, ElementLimitedQueryRejection>> {
+            let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+            Ok(
+                ElementLimitedQuery::<Parameters, 5>::from_request_parts(&mut parts, &())
+                    .await
+                    .map(|ElementLimitedQuery(parameters)| parameters),
+            )
+        }
+
+        let parameters = extract("/?tags=a,b,c&ids=1,2")
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.tags.len() + parameters.ids.len(), 5);
+
+        // each array is short, but there are too many elements in total
+        let rejection = extract("/?tags=a,b,c&ids=1,2,3")
+            .await?
+            .expect_err("too many elements");
+        let ElementLimitedQueryRejection::TooManyElements(body) = &rejection else {
+            panic!("expected a too many elements rejection");
+        };
+        assert_eq!(body.errors[0].code.as_deref(), Some(TOO_MANY_ELEMENTS));
+        assert_eq!(body.errors[0].params["max"], 5);
+        assert_eq!(body.errors[0].params["count"], 6);
+        assert_eq!(
+            rejection.into_response().status(),
+            crate::VALIDATION_ERROR_STATUS
+        );
+        Ok(())
+    }
+}
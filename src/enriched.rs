@@ -0,0 +1,206 @@
+//! # Server-computed fields
+//!
+//! ## Feature
+//!
+//! Enable the `enriched` feature to use `ValidEnriched<T>`.
+//!
+//! Resources stored or returned by a handler often hold fields computed by the server, e.g. `created_at`
+//! or the id of the author, which clients must not send and rules must not apply to.
+//! `ValidEnriched<T>` extracts a JSON body like `Json<T>` and validates it, then serializes the validated data
+//! to its canonical JSON and lets the `Enricher` provided by the state inject the server-computed fields.
+//! The handler receives both the validated data and the enriched JSON.
+//!
+//! Only the data sent by the client is validated: injected fields aren't, and they overwrite fields of the same name.
+//! The injector is applied to data serialized to a JSON object, other data is left unchanged.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::enriched::{Enricher, ValidEnriched};
+//! use serde::{Deserialize, Serialize};
+//! use serde_json::Value;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Serialize, Deserialize)]
+//! pub struct Comment {
+//!     #[validate(length(min = 1, max = 500))]
+//!     pub text: String,
+//! }
+//!
+//! async fn handler(ValidEnriched(_, comment): ValidEnriched<Comment>) -> Json<Value> {
+//!     Json(comment)
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let enricher = Enricher::new(|fields: &mut serde_json::Map<String, Value>| {
+//!     fields.insert("created_at".to_owned(), Value::from("2024-03-05T12:00:00Z"));
+//! });
+//! let router = Router::new()
+//!     .route("/comments", post(handler))
+//!     .with_state(enricher);
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::{SerializedRejection, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use validator::Validate;
+
+/// Injector of server-computed fields
+pub trait FieldInjector: Send + Sync {
+    /// Inject the server-computed fields into the fields of the validated data
+    fn inject(&self, fields: &mut Map<String, Value>);
+}
+
+impl<F> FieldInjector for F
+where
+    F: Fn(&mut Map<String, Value>) + Send + Sync,
+{
+    fn inject(&self, fields: &mut Map<String, Value>) {
+        self(fields)
+    }
+}
+
+/// Field injector provided by the state
+#[derive(Clone)]
+pub struct Enricher(Arc<dyn FieldInjector>);
+
+impl Enricher {
+    /// Create the enricher from an injector
+    pub fn new(injector: impl FieldInjector + 'static) -> Self {
+        Enricher(Arc::new(injector))
+    }
+
+    /// Inject the server-computed fields into `value`, if it's an object
+    pub fn enrich(&self, value: &mut Value) {
+        if let Value::Object(fields) = value {
+            self.0.inject(fields);
+        }
+    }
+}
+
+impl Debug for Enricher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Enricher").finish_non_exhaustive()
+    }
+}
+
+/// # `ValidEnriched` data extractor
+///
+/// See the [module documentation](crate::enriched) for details.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ValidEnriched<T>(pub T, pub Value);
+
+impl<T> ValidEnriched<T> {
+    /// Consumes the `ValidEnriched` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    /// Consumes the `ValidEnriched` and returns the canonical JSON with the server-computed fields.
+    pub fn into_enriched(self) -> Value {
+        self.1
+    }
+}
+
+/// Inner rejection of `ValidEnriched`
+pub type EnrichedRejection = SerializedRejection;
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidEnriched<T>
+where
+    State: Send + Sync,
+    Enricher: FromRef<State>,
+    T: DeserializeOwned + Serialize + Validate,
+{
+    type Rejection = ValidRejection<EnrichedRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let enricher = Enricher::from_ref(state);
+        let Json(body) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Json(e)))?;
+        let data = T::deserialize(&body)
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Deserialize(e)))?;
+        validate_redacted(&data).map_err(ValidRejection::Valid)?;
+        let mut enriched = serde_json::to_value(&data)
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Serialize(e)))?;
+        enricher.enrich(&mut enriched);
+        Ok(ValidEnriched(data, enriched))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[derive(Debug, serde::Deserialize, Serialize, Validate)]
+    struct Comment {
+        #[validate(length(min = 1, max = 10))]
+        text: String,
+    }
+
+    async fn extract(
+        body: &'static str,
+    ) -> anyhow::Result<Result<ValidEnriched<Comment>, ValidRejection<EnrichedRejection>>> {
+        let enricher = Enricher::new(|fields: &mut Map<String, Value>| {
+            fields.insert("created_at".to_owned(), Value::from("2024-03-05T12:00:00Z"));
+            fields.insert("author".to_owned(), Value::from(42));
+        });
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        Ok(ValidEnriched::<Comment>::from_request(request, &enricher).await)
+    }
+
+    #[tokio::test]
+    async fn server_computed_fields_are_injected() -> anyhow::Result<()> {
+        let ValidEnriched(comment, enriched) = extract(r#"{"text": "hello"}"#)
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(comment.text, "hello");
+        assert_eq!(
+            enriched,
+            serde_json::json!({"text": "hello", "created_at": "2024-03-05T12:00:00Z", "author": 42})
+        );
+
+        // fields sent by the client are dropped from the canonical JSON, or overwritten by the injector
+        let ValidEnriched(_, enriched) =
+            extract(r#"{"text": "hello", "author": 1, "admin": true}"#)
+                .await?
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(enriched["author"], 42);
+        assert!(enriched.get("admin").is_none());
+
+        // the data is validated before the fields are injected
+        assert!(matches!(
+            extract(r#"{"text": "a comment too long"}"#).await?,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("text")
+        ));
+        assert!(matches!(
+            extract(r#"{"created_at": "now"}"#).await?,
+            Err(ValidRejection::Inner(SerializedRejection::Deserialize(_)))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,1153 @@
+//! # Structured error body
+//!
+//! ## Feature
+//!
+//! Enable the `error_body` feature to use `ErrorBody`.
+//!
+//! `ErrorBody` is a backend-independent representation of validation errors.
+//! It flattens the (possibly nested) errors reported by validator, garde or validify
+//! into a list of `FieldError`s, each carrying the path of the invalid field,
+//! the error code, an optional message and the parameters of the failed rule.
+//! Parameters attached by custom validation functions are preserved as well, and can be read back
+//! with `FieldError::param`, e.g. the `limit` of a custom `too_many_tags` rule.
+//! Parameters keep their JSON types, e.g. the `min` and `max` of `length` and `range` are numbers, not strings,
+//! so that clients can compare them with the values.
+//! For `length` and `range` rules of validator and validify, the constraint and the checked value
+//! are also reported as `expected` and `actual`, e.g. `{"min": 5, "max": 10}` and `3`.
+//! garde doesn't expose rule parameters, so they are omitted for its errors.
+//! Paths are reported like `address.lines[1]`, use `ErrorBody::with_json_pointers`
+//! to also report them as JSON Pointers (RFC 6901) like `/address/lines/1`.
+//! `ErrorBody::redact` replaces the values carried by the errors of sensitive fields with `[REDACTED]`.
+//! The sensitive fields of a type are listed by its `SensitiveFields` implementation, which can be derived
+//! with the `derive` feature by marking the fields with `#[redact]`, see `ErrorBody::redact_sensitive`.
+//!
+//! With the `sse` feature, `ErrorBody` and validation rejections convert into a server-sent `Event`,
+//! so that errors can be reported on a stream without closing it.
+//!
+//! `ErrorBody` implements `IntoResponse`, it is returned as a JSON body with
+//! `VALIDATION_ERROR_STATUS` as the status code.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::extract::rejection::JsonRejection;
+//!     use axum::response::{IntoResponse, Response};
+//!     use axum::routing::post;
+//!     use axum::{Json, Router};
+//!     use axum_valid::{ErrorBody, Valid, ValidRejection};
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/json", post(handler))
+//!     }
+//!
+//!     async fn handler(
+//!         parameter: Result<Valid<Json<Parameter>>, ValidRejection<JsonRejection>>,
+//!     ) -> Response {
+//!         match parameter {
+//!             Ok(Valid(Json(parameter))) => parameter.v0.to_string().into_response(),
+//!             Err(ValidRejection::Valid(errors)) => ErrorBody::from(&errors).into_response(),
+//!             Err(ValidRejection::Inner(rejection)) => rejection.into_response(),
+//!         }
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(range(min = 5, max = 10))]
+//!         pub v0: i32,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! ## CSV
+//!
+//! `ErrorBody::to_csv` renders the errors as `field,code,message` rows, e.g. for pasting into spreadsheets.
+//! `ErrorFormat` selects between JSON and CSV, either explicitly or from the `Accept` request header:
+//!
+//! ```
+//! use axum::http::HeaderMap;
+//! use axum::response::Response;
+//! use axum_valid::error_body::{ErrorBody, ErrorFormat};
+//!
+//! fn render(body: ErrorBody, headers: &HeaderMap) -> Response {
+//!     body.into_response_as(ErrorFormat::negotiate(headers))
+//! }
+//! ```
+//!
+//! ## OpenAPI
+//!
+//! Enable the `utoipa` feature to register `ErrorBody` and `FieldError` as schemas
+//! with [`utoipa`](https://crates.io/crates/utoipa).
+//!
+
+#[cfg(feature = "sse")]
+use crate::ValidationRejection;
+use crate::VALIDATION_ERROR_STATUS;
+use axum::http::header::{ACCEPT, CONTENT_TYPE};
+use axum::http::HeaderMap;
+#[cfg(feature = "sse")]
+use axum::response::sse::Event;
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// A segment of a `FieldPath`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PathSegment {
+    /// A struct field or a map key
+    Key(String),
+    /// An index into a list
+    Index(usize),
+}
+
+/// Path of an invalid field, displayed and serialized as `address.lines[1]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FieldPath(Vec<PathSegment>);
+
+impl FieldPath {
+    /// Create an empty path, which refers to the validated value itself
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a key segment
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.0.push(PathSegment::Key(key.into()));
+        self
+    }
+
+    /// Append an index segment
+    pub fn index(mut self, index: usize) -> Self {
+        self.0.push(PathSegment::Index(index));
+        self
+    }
+
+    /// Get the segments of this path
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    /// Parse a path displayed like `address.lines[1]`
+    #[cfg(any(feature = "garde", feature = "i18n", feature = "redirect"))]
+    pub(crate) fn parse(path: &str) -> Self {
+        let mut segments = Vec::new();
+        for part in path.split('.').filter(|s| !s.is_empty()) {
+            let mut indexes = part.split('[');
+            if let Some(key) = indexes.next().filter(|key| !key.is_empty()) {
+                segments.push(PathSegment::Key(key.to_string()));
+            }
+            for index in indexes {
+                let index = index.trim_end_matches(']');
+                segments.push(match index.parse() {
+                    Ok(index) => PathSegment::Index(index),
+                    Err(_) => PathSegment::Key(index.to_string()),
+                });
+            }
+        }
+        FieldPath(segments)
+    }
+
+    /// Check if this path is `field` or one of its nested fields, e.g. `card.number` is within `card`
+    pub fn is_within(&self, field: &str) -> bool {
+        self.to_string()
+            .strip_prefix(field)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+    }
+
+    /// Format this path as a JSON Pointer (RFC 6901), e.g. `/address/lines/1`
+    ///
+    /// `~` and `/` in keys are escaped as `~0` and `~1`.
+    pub fn to_json_pointer(&self) -> String {
+        self.0
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Key(key) => format!("/{}", key.replace('~', "~0").replace('/', "~1")),
+                PathSegment::Index(index) => format!("/{index}"),
+            })
+            .collect()
+    }
+}
+
+impl Display for FieldPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if i == 0 => f.write_str(key)?,
+                PathSegment::Key(key) => write!(f, ".{key}")?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for FieldPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Placeholder replacing redacted values, see `ErrorBody::redact`
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Fields whose values must not appear in error output, see `ErrorBody::redact_sensitive`
+///
+/// With the `derive` feature, derive it and mark the sensitive fields with `#[redact]`.
+pub trait SensitiveFields {
+    /// Paths of the sensitive fields, such as `password` or `card.number`
+    const SENSITIVE_FIELDS: &'static [&'static str];
+}
+
+#[cfg(feature = "derive")]
+pub use axum_valid_derive::SensitiveFields;
+
+/// Parameters of `length` and `range` rules holding their constraint
+const BOUNDS: [&str; 5] = ["min", "max", "equal", "exclusive_min", "exclusive_max"];
+
+/// A single validation error of a field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[non_exhaustive]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    /// Path of the invalid field
+    #[cfg_attr(feature = "utoipa", schema(value_type = String, example = "address.zip"))]
+    pub path: FieldPath,
+    /// Path of the invalid field as a JSON Pointer, see `ErrorBody::with_json_pointers`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+    /// Code of the failed rule, garde doesn't provide error codes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Human readable message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Parameters of the failed rule
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    #[cfg_attr(feature = "utoipa", schema(value_type = Object))]
+    pub params: Map<String, Value>,
+    /// Constraint of the failed rule, e.g. `{"min": 1, "max": 10}` for `length` and `range`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "utoipa", schema(value_type = Option<Value>))]
+    pub expected: Option<Value>,
+    /// Value checked by the failed rule, e.g. the length of the field for `length`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "utoipa", schema(value_type = Option<Value>))]
+    pub actual: Option<Value>,
+}
+
+impl FieldError {
+    /// Create a field error with a path and a code
+    pub fn new(path: FieldPath, code: impl Into<String>) -> Self {
+        Self {
+            path,
+            pointer: None,
+            code: Some(code.into()),
+            message: None,
+            params: Map::new(),
+            expected: None,
+            actual: None,
+        }
+    }
+
+    /// Add a parameter, e.g. the `limit` of a custom rule
+    ///
+    /// Values which can't be serialized into JSON are ignored.
+    pub fn with_param(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        if let Ok(value) = serde_json::to_value(value) {
+            self.params.insert(name.into(), value);
+        }
+        self
+    }
+
+    /// Get a parameter, deserialized into `T`, or `None` if it's missing or of another type
+    pub fn param<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        self.params
+            .get(name)
+            .and_then(|value| T::deserialize(value).ok())
+    }
+
+    /// Fill `expected` and `actual` from the parameters of `length` and `range` rules
+    ///
+    /// Other rules don't have a well-known constraint, they are left unchanged.
+    pub fn with_expected_actual(mut self) -> Self {
+        let actual = match (self.code.as_deref(), self.params.get("value")) {
+            (Some("length"), Some(Value::String(value))) => Value::from(value.chars().count()),
+            (Some("length"), Some(Value::Array(value))) => Value::from(value.len()),
+            (Some("length"), Some(Value::Object(value))) => Value::from(value.len()),
+            (Some("range"), Some(value)) => value.clone(),
+            _ => return self,
+        };
+        let expected = BOUNDS
+            .iter()
+            .filter_map(|bound| {
+                self.params
+                    .get(*bound)
+                    .map(|value| (bound.to_string(), value.clone()))
+            })
+            .collect::<Map<_, _>>();
+        self.expected = Some(Value::Object(expected));
+        self.actual = Some(actual);
+        self
+    }
+
+    /// Replace everything that may carry the value of the field with `[REDACTED]`
+    ///
+    /// That's `actual`, the message, which custom rules may build from the value,
+    /// and all parameters but the bounds of `length` and `range`, e.g. `value`.
+    pub fn redact(&mut self) {
+        for (name, value) in &mut self.params {
+            if !BOUNDS.contains(&name.as_str()) {
+                *value = Value::from(REDACTED);
+            }
+        }
+        if let Some(actual) = &mut self.actual {
+            *actual = Value::from(REDACTED);
+        }
+        if let Some(message) = &mut self.message {
+            *message = REDACTED.to_owned();
+        }
+    }
+}
+
+/// # Structured validation error body
+///
+/// See the [module documentation](crate::error_body) for details.
+///
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[non_exhaustive]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ErrorBody {
+    /// Version of the error schema, echoed for clients pinning to a version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<String>,
+    /// Size of the request body in bytes, for debugging
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_size: Option<u64>,
+    /// Whether the request may succeed if retried unchanged, see `ErrorBody::with_retry_hint`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retryable: Option<bool>,
+    /// Suggested delay before retrying, in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+    /// Errors sorted by field path
+    pub errors: Vec<FieldError>,
+    /// Non-blocking errors, see `severity::Severities`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<FieldError>,
+}
+
+impl ErrorBody {
+    /// Create an error body from field errors, errors are sorted by their paths
+    pub fn new(mut errors: Vec<FieldError>) -> Self {
+        errors.sort_by(|a, b| a.path.cmp(&b.path));
+        Self {
+            schema_version: None,
+            body_size: None,
+            retryable: None,
+            retry_after: None,
+            errors,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Set the version of the error schema, it's included in the body as `schema_version`
+    pub fn with_schema_version(mut self, version: impl Into<String>) -> Self {
+        self.schema_version = Some(version.into());
+        self
+    }
+
+    /// Set the size of the request body, it's included in the body as `body_size`
+    pub fn with_body_size(mut self, size: u64) -> Self {
+        self.body_size = Some(size);
+        self
+    }
+
+    /// Mark the errors as transient, with a suggested delay before retrying
+    ///
+    /// The body includes `retryable: true` and the delay in seconds, rounded up, as `retry_after`.
+    pub fn with_retry_hint(mut self, delay: Duration) -> Self {
+        self.retryable = Some(true);
+        self.retry_after = Some(delay.as_secs() + u64::from(delay.subsec_nanos() > 0));
+        self
+    }
+
+    /// Redact the errors of `fields` and of their nested fields, see `FieldError::redact`
+    ///
+    /// Fields are given by their paths, e.g. `password` or `card.number`.
+    pub fn redact(mut self, fields: &[&str]) -> Self {
+        self.errors
+            .iter_mut()
+            .chain(&mut self.warnings)
+            .filter(|error| fields.iter().any(|field| error.path.is_within(field)))
+            .for_each(FieldError::redact);
+        self
+    }
+
+    /// Redact the errors of the `SensitiveFields` of `T`
+    pub fn redact_sensitive<T: SensitiveFields>(self) -> Self {
+        self.redact(T::SENSITIVE_FIELDS)
+    }
+
+    /// Include the path of each error and warning as a JSON Pointer (RFC 6901) in its `pointer` field
+    ///
+    /// This helps clients applying the errors to a document with JSON Patch, e.g. `/address/zip`.
+    pub fn with_json_pointers(mut self) -> Self {
+        for error in self.errors.iter_mut().chain(&mut self.warnings) {
+            error.pointer = Some(error.path.to_json_pointer());
+        }
+        self
+    }
+}
+
+impl ErrorBody {
+    /// Render the errors as CSV with a `field,code,message` header row
+    pub fn to_csv(&self) -> String {
+        fn escape(field: &str) -> String {
+            if field.contains([',', '"', '\r', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_owned()
+            }
+        }
+
+        let mut csv = String::from("field,code,message\r\n");
+        for error in &self.errors {
+            csv.push_str(&escape(&error.path.to_string()));
+            csv.push(',');
+            csv.push_str(&escape(error.code.as_deref().unwrap_or_default()));
+            csv.push(',');
+            csv.push_str(&escape(error.message.as_deref().unwrap_or_default()));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    /// Convert the errors into a response in the given format
+    pub fn into_response_as(self, format: ErrorFormat) -> Response {
+        match format {
+            ErrorFormat::Json => self.into_response(),
+            ErrorFormat::Csv => (
+                VALIDATION_ERROR_STATUS,
+                [(CONTENT_TYPE, "text/csv; charset=utf-8")],
+                self.to_csv(),
+            )
+                .into_response(),
+        }
+    }
+}
+
+impl IntoResponse for ErrorBody {
+    fn into_response(self) -> Response {
+        (VALIDATION_ERROR_STATUS, axum::Json(self)).into_response()
+    }
+}
+
+/// Formats one error per line, as `path: message`, or `path: code` for errors without a message
+impl Display for ErrorBody {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for error in &self.errors {
+            writeln!(
+                f,
+                "{}: {}",
+                error.path,
+                error
+                    .message
+                    .as_deref()
+                    .or(error.code.as_deref())
+                    .unwrap_or_default()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Name of the SSE events carrying validation errors
+#[cfg(feature = "sse")]
+pub const SSE_VALIDATION_ERROR_EVENT: &str = "validation_error";
+
+/// Name of the SSE events carrying inner extractor errors
+#[cfg(feature = "sse")]
+pub const SSE_ERROR_EVENT: &str = "error";
+
+#[cfg(feature = "sse")]
+impl ErrorBody {
+    /// Convert the errors into a `validation_error` SSE event with the JSON body as its data
+    ///
+    /// This reports validation errors in-band on a long-lived stream instead of closing it.
+    pub fn to_sse_event(&self) -> Event {
+        Event::default()
+            .event(SSE_VALIDATION_ERROR_EVENT)
+            .json_data(self)
+            .unwrap_or_else(|e| {
+                Event::default()
+                    .event(SSE_ERROR_EVENT)
+                    .data(e.to_string().replace('\r', ""))
+            })
+    }
+}
+
+#[cfg(feature = "sse")]
+impl From<ErrorBody> for Event {
+    fn from(value: ErrorBody) -> Self {
+        value.to_sse_event()
+    }
+}
+
+/// Validation errors are sent as `validation_error` events with an `ErrorBody` as data,
+/// inner extractor errors as `error` events with their message as data.
+#[cfg(feature = "sse")]
+impl<V, E> From<ValidationRejection<V, E>> for Event
+where
+    ErrorBody: From<V>,
+    E: Display,
+{
+    fn from(value: ValidationRejection<V, E>) -> Self {
+        match value {
+            ValidationRejection::Valid(errors) => ErrorBody::from(errors).to_sse_event(),
+            ValidationRejection::Inner(e) => Event::default()
+                .event(SSE_ERROR_EVENT)
+                .data(e.to_string().replace('\r', "")),
+        }
+    }
+}
+
+/// Response format of an `ErrorBody`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// `application/json`
+    #[default]
+    Json,
+    /// `text/csv`
+    Csv,
+}
+
+impl ErrorFormat {
+    /// Choose the format from the `Accept` request header
+    ///
+    /// CSV is chosen when `text/csv` is accepted with a higher quality than JSON, JSON otherwise.
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        let mut csv = 0.0f32;
+        let mut json = 0.0f32;
+        for value in headers.get_all(ACCEPT) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            for range in value.split(',') {
+                let mut parts = range.split(';');
+                let media_type = parts.next().unwrap_or_default().trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .and_then(|q| q.trim().parse().ok())
+                    .unwrap_or(1.0);
+                if media_type.eq_ignore_ascii_case("text/csv") {
+                    csv = csv.max(quality);
+                } else if media_type.eq_ignore_ascii_case("application/json") {
+                    json = json.max(quality);
+                }
+            }
+        }
+        if csv > json {
+            ErrorFormat::Csv
+        } else {
+            ErrorFormat::Json
+        }
+    }
+}
+
+#[cfg(feature = "validator")]
+impl From<&validator::ValidationErrors> for ErrorBody {
+    fn from(value: &validator::ValidationErrors) -> Self {
+        fn flatten(
+            errors: &validator::ValidationErrors,
+            path: &FieldPath,
+            output: &mut Vec<FieldError>,
+        ) {
+            use validator::ValidationErrorsKind;
+            for (field, kind) in errors.errors() {
+                let path = path.clone().key(*field);
+                match kind {
+                    ValidationErrorsKind::Struct(errors) => flatten(errors, &path, output),
+                    ValidationErrorsKind::List(list) => {
+                        for (index, errors) in list {
+                            flatten(errors, &path.clone().index(*index), output);
+                        }
+                    }
+                    ValidationErrorsKind::Field(errors) => {
+                        output.extend(errors.iter().map(|error| {
+                            FieldError {
+                                path: path.clone(),
+                                pointer: None,
+                                code: Some(error.code.to_string()),
+                                message: error.message.as_ref().map(ToString::to_string),
+                                params: error
+                                    .params
+                                    .iter()
+                                    .map(|(k, v)| (k.to_string(), v.clone()))
+                                    .collect(),
+                                expected: None,
+                                actual: None,
+                            }
+                            .with_expected_actual()
+                        }))
+                    }
+                }
+            }
+        }
+
+        let mut errors = Vec::new();
+        flatten(value, &FieldPath::new(), &mut errors);
+        ErrorBody::new(errors)
+    }
+}
+
+#[cfg(feature = "validator")]
+impl From<validator::ValidationErrors> for ErrorBody {
+    fn from(value: validator::ValidationErrors) -> Self {
+        ErrorBody::from(&value)
+    }
+}
+
+#[cfg(feature = "garde")]
+impl From<&garde::Report> for ErrorBody {
+    fn from(value: &garde::Report) -> Self {
+        ErrorBody::new(
+            value
+                .iter()
+                .map(|(path, error)| FieldError {
+                    // garde displays paths as `a.b[0].c`
+                    path: FieldPath::parse(&path.to_string()),
+                    pointer: None,
+                    code: None,
+                    message: Some(error.message().to_string()),
+                    params: Map::new(),
+                    expected: None,
+                    actual: None,
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(feature = "garde")]
+impl From<garde::Report> for ErrorBody {
+    fn from(value: garde::Report) -> Self {
+        ErrorBody::from(&value)
+    }
+}
+
+#[cfg(feature = "validify")]
+impl From<&validify::ValidationErrors> for ErrorBody {
+    fn from(value: &validify::ValidationErrors) -> Self {
+        ErrorBody::new(
+            value
+                .errors()
+                .iter()
+                .map(|error| {
+                    FieldError {
+                        // validify reports locations similar to JSON pointers, e.g. `/a/0/b`
+                        path: FieldPath(
+                            error
+                                .location()
+                                .split('/')
+                                .filter(|s| !s.is_empty())
+                                .map(|s| match s.parse() {
+                                    Ok(index) => PathSegment::Index(index),
+                                    Err(_) => PathSegment::Key(s.to_string()),
+                                })
+                                .collect(),
+                        ),
+                        pointer: None,
+                        code: Some(error.code()),
+                        message: error.message(),
+                        params: error
+                            .params()
+                            .into_iter()
+                            .map(|(k, v)| (k.to_string(), v))
+                            .collect(),
+                        expected: None,
+                        actual: None,
+                    }
+                    .with_expected_actual()
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(feature = "validify")]
+impl From<validify::ValidationErrors> for ErrorBody {
+    fn from(value: validify::ValidationErrors) -> Self {
+        ErrorBody::from(&value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_path_display() {
+        let path = FieldPath::new().key("address").key("lines").index(1);
+        assert_eq!(path.to_string(), "address.lines[1]");
+        assert_eq!(FieldPath::new().index(0).key("v0").to_string(), "[0].v0");
+        assert_eq!(
+            serde_json::to_value(&path).expect("serialize path"),
+            Value::from("address.lines[1]")
+        );
+    }
+
+    #[test]
+    fn error_body_display() {
+        let mut error = FieldError::new(FieldPath::new().key("name"), "length");
+        error.message = Some(String::from("too short"));
+        let body = ErrorBody::new(vec![
+            FieldError::new(FieldPath::new().key("tags").index(1), "unknown_tag"),
+            error,
+        ]);
+        assert_eq!(body.to_string(), "name: too short\ntags[1]: unknown_tag\n");
+    }
+
+    #[test]
+    fn field_path_is_within() {
+        let path = |keys: &[&str]| {
+            keys.iter()
+                .fold(FieldPath::new(), |path, key| path.key(*key))
+        };
+        assert!(path(&["card"]).is_within("card"));
+        assert!(path(&["card", "number"]).is_within("card"));
+        assert!(path(&["password"]).index(0).is_within("password"));
+        assert!(path(&["card", "number"]).is_within("card.number"));
+        assert!(!path(&["cardholder"]).is_within("card"));
+        assert!(!path(&["amount"]).is_within("card"));
+    }
+
+    #[test]
+    fn redact_errors() {
+        let error = |key: &str, code: &str| {
+            FieldError::new(FieldPath::new().key(key), code)
+                .with_param("min", 1000)
+                .with_param("max", 9999)
+                .with_param("value", 12345)
+                .with_param("hint", "12345")
+                .with_expected_actual()
+        };
+        let mut custom = error("code", "custom");
+        custom.message = Some(String::from("12345 is not a pin"));
+        let body = ErrorBody::new(vec![
+            error("pin", "range"),
+            error("amount", "range"),
+            custom,
+        ])
+        .redact(&["pin", "code"]);
+        let [amount, code, pin] = &body.errors[..] else {
+            panic!("expected three errors");
+        };
+        assert_eq!(pin.expected, amount.expected);
+        assert_eq!(pin.param::<u32>("max"), Some(9999));
+        assert_eq!(pin.param::<String>("value").as_deref(), Some(REDACTED));
+        assert_eq!(pin.param::<String>("hint").as_deref(), Some(REDACTED));
+        assert_eq!(pin.actual, Some(Value::from(REDACTED)));
+        assert_eq!(code.message.as_deref(), Some(REDACTED));
+        assert_eq!(amount.actual, Some(Value::from(12345)));
+        assert!(!serde_json::to_string(&[pin, code])
+            .expect("serialize errors")
+            .contains("12345"));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_sensitive_fields() {
+        #[derive(SensitiveFields)]
+        #[allow(dead_code)]
+        struct Login {
+            name: String,
+            #[redact]
+            password: String,
+            #[redact]
+            r#pin: u32,
+        }
+
+        assert_eq!(Login::SENSITIVE_FIELDS, ["password", "pin"]);
+        let body = ErrorBody::new(vec![
+            FieldError::new(FieldPath::new().key("name"), "length").with_param("value", "alice"),
+            FieldError::new(FieldPath::new().key("password"), "length")
+                .with_param("value", "hunter2"),
+        ])
+        .redact_sensitive::<Login>();
+        assert_eq!(
+            body.errors[0].param::<String>("value").as_deref(),
+            Some("alice")
+        );
+        assert_eq!(
+            body.errors[1].param::<String>("value").as_deref(),
+            Some(REDACTED)
+        );
+    }
+
+    #[cfg(feature = "validator")]
+    #[test]
+    fn from_validator_errors() {
+        use validator::Validate;
+
+        #[derive(Validate)]
+        #[validate(nested)]
+        struct Inner {
+            #[validate(length(min = 1))]
+            name: String,
+        }
+
+        #[derive(Validate)]
+        struct Outer {
+            #[validate(range(min = 5, max = 10))]
+            v0: i32,
+            #[validate(nested)]
+            inner: Inner,
+            #[validate(nested)]
+            list: Vec<Inner>,
+        }
+
+        let outer = Outer {
+            v0: 11,
+            inner: Inner {
+                name: String::new(),
+            },
+            list: vec![
+                Inner {
+                    name: String::from("ok"),
+                },
+                Inner {
+                    name: String::new(),
+                },
+            ],
+        };
+        let errors = outer.validate().expect_err("outer is invalid");
+        let body = ErrorBody::from(&errors);
+        let paths = body
+            .errors
+            .iter()
+            .map(|error| error.path.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["inner.name", "list[1].name", "v0"]);
+        let range = &body.errors[2];
+        assert_eq!(range.code.as_deref(), Some("range"));
+        assert_eq!(range.params.get("max"), Some(&Value::from(10)));
+    }
+
+    #[cfg(all(feature = "validator", feature = "query"))]
+    #[tokio::test]
+    async fn expected_actual_from_query() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use axum::extract::{FromRequestParts, Query};
+        use axum::http::Request;
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[validate(length(min = 5, max = 10))]
+            name: String,
+            #[validate(range(min = 1))]
+            page: u32,
+        }
+
+        let (mut parts, _) = Request::builder()
+            .uri("/?name=abc&page=0")
+            .body(())?
+            .into_parts();
+        let Err(ValidRejection::Valid(errors)) =
+            Valid::<Query<Parameters>>::from_request_parts(&mut parts, &()).await
+        else {
+            panic!("expected validation errors");
+        };
+        let body = serde_json::to_value(ErrorBody::from(errors))?;
+        let length = &body["errors"][0];
+        assert_eq!(length["path"], "name");
+        assert_eq!(length["expected"], serde_json::json!({"min": 5, "max": 10}));
+        assert_eq!(length["actual"], 3);
+        assert!(length["params"]["min"].is_u64());
+        assert!(length["params"]["max"].is_u64());
+        let range = &body["errors"][1];
+        assert_eq!(range["expected"], serde_json::json!({"min": 1}));
+        assert_eq!(range["actual"], 0);
+        assert!(range["params"]["min"].is_u64());
+        Ok(())
+    }
+
+    #[cfg(all(feature = "validator", feature = "json"))]
+    #[tokio::test]
+    async fn custom_validator_params() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use axum::body::Body;
+        use axum::extract::{FromRequest, Request};
+        use axum::http::header::CONTENT_TYPE;
+        use axum::Json;
+        use validator::{Validate, ValidationError};
+
+        const LIMIT: usize = 3;
+
+        fn validate_tags(tags: &[String]) -> Result<(), ValidationError> {
+            if tags.len() <= LIMIT {
+                return Ok(());
+            }
+            let mut error = ValidationError::new("too_many_tags");
+            error.add_param("limit".into(), &LIMIT);
+            Err(error)
+        }
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Post {
+            #[validate(custom(function = "validate_tags"))]
+            tags: Vec<String>,
+        }
+
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"tags": ["a", "b", "c", "d"]}"#))?;
+        let Err(ValidRejection::Valid(errors)) =
+            Valid::<Json<Post>>::from_request(request, &()).await
+        else {
+            panic!("tags should be invalid");
+        };
+
+        let error_body = ErrorBody::from(&errors);
+        assert_eq!(error_body.errors[0].param::<usize>("limit"), Some(LIMIT));
+        assert_eq!(error_body.errors[0].param::<String>("limit"), None);
+        assert_eq!(
+            error_body.errors[0].param::<Vec<String>>("value"),
+            Some(
+                vec!["a", "b", "c", "d"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            )
+        );
+
+        let response = error_body.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let body = serde_json::from_slice::<Value>(&body)?;
+        let error = &body["errors"][0];
+        assert_eq!(error["path"], "tags");
+        assert_eq!(error["code"], "too_many_tags");
+        assert_eq!(error["params"]["limit"], LIMIT);
+        Ok(())
+    }
+
+    #[test]
+    fn field_error_params() {
+        let error = FieldError::new(FieldPath::new().key("tags"), "too_many_tags")
+            .with_param("limit", 3)
+            .with_param("allowed", ["a", "b"]);
+        assert_eq!(error.param::<u32>("limit"), Some(3));
+        assert_eq!(
+            error.param::<Vec<String>>("allowed").map(|v| v.len()),
+            Some(2)
+        );
+        assert_eq!(error.param::<u32>("missing"), None);
+    }
+
+    #[test]
+    fn schema_version() -> anyhow::Result<()> {
+        let body = ErrorBody::new(vec![FieldError::new(FieldPath::new().key("v0"), "range")]);
+        assert!(serde_json::to_value(&body)?.get("schema_version").is_none());
+
+        let body = body.with_schema_version("2");
+        assert_eq!(
+            serde_json::to_value(&body)?,
+            serde_json::json!({
+                "schema_version": "2",
+                "errors": [{"path": "v0", "code": "range"}],
+            })
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[test]
+    fn json_pointers() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Validate)]
+        #[validate(nested)]
+        struct Address {
+            #[validate(length(equal = 5))]
+            zip: String,
+        }
+
+        #[derive(Validate)]
+        struct User {
+            #[validate(nested)]
+            addresses: Vec<Address>,
+        }
+
+        let user = User {
+            addresses: vec![Address {
+                zip: String::from("123"),
+            }],
+        };
+        let mut body = ErrorBody::from(user.validate().expect_err("zip is too short"));
+        body.warnings.push(FieldError::new(
+            FieldPath::new().key("addresses").index(0).key("city"),
+            "unknown_city",
+        ));
+        assert!(serde_json::to_value(&body)?["errors"][0]
+            .get("pointer")
+            .is_none());
+
+        let body = serde_json::to_value(body.with_json_pointers())?;
+        assert_eq!(body["errors"][0]["path"], "addresses[0].zip");
+        assert_eq!(body["errors"][0]["pointer"], "/addresses/0/zip");
+        assert_eq!(body["warnings"][0]["path"], "addresses[0].city");
+        assert_eq!(body["warnings"][0]["pointer"], "/addresses/0/city");
+
+        let path = FieldPath::new().key("a/b").key("m~n");
+        assert_eq!(path.to_json_pointer(), "/a~1b/m~0n");
+        assert_eq!(FieldPath::new().to_json_pointer(), "");
+        Ok(())
+    }
+
+    #[cfg(all(feature = "validator", feature = "sse"))]
+    #[tokio::test]
+    async fn sse_error_events() -> anyhow::Result<()> {
+        use crate::ValidRejection;
+        use axum::response::sse::Sse;
+        use std::convert::Infallible;
+        use validator::Validate;
+
+        #[derive(Validate)]
+        struct Parameters {
+            #[validate(range(max = 10))]
+            v0: i32,
+        }
+
+        async fn render(event: Event) -> anyhow::Result<String> {
+            let stream = futures_util::stream::once(async { Ok::<_, Infallible>(event) });
+            let response = Sse::new(stream).into_response();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+            Ok(String::from_utf8(body.to_vec())?)
+        }
+
+        let errors = Parameters { v0: 11 }
+            .validate()
+            .expect_err("v0 is too large");
+        let event = render(Event::from(ValidRejection::<String>::Valid(errors))).await?;
+        let mut lines = event.lines();
+        assert_eq!(lines.next(), Some("event: validation_error"));
+        let data = lines
+            .next()
+            .and_then(|line| line.strip_prefix("data: "))
+            .expect("data line");
+        let data = serde_json::from_str::<Value>(data)?;
+        assert_eq!(data["errors"][0]["path"], "v0");
+        assert_eq!(data["errors"][0]["code"], "range");
+
+        let event = render(Event::from(ValidRejection::Inner("bad\r\nrequest"))).await?;
+        assert_eq!(event, "event: error\ndata: bad\ndata: request\n\n");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn csv_errors() -> anyhow::Result<()> {
+        let mut range = FieldError::new(FieldPath::new().key("v0"), "range");
+        range.message = Some(String::from("must be between 5 and 10"));
+        let mut length =
+            FieldError::new(FieldPath::new().key("list").index(1).key("name"), "length");
+        length.message = Some(String::from("too short, \"name\" is required"));
+        let body = ErrorBody::new(vec![range, length]);
+        assert_eq!(
+            body.to_csv(),
+            "field,code,message\r\n\
+             list[1].name,length,\"too short, \"\"name\"\" is required\"\r\n\
+             v0,range,must be between 5 and 10\r\n"
+        );
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(ErrorFormat::negotiate(&headers), ErrorFormat::Json);
+        headers.insert(ACCEPT, "text/csv, application/json;q=0.5".parse()?);
+        assert_eq!(ErrorFormat::negotiate(&headers), ErrorFormat::Csv);
+        headers.insert(ACCEPT, "application/json, text/csv;q=0.5".parse()?);
+        assert_eq!(ErrorFormat::negotiate(&headers), ErrorFormat::Json);
+
+        let response = body.clone().into_response_as(ErrorFormat::Csv);
+        assert_eq!(response.status(), VALIDATION_ERROR_STATUS);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/csv; charset=utf-8");
+        let csv = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        assert_eq!(csv, body.to_csv());
+        Ok(())
+    }
+
+    #[cfg(feature = "garde")]
+    #[test]
+    fn from_garde_report() {
+        let mut report = garde::Report::new();
+        report.append(
+            garde::Path::new("list").join(1usize).join("name"),
+            garde::Error::new("length is lower than 1"),
+        );
+        let body = ErrorBody::from(&report);
+        assert_eq!(body.errors[0].path.to_string(), "list[1].name");
+        assert_eq!(body.errors[0].code, None);
+        assert_eq!(
+            body.errors[0].message.as_deref(),
+            Some("length is lower than 1")
+        );
+    }
+
+    #[cfg(feature = "validify")]
+    #[test]
+    fn from_validify_errors() {
+        let mut error = validify::ValidationError::new_field_named("name", "length");
+        error.set_location("name");
+        error.set_location_idx(1, "list");
+        let mut errors = validify::ValidationErrors::new();
+        errors.add(error);
+        let body = ErrorBody::from(&errors);
+        assert_eq!(body.errors[0].path.to_string(), "list[1].name");
+        assert_eq!(body.errors[0].code.as_deref(), Some("length"));
+    }
+
+    #[cfg(feature = "utoipa")]
+    #[test]
+    fn register_utoipa_schema() {
+        use utoipa::OpenApi;
+
+        #[derive(OpenApi)]
+        #[openapi(components(schemas(ErrorBody, FieldError)))]
+        struct ApiDoc;
+
+        let openapi = ApiDoc::openapi();
+        let schemas = openapi
+            .components
+            .expect("components are registered")
+            .schemas;
+        assert!(schemas.contains_key("ErrorBody"));
+        // `expected` and `actual` are numbers, strings or objects
+        let field_error = serde_json::to_value(&schemas["FieldError"]).expect("serialize schema");
+        for name in ["expected", "actual"] {
+            let property = &field_error["properties"][name];
+            assert!(
+                property.is_object() && property.get("type").is_none(),
+                "{name}: {property}"
+            );
+        }
+    }
+}
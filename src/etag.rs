@@ -0,0 +1,242 @@
+//! # ETags of validated data
+//!
+//! ## Feature
+//!
+//! Enable the `etag` feature to use `ValidEtag<T>` and `attach_etag`.
+//!
+//! Responses derived from a request payload, e.g. a rendered preview or a computed quote, can be cached
+//! by clients and proxies if they carry an `ETag`. `ValidEtag<T>` extracts a JSON body like `Json<T>`,
+//! validates it, and computes the `ETag` of the validated data from its content hash (see `hashed`),
+//! so that payloads only differing in their formatting or key order get the same strong `ETag`, e.g. `"0f3a…"`.
+//! Any other difference, such as surrounding whitespace in a string, changes the `ETag`.
+//!
+//! Add the `attach_etag` middleware with `axum::middleware::from_fn` to attach the `ETag` to the response:
+//! the middleware provides a slot in the request extensions, filled by `ValidEtag` after validation,
+//! and sets the `ETag` header of successful responses that don't already have one.
+//! Without the middleware, the `ETag` is still returned to the handler.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::middleware::from_fn;
+//! use axum::routing::post;
+//! use axum::Router;
+//! use axum_valid::etag::{attach_etag, ValidEtag};
+//! use serde::{Deserialize, Serialize};
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Serialize, Deserialize)]
+//! pub struct Quote {
+//!     #[validate(length(min = 1, max = 64))]
+//!     pub product: String,
+//!     #[validate(range(min = 1))]
+//!     pub quantity: u32,
+//! }
+//!
+//! async fn handler(ValidEtag(quote, _): ValidEtag<Quote>) -> String {
+//!     format!("{} x {}", quote.quantity, quote.product)
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new()
+//!     .route("/quotes", post(handler))
+//!     .layer(from_fn(attach_etag));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::hashed::ContentHash;
+use crate::validator::validate_redacted;
+use crate::{SerializedRejection, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, Request};
+use axum::http::header::ETAG;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+use validator::Validate;
+
+/// Strong entity tag of validated data
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag(ContentHash);
+
+impl ETag {
+    /// Get the `ETag` of a value, from the hash of its canonical JSON serialization
+    pub fn of<T: Serialize>(value: &T) -> Result<Self, serde_json::Error> {
+        ContentHash::of(value).map(ETag)
+    }
+
+    /// The value of the `ETag` header
+    pub fn header_value(&self) -> HeaderValue {
+        // 64 hexadecimal digits between quotes are always a valid header value
+        HeaderValue::from_str(&self.to_string()).unwrap_or(HeaderValue::from_static("\"\""))
+    }
+}
+
+/// Formats the `ETag` as its header value, the content hash between quotes
+impl Display for ETag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\"", self.0)
+    }
+}
+
+/// Slot of the `ETag` of the request, inserted in the request extensions by `attach_etag`
+#[derive(Debug, Clone, Default)]
+struct EtagSlot(Arc<Mutex<Option<ETag>>>);
+
+/// Middleware setting the `ETag` header of successful responses to the `ETag` computed by `ValidEtag`
+///
+/// Use it with `axum::middleware::from_fn`. Responses that already have an `ETag` header are left unchanged.
+pub async fn attach_etag(mut req: Request, next: Next) -> Response {
+    let slot = EtagSlot::default();
+    req.extensions_mut().insert(slot.clone());
+    let mut response = next.run(req).await;
+    let etag = slot
+        .0
+        .lock()
+        .ok()
+        .and_then(|mut etag| etag.take())
+        .filter(|_| response.status().is_success());
+    if let Some(etag) = etag {
+        response
+            .headers_mut()
+            .entry(ETAG)
+            .or_insert_with(|| etag.header_value());
+    }
+    response
+}
+
+/// # `ValidEtag` data extractor
+///
+/// See the [module documentation](crate::etag) for details.
+///
+#[derive(Debug, Clone)]
+pub struct ValidEtag<T>(pub T, pub ETag);
+
+impl<T> ValidEtag<T> {
+    /// Consumes the `ValidEtag` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidEtag`
+pub type EtagRejection = SerializedRejection;
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidEtag<T>
+where
+    State: Send + Sync,
+    T: DeserializeOwned + Serialize + Validate,
+{
+    type Rejection = ValidRejection<EtagRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let slot = req.extensions().get::<EtagSlot>().cloned();
+        let Json(data) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Json(e)))?;
+        validate_redacted(&data).map_err(ValidRejection::Valid)?;
+        let etag = ETag::of(&data)
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Serialize(e)))?;
+        if let Some(Ok(mut slot)) = slot.as_ref().map(|slot| slot.0.lock()) {
+            *slot = Some(etag.clone());
+        }
+        Ok(ValidEtag(data, etag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::middleware::from_fn;
+    use axum::routing::post;
+    use axum::Router;
+    use reqwest::Client;
+    use std::net::SocketAddr;
+    use tokio::net::TcpListener;
+
+    #[derive(Debug, serde::Deserialize, Serialize, Validate)]
+    struct Quote {
+        #[validate(length(min = 1, max = 64))]
+        product: String,
+        #[validate(range(min = 1))]
+        quantity: u32,
+    }
+
+    async fn handler(ValidEtag(quote, _): ValidEtag<Quote>) -> String {
+        format!("{} x {}", quote.quantity, quote.product)
+    }
+
+    #[tokio::test]
+    async fn etag_is_stable_for_identical_content() -> anyhow::Result<()> {
+        let router = Router::new()
+            .route("/quotes", post(handler))
+            .layer(from_fn(attach_etag));
+        let listener = TcpListener::bind(&SocketAddr::from(([127u8, 0, 0, 1], 0u16))).await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, router.into_make_service()).await;
+        });
+
+        let client = Client::new();
+        let url = format!("http://{addr}/quotes");
+        let post = |body: &'static str| {
+            client
+                .post(&url)
+                .header("content-type", "application/json")
+                .body(body)
+                .send()
+        };
+
+        let etag_of = |response: &reqwest::Response| {
+            response
+                .headers()
+                .get("etag")
+                .and_then(|etag| etag.to_str().ok())
+                .map(ToOwned::to_owned)
+        };
+
+        let response = post(r#"{"product": "apple", "quantity": 3}"#).await?;
+        assert_eq!(response.status().as_u16(), StatusCode::OK.as_u16());
+        let etag = etag_of(&response).expect("ETag header");
+        let quote = Quote {
+            product: "apple".to_owned(),
+            quantity: 3,
+        };
+        assert_eq!(etag, ETag::of(&quote)?.to_string());
+        assert_eq!(etag.len(), 66);
+
+        // identical content, spelled differently
+        let response = post(r#"{ "quantity": 3, "product": "apple" }"#).await?;
+        assert_eq!(etag_of(&response), Some(etag.clone()));
+
+        // different content, even if only by whitespace
+        let response = post(r#"{"product": " apple", "quantity": 3}"#).await?;
+        assert_ne!(etag_of(&response), Some(etag.clone()));
+
+        let response = post(r#"{"product": "apple", "quantity": 4}"#).await?;
+        assert_ne!(etag_of(&response), Some(etag));
+
+        // no `ETag` for invalid content
+        let response = post(r#"{"product": "apple", "quantity": 0}"#).await?;
+        assert_eq!(
+            response.status().as_u16(),
+            crate::VALIDATION_ERROR_STATUS.as_u16()
+        );
+        assert_eq!(etag_of(&response), None);
+        Ok(())
+    }
+}
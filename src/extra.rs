@@ -556,6 +556,7 @@ mod tests {
         const INVALID_STATUS_CODE: StatusCode = StatusCode::IM_A_TEAPOT;
         // If `WithRejection` is the outermost extractor,
         // the returned body may not be in JSON format.
+        #[cfg(feature = "into_json")]
         const JSON_SERIALIZABLE: bool = false;
 
         fn set_valid_request(builder: RequestBuilder) -> RequestBuilder {
@@ -581,6 +582,7 @@ mod tests {
         const INVALID_STATUS_CODE: StatusCode = StatusCode::IM_A_TEAPOT;
         // If `WithRejection` is the outermost extractor,
         // the returned body may not be in JSON format.
+        #[cfg(feature = "into_json")]
         const JSON_SERIALIZABLE: bool = false;
 
         fn set_valid_request(builder: RequestBuilder) -> RequestBuilder {
@@ -606,6 +608,7 @@ mod tests {
         const INVALID_STATUS_CODE: StatusCode = StatusCode::IM_A_TEAPOT;
         // If `WithRejection` is the outermost extractor,
         // the returned body may not be in JSON format.
+        #[cfg(feature = "into_json")]
         const JSON_SERIALIZABLE: bool = false;
 
         fn set_valid_request(builder: RequestBuilder) -> RequestBuilder {
@@ -631,6 +634,7 @@ mod tests {
         const INVALID_STATUS_CODE: StatusCode = StatusCode::OK;
         // If `WithRejection` is the outermost extractor,
         // the returned body may not be in JSON format.
+        #[cfg(feature = "into_json")]
         const JSON_SERIALIZABLE: bool = false;
 
         fn set_valid_request(builder: RequestBuilder) -> RequestBuilder {
@@ -656,6 +660,7 @@ mod tests {
         const INVALID_STATUS_CODE: StatusCode = StatusCode::IM_A_TEAPOT;
         // If `WithRejection` is the outermost extractor,
         // the returned body may not be in JSON format.
+        #[cfg(feature = "into_json")]
         const JSON_SERIALIZABLE: bool = false;
 
         fn set_valid_request(builder: RequestBuilder) -> RequestBuilder {
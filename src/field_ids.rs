@@ -0,0 +1,298 @@
+//! # Errors keyed by client field ids
+//!
+//! ## Feature
+//!
+//! Enable the `field_ids` feature to use `ValidFieldIds<T>`.
+//!
+//! Dynamic forms render fields whose ids, e.g. `f_12`, don't match the names of the fields in the payload.
+//! Such clients can send a `field_ids` member along with the data, mapping field paths to their ids:
+//!
+//! ```json
+//! {"email": "", "address": {"zip": "x"}, "field_ids": {"email": "f_12", "address": "f_13"}}
+//! ```
+//!
+//! `ValidFieldIds<T>` extracts a JSON body like `Json<T>`, without the `field_ids` member, and validates it.
+//! The paths of the errors are remapped to the ids provided by the client, e.g. `email` to `f_12`:
+//! a path is looked up in full first, e.g. `address.zip`, then by its first key, e.g. `address`, keeping
+//! the rest of the path, e.g. `f_13.zip`. Paths without an id are left unchanged.
+//!
+//! The errors are returned as an `ErrorBody`. A `field_ids` member that isn't an object of strings
+//! is rejected with `400 Bad Request`, data that can't be deserialized into `T` with `422 Unprocessable Entity`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::Router;
+//! use axum_valid::field_ids::ValidFieldIds;
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Contact {
+//!     #[validate(email)]
+//!     pub email: String,
+//! }
+//!
+//! async fn handler(ValidFieldIds(contact): ValidFieldIds<Contact>) -> String {
+//!     contact.email
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/contacts", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::{FieldPath, PathSegment};
+use crate::validator::validate_redacted;
+use crate::ErrorBody;
+use axum::async_trait;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use validator::Validate;
+
+/// Name of the member holding the field ids
+pub const FIELD_IDS: &str = "field_ids";
+
+/// Client-provided ids of fields, indexed by field path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldIds(pub HashMap<String, String>);
+
+impl FieldIds {
+    /// Remap the paths of the errors and warnings of a body to the field ids
+    pub fn apply(&self, mut body: ErrorBody) -> ErrorBody {
+        for error in body.errors.iter_mut().chain(body.warnings.iter_mut()) {
+            error.path = self.remap(&error.path);
+        }
+        body
+    }
+
+    /// Remap a path to its field id, or its first key to its field id
+    pub fn remap(&self, path: &FieldPath) -> FieldPath {
+        if let Some(id) = self.0.get(&path.to_string()) {
+            return FieldPath::new().key(id.clone());
+        }
+        let Some((PathSegment::Key(key), rest)) = path.segments().split_first() else {
+            return path.clone();
+        };
+        let Some(id) = self.0.get(key) else {
+            return path.clone();
+        };
+        rest.iter().fold(
+            FieldPath::new().key(id.clone()),
+            |path, segment| match segment {
+                PathSegment::Key(key) => path.key(key.clone()),
+                PathSegment::Index(index) => path.index(*index),
+            },
+        )
+    }
+}
+
+/// # `ValidFieldIds` data extractor
+///
+/// See the [module documentation](crate::field_ids) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidFieldIds<T>(pub T);
+
+impl<T> Deref for ValidFieldIds<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ValidFieldIds<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> ValidFieldIds<T> {
+    /// Consumes the `ValidFieldIds` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// `FieldIdsRejection` is returned when the `ValidFieldIds` extractor fails.
+#[derive(Debug)]
+pub enum FieldIdsRejection {
+    /// The body is rejected by `Json`
+    Json(JsonRejection),
+    /// The `field_ids` member isn't an object of strings
+    FieldIds(serde_json::Error),
+    /// The data can't be deserialized
+    Deserialize(serde_json::Error),
+    /// The data is invalid, the paths of the errors are remapped to the field ids
+    Valid(ErrorBody),
+}
+
+impl Display for FieldIdsRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldIdsRejection::Json(e) => write!(f, "{e}"),
+            FieldIdsRejection::FieldIds(e) => write!(f, "Invalid `{FIELD_IDS}`: {e}"),
+            FieldIdsRejection::Deserialize(e) => {
+                write!(
+                    f,
+                    "Failed to deserialize the JSON body into the target type: {e}"
+                )
+            }
+            FieldIdsRejection::Valid(body) => write!(f, "{} validation errors", body.errors.len()),
+        }
+    }
+}
+
+impl std::error::Error for FieldIdsRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FieldIdsRejection::Json(e) => Some(e),
+            FieldIdsRejection::FieldIds(e) => Some(e),
+            FieldIdsRejection::Deserialize(e) => Some(e),
+            FieldIdsRejection::Valid(_) => None,
+        }
+    }
+}
+
+impl IntoResponse for FieldIdsRejection {
+    fn into_response(self) -> Response {
+        match self {
+            FieldIdsRejection::Json(e) => e.into_response(),
+            e @ FieldIdsRejection::FieldIds(_) => {
+                (StatusCode::BAD_REQUEST, e.to_string()).into_response()
+            }
+            // Same status code as `JsonDataError`
+            e @ FieldIdsRejection::Deserialize(_) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response()
+            }
+            FieldIdsRejection::Valid(body) => body.into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidFieldIds<T>
+where
+    State: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = FieldIdsRejection;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let Json(mut value) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(FieldIdsRejection::Json)?;
+        let field_ids = match value
+            .as_object_mut()
+            .and_then(|data| data.remove(FIELD_IDS))
+        {
+            Some(ids) => {
+                FieldIds(serde_json::from_value(ids).map_err(FieldIdsRejection::FieldIds)?)
+            }
+            None => FieldIds::default(),
+        };
+        let data = serde_json::from_value::<T>(value).map_err(FieldIdsRejection::Deserialize)?;
+        match validate_redacted(&data) {
+            Ok(()) => Ok(ValidFieldIds(data)),
+            Err(errors) => Err(FieldIdsRejection::Valid(
+                field_ids.apply(ErrorBody::from(errors)),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    #[serde(deny_unknown_fields)]
+    struct Contact {
+        #[validate(length(min = 1))]
+        name: String,
+        #[validate(email)]
+        email: String,
+        #[validate(nested)]
+        address: Address,
+    }
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    #[serde(deny_unknown_fields)]
+    #[validate(nested)]
+    struct Address {
+        #[validate(length(equal = 5))]
+        zip: String,
+    }
+
+    async fn extract(body: &'static str) -> anyhow::Result<Result<Contact, FieldIdsRejection>> {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        Ok(ValidFieldIds::<Contact>::from_request(request, &())
+            .await
+            .map(ValidFieldIds::into_inner))
+    }
+
+    #[tokio::test]
+    async fn errors_are_keyed_by_field_ids() -> anyhow::Result<()> {
+        let contact = extract(
+            r#"{"name": "a", "email": "a@example.com", "address": {"zip": "12345"},
+                "field_ids": {"email": "f_12"}}"#,
+        )
+        .await?
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(contact.email, "a@example.com");
+
+        let rejection = extract(
+            r#"{"name": "", "email": "a", "address": {"zip": "1"},
+                "field_ids": {"email": "f_12", "address": "f_13"}}"#,
+        )
+        .await?
+        .expect_err("invalid contact");
+        let FieldIdsRejection::Valid(body) = rejection else {
+            panic!("expected validation errors");
+        };
+        let paths = body
+            .errors
+            .iter()
+            .map(|error| error.path.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["f_13.zip", "f_12", "name"]);
+
+        // without field ids, the paths are the field names
+        let Err(FieldIdsRejection::Valid(body)) =
+            extract(r#"{"name": "a", "email": "a", "address": {"zip": "12345"}}"#).await?
+        else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(body.errors[0].path.to_string(), "email");
+
+        let rejection = extract(
+            r#"{"name": "a", "email": "a", "address": {"zip": "12345"}, "field_ids": [1]}"#,
+        )
+        .await?
+        .expect_err("invalid field ids");
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
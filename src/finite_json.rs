@@ -0,0 +1,307 @@
+//! # Support for `FiniteJson<T>`
+//!
+//! ## Feature
+//!
+//! Enable the `finite_json` feature to use `Valid<FiniteJson<T>>`.
+//!
+//! JSON numbers can't be `NaN` or infinite, but some clients emit `NaN`, `Infinity` and `-Infinity` anyway,
+//! or numbers too large for an `f64` (e.g. `1e400`). `Json<T>` rejects the former with a generic syntax error,
+//! and the latter may be silently turned into an infinite value by some deserializers.
+//! `FiniteJson<T>` checks the body for non-finite numbers before deserializing it,
+//! and rejects them with an `ErrorBody` listing each offending field with the code `non_finite`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::Router;
+//!     use axum_valid::finite_json::FiniteJson;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/finite_json", post(handler))
+//!     }
+//!
+//!     async fn handler(Valid(FiniteJson(parameter)): Valid<FiniteJson<Parameter>>) {
+//!         assert!(parameter.validate().is_ok());
+//!         assert!(parameter.ratio.is_finite());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(range(min = 0.0, max = 1.0))]
+//!         pub ratio: f64,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::buffered::BufferedRequest;
+use crate::error_body::{ErrorBody, FieldError, FieldPath};
+use crate::json_scan::{Scanner, Token};
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::extract::rejection::{BytesRejection, JsonRejection};
+use axum::extract::{FromRequest, Request};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "validator")]
+use validator::ValidateArgs;
+
+/// Error code of non-finite numbers
+pub const NON_FINITE: &str = "non_finite";
+
+/// # `FiniteJson` extractor
+///
+/// See the [module documentation](crate::finite_json) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FiniteJson<T>(pub T);
+
+impl<T> Deref for FiniteJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for FiniteJson<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// `FiniteJsonRejection` is returned when the `FiniteJson` extractor fails.
+#[derive(Debug)]
+pub enum FiniteJsonRejection {
+    /// The body can't be read
+    Bytes(BytesRejection),
+    /// The body contains non-finite numbers
+    NonFinite(ErrorBody),
+    /// The body is rejected by `Json`
+    Json(JsonRejection),
+}
+
+impl Display for FiniteJsonRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FiniteJsonRejection::Bytes(e) => write!(f, "{e}"),
+            FiniteJsonRejection::NonFinite(body) => {
+                let paths = body
+                    .errors
+                    .iter()
+                    .map(|error| error.path.to_string())
+                    .collect::<Vec<_>>();
+                write!(f, "Non-finite numbers at: {}", paths.join(", "))
+            }
+            FiniteJsonRejection::Json(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for FiniteJsonRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FiniteJsonRejection::Bytes(e) => Some(e),
+            FiniteJsonRejection::NonFinite(_) => None,
+            FiniteJsonRejection::Json(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for FiniteJsonRejection {
+    fn into_response(self) -> Response {
+        match self {
+            FiniteJsonRejection::Bytes(e) => e.into_response(),
+            FiniteJsonRejection::NonFinite(body) => body.into_response(),
+            FiniteJsonRejection::Json(e) => e.into_response(),
+        }
+    }
+}
+
+/// Find the paths of non-finite numbers in a JSON document
+///
+/// Syntax errors are ignored, they are reported by the JSON deserializer afterwards.
+fn find_non_finite(json: &[u8]) -> Vec<FieldPath> {
+    let mut found = Vec::new();
+    let mut scanner = Scanner::new(json);
+    while let Some(token) = scanner.next() {
+        let Token::Scalar(token) = token else {
+            continue;
+        };
+        let token = std::str::from_utf8(token).unwrap_or_default();
+        let non_finite = match token {
+            "NaN" | "-NaN" | "Infinity" | "-Infinity" => true,
+            _ if token.starts_with(|c: char| c == '-' || c.is_ascii_digit()) => {
+                token.parse().is_ok_and(|number: f64| !number.is_finite())
+            }
+            _ => false,
+        };
+        if non_finite {
+            found.push(scanner.path());
+        }
+    }
+    found
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for FiniteJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = FiniteJsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let buffered = BufferedRequest::read(req, state)
+            .await
+            .map_err(FiniteJsonRejection::Bytes)?;
+        let non_finite = find_non_finite(&buffered.bytes);
+        if !non_finite.is_empty() {
+            return Err(FiniteJsonRejection::NonFinite(ErrorBody::new(
+                non_finite
+                    .into_iter()
+                    .map(|path| FieldError::new(path, NON_FINITE))
+                    .collect(),
+            )));
+        }
+        let Json(value) = Json::from_request(buffered.into_request(), state)
+            .await
+            .map_err(FiniteJsonRejection::Json)?;
+        Ok(FiniteJson(value))
+    }
+}
+
+impl<T> HasValidate for FiniteJson<T> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>> HasValidateArgs<'v> for FiniteJson<T> {
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify> crate::HasModify for FiniteJson<T> {
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T> crate::PayloadExtractor for FiniteJson<T> {
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload> crate::HasValidify for FiniteJson<T> {
+    type Validify = T;
+    type PayloadExtractor = FiniteJson<T::Payload>;
+    fn from_validify(v: Self::Validify) -> Self {
+        FiniteJson(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[test]
+    fn find_non_finite_numbers() {
+        let paths = |json: &str| {
+            find_non_finite(json.as_bytes())
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert!(paths(r#"{"a": 1.5, "b": [-2, 3e10], "c": "Infinity", "d": null}"#).is_empty());
+        assert_eq!(paths(r#"{"a": Infinity}"#), ["a"]);
+        assert_eq!(
+            paths(r#"{"a": {"b\"c": [1, -Infinity, NaN]}, "d": 1e400}"#),
+            ["a.b\"c[1]", "a.b\"c[2]", "d"]
+        );
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn reject_non_finite_before_validation() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[validate(range(min = 0.0, max = 1.0))]
+            ratio: f64,
+        }
+
+        let request = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+
+        let Valid(FiniteJson(parameters)) =
+            Valid::<FiniteJson<Parameters>>::from_request(request(r#"{"ratio": 0.5}"#)?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.ratio, 0.5);
+
+        let rejection =
+            Valid::<FiniteJson<Parameters>>::from_request(request(r#"{"ratio": Infinity}"#)?, &())
+                .await
+                .expect_err("infinity is rejected");
+        let ValidRejection::Inner(FiniteJsonRejection::NonFinite(body)) = &rejection else {
+            panic!("expected a non-finite rejection");
+        };
+        assert_eq!(body.errors[0].path.to_string(), "ratio");
+        assert_eq!(body.errors[0].code.as_deref(), Some(NON_FINITE));
+        assert_eq!(
+            rejection.into_response().status(),
+            crate::VALIDATION_ERROR_STATUS
+        );
+
+        assert!(matches!(
+            Valid::<FiniteJson<Parameters>>::from_request(request(r#"{"ratio": 2}"#)?, &()).await,
+            Err(ValidRejection::Valid(_))
+        ));
+        Ok(())
+    }
+}
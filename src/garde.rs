@@ -6,7 +6,7 @@
 //!
 
 #[cfg(test)]
-pub mod test;
+mod test;
 
 use crate::{HasValidate, ValidationRejection};
 use axum::async_trait;
@@ -23,6 +23,7 @@ use std::ops::{Deref, DerefMut};
 /// If not using arguments, its usage is similar to `Valid`. However, if your axum router uses a state, you need to implement `FromRef<StateType>` for `()`.
 ///
 /// If using arguments, you must pass the arguments to Garde extractor via state, meaning implementing `FromRef<StateType>` for your validation arguments type.
+/// The whole state can also be the context itself (`#[garde(context(AppState))]`), since axum implements `FromRef<S>` for any `S: Clone`.
 ///
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Garde<E>(pub E);
@@ -160,9 +161,48 @@ mod tests {
         assert!(matches!(vr.source(), Some(source) if source.downcast_ref::<Report>().is_some()));
 
         // GardeRejection::Valid Error
-        let vr = GardeRejection::<io::Error>::Inner(io::Error::new(io::ErrorKind::Other, GARDE));
+        let vr = GardeRejection::<io::Error>::Inner(io::Error::other(GARDE));
         assert!(
             matches!(vr.source(), Some(source) if source.downcast_ref::<io::Error>().is_some())
         );
     }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn state_as_validation_context() -> anyhow::Result<()> {
+        use axum::body::Body;
+        use axum::http::header::CONTENT_TYPE;
+        use axum::Json;
+
+        #[derive(Debug, Clone)]
+        struct AppState {
+            max_items: usize,
+        }
+
+        #[derive(Debug, Validate, serde::Deserialize)]
+        #[garde(context(AppState as state))]
+        struct Order {
+            #[garde(length(max = state.max_items))]
+            items: Vec<u32>,
+        }
+
+        let request = || {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"items": [1, 2, 3]}"#))
+        };
+
+        let Garde(Json(order)) =
+            Garde::<Json<Order>>::from_request(request()?, &AppState { max_items: 3 })
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(order.items, [1, 2, 3]);
+
+        assert!(matches!(
+            Garde::<Json<Order>>::from_request(request()?, &AppState { max_items: 2 }).await,
+            Err(GardeRejection::Valid(_))
+        ));
+        Ok(())
+    }
 }
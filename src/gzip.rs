@@ -0,0 +1,91 @@
+//! Gzip decompression of request bodies
+
+use flate2::read::MultiGzDecoder;
+use std::io::Read;
+
+/// Error of `decompress`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GzipError {
+    /// The data isn't valid gzip
+    Malformed,
+    /// The decompressed data is larger than the limit
+    TooLarge,
+}
+
+/// Decompress gzip data (RFC 1952), including concatenated members, up to `max` bytes
+pub(crate) fn decompress(data: &[u8], max: usize) -> Result<Vec<u8>, GzipError> {
+    if data.is_empty() {
+        return Err(GzipError::Malformed);
+    }
+    let mut output = Vec::new();
+    // one more byte than the limit tells whether it's exceeded, without decompressing the rest
+    MultiGzDecoder::new(data)
+        .take(max.saturating_add(1) as u64)
+        .read_to_end(&mut output)
+        .map_err(|_| GzipError::Malformed)?;
+    if output.len() > max {
+        return Err(GzipError::TooLarge);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `gzip.compress(b'{"name": "report", "tags": ["a", "b"]}', mtime=0)`, a fixed Huffman block
+    const FIXED: &[u8] = &[
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xab, 0x56, 0xca, 0x4b, 0xcc,
+        0x4d, 0x55, 0xb2, 0x52, 0x50, 0x2a, 0x4a, 0x2d, 0xc8, 0x2f, 0x2a, 0x51, 0xd2, 0x51, 0x50,
+        0x2a, 0x49, 0x4c, 0x2f, 0x06, 0x8a, 0x44, 0x2b, 0x25, 0x82, 0x78, 0x49, 0x4a, 0xb1, 0xb5,
+        0x00, 0xa4, 0x36, 0x8f, 0x0a, 0x26, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn decompress_blocks() {
+        let text = br#"{"name": "report", "tags": ["a", "b"]}"#;
+        assert_eq!(decompress(FIXED, usize::MAX).as_deref(), Ok(&text[..]));
+
+        // a stored block, in two concatenated members
+        let stored = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x03, 0x01, 0x03, 0x00, 0xfc,
+            0xff, b'a', b'b', b'c', 0xc2, 0x41, 0x24, 0x35, 0x03, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(
+            decompress(&[&stored[..], &stored[..]].concat(), usize::MAX).as_deref(),
+            Ok(&b"abcabc"[..])
+        );
+
+        // the limit applies to the decompressed size
+        assert_eq!(decompress(FIXED, text.len()).as_deref(), Ok(&text[..]));
+        assert_eq!(decompress(FIXED, text.len() - 1), Err(GzipError::TooLarge));
+
+        // truncated, corrupted and non-gzip data
+        assert_eq!(
+            decompress(&FIXED[..FIXED.len() - 1], usize::MAX),
+            Err(GzipError::Malformed)
+        );
+        let mut corrupted = FIXED.to_vec();
+        corrupted[20] ^= 0x01;
+        assert_eq!(
+            decompress(&corrupted, usize::MAX),
+            Err(GzipError::Malformed)
+        );
+        assert_eq!(decompress(text, usize::MAX), Err(GzipError::Malformed));
+    }
+
+    #[test]
+    fn decompress_dynamic_block() {
+        // `gzip.compress(b'{"name": "' + b'x' * 5000 + b'"}', mtime=0)`, a dynamic Huffman block
+        let data = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xed, 0xc1, 0x31, 0x0d,
+            0x00, 0x20, 0x10, 0x04, 0x30, 0x2b, 0xe4, 0x64, 0xe0, 0x86, 0x81, 0x11, 0xe6, 0x4f,
+            0x08, 0xde, 0x51, 0xc1, 0xd6, 0xf6, 0x64, 0x8f, 0x35, 0xd3, 0x5b, 0x0a, 0x00, 0x00,
+            0x00, 0x00, 0xf8, 0x2e, 0xf7, 0x01, 0xd8, 0x7a, 0x6a, 0xf5, 0x94, 0x13, 0x00, 0x00,
+        ];
+        let text = decompress(&data, usize::MAX).expect("valid gzip");
+        assert_eq!(text.len(), 5012);
+        assert!(text.starts_with(br#"{"name": "xxx"#) && text.ends_with(br#"xxx"}"#));
+        assert_eq!(decompress(&data, 5000), Err(GzipError::TooLarge));
+    }
+}
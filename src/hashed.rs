@@ -0,0 +1,177 @@
+//! # Content hashes of validated data
+//!
+//! ## Feature
+//!
+//! Enable the `hashed` feature to use `ValidHashed<T>`.
+//!
+//! For idempotent ingestion, handlers need to recognize payloads they have already processed.
+//! `ValidHashed<T>` extracts a JSON body like `Json<T>`, validates it, and computes the `ContentHash`
+//! of the validated data, which can be used as a deduplication key.
+//!
+//! The hash is computed from the canonical JSON serialization of the data, with the keys of objects sorted,
+//! so it doesn't depend on the whitespace or key order of the request body, and is stable across builds.
+//! It's a SHA-256 hash, so clients can't craft payloads colliding with the payloads of other clients.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::Router;
+//! use axum_valid::hashed::ValidHashed;
+//! use serde::{Deserialize, Serialize};
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Serialize, Deserialize)]
+//! pub struct Event {
+//!     #[validate(length(min = 1, max = 64))]
+//!     pub source: String,
+//!     pub value: i64,
+//! }
+//!
+//! async fn handler(ValidHashed(event, hash): ValidHashed<Event>) {
+//!     println!("event {hash} from {}", event.source);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/events", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::canonical;
+use crate::validator::validate_redacted;
+use crate::{SerializedRejection, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, Request};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
+use validator::Validate;
+
+/// SHA-256 content hash of validated data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContentHash([u8; 32]);
+
+impl ContentHash {
+    /// Hash bytes with SHA-256
+    pub fn of_bytes(bytes: &[u8]) -> Self {
+        ContentHash(Sha256::digest(bytes).into())
+    }
+
+    /// Hash the canonical JSON serialization of a value
+    pub fn of<T: Serialize>(value: &T) -> Result<Self, serde_json::Error> {
+        Ok(Self::of_bytes(&canonical::to_vec(value)?))
+    }
+
+    /// The hash as bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// Formats the hash as 64 lowercase hexadecimal digits
+impl Display for ContentHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+/// # `ValidHashed` data extractor
+///
+/// See the [module documentation](crate::hashed) for details.
+///
+#[derive(Debug, Clone)]
+pub struct ValidHashed<T>(pub T, pub ContentHash);
+
+impl<T> ValidHashed<T> {
+    /// Consumes the `ValidHashed` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidHashed`
+pub type HashedRejection = SerializedRejection;
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidHashed<T>
+where
+    State: Send + Sync,
+    T: DeserializeOwned + Serialize + Validate,
+{
+    type Rejection = ValidRejection<HashedRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let Json(data) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Json(e)))?;
+        validate_redacted(&data).map_err(ValidRejection::Valid)?;
+        let hash = ContentHash::of(&data)
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Serialize(e)))?;
+        Ok(ValidHashed(data, hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use std::collections::HashMap;
+
+    #[derive(Debug, serde::Deserialize, Serialize, Validate)]
+    struct Event {
+        #[validate(length(min = 1, max = 64))]
+        source: String,
+        value: i64,
+        #[serde(default)]
+        labels: HashMap<String, String>,
+    }
+
+    async fn hash(body: &'static str) -> anyhow::Result<ContentHash> {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        let ValidHashed(_, hash) = ValidHashed::<Event>::from_request(request, &())
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        Ok(hash)
+    }
+
+    #[test]
+    fn sha256_test_vectors() {
+        assert_eq!(
+            ContentHash::of_bytes(b"").to_string(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            ContentHash::of_bytes(b"abc").to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn identical_payloads_have_identical_hashes() -> anyhow::Result<()> {
+        let hash0 = hash(r#"{"source": "a", "value": 1, "labels": {"x": "1", "y": "2"}}"#).await?;
+        let hash1 =
+            hash(r#"{ "labels": {"y": "2", "x": "1"}, "value": 1, "source": "a" }"#).await?;
+        assert_eq!(hash0, hash1);
+
+        let hash2 = hash(r#"{"source": "a", "value": 2, "labels": {"x": "1", "y": "2"}}"#).await?;
+        assert_ne!(hash0, hash2);
+        let hash3 = hash(r#"{"source": "a", "value": 1}"#).await?;
+        assert_ne!(hash0, hash3);
+
+        assert!(hash(r#"{"source": "", "value": 1}"#).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+//! # Validation of `HEAD` requests
+//!
+//! ## Feature
+//!
+//! Enable the `head` feature to use `ValidHead<E>`.
+//!
+//! Responses to `HEAD` requests must not have a body, yet validation failures are rendered with one.
+//! `ValidHead<E>` validates the inner data like `Valid<E>`, and applies the `HeadPolicy` provided by the state
+//! to `HEAD` requests, so that each router, given its own state, can choose how to answer them:
+//!
+//! * `Validate`, the default, rejects invalid data with the status code and headers of a `GET` request,
+//!   including its `Content-Length`, but without a body.
+//! * `Status(status)` rejects invalid data with `status` instead, without a body either.
+//! * `Skip` doesn't validate `HEAD` requests at all, e.g. when their handlers only check that a resource exists.
+//!
+//! Requests with other methods are validated and rejected like with `Valid<E>`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::extract::Query;
+//! use axum::http::StatusCode;
+//! use axum::routing::get;
+//! use axum::Router;
+//! use axum_valid::head::{HeadPolicy, ValidHead};
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Page {
+//!     #[validate(range(min = 1, max = 100))]
+//!     pub size: u32,
+//! }
+//!
+//! async fn handler(ValidHead(Query(page)): ValidHead<Query<Page>>) -> String {
+//!     page.size.to_string()
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new()
+//!     .route("/items", get(handler))
+//!     .with_state(HeadPolicy::Status(StatusCode::BAD_REQUEST));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::{HasValidate, ValidRejection};
+use axum::async_trait;
+use axum::body::{Body, HttpBody};
+use axum::extract::{FromRef, FromRequest, FromRequestParts, Request};
+use axum::http::header::CONTENT_LENGTH;
+use axum::http::request::Parts;
+use axum::http::{Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use validator::{Validate, ValidationErrors};
+
+/// How `ValidHead` answers `HEAD` requests
+///
+/// It's provided by the state with `FromRef`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeadPolicy {
+    /// Reject invalid data with the status code of other methods, without a body
+    #[default]
+    Validate,
+    /// Reject invalid data with this status code, without a body
+    Status(StatusCode),
+    /// Don't validate the data
+    Skip,
+}
+
+/// # `ValidHead` data extractor
+///
+/// See the [module documentation](crate::head) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidHead<E>(pub E);
+
+impl<E> Deref for ValidHead<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidHead<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidHead<E> {
+    /// Consumes the `ValidHead` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+/// `HeadRejection` is returned when the `ValidHead` extractor fails.
+#[derive(Debug)]
+pub enum HeadRejection<E> {
+    /// The data is invalid
+    Valid(ValidationErrors),
+    /// The data of a `HEAD` request is invalid, it's rejected with the status code, if any, and without a body
+    Head(Option<StatusCode>, ValidationErrors),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for HeadRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeadRejection::Valid(errors) | HeadRejection::Head(_, errors) => write!(f, "{errors}"),
+            HeadRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for HeadRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HeadRejection::Valid(errors) | HeadRejection::Head(_, errors) => Some(errors),
+            HeadRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for HeadRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            HeadRejection::Valid(errors) => ValidRejection::<E>::Valid(errors).into_response(),
+            HeadRejection::Head(status, errors) => {
+                // the headers of the full response are kept, including the length of its body
+                let (mut parts, body) = ValidRejection::<E>::Valid(errors)
+                    .into_response()
+                    .into_parts();
+                if let Some(length) = body.size_hint().exact() {
+                    parts.headers.entry(CONTENT_LENGTH).or_insert(length.into());
+                }
+                if let Some(status) = status {
+                    parts.status = status;
+                }
+                Response::from_parts(parts, Body::empty())
+            }
+            HeadRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+fn validate<E>(
+    data: &impl Validate,
+    method: &Method,
+    policy: HeadPolicy,
+) -> Result<(), HeadRejection<E>> {
+    if method != Method::HEAD {
+        return validate_redacted(data).map_err(HeadRejection::Valid);
+    }
+    match policy {
+        HeadPolicy::Validate => {
+            validate_redacted(data).map_err(|errors| HeadRejection::Head(None, errors))
+        }
+        HeadPolicy::Status(status) => {
+            validate_redacted(data).map_err(|errors| HeadRejection::Head(Some(status), errors))
+        }
+        HeadPolicy::Skip => Ok(()),
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidHead<Extractor>
+where
+    State: Send + Sync,
+    HeadPolicy: FromRef<State>,
+    Extractor: HasValidate + FromRequest<State>,
+    Extractor::Validate: Validate,
+{
+    type Rejection = HeadRejection<<Extractor as FromRequest<State>>::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let method = req.method().clone();
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(HeadRejection::Inner)?;
+        validate(inner.get_validate(), &method, HeadPolicy::from_ref(state))?;
+        Ok(ValidHead(inner))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequestParts<State> for ValidHead<Extractor>
+where
+    State: Send + Sync,
+    HeadPolicy: FromRef<State>,
+    Extractor: HasValidate + FromRequestParts<State>,
+    Extractor::Validate: Validate,
+{
+    type Rejection = HeadRejection<<Extractor as FromRequestParts<State>>::Rejection>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request_parts(parts, state)
+            .await
+            .map_err(HeadRejection::Inner)?;
+        validate(
+            inner.get_validate(),
+            &parts.method,
+            HeadPolicy::from_ref(state),
+        )?;
+        Ok(ValidHead(inner))
+    }
+}
+
+#[cfg(all(test, feature = "query"))]
+mod tests {
+    use super::*;
+    use crate::VALIDATION_ERROR_STATUS;
+    use axum::extract::Query;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Page {
+        #[validate(range(min = 1, max = 100))]
+        size: u32,
+    }
+
+    async fn extract(method: Method, policy: HeadPolicy) -> anyhow::Result<Result<u32, Response>> {
+        let (mut parts, _) = axum::http::Request::builder()
+            .method(method)
+            .uri("/?size=500")
+            .body(())?
+            .into_parts();
+        Ok(
+            ValidHead::<Query<Page>>::from_request_parts(&mut parts, &policy)
+                .await
+                .map(|ValidHead(Query(page))| page.size)
+                .map_err(IntoResponse::into_response),
+        )
+    }
+
+    async fn body_len(response: Response) -> anyhow::Result<usize> {
+        Ok(axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await?
+            .len())
+    }
+
+    #[tokio::test]
+    async fn head_policies() -> anyhow::Result<()> {
+        // other methods are rejected with a body
+        let get = extract(Method::GET, HeadPolicy::Skip)
+            .await?
+            .expect_err("size is too large");
+        assert_eq!(get.status(), VALIDATION_ERROR_STATUS);
+        let content_length = body_len(get).await?;
+        assert!(content_length > 0);
+
+        let head = extract(Method::HEAD, HeadPolicy::Validate)
+            .await?
+            .expect_err("size is too large");
+        assert_eq!(head.status(), VALIDATION_ERROR_STATUS);
+        assert_eq!(head.headers()[CONTENT_LENGTH], content_length.to_string());
+        assert_eq!(body_len(head).await?, 0);
+
+        let head = extract(Method::HEAD, HeadPolicy::Status(StatusCode::BAD_REQUEST))
+            .await?
+            .expect_err("size is too large");
+        assert_eq!(head.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body_len(head).await?, 0);
+
+        assert_eq!(
+            extract(Method::HEAD, HeadPolicy::Skip)
+                .await?
+                .map_err(|response| anyhow::anyhow!("{}", response.status()))?,
+            500
+        );
+        Ok(())
+    }
+}
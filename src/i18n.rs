@@ -0,0 +1,822 @@
+//! # Localized validation errors
+//!
+//! ## Feature
+//!
+//! Enable the `i18n` feature to use `Localized<E>`.
+//!
+//! `Localized<E>` wraps a validation extractor such as `Valid<E>`. When validation fails,
+//! the locale is negotiated from the `Accept-Language` request header among the locales of the `MessageCatalog`
+//! provided by the state, the messages of the errors are looked up by error code in that locale,
+//! and the errors are returned as an `ErrorBody` with the `Content-Language` response header set to the chosen locale.
+//!
+//! Messages may refer to the parameters of the failed rule with `{name}`, e.g. `"must be at most {max}"`.
+//! Errors without a message in the catalog keep their original message.
+//!
+//! ## Deserialization errors
+//!
+//! serde reports deserialization errors in English. `FullyLocalized<E>` works like `Localized<E>`,
+//! but also converts the deserialization errors of `Json`, `Query` and `Form` into an `ErrorBody`,
+//! localized with the same catalog, so that clients get a single error format in their language.
+//! Common errors are given a code and parameters:
+//!
+//! | Code              | Example                                     | Parameters               |
+//! |-------------------|---------------------------------------------|--------------------------|
+//! | `invalid_type`    | `invalid type: string "a", expected i32`    | `unexpected`, `expected` |
+//! | `invalid_value`   | `invalid value: integer 300, expected u8`   | `unexpected`, `expected` |
+//! | `invalid_length`  | `invalid length 1, expected 2 elements`     | `unexpected`, `expected` |
+//! | `missing_field`   | ``missing field `name` ``                   | `field`                  |
+//! | `unknown_field`   | ``unknown field `nmae`, expected `name` ``  | `field`, `suggestion`    |
+//! | `unknown_variant` | ``unknown variant `x`, expected `a` ``      | `variant`, `suggestion`  |
+//! | `deserialize`     | other errors                                |                          |
+//!
+//! For typos in field names and enum variants, e.g. `activ` instead of `active`, the closest expected value
+//! by edit distance is given as the `suggestion` parameter, and the message ends with ``did you mean `active`?``.
+//! There's no suggestion if no expected value is close enough. Use `suggest` to give the same suggestions
+//! in custom rules, e.g. for strings validated against a list of values.
+//!
+//! The path of the error is the path of the invalid field when it's known, e.g. with `Json`.
+//! Deserialization errors are returned as `LocalizedRejection::Deserialize`, with the `VALIDATION_ERROR_STATUS`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::{Json, Router};
+//!     use axum_valid::i18n::{Localized, MessageCatalog};
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         let catalog = MessageCatalog::new("en")
+//!             .message("en", "range", "must be between {min} and {max}")
+//!             .message("fr", "range", "doit être entre {min} et {max}");
+//!         Router::new()
+//!             .route("/json", post(handler))
+//!             .with_state(catalog)
+//!     }
+//!
+//!     async fn handler(Localized(Valid(Json(parameter))): Localized<Valid<Json<Parameter>>>) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(range(min = 5, max = 10))]
+//!         pub v0: i32,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::{FieldError, FieldPath};
+use crate::{ErrorBody, ValidationRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, FromRequestParts, Request};
+use axum::http::header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE};
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// Localized error messages, indexed by locale and error code
+#[derive(Debug, Clone)]
+pub struct MessageCatalog(Arc<CatalogInner>);
+
+#[derive(Debug, Clone)]
+struct CatalogInner {
+    default_locale: String,
+    messages: HashMap<String, HashMap<String, String>>,
+}
+
+impl MessageCatalog {
+    /// Create an empty catalog, `default_locale` is used when no requested locale is available
+    pub fn new(default_locale: impl Into<String>) -> Self {
+        let default_locale = default_locale.into();
+        let mut messages = HashMap::new();
+        messages.insert(default_locale.clone(), HashMap::new());
+        MessageCatalog(Arc::new(CatalogInner {
+            default_locale,
+            messages,
+        }))
+    }
+
+    /// Add the message of an error code in a locale
+    pub fn message(
+        mut self,
+        locale: impl Into<String>,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Arc::make_mut(&mut self.0)
+            .messages
+            .entry(locale.into())
+            .or_default()
+            .insert(code.into(), message.into());
+        self
+    }
+
+    /// The default locale
+    pub fn default_locale(&self) -> &str {
+        &self.0.default_locale
+    }
+
+    /// Choose the best available locale for an `Accept-Language` header value
+    ///
+    /// Language ranges are tried by descending quality, a range matches a locale
+    /// either exactly or by its primary language (`fr-CH` matches `fr`), case-insensitively.
+    pub fn negotiate(&self, accept_language: Option<&str>) -> &str {
+        let mut ranges = accept_language
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+            })
+            .collect::<Vec<_>>();
+        // stable sort keeps the header order for equal qualities
+        ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        ranges
+            .into_iter()
+            .find_map(|(tag, _)| self.find_locale(tag))
+            .unwrap_or(&self.0.default_locale)
+    }
+
+    fn find_locale(&self, tag: &str) -> Option<&str> {
+        let primary = tag.split('-').next().unwrap_or(tag);
+        let locales = || self.0.messages.keys().map(String::as_str);
+        locales()
+            .find(|locale| locale.eq_ignore_ascii_case(tag))
+            .or_else(|| locales().find(|locale| locale.eq_ignore_ascii_case(primary)))
+    }
+
+    /// Get the message of an error code in a locale
+    pub fn get(&self, locale: &str, code: &str) -> Option<&str> {
+        self.0
+            .messages
+            .get(locale)
+            .and_then(|messages| messages.get(code))
+            .map(String::as_str)
+    }
+
+    /// Replace the messages of the errors in `body` with their localized messages
+    pub fn localize(&self, locale: &str, body: &mut ErrorBody) {
+        for error in &mut body.errors {
+            let Some(template) = error
+                .code
+                .as_deref()
+                .and_then(|code| self.get(locale, code))
+            else {
+                continue;
+            };
+            let mut message = template.to_owned();
+            for (name, value) in &error.params {
+                let value = match value {
+                    Value::String(s) => s.clone(),
+                    value => value.to_string(),
+                };
+                message = message.replace(&format!("{{{name}}}"), &value);
+            }
+            error.message = Some(message);
+        }
+    }
+}
+
+/// Localized validation errors, returned with the `Content-Language` header
+#[derive(Debug, Clone)]
+pub struct LocalizedErrors {
+    /// The negotiated locale
+    pub locale: String,
+    /// The localized errors
+    pub body: ErrorBody,
+}
+
+impl Display for LocalizedErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.body, f)
+    }
+}
+
+impl IntoResponse for LocalizedErrors {
+    fn into_response(self) -> Response {
+        let mut response = self.body.into_response();
+        if let Ok(locale) = self.locale.parse() {
+            response.headers_mut().insert(CONTENT_LANGUAGE, locale);
+        }
+        response
+    }
+}
+
+/// `LocalizedRejection` is returned when the `Localized` or `FullyLocalized` extractor fails.
+#[derive(Debug)]
+pub enum LocalizedRejection<E> {
+    /// The data is invalid
+    Valid(LocalizedErrors),
+    /// The data can't be deserialized, only returned by `FullyLocalized`
+    Deserialize(LocalizedErrors),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for LocalizedRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LocalizedRejection::Valid(errors) | LocalizedRejection::Deserialize(errors) => {
+                write!(f, "{errors}")
+            }
+            LocalizedRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for LocalizedRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LocalizedRejection::Valid(_) | LocalizedRejection::Deserialize(_) => None,
+            LocalizedRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for LocalizedRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            LocalizedRejection::Valid(errors) | LocalizedRejection::Deserialize(errors) => {
+                errors.into_response()
+            }
+            LocalizedRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+/// Rejections that may be caused by a deserialization error
+///
+/// Implemented for the rejections of `Json`, `Query` and `Form`, so they can be used with `FullyLocalized`.
+pub trait DeserializeRejection {
+    /// The message of the deserialization error, `None` if the rejection has another cause
+    fn deserialize_error(&self) -> Option<String>;
+}
+
+fn source_message(error: &dyn std::error::Error) -> Option<String> {
+    error.source().map(ToString::to_string)
+}
+
+#[cfg(feature = "json")]
+impl DeserializeRejection for axum::extract::rejection::JsonRejection {
+    fn deserialize_error(&self) -> Option<String> {
+        match self {
+            axum::extract::rejection::JsonRejection::JsonDataError(e) => source_message(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "query")]
+impl DeserializeRejection for axum::extract::rejection::QueryRejection {
+    fn deserialize_error(&self) -> Option<String> {
+        match self {
+            axum::extract::rejection::QueryRejection::FailedToDeserializeQueryString(e) => {
+                source_message(e)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "form")]
+impl DeserializeRejection for axum::extract::rejection::FormRejection {
+    fn deserialize_error(&self) -> Option<String> {
+        match self {
+            axum::extract::rejection::FormRejection::FailedToDeserializeForm(e) => {
+                source_message(e)
+            }
+            axum::extract::rejection::FormRejection::FailedToDeserializeFormBody(e) => {
+                source_message(e)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Convert the message of a serde error into a field error
+///
+/// The message may be prefixed by the path of the field and suffixed by its position,
+/// e.g. `v0: invalid type: string "a", expected i32 at line 1 column 10`.
+/// See the [module documentation](crate::i18n) for the codes and parameters of common errors.
+pub fn deserialize_error(message: &str) -> FieldError {
+    const MESSAGES: [&str; 6] = [
+        "invalid type: ",
+        "invalid value: ",
+        "invalid length ",
+        "missing field `",
+        "unknown field `",
+        "unknown variant `",
+    ];
+    fn quoted(text: &str) -> &str {
+        text.split('`').nth(1).unwrap_or_default()
+    }
+
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message, |(message, _)| message);
+    // the path ends with `: `, e.g. `address.lines[1]: `
+    let (path, text) = std::iter::once(0)
+        .chain(message.match_indices(": ").map(|(index, _)| index + 2))
+        .map(|index| message.split_at(index))
+        .find(|(_, text)| MESSAGES.iter().any(|prefix| text.starts_with(prefix)))
+        .unwrap_or(("", message));
+    let mut path = FieldPath::parse(path.trim_end_matches(": "));
+
+    let (code, params) = match MESSAGES.iter().position(|prefix| text.starts_with(prefix)) {
+        Some(index @ 0..=2) => {
+            let rest = &text[MESSAGES[index].len()..];
+            let (unexpected, expected) = rest.split_once(", expected ").unwrap_or((rest, ""));
+            let code = ["invalid_type", "invalid_value", "invalid_length"][index];
+            (
+                code,
+                vec![("unexpected", unexpected), ("expected", expected)],
+            )
+        }
+        Some(3) => {
+            path = path.key(quoted(text));
+            ("missing_field", vec![("field", quoted(text))])
+        }
+        Some(index @ 4..) => {
+            let (name, code) =
+                [("field", "unknown_field"), ("variant", "unknown_variant")][index - 4];
+            let mut params = vec![(name, quoted(text))];
+            // the expected values are quoted after the unknown one, e.g. "expected `a`, `b` or `c`"
+            if let Some((_, expected)) = text.split_once(", expected ") {
+                let candidates = expected.split('`').skip(1).step_by(2);
+                if let Some(suggestion) = suggest(quoted(text), candidates) {
+                    params.push(("suggestion", suggestion));
+                }
+            }
+            (code, params)
+        }
+        None => ("deserialize", vec![]),
+    };
+    let mut error = FieldError::new(path, code);
+    error.message = Some(
+        match params.iter().find(|(name, _)| *name == "suggestion") {
+            Some((_, suggestion)) => format!("{text}, did you mean `{suggestion}`?"),
+            None => text.to_owned(),
+        },
+    );
+    error.params = params
+        .into_iter()
+        .map(|(name, value)| (name.to_owned(), Value::from(value)))
+        .collect();
+    error
+}
+
+/// Get the candidate closest to `value` by edit distance, `None` if no candidate is close enough
+///
+/// A candidate is close enough if its Levenshtein distance to `value` is at most a third of the length
+/// of the longest of both, and at least 1, e.g. `activ` suggests `active` among `active` and `inactive`.
+/// The first candidate wins ties.
+pub fn suggest<'a>(value: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    fn distance(a: &[char], b: &[char]) -> usize {
+        let mut row = (0..=b.len()).collect::<Vec<_>>();
+        for (i, a) in a.iter().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            for (j, b) in b.iter().enumerate() {
+                let above = row[j + 1];
+                row[j + 1] = (diagonal + usize::from(a != b))
+                    .min(above + 1)
+                    .min(row[j] + 1);
+                diagonal = above;
+            }
+        }
+        row[b.len()]
+    }
+
+    let value = value.chars().collect::<Vec<_>>();
+    candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let chars = candidate.chars().collect::<Vec<_>>();
+            let distance = distance(&value, &chars);
+            let max = (value.len().max(chars.len()) / 3).max(1);
+            (distance <= max).then_some((distance, candidate))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// # `Localized` validation extractor
+///
+/// See the [module documentation](crate::i18n) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Localized<E>(pub E);
+
+impl<E> Deref for Localized<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for Localized<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> Localized<E> {
+    /// Consumes the `Localized` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+fn localized_errors(
+    mut body: ErrorBody,
+    catalog: &MessageCatalog,
+    headers: &HeaderMap,
+) -> LocalizedErrors {
+    let accept_language = headers
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok());
+    let locale = catalog.negotiate(accept_language).to_owned();
+    catalog.localize(&locale, &mut body);
+    LocalizedErrors { locale, body }
+}
+
+fn localize<V, E>(
+    rejection: ValidationRejection<V, E>,
+    catalog: &MessageCatalog,
+    headers: &HeaderMap,
+) -> LocalizedRejection<E>
+where
+    ErrorBody: From<V>,
+{
+    match rejection {
+        ValidationRejection::Valid(errors) => {
+            LocalizedRejection::Valid(localized_errors(ErrorBody::from(errors), catalog, headers))
+        }
+        ValidationRejection::Inner(error) => LocalizedRejection::Inner(error),
+    }
+}
+
+fn localize_all<V, E>(
+    rejection: ValidationRejection<V, E>,
+    catalog: &MessageCatalog,
+    headers: &HeaderMap,
+) -> LocalizedRejection<E>
+where
+    ErrorBody: From<V>,
+    E: DeserializeRejection,
+{
+    match rejection {
+        ValidationRejection::Inner(error) => match error.deserialize_error() {
+            Some(message) => LocalizedRejection::Deserialize(localized_errors(
+                ErrorBody::new(vec![deserialize_error(&message)]),
+                catalog,
+                headers,
+            )),
+            None => LocalizedRejection::Inner(error),
+        },
+        rejection => localize(rejection, catalog, headers),
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequest<State> for Localized<Extractor>
+where
+    State: Send + Sync,
+    MessageCatalog: FromRef<State>,
+    Extractor: FromRequest<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = LocalizedRejection<E>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let catalog = MessageCatalog::from_ref(state);
+        let headers = req.headers().clone();
+        Extractor::from_request(req, state)
+            .await
+            .map(Localized)
+            .map_err(|rejection| localize(rejection, &catalog, &headers))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequestParts<State> for Localized<Extractor>
+where
+    State: Send + Sync,
+    MessageCatalog: FromRef<State>,
+    Extractor: FromRequestParts<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = LocalizedRejection<E>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let catalog = MessageCatalog::from_ref(state);
+        Extractor::from_request_parts(parts, state)
+            .await
+            .map(Localized)
+            .map_err(|rejection| localize(rejection, &catalog, &parts.headers))
+    }
+}
+
+/// # `FullyLocalized` validation extractor
+///
+/// See the [module documentation](crate::i18n) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FullyLocalized<E>(pub E);
+
+impl<E> Deref for FullyLocalized<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for FullyLocalized<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> FullyLocalized<E> {
+    /// Consumes the `FullyLocalized` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequest<State> for FullyLocalized<Extractor>
+where
+    State: Send + Sync,
+    MessageCatalog: FromRef<State>,
+    Extractor: FromRequest<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse + DeserializeRejection,
+{
+    type Rejection = LocalizedRejection<E>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let catalog = MessageCatalog::from_ref(state);
+        let headers = req.headers().clone();
+        Extractor::from_request(req, state)
+            .await
+            .map(FullyLocalized)
+            .map_err(|rejection| localize_all(rejection, &catalog, &headers))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequestParts<State> for FullyLocalized<Extractor>
+where
+    State: Send + Sync,
+    MessageCatalog: FromRef<State>,
+    Extractor: FromRequestParts<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse + DeserializeRejection,
+{
+    type Rejection = LocalizedRejection<E>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let catalog = MessageCatalog::from_ref(state);
+        Extractor::from_request_parts(parts, state)
+            .await
+            .map(FullyLocalized)
+            .map_err(|rejection| localize_all(rejection, &catalog, &parts.headers))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> MessageCatalog {
+        MessageCatalog::new("en")
+            .message("en", "range", "must be between {min} and {max}")
+            .message("fr", "range", "doit être entre {min} et {max}")
+            .message("de-AT", "range", "muss zwischen {min} und {max} liegen")
+    }
+
+    #[test]
+    fn negotiate_locale() {
+        let catalog = catalog();
+        assert_eq!(catalog.negotiate(None), "en");
+        assert_eq!(catalog.negotiate(Some("fr")), "fr");
+        assert_eq!(catalog.negotiate(Some("fr-CH, fr;q=0.9, en;q=0.8")), "fr");
+        assert_eq!(catalog.negotiate(Some("en;q=0.5, fr;q=0.9")), "fr");
+        assert_eq!(catalog.negotiate(Some("DE-at")), "de-AT");
+        assert_eq!(catalog.negotiate(Some("es, it")), "en");
+        assert_eq!(catalog.negotiate(Some("fr;q=0, en")), "en");
+    }
+
+    #[cfg(all(feature = "validator", feature = "query"))]
+    #[tokio::test]
+    async fn content_language_reflects_locale() -> anyhow::Result<()> {
+        use crate::Valid;
+        use axum::extract::Query;
+        use axum::http::Request;
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[validate(range(min = 5, max = 10))]
+            v0: i32,
+        }
+
+        let catalog = catalog();
+        for (accept_language, locale, message) in [
+            ("fr-CH, fr;q=0.9", "fr", "doit être entre 5 et 10"),
+            ("en-US", "en", "must be between 5 and 10"),
+            ("es", "en", "must be between 5 and 10"),
+        ] {
+            let (mut parts, _) = Request::builder()
+                .uri("/?v0=11")
+                .header(ACCEPT_LANGUAGE, accept_language)
+                .body(())?
+                .into_parts();
+            let rejection =
+                Localized::<Valid<Query<Parameters>>>::from_request_parts(&mut parts, &catalog)
+                    .await
+                    .expect_err("v0 is out of range");
+            let LocalizedRejection::Valid(errors) = &rejection else {
+                panic!("expected validation errors");
+            };
+            assert_eq!(errors.locale, locale);
+            assert_eq!(errors.body.errors[0].message.as_deref(), Some(message));
+
+            let response = rejection.into_response();
+            assert_eq!(response.status(), crate::VALIDATION_ERROR_STATUS);
+            assert_eq!(response.headers()[CONTENT_LANGUAGE], locale);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_deserialize_errors() {
+        let error =
+            deserialize_error(r#"v0: invalid type: string "a", expected i32 at line 1 column 10"#);
+        assert_eq!(error.path.to_string(), "v0");
+        assert_eq!(error.code.as_deref(), Some("invalid_type"));
+        assert_eq!(error.params["unexpected"], r#"string "a""#);
+        assert_eq!(error.params["expected"], "i32");
+        assert_eq!(
+            error.message.as_deref(),
+            Some(r#"invalid type: string "a", expected i32"#)
+        );
+
+        let error = deserialize_error("inner.list[1]: invalid value: integer `300`, expected u8");
+        assert_eq!(error.path.to_string(), "inner.list[1]");
+        assert_eq!(error.code.as_deref(), Some("invalid_value"));
+
+        let error = deserialize_error("address: missing field `zip`");
+        assert_eq!(error.path.to_string(), "address.zip");
+        assert_eq!(error.code.as_deref(), Some("missing_field"));
+        assert_eq!(error.params["field"], "zip");
+
+        let error =
+            deserialize_error("status: unknown variant `activ`, expected `active` or `inactive`");
+        assert_eq!(error.path.to_string(), "status");
+        assert_eq!(error.code.as_deref(), Some("unknown_variant"));
+        assert_eq!(error.params["variant"], "activ");
+        assert_eq!(error.params["suggestion"], "active");
+        assert_eq!(
+            error.message.as_deref(),
+            Some(
+                "unknown variant `activ`, expected `active` or `inactive`, did you mean `active`?"
+            )
+        );
+
+        let error = deserialize_error("unknown field `nme`, expected one of `id`, `name`, `email`");
+        assert_eq!(error.params["suggestion"], "name");
+
+        // nothing close enough
+        let error = deserialize_error("unknown variant `x`, expected `active` or `inactive`");
+        assert!(error.params.get("suggestion").is_none());
+
+        let error = deserialize_error("invalid digit found in string");
+        assert_eq!(error.path.to_string(), "");
+        assert_eq!(error.code.as_deref(), Some("deserialize"));
+        assert!(error.params.is_empty());
+    }
+
+    #[test]
+    fn suggest_closest() {
+        assert_eq!(suggest("activ", ["active", "inactive"]), Some("active"));
+        assert_eq!(suggest("inactiv", ["active", "inactive"]), Some("inactive"));
+        assert_eq!(suggest("pending", ["active", "inactive"]), None);
+        assert_eq!(suggest("b", ["a", "c"]), Some("a"));
+        assert_eq!(suggest("b", []), None);
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn suggest_enum_variant_json() -> anyhow::Result<()> {
+        use axum::body::Body;
+        use axum::extract::rejection::JsonRejection;
+        use axum::http::header::CONTENT_TYPE;
+        use axum::Json;
+
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        #[allow(dead_code)]
+        enum Status {
+            Active,
+            Inactive,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Account {
+            status: Status,
+        }
+
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"status": "activ"}"#))?;
+        let rejection: JsonRejection = Json::<Account>::from_request(request, &())
+            .await
+            .expect_err("unknown variant");
+        let error = deserialize_error(&rejection.deserialize_error().unwrap_or_default());
+        assert_eq!(error.path.to_string(), "status");
+        assert_eq!(error.code.as_deref(), Some("unknown_variant"));
+        assert_eq!(error.params["suggestion"], "active");
+        Ok(())
+    }
+
+    #[cfg(all(feature = "validator", feature = "json"))]
+    #[tokio::test]
+    async fn localize_deserialize_errors() -> anyhow::Result<()> {
+        use crate::Valid;
+        use axum::body::Body;
+        use axum::http::header::CONTENT_TYPE;
+        use axum::Json;
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[validate(range(min = 5, max = 10))]
+            v0: i32,
+        }
+
+        let catalog = catalog()
+            .message("fr", "invalid_type", "type invalide, {expected} attendu")
+            .message("fr", "missing_field", "le champ `{field}` est requis");
+        for (body, message) in [
+            (r#"{"v0": "a"}"#, "type invalide, i32 attendu"),
+            (r#"{}"#, "le champ `v0` est requis"),
+            (r#"{"v0": 11}"#, "doit être entre 5 et 10"),
+        ] {
+            let request = Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .header(ACCEPT_LANGUAGE, "fr")
+                .body(Body::from(body))?;
+            let rejection =
+                FullyLocalized::<Valid<Json<Parameters>>>::from_request(request, &catalog)
+                    .await
+                    .expect_err("invalid body");
+            let (LocalizedRejection::Valid(errors) | LocalizedRejection::Deserialize(errors)) =
+                &rejection
+            else {
+                panic!("expected localized errors");
+            };
+            assert_eq!(errors.locale, "fr");
+            assert_eq!(errors.body.errors[0].path.to_string(), "v0");
+            assert_eq!(errors.body.errors[0].message.as_deref(), Some(message));
+            assert_eq!(rejection.into_response().headers()[CONTENT_LANGUAGE], "fr");
+        }
+        Ok(())
+    }
+}
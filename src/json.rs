@@ -157,4 +157,65 @@ mod tests {
             builder.json(T::invalid())
         }
     }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn validate_after_serde_with() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use axum::body::Body;
+        use axum::extract::{FromRequest, Request};
+        use axum::http::header::CONTENT_TYPE;
+        use serde::Deserialize;
+        use validator::Validate;
+
+        /// Durations are sent as `"<n>s"` strings and stored as seconds
+        mod seconds {
+            use serde::de::Error;
+            use serde::{Deserialize, Deserializer};
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<u64, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                value
+                    .strip_suffix('s')
+                    .ok_or_else(|| D::Error::custom("missing `s` suffix"))?
+                    .parse()
+                    .map_err(D::Error::custom)
+            }
+        }
+
+        #[derive(Debug, Deserialize, Validate)]
+        struct Timeout {
+            #[serde(with = "seconds")]
+            #[validate(range(min = 1, max = 60))]
+            timeout: u64,
+        }
+
+        let request = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+
+        let Valid(Json(timeout)) =
+            Valid::<Json<Timeout>>::from_request(request(r#"{"timeout": "30s"}"#)?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(timeout.timeout, 30);
+
+        // the deserialized value is validated
+        assert!(matches!(
+            Valid::<Json<Timeout>>::from_request(request(r#"{"timeout": "90s"}"#)?, &()).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["timeout"][0].code == "range"
+        ));
+
+        // deserialization errors are reported by the inner extractor
+        assert!(matches!(
+            Valid::<Json<Timeout>>::from_request(request(r#"{"timeout": "30"}"#)?, &()).await,
+            Err(ValidRejection::Inner(_))
+        ));
+        Ok(())
+    }
 }
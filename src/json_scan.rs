@@ -0,0 +1,156 @@
+//! Scanner of raw JSON documents, for extractors checking the body before it's deserialized
+//!
+//! The scanner is lenient: syntax errors are ignored, they are reported by the JSON deserializer afterwards.
+
+#[cfg(any(feature = "finite_json", feature = "str_limit"))]
+use crate::error_body::FieldPath;
+
+/// A token of a JSON document
+// each extractor only reads the contents of some kinds of tokens
+#[allow(dead_code)]
+pub(crate) enum Token<'a> {
+    /// An object key, with its quotes
+    Key(&'a [u8]),
+    /// A string value, with its quotes
+    String(&'a [u8]),
+    /// A number or a literal, such as `true`, `null` or `NaN`
+    Scalar(&'a [u8]),
+}
+
+/// An open container of a JSON document
+enum Container<'a> {
+    /// An object and the raw key of its current value
+    Object {
+        key: Option<&'a [u8]>,
+        expect_key: bool,
+    },
+    /// An array and the index of its current value
+    Array { index: usize },
+}
+
+/// Iterator over the tokens of a JSON document, tracking the path of the current value
+pub(crate) struct Scanner<'a> {
+    json: &'a [u8],
+    position: usize,
+    stack: Vec<Container<'a>>,
+    /// The last key, which becomes the key of the current value at the following `:`
+    pending_key: Option<&'a [u8]>,
+}
+
+impl<'a> Scanner<'a> {
+    pub(crate) fn new(json: &'a [u8]) -> Self {
+        Self {
+            json,
+            position: 0,
+            stack: Vec::new(),
+            pending_key: None,
+        }
+    }
+
+    /// Path of the current value, or of its object for a key
+    #[cfg(any(feature = "finite_json", feature = "str_limit"))]
+    pub(crate) fn path(&self) -> FieldPath {
+        self.stack
+            .iter()
+            .fold(FieldPath::new(), |path, container| match container {
+                Container::Object { key: Some(key), .. } => {
+                    path.key(serde_json::from_slice::<String>(key).unwrap_or_default())
+                }
+                Container::Object { key: None, .. } => path,
+                Container::Array { index } => path.index(*index),
+            })
+    }
+
+    /// Find the closing quote of the string opened at `start`
+    fn string_end(&self, start: usize) -> usize {
+        let mut i = start + 1;
+        while i < self.json.len() && self.json[i] != b'"' {
+            i += if self.json[i] == b'\\' { 2 } else { 1 };
+        }
+        i
+    }
+}
+
+impl<'a> Iterator for Scanner<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let json = self.json;
+        while self.position < json.len() {
+            let start = self.position;
+            self.position += 1;
+            match json[start] {
+                b'{' => self.stack.push(Container::Object {
+                    key: None,
+                    expect_key: true,
+                }),
+                b'[' => self.stack.push(Container::Array { index: 0 }),
+                b'}' | b']' => {
+                    self.stack.pop();
+                }
+                b',' => match self.stack.last_mut() {
+                    Some(Container::Object { key, expect_key }) => {
+                        *key = None;
+                        *expect_key = true;
+                    }
+                    Some(Container::Array { index }) => *index += 1,
+                    None => {}
+                },
+                b':' => {
+                    if let Some(Container::Object { key, .. }) = self.stack.last_mut() {
+                        *key = self.pending_key.take();
+                    }
+                }
+                b'"' => {
+                    let end = self.string_end(start);
+                    self.position = end + 1;
+                    let raw = json.get(start..=end).unwrap_or(&json[start..]);
+                    if let Some(Container::Object {
+                        expect_key: expect_key @ true,
+                        ..
+                    }) = self.stack.last_mut()
+                    {
+                        *expect_key = false;
+                        self.pending_key = Some(raw);
+                        return Some(Token::Key(raw));
+                    }
+                    return Some(Token::String(raw));
+                }
+                b'-' | b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z' => {
+                    while self.position < json.len()
+                        && matches!(json[self.position], b'-' | b'+' | b'.' | b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z')
+                    {
+                        self.position += 1;
+                    }
+                    return Some(Token::Scalar(&json[start..self.position]));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// Length of a raw string, with its quotes, once unescaped, in characters
+#[cfg(feature = "str_limit")]
+pub(crate) fn unescaped_len(raw: &[u8]) -> usize {
+    let content = raw
+        .strip_prefix(b"\"")
+        .map(|raw| raw.strip_suffix(b"\"").unwrap_or(raw))
+        .unwrap_or(raw);
+    let mut i = 0;
+    let mut len = 0;
+    while i < content.len() {
+        let byte = content[i];
+        i += match byte {
+            b'\\' if content.get(i + 1) == Some(&b'u') => 6,
+            b'\\' => 2,
+            _ => 1,
+        };
+        // UTF-8 continuation bytes don't start a character
+        if byte & 0xc0 != 0x80 {
+            len += 1;
+        }
+    }
+    len
+}
@@ -0,0 +1,232 @@
+//! # JSON:API error objects
+//!
+//! ## Feature
+//!
+//! Enable the `jsonapi` feature to use `JsonApiErrors`.
+//!
+//! Clients built on [JSON:API](https://jsonapi.org/format/#errors) expect errors as a top-level `errors` array
+//! of error objects, each with a `status`, a `source.pointer` to the invalid member of the request document,
+//! and a `detail`. `JsonApiErrors` converts an `ErrorBody` into this shape:
+//!
+//! ```json
+//! {"errors": [{"status": "400", "code": "length", "source": {"pointer": "/data/attributes/name"}, "detail": "..."}]}
+//! ```
+//!
+//! The `status` is `VALIDATION_ERROR_STATUS`, the `code` and the `detail` are the code and the message of the error,
+//! the code being used as `detail` when there's no message. The parameters of the failed rule are included as `meta`.
+//! Pointers are relative to the validated data, use `JsonApiErrors::with_pointer_prefix` with `DATA_ATTRIBUTES`
+//! when the data is the `attributes` of a resource object.
+//!
+//! The response has the `application/vnd.api+json` content type.
+//! `ValidationRejection::into_jsonapi_response` renders validation rejections this way.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::extract::rejection::JsonRejection;
+//!     use axum::response::{IntoResponse, Response};
+//!     use axum::routing::post;
+//!     use axum::{Json, Router};
+//!     use axum_valid::{Valid, ValidRejection};
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/json", post(handler))
+//!     }
+//!
+//!     async fn handler(
+//!         parameter: Result<Valid<Json<Parameter>>, ValidRejection<JsonRejection>>,
+//!     ) -> Response {
+//!         match parameter {
+//!             Ok(Valid(Json(parameter))) => parameter.name.into_response(),
+//!             Err(rejection) => rejection.into_jsonapi_response(),
+//!         }
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(length(min = 1, max = 32))]
+//!         pub name: String,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::{ErrorBody, ValidationRejection, VALIDATION_ERROR_STATUS};
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Media type of JSON:API documents
+pub const JSONAPI_CONTENT_TYPE: &str = "application/vnd.api+json";
+
+/// Pointer to the attributes of the primary resource object of a JSON:API document
+pub const DATA_ATTRIBUTES: &str = "/data/attributes";
+
+/// Location of the invalid member of the request document
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonApiSource {
+    /// JSON Pointer (RFC 6901) to the invalid member
+    pub pointer: String,
+}
+
+/// A JSON:API error object
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonApiError {
+    /// HTTP status code, as a string
+    pub status: String,
+    /// Code of the failed rule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Location of the invalid member
+    pub source: JsonApiSource,
+    /// Human readable explanation
+    pub detail: String,
+    /// Parameters of the failed rule
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Map<String, Value>>,
+}
+
+/// # JSON:API error document
+///
+/// See the [module documentation](crate::jsonapi) for details.
+///
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonApiErrors {
+    /// The error objects
+    pub errors: Vec<JsonApiError>,
+}
+
+impl JsonApiErrors {
+    /// Prefix the pointers of all errors, e.g. with `DATA_ATTRIBUTES`
+    pub fn with_pointer_prefix(mut self, prefix: &str) -> Self {
+        for error in &mut self.errors {
+            error.source.pointer.insert_str(0, prefix);
+        }
+        self
+    }
+}
+
+impl From<ErrorBody> for JsonApiErrors {
+    fn from(body: ErrorBody) -> Self {
+        let status = VALIDATION_ERROR_STATUS.as_u16().to_string();
+        let errors = body
+            .errors
+            .into_iter()
+            .map(|error| JsonApiError {
+                status: status.clone(),
+                source: JsonApiSource {
+                    pointer: error.path.to_json_pointer(),
+                },
+                detail: error
+                    .message
+                    .or_else(|| error.code.clone())
+                    .unwrap_or_else(|| String::from("invalid value")),
+                code: error.code,
+                meta: (!error.params.is_empty()).then_some(error.params),
+            })
+            .collect();
+        JsonApiErrors { errors }
+    }
+}
+
+impl IntoResponse for JsonApiErrors {
+    fn into_response(self) -> Response {
+        (
+            VALIDATION_ERROR_STATUS,
+            [(CONTENT_TYPE, JSONAPI_CONTENT_TYPE)],
+            axum::Json(self),
+        )
+            .into_response()
+    }
+}
+
+impl<V, E> ValidationRejection<V, E>
+where
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    /// Convert the rejection into a response, with validation errors as a JSON:API error document
+    ///
+    /// Inner extractor errors are converted into responses as usual.
+    pub fn into_jsonapi_response(self) -> Response {
+        match self {
+            ValidationRejection::Valid(errors) => {
+                JsonApiErrors::from(ErrorBody::from(errors)).into_response()
+            }
+            ValidationRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "validator"))]
+mod tests {
+    use super::*;
+    use crate::{Valid, ValidRejection};
+    use axum::body::Body;
+    use axum::extract::rejection::JsonRejection;
+    use axum::extract::{FromRequest, Request};
+    use axum::Json;
+    use validator::Validate;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Attributes {
+        #[validate(length(min = 1, max = 5))]
+        name: String,
+        #[validate(range(min = 18))]
+        age: u8,
+    }
+
+    #[tokio::test]
+    async fn jsonapi_error_document() -> anyhow::Result<()> {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"name": "toolong", "age": 30}"#))?;
+        let rejection: ValidRejection<JsonRejection> =
+            Valid::<Json<Attributes>>::from_request(request, &())
+                .await
+                .expect_err("name is too long");
+        let ValidRejection::Valid(errors) = rejection else {
+            panic!("expected validation errors");
+        };
+        let document = JsonApiErrors::from(ErrorBody::from(&errors));
+        assert_eq!(
+            serde_json::to_value(document.clone().with_pointer_prefix(DATA_ATTRIBUTES))?,
+            serde_json::json!({
+                "errors": [{
+                    "status": VALIDATION_ERROR_STATUS.as_u16().to_string(),
+                    "code": "length",
+                    "source": {"pointer": "/data/attributes/name"},
+                    "detail": "length",
+                    "meta": {"min": 1, "max": 5, "value": "toolong"},
+                }]
+            })
+        );
+
+        let response = ValidRejection::<JsonRejection>::Valid(errors).into_jsonapi_response();
+        assert_eq!(response.status(), VALIDATION_ERROR_STATUS);
+        assert_eq!(response.headers()[CONTENT_TYPE], JSONAPI_CONTENT_TYPE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let body = serde_json::from_slice::<Value>(&body)?;
+        assert_eq!(body["errors"][0]["source"]["pointer"], "/name");
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+//! # Support for `KeyLimitedJson<T, MAX_KEYS>`
+//!
+//! ## Feature
+//!
+//! Enable the `key_limit` feature to use `Valid<KeyLimitedJson<T, MAX_KEYS>>`.
+//!
+//! Maps such as `#[serde(flatten)] extra: HashMap<String, Value>` accept any number of keys,
+//! so a small body with many tiny keys can make deserialization allocate far more than the body size.
+//! `KeyLimitedJson<T, MAX_KEYS>` counts the object keys of the whole body before deserializing it,
+//! and rejects bodies with more than `MAX_KEYS` keys with an `ErrorBody` with the code `too_many_keys`.
+//! The limit defaults to `DEFAULT_MAX_KEYS`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::Router;
+//!     use axum_valid::key_limit::KeyLimitedJson;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use std::collections::HashMap;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/key_limit", post(handler))
+//!     }
+//!
+//!     async fn handler(
+//!         Valid(KeyLimitedJson(parameter)): Valid<KeyLimitedJson<Parameter, 100>>,
+//!     ) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(length(min = 1, max = 10))]
+//!         pub name: String,
+//!         #[serde(flatten)]
+//!         pub labels: HashMap<String, String>,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::buffered::BufferedRequest;
+use crate::error_body::{ErrorBody, FieldError, FieldPath};
+use crate::json_scan::{Scanner, Token};
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::extract::rejection::{BytesRejection, JsonRejection};
+use axum::extract::{FromRequest, Request};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "validator")]
+use validator::ValidateArgs;
+
+/// Error code of bodies with too many object keys
+pub const TOO_MANY_KEYS: &str = "too_many_keys";
+
+/// Default maximum number of object keys of `KeyLimitedJson`
+pub const DEFAULT_MAX_KEYS: usize = 1000;
+
+/// # `KeyLimitedJson` extractor
+///
+/// See the [module documentation](crate::key_limit) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeyLimitedJson<T, const MAX_KEYS: usize = DEFAULT_MAX_KEYS>(pub T);
+
+impl<T, const MAX_KEYS: usize> Deref for KeyLimitedJson<T, MAX_KEYS> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, const MAX_KEYS: usize> DerefMut for KeyLimitedJson<T, MAX_KEYS> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// `KeyLimitedJsonRejection` is returned when the `KeyLimitedJson` extractor fails.
+#[derive(Debug)]
+pub enum KeyLimitedJsonRejection {
+    /// The body can't be read
+    Bytes(BytesRejection),
+    /// The body contains too many object keys
+    TooManyKeys(ErrorBody),
+    /// The body is rejected by `Json`
+    Json(JsonRejection),
+}
+
+impl Display for KeyLimitedJsonRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyLimitedJsonRejection::Bytes(e) => write!(f, "{e}"),
+            KeyLimitedJsonRejection::TooManyKeys(body) => {
+                let limit = body
+                    .errors
+                    .first()
+                    .and_then(|error| error.params.get("max"))
+                    .unwrap_or(&Value::Null);
+                write!(f, "The body contains more than {limit} object keys")
+            }
+            KeyLimitedJsonRejection::Json(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for KeyLimitedJsonRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KeyLimitedJsonRejection::Bytes(e) => Some(e),
+            KeyLimitedJsonRejection::TooManyKeys(_) => None,
+            KeyLimitedJsonRejection::Json(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for KeyLimitedJsonRejection {
+    fn into_response(self) -> Response {
+        match self {
+            KeyLimitedJsonRejection::Bytes(e) => e.into_response(),
+            KeyLimitedJsonRejection::TooManyKeys(body) => body.into_response(),
+            KeyLimitedJsonRejection::Json(e) => e.into_response(),
+        }
+    }
+}
+
+/// Check if a JSON document has more than `max` object keys in total
+///
+/// Syntax errors are ignored, they are reported by the JSON deserializer afterwards.
+fn exceeds_key_limit(json: &[u8], max: usize) -> bool {
+    Scanner::new(json)
+        .filter(|token| matches!(token, Token::Key(_)))
+        .nth(max)
+        .is_some()
+}
+
+#[async_trait]
+impl<T, S, const MAX_KEYS: usize> FromRequest<S> for KeyLimitedJson<T, MAX_KEYS>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = KeyLimitedJsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let buffered = BufferedRequest::read(req, state)
+            .await
+            .map_err(KeyLimitedJsonRejection::Bytes)?;
+        if exceeds_key_limit(&buffered.bytes, MAX_KEYS) {
+            let mut error = FieldError::new(FieldPath::new(), TOO_MANY_KEYS);
+            error
+                .params
+                .insert(String::from("max"), Value::from(MAX_KEYS));
+            return Err(KeyLimitedJsonRejection::TooManyKeys(ErrorBody::new(vec![
+                error,
+            ])));
+        }
+        let Json(value) = Json::from_request(buffered.into_request(), state)
+            .await
+            .map_err(KeyLimitedJsonRejection::Json)?;
+        Ok(KeyLimitedJson(value))
+    }
+}
+
+impl<T, const MAX_KEYS: usize> HasValidate for KeyLimitedJson<T, MAX_KEYS> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>, const MAX_KEYS: usize> HasValidateArgs<'v>
+    for KeyLimitedJson<T, MAX_KEYS>
+{
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify, const MAX_KEYS: usize> crate::HasModify for KeyLimitedJson<T, MAX_KEYS> {
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T, const MAX_KEYS: usize> crate::PayloadExtractor for KeyLimitedJson<T, MAX_KEYS> {
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload, const MAX_KEYS: usize> crate::HasValidify
+    for KeyLimitedJson<T, MAX_KEYS>
+{
+    type Validify = T;
+    type PayloadExtractor = KeyLimitedJson<T::Payload, MAX_KEYS>;
+    fn from_validify(v: Self::Validify) -> Self {
+        KeyLimitedJson(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use std::collections::HashMap;
+
+    #[test]
+    fn count_object_keys() {
+        let json = br#"{"a": {"b": 1, "c": ["d", {"e": "f,g"}]}, "h\"": null}"#;
+        assert!(!exceeds_key_limit(json, 5));
+        assert!(exceeds_key_limit(json, 4));
+        assert!(!exceeds_key_limit(br#"["a", "b", "c"]"#, 0));
+    }
+
+    #[tokio::test]
+    async fn reject_too_many_keys() -> anyhow::Result<()> {
+        #[derive(Debug, serde::Deserialize)]
+        struct Parameters {
+            #[serde(flatten)]
+            labels: HashMap<String, u32>,
+        }
+
+        let request = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+
+        let KeyLimitedJson(parameters) =
+            KeyLimitedJson::<Parameters, 3>::from_request(request(r#"{"a":1,"b":2,"c":3}"#)?, &())
+                .await?;
+        assert_eq!(parameters.labels.len(), 3);
+
+        let rejection = KeyLimitedJson::<Parameters, 3>::from_request(
+            request(r#"{"a":1,"b":2,"c":3,"d":4}"#)?,
+            &(),
+        )
+        .await
+        .expect_err("too many keys");
+        let KeyLimitedJsonRejection::TooManyKeys(body) = &rejection else {
+            panic!("expected a too many keys rejection");
+        };
+        assert_eq!(body.errors[0].code.as_deref(), Some(TOO_MANY_KEYS));
+        assert_eq!(body.errors[0].params["max"], 3);
+        assert_eq!(
+            rejection.into_response().status(),
+            crate::VALIDATION_ERROR_STATUS
+        );
+        Ok(())
+    }
+}
@@ -1,25 +1,176 @@
 #![doc = include_str!("../README.md")]
 #![deny(unsafe_code, missing_docs, clippy::unwrap_used)]
 
+// Code generated by the derive macros refers to `::axum_valid`, also within this crate
+#[cfg(feature = "derive")]
+extern crate self as axum_valid;
+
+#[cfg(feature = "allow_list")]
+pub mod allow_list;
+#[cfg(feature = "analytics")]
+pub mod analytics;
+#[cfg(feature = "any_body")]
+pub mod any_body;
+#[cfg(feature = "auth")]
+pub mod auth;
+#[cfg(feature = "batch")]
+pub mod batch;
+#[cfg(feature = "body_size")]
+pub mod body_size;
+#[cfg(any(
+    feature = "body_size",
+    feature = "checksum",
+    feature = "compressed",
+    feature = "finite_json",
+    feature = "key_limit",
+    feature = "str_limit",
+    feature = "with_raw"
+))]
+mod buffered;
+#[cfg(any(feature = "canonical_key", feature = "hashed", feature = "signed"))]
+mod canonical;
+#[cfg(feature = "canonical_key")]
+pub mod canonical_key;
+#[cfg(feature = "checksum")]
+pub mod checksum;
+#[cfg(feature = "complexity")]
+pub mod complexity;
+#[cfg(feature = "compressed")]
+pub mod compressed;
+#[cfg(feature = "cost")]
+pub mod cost;
+#[cfg(feature = "de")]
+pub mod de;
+#[cfg(feature = "defaults")]
+pub mod defaults;
+#[cfg(feature = "dependent")]
+pub mod dependent;
+#[cfg(feature = "depth")]
+pub mod depth;
+#[cfg(feature = "diff")]
+pub mod diff;
+#[cfg(feature = "element_limit")]
+pub mod element_limit;
+#[cfg(feature = "enriched")]
+pub mod enriched;
+#[cfg(feature = "error_body")]
+pub mod error_body;
+#[cfg(feature = "etag")]
+pub mod etag;
 #[cfg(feature = "extra")]
 pub mod extra;
+#[cfg(feature = "field_ids")]
+pub mod field_ids;
+#[cfg(feature = "finite_json")]
+pub mod finite_json;
 #[cfg(feature = "form")]
 pub mod form;
 #[cfg(feature = "garde")]
 pub mod garde;
+#[cfg(feature = "compressed")]
+mod gzip;
+#[cfg(feature = "hashed")]
+pub mod hashed;
+#[cfg(feature = "head")]
+pub mod head;
+#[cfg(feature = "i18n")]
+pub mod i18n;
 #[cfg(feature = "json")]
 pub mod json;
+#[cfg(any(feature = "finite_json", feature = "key_limit", feature = "str_limit"))]
+mod json_scan;
+#[cfg(feature = "jsonapi")]
+pub mod jsonapi;
+#[cfg(feature = "key_limit")]
+pub mod key_limit;
+#[cfg(feature = "logged")]
+pub mod logged;
+#[cfg(feature = "method")]
+pub mod method;
 #[cfg(feature = "msgpack")]
 pub mod msgpack;
+#[cfg(feature = "multi_form")]
+pub mod multi_form;
+#[cfg(feature = "nested_form")]
+pub mod nested_form;
+#[cfg(feature = "non_zero")]
+pub mod non_zero;
+#[cfg(feature = "packed_query")]
+pub mod packed_query;
+#[cfg(feature = "parsed")]
+pub mod parsed;
+#[cfg(feature = "patch")]
+pub mod patch;
 pub mod path;
+#[cfg(feature = "profile")]
+pub mod profile;
 #[cfg(feature = "query")]
 pub mod query;
+#[cfg(feature = "query_schema")]
+pub mod query_schema;
+#[cfg(feature = "rate_limit")]
+pub mod rate_limit;
+#[cfg(feature = "redirect")]
+pub mod redirect;
+#[cfg(feature = "regex_cache")]
+pub mod regex_cache;
+#[cfg(feature = "registry")]
+pub mod registry;
+#[cfg(feature = "retry")]
+pub mod retry;
+#[cfg(feature = "rule_cache")]
+pub mod rule_cache;
+#[cfg(feature = "rules")]
+pub mod rules;
+#[cfg(feature = "schema_hash")]
+pub mod schema_hash;
+#[cfg(feature = "secret")]
+pub mod secret;
+#[cfg(any(
+    feature = "canonical_key",
+    feature = "defaults",
+    feature = "diff",
+    feature = "enriched",
+    feature = "hashed",
+    feature = "signed"
+))]
+mod serialized;
+#[cfg(feature = "severity")]
+pub mod severity;
+#[cfg(feature = "signed")]
+pub mod signed;
+#[cfg(feature = "status_map")]
+pub mod status_map;
+#[cfg(feature = "str_limit")]
+pub mod str_limit;
+#[cfg(feature = "streaming_json")]
+pub mod streaming_json;
+#[cfg(feature = "strip")]
+pub mod strip;
+#[cfg(feature = "text_form")]
+pub mod text_form;
+#[cfg(feature = "timestamped")]
+pub mod timestamped;
+#[cfg(feature = "timing")]
+pub mod timing;
+#[cfg(feature = "trailer")]
+pub mod trailer;
+#[cfg(feature = "transform")]
+pub mod transform;
 #[cfg(feature = "typed_header")]
 pub mod typed_header;
+#[cfg(feature = "unknown_fields")]
+pub mod unknown_fields;
+#[cfg(feature = "untagged")]
+pub mod untagged;
 #[cfg(feature = "validator")]
 pub mod validator;
 #[cfg(feature = "validify")]
 pub mod validify;
+#[cfg(feature = "verified_upload")]
+pub mod verified_upload;
+#[cfg(feature = "with_raw")]
+pub mod with_raw;
 #[cfg(feature = "yaml")]
 pub mod yaml;
 
@@ -29,6 +180,8 @@ pub mod sonic;
 pub mod toml;
 #[cfg(feature = "typed_multipart")]
 pub mod typed_multipart;
+#[cfg(feature = "typed_query")]
+pub mod typed_query;
 #[cfg(feature = "xml")]
 pub mod xml;
 
@@ -55,6 +208,28 @@ pub trait HasValidate {
     fn get_validate(&self) -> &Self::Validate;
 }
 
+#[cfg(any(
+    feature = "body_size",
+    feature = "checksum",
+    feature = "compressed",
+    feature = "finite_json",
+    feature = "key_limit",
+    feature = "str_limit",
+    feature = "with_raw"
+))]
+pub use crate::buffered::BufferedRejection;
+#[cfg(feature = "error_body")]
+pub use crate::error_body::{ErrorBody, FieldError};
+#[cfg(any(
+    feature = "canonical_key",
+    feature = "defaults",
+    feature = "diff",
+    feature = "enriched",
+    feature = "hashed",
+    feature = "signed"
+))]
+pub use crate::serialized::SerializedRejection;
+
 #[cfg(feature = "validator")]
 pub use crate::validator::{HasValidateArgs, Valid, ValidEx, ValidRejection};
 
@@ -72,6 +247,11 @@ pub use crate::validify::{
 /// This enumeration captures two types of errors that can occur when using `Valid`: errors related to the validation
 /// extractor itself , and errors that may arise within the inner extractor (represented by `Inner`).
 ///
+/// Responses to `HEAD` requests must not have a body. When the rejection is returned from a handler
+/// routed by `axum::Router`, the body is omitted for `HEAD` requests, while the status code and headers
+/// (including `Content-Length`) are kept. See `head::ValidHead` (`head` feature) to configure how `HEAD` requests
+/// are validated and rejected.
+///
 #[derive(Debug)]
 pub enum ValidationRejection<V, E> {
     /// `Valid` variant captures errors related to the validation logic.
@@ -80,6 +260,35 @@ pub enum ValidationRejection<V, E> {
     Inner(E),
 }
 
+impl<V, E> ValidationRejection<V, E> {
+    /// Convert the rejection into an application-defined error type.
+    ///
+    /// This allows validation failures and inner extractor failures to be mapped into
+    /// a central error type (e.g. an `AppError` enum) that renders responses on its own:
+    ///
+    /// ```ignore
+    /// impl<E> From<ValidRejection<E>> for AppError
+    /// where
+    ///     AppError: From<E>,
+    /// {
+    ///     fn from(rejection: ValidRejection<E>) -> Self {
+    ///         rejection.into_app_error()
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// With such an implementation, `WithRejection<Valid<T>, AppError>` from `axum-extra` can be used in handlers.
+    pub fn into_app_error<A>(self) -> A
+    where
+        A: From<V> + From<E>,
+    {
+        match self {
+            ValidationRejection::Valid(v) => A::from(v),
+            ValidationRejection::Inner(e) => A::from(e),
+        }
+    }
+}
+
 impl<V: Display, E: Display> Display for ValidationRejection<V, E> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -153,6 +362,7 @@ mod tests {
         /// Use crate::VALIDATION_ERROR_STATUS by default.
         const INVALID_STATUS_CODE: StatusCode = crate::VALIDATION_ERROR_STATUS;
         /// If the response body can be serialized into JSON format
+        #[cfg(feature = "into_json")]
         const JSON_SERIALIZABLE: bool = true;
         /// Build a valid request, the server should return `200 OK`.
         fn set_valid_request(builder: RequestBuilder) -> RequestBuilder;
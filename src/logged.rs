@@ -0,0 +1,296 @@
+//! # Logging of validation failures
+//!
+//! ## Feature
+//!
+//! Enable the `logged` feature to use `ValidLogged<E>`.
+//!
+//! `ValidLogged<E>` works like `Valid<E>`, and also emits a `tracing` event at the `INFO` level,
+//! with the target `axum_valid::logged`, when validation fails. The event has the errors as an `ErrorBody`
+//! JSON array in its `errors` field, so that failures can be investigated from the logs.
+//!
+//! Validation errors may carry the values of invalid fields, e.g. the `value` parameter of `length` errors,
+//! which shouldn't be written to logs for sensitive fields such as card numbers.
+//! Mark such fields with `#[redact]` and derive `SensitiveFields`, or implement it to list the paths of the fields,
+//! e.g. `password` or `card.number`:
+//! everything that may carry the value in their errors, and in errors of their nested fields,
+//! is replaced with `[REDACTED]` in the event, see `FieldError::redact`. That's `actual`, the message
+//! and all parameters but the bounds of `length` and `range`. The masking only applies to the event: the rejection still carries the full errors,
+//! and the handler receives the full values on success. To redact values in error responses too, see `Secret<T>`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::error_body::SensitiveFields;
+//! use axum_valid::logged::ValidLogged;
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize, SensitiveFields)]
+//! pub struct Payment {
+//!     #[validate(length(min = 12, max = 19))]
+//!     #[redact]
+//!     pub card_number: String,
+//!     #[validate(range(min = 1))]
+//!     pub amount: u64,
+//! }
+//!
+//! async fn handler(ValidLogged(Json(payment)): ValidLogged<Json<Payment>>) {
+//!     println!("payment of {}", payment.amount);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/payments", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::SensitiveFields;
+use crate::validator::validate_redacted;
+use crate::{ErrorBody, HasValidate, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use std::ops::{Deref, DerefMut};
+use validator::{Validate, ValidationErrors};
+
+/// Log validation errors, masking the values of `masked` fields
+fn log_errors(errors: &ValidationErrors, masked: &[&str]) {
+    let errors = ErrorBody::from(errors).redact(masked).errors;
+    let errors = serde_json::to_string(&errors).unwrap_or_default();
+    tracing::info!(target: "axum_valid::logged", errors = %errors, "validation failed");
+}
+
+/// # `ValidLogged` data extractor
+///
+/// See the [module documentation](crate::logged) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidLogged<E>(pub E);
+
+impl<E> Deref for ValidLogged<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidLogged<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidLogged<E> {
+    /// Consumes the `ValidLogged` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidLogged<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequest<State>,
+    Extractor::Validate: Validate + SensitiveFields,
+{
+    type Rejection = ValidRejection<<Extractor as FromRequest<State>>::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(ValidRejection::Inner)?;
+        validate_redacted(inner.get_validate()).inspect_err(|errors| {
+            log_errors(errors, Extractor::Validate::SENSITIVE_FIELDS);
+        })?;
+        Ok(ValidLogged(inner))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequestParts<State> for ValidLogged<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequestParts<State>,
+    Extractor::Validate: Validate + SensitiveFields,
+{
+    type Rejection = ValidRejection<<Extractor as FromRequestParts<State>>::Rejection>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request_parts(parts, state)
+            .await
+            .map_err(ValidRejection::Inner)?;
+        validate_redacted(inner.get_validate()).inspect_err(|errors| {
+            log_errors(errors, Extractor::Validate::SENSITIVE_FIELDS);
+        })?;
+        Ok(ValidLogged(inner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::rejection::JsonRejection;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    #[derive(Debug, serde::Deserialize, Validate, SensitiveFields)]
+    struct Payment {
+        #[validate(length(min = 12, max = 19))]
+        #[redact]
+        card_number: String,
+        #[validate(length(max = 8))]
+        reference: String,
+        #[serde(default)]
+        #[validate(range(min = 1000, max = 9999))]
+        #[redact]
+        pin: Option<u32>,
+    }
+
+    /// Subscriber recording the fields of events
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Recorder {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if let Ok(mut events) = self.0.lock() {
+                events.push(format!("{}={value:?}", field.name()));
+            }
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut self.clone());
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    async fn extract(
+        body: &'static str,
+    ) -> anyhow::Result<Result<Payment, ValidRejection<JsonRejection>>> {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        Ok(ValidLogged::<Json<Payment>>::from_request(request, &())
+            .await
+            .map(|ValidLogged(Json(payment))| payment))
+    }
+
+    #[tokio::test]
+    async fn masked_values_are_not_logged() -> anyhow::Result<()> {
+        let recorder = Recorder::default();
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+
+        // the handler receives the full values
+        let payment =
+            extract(r#"{"card_number": "4111111111111111", "reference": "abc"}"#).await??;
+        assert_eq!(payment.card_number, "4111111111111111");
+        assert!(recorder
+            .0
+            .lock()
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .is_empty());
+
+        let rejection =
+            extract(r#"{"card_number": "4111-secret", "reference": "too long ref", "pin": 12345}"#)
+                .await?
+                .expect_err("invalid payment");
+        let events = recorder
+            .0
+            .lock()
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .join("\n");
+        assert!(events.contains("validation failed"));
+        assert!(events.contains("card_number"));
+        assert!(events.contains(crate::error_body::REDACTED));
+        assert!(!events.contains("4111-secret"));
+        // range errors copy the value into `actual`, which is masked too
+        assert!(events.contains("pin"));
+        assert!(!events.contains("12345"));
+        assert!(events.contains("9999"));
+        // unmasked values are logged
+        assert!(events.contains("too long ref"));
+
+        // the rejection still carries the full errors
+        let ValidRejection::Valid(errors) = rejection else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(
+            errors.field_errors()["card_number"][0].params["value"],
+            "4111-secret"
+        );
+        assert_eq!(errors.field_errors()["pin"][0].params["value"], 12345);
+        Ok(())
+    }
+
+    #[cfg(feature = "secret")]
+    #[tokio::test]
+    async fn secret_values_are_not_logged() -> anyhow::Result<()> {
+        use crate::secret::Secret;
+        use axum::response::IntoResponse;
+
+        #[derive(serde::Deserialize, Validate, SensitiveFields)]
+        struct Login {
+            #[validate(length(min = 8))]
+            token: Secret<String>,
+        }
+
+        let recorder = Recorder::default();
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"token": "leaked"}"#))?;
+        let Some(rejection) = ValidLogged::<Json<Login>>::from_request(request, &())
+            .await
+            .err()
+        else {
+            panic!("login should be invalid");
+        };
+        let events = recorder
+            .0
+            .lock()
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .join("\n");
+        assert!(events.contains("validation failed"));
+        assert!(events.contains("token"));
+        assert!(!events.contains("leaked"));
+
+        let response = rejection.into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        assert!(!String::from_utf8(body.to_vec())?.contains("leaked"));
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+//! # Validation depending on the request method
+//!
+//! ## Feature
+//!
+//! Enable the `method` feature to use `ValidMethod<E>`.
+//!
+//! The same type is often used for several methods of a resource, but with different rules,
+//! e.g. a field required to create a resource with `POST` may be omitted to replace it with `PUT`.
+//! `ValidMethod<E>` works like `ValidEx<E>`, but the validation arguments are the method of the request,
+//! so that rules can be selected by method with `#[validate(context = Method)]` and `use_context`.
+//!
+//! `method::required_for` checks that a field is provided for some methods only.
+//! It reports the code `required_for_method`, with the field name and the request method as its
+//! `field` and `method` parameters.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::http::Method;
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::method::{self, ValidMethod};
+//! use serde::Deserialize;
+//! use validator::{Validate, ValidationError};
+//!
+//! #[derive(Validate, Deserialize)]
+//! #[validate(context = Method)]
+//! #[validate(schema(function = "validate_article", use_context))]
+//! pub struct Article {
+//!     #[validate(length(min = 1, max = 64))]
+//!     pub title: Option<String>,
+//! }
+//!
+//! fn validate_article(article: &Article, method: &Method) -> Result<(), ValidationError> {
+//!     method::required_for(("title", article.title.is_some()), &[Method::POST], method)
+//! }
+//!
+//! async fn handler(ValidMethod(Json(article)): ValidMethod<Json<Article>>) {
+//!     println!("title = {:?}", article.title);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/articles", post(handler).put(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::redacting;
+use crate::{HasValidateArgs, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::Method;
+use std::borrow::Cow;
+use std::ops::{Deref, DerefMut};
+use validator::{ValidateArgs, ValidationError};
+
+/// Check that a field is provided if the request method is one of `methods`
+///
+/// `field` is the field name and whether it is provided, `method` is the request method,
+/// the validation argument of `ValidMethod`.
+pub fn required_for(
+    (field, provided): (&'static str, bool),
+    methods: &[Method],
+    method: &Method,
+) -> Result<(), ValidationError> {
+    if provided || !methods.contains(method) {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("required_for_method");
+    error.message = Some(Cow::from(format!(
+        "`{field}` is required for {method} requests"
+    )));
+    error.add_param(Cow::from("field"), &field);
+    error.add_param(Cow::from("method"), &method.as_str());
+    Err(error)
+}
+
+/// # `ValidMethod` data extractor
+///
+/// See the [module documentation](crate::method) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidMethod<E>(pub E);
+
+impl<E> Deref for ValidMethod<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidMethod<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidMethod<E> {
+    /// Consumes the `ValidMethod` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidMethod<Extractor>
+where
+    State: Send + Sync,
+    Extractor: for<'v> HasValidateArgs<'v> + FromRequest<State>,
+    for<'v> <Extractor as HasValidateArgs<'v>>::ValidateArgs: ValidateArgs<'v, Args = &'v Method>,
+{
+    type Rejection = ValidRejection<<Extractor as FromRequest<State>>::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let method = req.method().clone();
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(ValidRejection::Inner)?;
+        redacting(|| inner.get_validate_args().validate_with_args(&method))?;
+        Ok(ValidMethod(inner))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequestParts<State> for ValidMethod<Extractor>
+where
+    State: Send + Sync,
+    Extractor: for<'v> HasValidateArgs<'v> + FromRequestParts<State>,
+    for<'v> <Extractor as HasValidateArgs<'v>>::ValidateArgs: ValidateArgs<'v, Args = &'v Method>,
+{
+    type Rejection = ValidRejection<<Extractor as FromRequestParts<State>>::Rejection>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request_parts(parts, state)
+            .await
+            .map_err(ValidRejection::Inner)?;
+        redacting(|| inner.get_validate_args().validate_with_args(&parts.method))?;
+        Ok(ValidMethod(inner))
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::rejection::JsonRejection;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+    use validator::{Validate, ValidationErrorsKind};
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    #[validate(context = Method)]
+    #[validate(schema(function = "validate_article", use_context))]
+    struct Article {
+        #[validate(length(min = 1, max = 64))]
+        title: Option<String>,
+        #[validate(length(max = 1024))]
+        body: String,
+    }
+
+    fn validate_article(article: &Article, method: &Method) -> Result<(), ValidationError> {
+        required_for(("title", article.title.is_some()), &[Method::POST], method)
+    }
+
+    async fn extract(
+        method: Method,
+        body: &'static str,
+    ) -> anyhow::Result<Result<Article, ValidRejection<JsonRejection>>> {
+        let request = Request::builder()
+            .method(method)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        Ok(ValidMethod::<Json<Article>>::from_request(request, &())
+            .await
+            .map(|ValidMethod(Json(article))| article))
+    }
+
+    #[tokio::test]
+    async fn required_for_post_only() -> anyhow::Result<()> {
+        let article = extract(Method::PUT, r#"{"body": "text"}"#).await??;
+        assert_eq!(article.title, None);
+
+        let Err(ValidRejection::Valid(errors)) =
+            extract(Method::POST, r#"{"body": "text"}"#).await?
+        else {
+            panic!("title is required for POST");
+        };
+        let ValidationErrorsKind::Field(errors) = &errors.errors()["__all__"] else {
+            panic!("expected schema errors");
+        };
+        assert_eq!(errors[0].code, "required_for_method");
+        assert_eq!(errors[0].params["method"], "POST");
+        assert_eq!(
+            errors[0].message.as_deref(),
+            Some("`title` is required for POST requests")
+        );
+
+        let article = extract(Method::POST, r#"{"title": "Hello", "body": "text"}"#).await??;
+        assert_eq!(article.title.as_deref(), Some("Hello"));
+
+        // other rules apply to all methods
+        assert!(matches!(
+            extract(Method::PUT, r#"{"title": "", "body": "text"}"#).await?,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("title")
+        ));
+        Ok(())
+    }
+}
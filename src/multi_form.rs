@@ -0,0 +1,296 @@
+//! # Support for `MultiForm<T>`
+//!
+//! ## Feature
+//!
+//! Enable the `multi_form` feature to use `Valid<MultiForm<T>>`.
+//!
+//! HTML checkbox groups and multi-selects submit one key per selected value, e.g. `tag=a&tag=b`.
+//! `axum::Form<T>` can't deserialize repeated keys into a `Vec`, so the values are lost or rejected.
+//! `MultiForm<T>` works like `axum::Form<T>`, but collects repeated keys into sequences,
+//! so that the whole `Vec` can be validated. A single occurrence is deserialized as a sequence of one value.
+//!
+//! Unlike `axum_extra::extract::Form`, this doesn't require the `extra` feature.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::Router;
+//!     use axum_valid::multi_form::MultiForm;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/multi_form", post(handler))
+//!     }
+//!
+//!     async fn handler(Valid(MultiForm(parameter)): Valid<MultiForm<Parameter>>) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[serde(default)]
+//!         #[validate(length(min = 1, max = 3))]
+//!         pub tag: Vec<String>,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "validator")]
+use validator::ValidateArgs;
+
+/// # `MultiForm` extractor
+///
+/// See the [module documentation](crate::multi_form) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MultiForm<T>(pub T);
+
+impl<T> Deref for MultiForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for MultiForm<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// `MultiFormRejection` is returned when the `MultiForm` extractor fails.
+#[derive(Debug)]
+pub enum MultiFormRejection {
+    /// The content type is not `application/x-www-form-urlencoded`
+    InvalidContentType,
+    /// The body can't be read
+    Bytes(BytesRejection),
+    /// The query string of a `GET` or `HEAD` request can't be deserialized
+    Query(serde_html_form::de::Error),
+    /// The body can't be deserialized
+    Body(serde_html_form::de::Error),
+}
+
+impl Display for MultiFormRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MultiFormRejection::InvalidContentType => write!(
+                f,
+                "Form requests must have `Content-Type: application/x-www-form-urlencoded`"
+            ),
+            MultiFormRejection::Bytes(e) => write!(f, "{e}"),
+            MultiFormRejection::Query(e) | MultiFormRejection::Body(e) => {
+                write!(f, "Failed to deserialize form: {e}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MultiFormRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MultiFormRejection::InvalidContentType => None,
+            MultiFormRejection::Bytes(e) => Some(e),
+            MultiFormRejection::Query(e) | MultiFormRejection::Body(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for MultiFormRejection {
+    fn into_response(self) -> Response {
+        // Same status codes as `FormRejection`
+        let status = match self {
+            MultiFormRejection::Bytes(e) => return e.into_response(),
+            MultiFormRejection::InvalidContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            MultiFormRejection::Query(_) => StatusCode::BAD_REQUEST,
+            MultiFormRejection::Body(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for MultiForm<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = MultiFormRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if req.method() == Method::GET || req.method() == Method::HEAD {
+            let query = req.uri().query().unwrap_or_default();
+            return serde_html_form::from_str(query)
+                .map(MultiForm)
+                .map_err(MultiFormRejection::Query);
+        }
+
+        let is_form = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+        if !is_form {
+            return Err(MultiFormRejection::InvalidContentType);
+        }
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(MultiFormRejection::Bytes)?;
+        serde_html_form::from_bytes(&bytes)
+            .map(MultiForm)
+            .map_err(MultiFormRejection::Body)
+    }
+}
+
+impl<T> HasValidate for MultiForm<T> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>> HasValidateArgs<'v> for MultiForm<T> {
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify> crate::HasModify for MultiForm<T> {
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T> crate::PayloadExtractor for MultiForm<T> {
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload> crate::HasValidify for MultiForm<T> {
+    type Validify = T;
+    type PayloadExtractor = MultiForm<T::Payload>;
+    fn from_validify(v: Self::Validify) -> Self {
+        MultiForm(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn request(body: &'static str) -> anyhow::Result<Request> {
+        Ok(Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(body))?)
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn validate_checkbox_group() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[serde(default)]
+            #[validate(length(min = 1, max = 3))]
+            tag: Vec<String>,
+            name: String,
+        }
+
+        let Valid(MultiForm(parameters)) = Valid::<MultiForm<Parameters>>::from_request(
+            request("tag=a&name=n&tag=b&tag=c%26d")?,
+            &(),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.tag, ["a", "b", "c&d"]);
+        assert_eq!(parameters.name, "n");
+
+        let Valid(MultiForm(parameters)) =
+            Valid::<MultiForm<Parameters>>::from_request(request("tag=a&name=n")?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.tag, ["a"]);
+
+        for body in ["name=n", "tag=a&tag=b&tag=c&tag=d&name=n"] {
+            assert!(matches!(
+                Valid::<MultiForm<Parameters>>::from_request(request(body)?, &()).await,
+                Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("tag")
+            ));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reject_non_form_body() -> anyhow::Result<()> {
+        #[derive(Debug, serde::Deserialize)]
+        struct Parameters {
+            #[allow(dead_code)]
+            tag: Vec<u32>,
+        }
+
+        let json = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"tag": [1]}"#))?;
+        let rejection = MultiForm::<Parameters>::from_request(json, &())
+            .await
+            .expect_err("json is not a form");
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+
+        let rejection = MultiForm::<Parameters>::from_request(request("tag=1&tag=x")?, &())
+            .await
+            .expect_err("x is not a number");
+        assert!(matches!(rejection, MultiFormRejection::Body(_)));
+        Ok(())
+    }
+}
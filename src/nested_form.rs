@@ -0,0 +1,585 @@
+//! # Support for `NestedForm<T>`
+//!
+//! ## Feature
+//!
+//! Enable the `nested_form` feature to use `Valid<NestedForm<T>>`.
+//!
+//! Many server-side frameworks, starting with PHP, name form fields with a bracket notation
+//! to submit nested data, e.g. `user[name]=x&user[age]=30`. `axum::Form<T>` only supports flat forms.
+//! `NestedForm<T>` works like `axum::Form<T>`, but parses the bracket notation first,
+//! so that the form can be deserialized into nested structs, which are then validated like any other data:
+//!
+//! * `user[name]=x` sets the `name` field of the `user` struct.
+//! * `tags[]=a&tags[]=b` appends to the `tags` sequence, repeated keys such as `tag=a&tag=b` are collected too.
+//! * `items[0][name]=x&items[1][name]=y` builds a sequence of structs, ordered by their indices.
+//!
+//! Values are parsed into numbers or booleans when the fields require them.
+//! Keys with more than 32 segments are rejected.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::Router;
+//!     use axum_valid::nested_form::NestedForm;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/nested_form", post(handler))
+//!     }
+//!
+//!     // `user[name]=x&user[age]=30`
+//!     async fn handler(Valid(NestedForm(parameter)): Valid<NestedForm<Parameter>>) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(nested)]
+//!         pub user: User,
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     #[validate(nested)]
+//!     pub struct User {
+//!         #[validate(length(min = 1, max = 32))]
+//!         pub name: String,
+//!         #[validate(range(min = 18))]
+//!         pub age: u8,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+use serde::de::{DeserializeOwned, Error as _, IntoDeserializer, Visitor};
+use serde::{forward_to_deserialize_any, Deserializer};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "validator")]
+use validator::ValidateArgs;
+
+/// # `NestedForm` extractor
+///
+/// See the [module documentation](crate::nested_form) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NestedForm<T>(pub T);
+
+impl<T> Deref for NestedForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for NestedForm<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Maximum number of segments in a form key, deeper keys are rejected
+const MAX_DEPTH: usize = 32;
+
+/// Parsed form data, before deserialization
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Leaf(String),
+    Seq(Vec<Node>),
+    Map(Entries),
+}
+
+/// Entries of a map in insertion order, indexed by key
+#[derive(Debug, Clone, Default)]
+struct Entries {
+    items: Vec<(String, Node)>,
+    index: HashMap<String, usize>,
+}
+
+impl PartialEq for Entries {
+    fn eq(&self, other: &Self) -> bool {
+        self.items == other.items
+    }
+}
+
+impl Entries {
+    fn push(&mut self, key: &str, node: Node) -> usize {
+        let position = self.items.len();
+        self.index.insert(key.to_owned(), position);
+        self.items.push((key.to_owned(), node));
+        position
+    }
+
+    /// Insert the value of a key into the map
+    fn insert(&mut self, key: &str, rest: &[&str], value: String) -> Result<(), Error> {
+        let position = self.index.get(key).copied();
+        let conflict = || Error::custom(format_args!("conflicting form keys for `{key}`"));
+        match rest.split_first() {
+            // `key=value` or `key[]=value`, repeated keys are collected into a sequence
+            None | Some((&"", _)) => match position.map(|index| &mut self.items[index].1) {
+                None if rest.is_empty() => {
+                    self.push(key, Node::Leaf(value));
+                }
+                None => {
+                    self.push(key, Node::Seq(vec![Node::Leaf(value)]));
+                }
+                Some(node @ Node::Leaf(_)) => {
+                    let previous = std::mem::replace(node, Node::Seq(Vec::new()));
+                    *node = Node::Seq(vec![previous, Node::Leaf(value)]);
+                }
+                Some(Node::Seq(items)) => items.push(Node::Leaf(value)),
+                Some(Node::Map(_)) => return Err(conflict()),
+            },
+            Some((next, rest)) => {
+                let index =
+                    position.unwrap_or_else(|| self.push(key, Node::Map(Entries::default())));
+                let Node::Map(children) = &mut self.items[index].1 else {
+                    return Err(conflict());
+                };
+                children.insert(next, rest, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split a key such as `user[address][city]` into its segments
+fn segments(key: &str) -> Result<Vec<&str>, Error> {
+    let invalid = || Error::custom(format_args!("invalid form key `{key}`"));
+    let Some(start) = key.find('[') else {
+        return Ok(vec![key]);
+    };
+    let (head, mut rest) = key.split_at(start);
+    if head.is_empty() {
+        return Err(invalid());
+    }
+    let mut segments = vec![head];
+    while !rest.is_empty() {
+        if segments.len() == MAX_DEPTH {
+            return Err(Error::custom(format_args!(
+                "form key `{key}` is nested too deeply"
+            )));
+        }
+        let (segment, tail) = rest
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .ok_or_else(invalid)?;
+        segments.push(segment);
+        rest = tail;
+    }
+    if segments[1..segments.len() - 1].contains(&"") {
+        // `[]` appends a value, so it can't be followed by other segments
+        return Err(invalid());
+    }
+    Ok(segments)
+}
+
+/// Parse an `application/x-www-form-urlencoded` string with bracket notation
+fn parse(input: &[u8]) -> Result<Node, Error> {
+    let mut entries = Entries::default();
+    for (key, value) in form_urlencoded::parse(input) {
+        let segments = segments(&key)?;
+        entries.insert(segments[0], &segments[1..], value.into_owned())?;
+    }
+    Ok(Node::Map(entries))
+}
+
+/// Deserialize a parsed form into `T`
+fn from_bytes<T: DeserializeOwned>(input: &[u8]) -> Result<T, Error> {
+    T::deserialize(parse(input)?)
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self {
+                    Node::Leaf(value) => visitor.$visit(value.parse().map_err(Error::custom)?),
+                    node => node.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Node {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Node::Leaf(value) => visitor.visit_string(value),
+            Node::Seq(items) => SeqDeserializer::new(items.into_iter()).deserialize_any(visitor),
+            Node::Map(entries) => {
+                MapDeserializer::new(entries.items.into_iter()).deserialize_any(visitor)
+            }
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // absent keys are missing fields, so any present value is `Some`
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let items = match self {
+            // a single occurrence of a repeatable key
+            leaf @ Node::Leaf(_) => vec![leaf],
+            Node::Seq(items) => items,
+            Node::Map(entries) => {
+                // `items[0][name]`, ordered by index
+                let mut indexed = Vec::with_capacity(entries.items.len());
+                for (key, node) in entries.items {
+                    let index = key.parse::<usize>().map_err(|_| {
+                        Error::custom(format_args!("invalid sequence index `{key}`"))
+                    })?;
+                    indexed.push((index, node));
+                }
+                indexed.sort_by_key(|(index, _)| *index);
+                indexed.into_iter().map(|(_, node)| node).collect()
+            }
+        };
+        SeqDeserializer::new(items.into_iter()).deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Node::Leaf(value) => IntoDeserializer::<Error>::into_deserializer(value)
+                .deserialize_enum(name, variants, visitor),
+            node => node.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Node {
+    type Deserializer = Node;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// `NestedFormRejection` is returned when the `NestedForm` extractor fails.
+#[derive(Debug)]
+pub enum NestedFormRejection {
+    /// The content type is not `application/x-www-form-urlencoded`
+    InvalidContentType,
+    /// The body can't be read
+    Bytes(BytesRejection),
+    /// The query string of a `GET` or `HEAD` request can't be deserialized
+    Query(Error),
+    /// The body can't be deserialized
+    Body(Error),
+}
+
+impl Display for NestedFormRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NestedFormRejection::InvalidContentType => write!(
+                f,
+                "Form requests must have `Content-Type: application/x-www-form-urlencoded`"
+            ),
+            NestedFormRejection::Bytes(e) => write!(f, "{e}"),
+            NestedFormRejection::Query(e) | NestedFormRejection::Body(e) => {
+                write!(f, "Failed to deserialize form: {e}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NestedFormRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NestedFormRejection::InvalidContentType => None,
+            NestedFormRejection::Bytes(e) => Some(e),
+            NestedFormRejection::Query(e) | NestedFormRejection::Body(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for NestedFormRejection {
+    fn into_response(self) -> Response {
+        // Same status codes as `FormRejection`
+        let status = match self {
+            NestedFormRejection::Bytes(e) => return e.into_response(),
+            NestedFormRejection::InvalidContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            NestedFormRejection::Query(_) => StatusCode::BAD_REQUEST,
+            NestedFormRejection::Body(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for NestedForm<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = NestedFormRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if req.method() == Method::GET || req.method() == Method::HEAD {
+            let query = req.uri().query().unwrap_or_default();
+            return from_bytes(query.as_bytes())
+                .map(NestedForm)
+                .map_err(NestedFormRejection::Query);
+        }
+
+        let is_form = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+        if !is_form {
+            return Err(NestedFormRejection::InvalidContentType);
+        }
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(NestedFormRejection::Bytes)?;
+        from_bytes(&bytes)
+            .map(NestedForm)
+            .map_err(NestedFormRejection::Body)
+    }
+}
+
+impl<T> HasValidate for NestedForm<T> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>> HasValidateArgs<'v> for NestedForm<T> {
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify> crate::HasModify for NestedForm<T> {
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T> crate::PayloadExtractor for NestedForm<T> {
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload> crate::HasValidify for NestedForm<T> {
+    type Validify = T;
+    type PayloadExtractor = NestedForm<T::Payload>;
+    fn from_validify(v: Self::Validify) -> Self {
+        NestedForm(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn request(body: &'static str) -> anyhow::Result<Request> {
+        Ok(Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from(body))?)
+    }
+
+    #[test]
+    fn parse_bracket_notation() -> anyhow::Result<()> {
+        let leaf = |value: &str| Node::Leaf(value.to_owned());
+        let map = |items: Vec<(String, Node)>| {
+            let mut entries = Entries::default();
+            for (key, node) in items {
+                entries.push(&key, node);
+            }
+            Node::Map(entries)
+        };
+        assert_eq!(
+            parse(b"user[name]=x&user[address][city]=y&tags[]=a&tags[]=b&tag=c&tag=d")?,
+            map(vec![
+                (
+                    "user".into(),
+                    map(vec![
+                        ("name".into(), leaf("x")),
+                        ("address".into(), map(vec![("city".into(), leaf("y"))])),
+                    ])
+                ),
+                ("tags".into(), Node::Seq(vec![leaf("a"), leaf("b")])),
+                ("tag".into(), Node::Seq(vec![leaf("c"), leaf("d")])),
+            ])
+        );
+        for invalid in [
+            "user[name=x",
+            "[name]=x",
+            "user[]x=1",
+            "tags[][a]=1",
+            "user=x&user[name]=y",
+        ] {
+            assert!(parse(invalid.as_bytes()).is_err(), "{invalid}");
+        }
+
+        let deep = |depth: usize| format!("a{}=x", "[a]".repeat(depth - 1));
+        assert!(parse(deep(MAX_DEPTH).as_bytes()).is_ok());
+        assert!(parse(deep(MAX_DEPTH + 1).as_bytes()).is_err());
+        assert!(parse(deep(10_000).as_bytes()).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn validate_nested_fields() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[validate(nested)]
+            user: User,
+            #[serde(default)]
+            items: Vec<Item>,
+        }
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(nested)]
+        struct User {
+            #[validate(length(min = 1, max = 5))]
+            name: String,
+            #[validate(range(min = 18))]
+            age: u8,
+            newsletter: Option<bool>,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        struct Item {
+            quantity: u32,
+        }
+
+        let Valid(NestedForm(parameters)) = Valid::<NestedForm<Parameters>>::from_request(
+            request("user[name]=x&user[age]=30&items[1][quantity]=2&items[0][quantity]=1")?,
+            &(),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.user.name, "x");
+        assert_eq!(parameters.user.age, 30);
+        assert_eq!(parameters.user.newsletter, None);
+        assert_eq!(parameters.items[0].quantity, 1);
+        assert_eq!(parameters.items[1].quantity, 2);
+
+        let rejection = Valid::<NestedForm<Parameters>>::from_request(
+            request("user[name]=toolong&user[age]=12&user[newsletter]=true")?,
+            &(),
+        )
+        .await
+        .expect_err("invalid user");
+        let ValidRejection::Valid(errors) = rejection else {
+            panic!("expected validation errors");
+        };
+        let validator::ValidationErrorsKind::Struct(user) = &errors.errors()["user"] else {
+            panic!("expected errors of `user`");
+        };
+        let fields = user.field_errors();
+        assert_eq!(fields["name"][0].code, "length");
+        assert_eq!(fields["age"][0].code, "range");
+
+        assert!(matches!(
+            Valid::<NestedForm<Parameters>>::from_request(
+                request("user[name]=x&user[age]=old")?,
+                &()
+            )
+            .await,
+            Err(ValidRejection::Inner(NestedFormRejection::Body(_)))
+        ));
+        Ok(())
+    }
+}
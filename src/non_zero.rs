@@ -0,0 +1,289 @@
+//! # Range validation for `NonZero*` integers
+//!
+//! ## Feature
+//!
+//! Enable the `non_zero` feature to use `NonZeroField<T>`.
+//!
+//! Neither validator nor garde implement their `range` rules for `std::num::NonZero*` types.
+//! Wrap such fields in `NonZeroField<T>` to apply `range` rules to them.
+//!
+//! Zero is still rejected while deserializing, so the inner extractor fails with its own rejection
+//! (e.g. `400 Bad Request` for `Query`), while values out of range are reported as validation errors.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::extract::Query;
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::non_zero::NonZeroField;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use std::num::NonZeroU32;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/query", get(handler))
+//!     }
+//!
+//!     async fn handler(Valid(Query(parameter)): Valid<Query<Parameter>>) {
+//!         assert!(parameter.validate().is_ok());
+//!         // Support automatic dereferencing
+//!         println!("id = {}", parameter.id.get());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(range(min = 1, max = 1000))]
+//!         pub id: NonZeroField<NonZeroU32>,
+//!     }
+//! }
+//!
+//! #[cfg(feature = "garde")]
+//! mod garde_example {
+//!     use axum::extract::Query;
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::non_zero::NonZeroField;
+//!     use axum_valid::Garde;
+//!     use garde::Validate;
+//!     use serde::Deserialize;
+//!     use std::num::NonZeroU32;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/query", get(handler))
+//!     }
+//!
+//!     async fn handler(Garde(Query(parameter)): Garde<Query<Parameter>>) {
+//!         assert!(parameter.validate(&()).is_ok());
+//!         println!("id = {}", parameter.id.get());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[garde(range(min = 1, max = 1000))]
+//!         pub id: NonZeroField<NonZeroU32>,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     #[cfg(feature = "garde")]
+//! #     let router = router.nest("/garde", garde_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+};
+use std::ops::{Deref, DerefMut};
+
+/// Trait for `NonZero*` integer types
+pub trait NonZeroInteger: Copy {
+    /// The primitive integer type
+    type Primitive: Copy + PartialOrd + Display + Serialize;
+    /// The minimum value of the primitive type
+    const MIN: Self::Primitive;
+    /// The maximum value of the primitive type
+    const MAX: Self::Primitive;
+    /// Get the value as a primitive integer
+    fn get_primitive(self) -> Self::Primitive;
+}
+
+macro_rules! impl_non_zero_integer {
+    ($($nz:ty => $p:ty),* $(,)?) => {
+        $(
+            impl NonZeroInteger for $nz {
+                type Primitive = $p;
+                const MIN: $p = <$p>::MIN;
+                const MAX: $p = <$p>::MAX;
+                fn get_primitive(self) -> $p {
+                    self.get()
+                }
+            }
+        )*
+    };
+}
+
+impl_non_zero_integer!(
+    NonZeroU8 => u8,
+    NonZeroU16 => u16,
+    NonZeroU32 => u32,
+    NonZeroU64 => u64,
+    NonZeroU128 => u128,
+    NonZeroUsize => usize,
+    NonZeroI8 => i8,
+    NonZeroI16 => i16,
+    NonZeroI32 => i32,
+    NonZeroI64 => i64,
+    NonZeroI128 => i128,
+    NonZeroIsize => isize,
+);
+
+/// # `NonZero*` field supporting `range` rules
+///
+/// See the [module documentation](crate::non_zero) for details.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NonZeroField<T>(pub T);
+
+impl<T> Deref for NonZeroField<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for NonZeroField<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: Display> Display for NonZeroField<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> NonZeroField<T> {
+    /// Consumes the `NonZeroField` and returns the inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Serialize> Serialize for NonZeroField<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for NonZeroField<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(NonZeroField)
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<T: NonZeroInteger> validator::ValidateRange<T::Primitive> for NonZeroField<T> {
+    fn greater_than(&self, max: T::Primitive) -> Option<bool> {
+        Some(self.0.get_primitive() > max)
+    }
+
+    fn less_than(&self, min: T::Primitive) -> Option<bool> {
+        Some(self.0.get_primitive() < min)
+    }
+}
+
+#[cfg(feature = "garde")]
+impl<T: NonZeroInteger + PartialOrd> garde::rules::range::Bounds for NonZeroField<T> {
+    type Size = T::Primitive;
+    const MIN: Self::Size = T::MIN;
+    const MAX: Self::Size = T::MAX;
+
+    fn validate_bounds(
+        &self,
+        lower_bound: Self::Size,
+        upper_bound: Self::Size,
+    ) -> Result<(), garde::rules::range::OutOfBounds> {
+        use garde::rules::range::OutOfBounds;
+        let value = self.0.get_primitive();
+        if value < lower_bound {
+            Err(OutOfBounds::Lower)
+        } else if value > upper_bound {
+            Err(OutOfBounds::Upper)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "query"))]
+mod tests {
+    use super::*;
+    use axum::extract::{FromRequestParts, Query};
+    use axum::http::request::Parts;
+    use axum::http::Request;
+
+    fn parts(uri: &str) -> anyhow::Result<Parts> {
+        Ok(Request::builder().uri(uri).body(())?.into_parts().0)
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn validator_range_on_non_zero() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::Validate;
+
+        #[derive(Deserialize, Validate)]
+        struct Parameters {
+            #[validate(range(min = 1, max = 100))]
+            id: NonZeroField<NonZeroU32>,
+        }
+
+        let Valid(Query(parameters)) =
+            Valid::<Query<Parameters>>::from_request_parts(&mut parts("/?id=5")?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.id.get(), 5);
+
+        // zero is a deserialization error
+        assert!(matches!(
+            Valid::<Query<Parameters>>::from_request_parts(&mut parts("/?id=0")?, &()).await,
+            Err(ValidRejection::Inner(_))
+        ));
+
+        // out of range is a validation error
+        assert!(matches!(
+            Valid::<Query<Parameters>>::from_request_parts(&mut parts("/?id=101")?, &()).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["id"][0].code == "range"
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "garde")]
+    #[tokio::test]
+    async fn garde_range_on_non_zero() -> anyhow::Result<()> {
+        use crate::{Garde, GardeRejection};
+        use garde::Validate;
+
+        #[derive(Deserialize, Validate)]
+        struct Parameters {
+            #[garde(range(min = -10, max = 100))]
+            id: NonZeroField<NonZeroI64>,
+        }
+
+        let Garde(Query(parameters)) =
+            Garde::<Query<Parameters>>::from_request_parts(&mut parts("/?id=-5")?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.id.get(), -5);
+
+        assert!(matches!(
+            Garde::<Query<Parameters>>::from_request_parts(&mut parts("/?id=0")?, &()).await,
+            Err(GardeRejection::Inner(_))
+        ));
+        assert!(matches!(
+            Garde::<Query<Parameters>>::from_request_parts(&mut parts("/?id=101")?, &()).await,
+            Err(GardeRejection::Valid(_))
+        ));
+        Ok(())
+    }
+}
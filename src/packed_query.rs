@@ -0,0 +1,248 @@
+//! # Packed query parameters
+//!
+//! ## Feature
+//!
+//! Enable the `packed_query` feature to use `ValidPackedQuery<T>`.
+//!
+//! Some clients pack complex filters into a single query parameter, as a Protocol Buffers message
+//! encoded with URL-safe base64, e.g. `/search?q=CgVzaG9lcxAF`, to keep URLs short and typed.
+//! `ValidPackedQuery<T>` reads that parameter, decodes the base64 text, with or without padding,
+//! decodes the message into `T` with [`prost`](https://crates.io/crates/prost), and validates it.
+//!
+//! The parameter is named `q` (`DEFAULT_PACKED_PARAM`), implement `PackedMessage` with another `PARAM` to change it.
+//! A missing parameter, malformed base64 and malformed messages are rejected with `400 Bad Request`,
+//! invalid messages like with `Valid<E>`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::get;
+//! use axum::Router;
+//! use axum_valid::packed_query::{PackedMessage, ValidPackedQuery};
+//! use validator::Validate;
+//!
+//! #[derive(Validate, prost::Message)]
+//! pub struct Filter {
+//!     #[validate(length(min = 1, max = 32))]
+//!     #[prost(string, tag = "1")]
+//!     pub name: String,
+//!     #[validate(range(max = 100))]
+//!     #[prost(uint32, tag = "2")]
+//!     pub limit: u32,
+//! }
+//!
+//! impl PackedMessage for Filter {}
+//!
+//! async fn handler(ValidPackedQuery(filter): ValidPackedQuery<Filter>) -> String {
+//!     filter.name
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/search", get(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::ValidRejection;
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use base64::alphabet::URL_SAFE;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use validator::Validate;
+
+/// Default name of the query parameter holding the packed message
+pub const DEFAULT_PACKED_PARAM: &str = "q";
+
+/// URL-safe base64, with or without padding
+const PACKED: GeneralPurpose = GeneralPurpose::new(
+    &URL_SAFE,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Message packed into a query parameter
+pub trait PackedMessage: prost::Message + Default {
+    /// Name of the query parameter holding the message
+    const PARAM: &'static str = DEFAULT_PACKED_PARAM;
+}
+
+/// # `ValidPackedQuery` data extractor
+///
+/// See the [module documentation](crate::packed_query) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidPackedQuery<T>(pub T);
+
+impl<T> Deref for ValidPackedQuery<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ValidPackedQuery<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> ValidPackedQuery<T> {
+    /// Consumes the `ValidPackedQuery` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidPackedQuery`
+#[derive(Debug)]
+pub enum PackedQueryRejection {
+    /// The query parameter is missing
+    Missing(&'static str),
+    /// The query parameter isn't valid base64
+    Base64(base64::DecodeError),
+    /// The decoded bytes aren't a valid message
+    Decode(prost::DecodeError),
+}
+
+impl Display for PackedQueryRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackedQueryRejection::Missing(param) => {
+                write!(f, "Missing the `{param}` query parameter")
+            }
+            PackedQueryRejection::Base64(e) => {
+                write!(f, "Failed to decode the packed query parameter: {e}")
+            }
+            PackedQueryRejection::Decode(e) => {
+                write!(f, "Failed to decode the packed message: {e}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PackedQueryRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PackedQueryRejection::Missing(_) => None,
+            PackedQueryRejection::Base64(e) => Some(e),
+            PackedQueryRejection::Decode(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for PackedQueryRejection {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+    }
+}
+
+/// Decode the message packed into a query string
+fn unpack<T: PackedMessage>(query: &str) -> Result<T, PackedQueryRejection> {
+    let packed = form_urlencoded::parse(query.as_bytes())
+        .find(|(name, _)| name == T::PARAM)
+        .map(|(_, value)| value)
+        .ok_or(PackedQueryRejection::Missing(T::PARAM))?;
+    let bytes = PACKED
+        .decode(packed.as_bytes())
+        .map_err(PackedQueryRejection::Base64)?;
+    T::decode(bytes.as_slice()).map_err(PackedQueryRejection::Decode)
+}
+
+#[async_trait]
+impl<State, T> FromRequestParts<State> for ValidPackedQuery<T>
+where
+    State: Send + Sync,
+    T: PackedMessage + Validate,
+{
+    type Rejection = ValidRejection<PackedQueryRejection>;
+
+    async fn from_request_parts(parts: &mut Parts, _: &State) -> Result<Self, Self::Rejection> {
+        let data =
+            unpack::<T>(parts.uri.query().unwrap_or_default()).map_err(ValidRejection::Inner)?;
+        validate_redacted(&data)?;
+        Ok(ValidPackedQuery(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use prost::Message;
+
+    #[derive(Validate, prost::Message)]
+    struct Filter {
+        #[validate(length(min = 1, max = 10))]
+        #[prost(string, tag = "1")]
+        name: String,
+        #[validate(range(max = 100))]
+        #[prost(uint32, tag = "2")]
+        limit: u32,
+    }
+
+    impl PackedMessage for Filter {}
+
+    fn pack(name: &str, limit: u32) -> String {
+        let filter = Filter {
+            name: name.to_owned(),
+            limit,
+        };
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(filter.encode_to_vec())
+    }
+
+    async fn extract(
+        uri: &str,
+    ) -> anyhow::Result<Result<Filter, ValidRejection<PackedQueryRejection>>> {
+        let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+        Ok(
+            ValidPackedQuery::<Filter>::from_request_parts(&mut parts, &())
+                .await
+                .map(ValidPackedQuery::into_inner),
+        )
+    }
+
+    #[tokio::test]
+    async fn packed_filter_is_decoded_and_validated() -> anyhow::Result<()> {
+        let filter = extract(&format!("/search?page=2&q={}", pack("shoes", 5)))
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(filter.name, "shoes");
+        assert_eq!(filter.limit, 5);
+
+        let Err(ValidRejection::Valid(errors)) =
+            extract(&format!("/search?q={}", pack("shoes", 500))).await?
+        else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(errors.field_errors()["limit"][0].code, "range");
+
+        assert!(matches!(
+            extract("/search?page=2").await?,
+            Err(ValidRejection::Inner(PackedQueryRejection::Missing("q")))
+        ));
+        assert!(matches!(
+            extract("/search?q=not%20base64").await?,
+            Err(ValidRejection::Inner(PackedQueryRejection::Base64(_)))
+        ));
+        let Err(rejection @ ValidRejection::Inner(PackedQueryRejection::Decode(_))) =
+            extract("/search?q=_w").await?
+        else {
+            panic!("expected a malformed message");
+        };
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+//! # Best-effort parsing of fields
+//!
+//! ## Feature
+//!
+//! Enable the `parsed` feature to use `Parsed<T>`.
+//!
+//! Extractors such as `Query<T>` abort on the first field that can't be parsed,
+//! so the client only learns about one problem at a time, and never about validation errors.
+//! Wrap fields in `Parsed<T>` to make parsing best-effort: the field is parsed with `T::from_str`,
+//! and a failure is recorded instead of rejecting the whole request.
+//! The [`parsed`] validator rule then reports the parse failure as a validation error with the code `parse`,
+//! so parse errors and validation errors of other fields end up in the same `ValidationErrors`.
+//!
+//! Other rules (`range`, `length`) skip fields that failed to parse.
+//! Missing fields are still rejected by the inner extractor unless they are `Option<Parsed<T>>`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::extract::Query;
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::parsed::Parsed;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/query", get(handler))
+//!     }
+//!
+//!     async fn handler(Valid(Query(parameter)): Valid<Query<Parameter>>) {
+//!         assert!(parameter.validate().is_ok());
+//!         // Fields that passed validation are parsed
+//!         println!("page = {:?}", parameter.page.get());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(custom(function = "axum_valid::parsed::parsed"), range(min = 1))]
+//!         pub page: Parsed<u32>,
+//!         #[validate(custom(function = "axum_valid::parsed::parsed"), range(max = 100))]
+//!         pub size: Parsed<u32>,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use serde::de::{Error, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/// A field value that failed to parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFailure {
+    /// The raw value
+    pub raw: String,
+    /// The parse error message
+    pub message: String,
+}
+
+impl Display for ParseFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid value `{}`: {}", self.raw, self.message)
+    }
+}
+
+/// # Best-effort parsed field
+///
+/// See the [module documentation](crate::parsed) for details.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parsed<T>(pub Result<T, ParseFailure>);
+
+impl<T> Parsed<T> {
+    /// Get the parsed value, if parsing succeeded
+    pub fn get(&self) -> Option<&T> {
+        self.0.as_ref().ok()
+    }
+
+    /// Get the parse failure, if parsing failed
+    pub fn failure(&self) -> Option<&ParseFailure> {
+        self.0.as_ref().err()
+    }
+
+    /// Consumes the `Parsed` and returns the parse result
+    pub fn into_result(self) -> Result<T, ParseFailure> {
+        self.0
+    }
+}
+
+impl<T: FromStr> FromStr for Parsed<T>
+where
+    T::Err: Display,
+{
+    type Err = std::convert::Infallible;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        Ok(Parsed(T::from_str(raw).map_err(|e| ParseFailure {
+            raw: raw.to_owned(),
+            message: e.to_string(),
+        })))
+    }
+}
+
+impl<T: Serialize> Serialize for Parsed<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.0 {
+            Ok(value) => value.serialize(serializer),
+            Err(failure) => serializer.serialize_str(&failure.raw),
+        }
+    }
+}
+
+struct ParsedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: FromStr> Visitor<'de> for ParsedVisitor<T>
+where
+    T::Err: Display,
+{
+    type Value = Parsed<T>;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("a string, number or boolean")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(v.parse().unwrap_or_else(|never| match never {}))
+    }
+
+    fn visit_bool<E: Error>(self, v: bool) -> Result<Self::Value, E> {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_i64<E: Error>(self, v: i64) -> Result<Self::Value, E> {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_f64<E: Error>(self, v: f64) -> Result<Self::Value, E> {
+        self.visit_str(&v.to_string())
+    }
+}
+
+impl<'de, T: FromStr> Deserialize<'de> for Parsed<T>
+where
+    T::Err: Display,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ParsedVisitor(PhantomData))
+    }
+}
+
+/// Validator rule reporting a field that failed to parse
+///
+/// The error has the code `parse` and the parse error as its `message` parameter.
+#[cfg(feature = "validator")]
+pub fn parsed<T>(value: &Parsed<T>) -> Result<(), validator::ValidationError> {
+    match value.failure() {
+        None => Ok(()),
+        Some(failure) => {
+            let mut error = validator::ValidationError::new("parse");
+            error.add_param("message".into(), &failure.message);
+            Err(error)
+        }
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<T: validator::ValidateLength<u64>> validator::ValidateLength<u64> for Parsed<T> {
+    fn length(&self) -> Option<u64> {
+        self.get().and_then(|value| value.length())
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<T: validator::ValidateRange<R>, R> validator::ValidateRange<R> for Parsed<T> {
+    fn greater_than(&self, max: R) -> Option<bool> {
+        self.get().and_then(|value| value.greater_than(max))
+    }
+
+    fn less_than(&self, min: R) -> Option<bool> {
+        self.get().and_then(|value| value.less_than(min))
+    }
+}
+
+#[cfg(all(test, feature = "validator", feature = "query"))]
+mod tests {
+    use super::*;
+    use crate::{Valid, ValidRejection};
+    use axum::extract::{FromRequestParts, Query};
+    use axum::http::request::Parts;
+    use axum::http::Request;
+    use validator::Validate;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Paging {
+        #[validate(custom(function = "parsed"), range(min = 1))]
+        page: Parsed<u32>,
+        #[validate(custom(function = "parsed"), range(max = 100))]
+        size: Parsed<u32>,
+    }
+
+    fn parts(uri: &str) -> anyhow::Result<Parts> {
+        Ok(Request::builder().uri(uri).body(())?.into_parts().0)
+    }
+
+    #[tokio::test]
+    async fn parse_and_validation_errors_are_reported_together() -> anyhow::Result<()> {
+        let rejection =
+            Valid::<Query<Paging>>::from_request_parts(&mut parts("/?page=abc&size=1000")?, &())
+                .await
+                .expect_err("paging is invalid");
+        let ValidRejection::Valid(errors) = rejection else {
+            panic!("expected validation errors");
+        };
+        let errors = errors.field_errors();
+        assert_eq!(errors["page"].len(), 1);
+        assert_eq!(errors["page"][0].code, "parse");
+        assert_eq!(errors["page"][0].params["value"], "abc");
+        assert_eq!(errors["size"].len(), 1);
+        assert_eq!(errors["size"][0].code, "range");
+
+        let Valid(Query(paging)) =
+            Valid::<Query<Paging>>::from_request_parts(&mut parts("/?page=2&size=50")?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(paging.page.get(), Some(&2));
+        assert_eq!(paging.size.into_result(), Ok(50));
+        Ok(())
+    }
+}
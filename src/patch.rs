@@ -0,0 +1,246 @@
+//! # Partial updates
+//!
+//! ## Feature
+//!
+//! Enable the `patch` feature to use `ValidPatch<T>`.
+//!
+//! For `PATCH` requests, only the fields sent by the client are updated, so only those should be validated.
+//! `ValidPatch<T>` extracts a JSON body like `Json<T>`, validates it, and keeps only the errors of touched fields.
+//!
+//! The touched fields are the top-level keys present in the body, unless the body contains
+//! a `__touched` array listing them explicitly, e.g. `{"name": "", "age": 20, "__touched": ["age"]}`.
+//! Errors that are not bound to a field (`__all__`, from `schema` rules) are always kept.
+//!
+//! Untouched fields are usually `Option`s or have a `#[serde(default)]`, so that they can be omitted.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::patch;
+//! use axum::Router;
+//! use axum_valid::patch::ValidPatch;
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct ProfilePatch {
+//!     #[serde(default)]
+//!     #[validate(length(min = 1, max = 10))]
+//!     pub name: String,
+//!     #[serde(default)]
+//!     #[validate(range(min = 18, max = 150))]
+//!     pub age: u8,
+//! }
+//!
+//! async fn handler(ValidPatch(profile, touched): ValidPatch<ProfilePatch>) {
+//!     if touched.contains("name") {
+//!         println!("new name = {}", profile.name);
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/profile", patch(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::ValidRejection;
+use axum::async_trait;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use validator::Validate;
+
+/// Key of the explicit list of touched fields
+pub const TOUCHED_KEY: &str = "__touched";
+
+/// Names of the fields touched by a partial update
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Touched(HashSet<String>);
+
+impl Touched {
+    /// Get the touched fields of a JSON value
+    pub fn from_json(value: &Value) -> Self {
+        let Value::Object(map) = value else {
+            return Touched::default();
+        };
+        match map.get(TOUCHED_KEY) {
+            Some(Value::Array(touched)) => Touched(
+                touched
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(ToOwned::to_owned)
+                    .collect(),
+            ),
+            _ => Touched(map.keys().cloned().collect()),
+        }
+    }
+
+    /// Check if a field is touched
+    pub fn contains(&self, field: &str) -> bool {
+        self.0.contains(field)
+    }
+
+    /// Iterate over the touched fields
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+/// # `ValidPatch` data extractor
+///
+/// See the [module documentation](crate::patch) for details.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ValidPatch<T>(pub T, pub Touched);
+
+impl<T> ValidPatch<T> {
+    /// Consumes the `ValidPatch` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidPatch`
+#[derive(Debug)]
+pub enum PatchRejection {
+    /// The body is not valid JSON
+    Json(JsonRejection),
+    /// The body can't be deserialized into the target type
+    Deserialize(serde_json::Error),
+}
+
+impl Display for PatchRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchRejection::Json(e) => write!(f, "{e}"),
+            PatchRejection::Deserialize(e) => {
+                write!(
+                    f,
+                    "Failed to deserialize the JSON body into the target type: {e}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatchRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PatchRejection::Json(e) => Some(e),
+            PatchRejection::Deserialize(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for PatchRejection {
+    fn into_response(self) -> Response {
+        match self {
+            PatchRejection::Json(e) => e.into_response(),
+            // Same status code as `JsonDataError`
+            e @ PatchRejection::Deserialize(_) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidPatch<T>
+where
+    State: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ValidRejection<PatchRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(PatchRejection::Json(e)))?;
+        let touched = Touched::from_json(&value);
+        let data = serde_json::from_value::<T>(value)
+            .map_err(|e| ValidRejection::Inner(PatchRejection::Deserialize(e)))?;
+        if let Err(mut errors) = validate_redacted(&data) {
+            errors
+                .errors_mut()
+                .retain(|field, _| *field == "__all__" || touched.contains(field));
+            if !errors.is_empty() {
+                return Err(ValidRejection::Valid(errors));
+            }
+        }
+        Ok(ValidPatch(data, touched))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct ProfilePatch {
+        #[serde(default)]
+        #[validate(length(min = 1, max = 10))]
+        name: String,
+        #[serde(default)]
+        #[validate(range(min = 18, max = 150))]
+        age: u8,
+    }
+
+    fn request(body: &'static str) -> anyhow::Result<Request> {
+        Ok(Request::builder()
+            .method("PATCH")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?)
+    }
+
+    #[tokio::test]
+    async fn validate_touched_fields_only() -> anyhow::Result<()> {
+        // `name` is untouched and invalid (empty by default)
+        let ValidPatch(profile, touched) =
+            ValidPatch::<ProfilePatch>::from_request(request(r#"{"age": 20}"#)?, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(profile.age, 20);
+        assert!(touched.contains("age"));
+        assert!(!touched.contains("name"));
+
+        // `age` is touched and invalid
+        assert!(matches!(
+            ValidPatch::<ProfilePatch>::from_request(request(r#"{"age": 10}"#)?, &()).await,
+            Err(ValidRejection::Valid(errors))
+                if errors.field_errors().contains_key("age") && !errors.field_errors().contains_key("name")
+        ));
+
+        // explicit list of touched fields
+        assert!(ValidPatch::<ProfilePatch>::from_request(
+            request(r#"{"name": "", "age": 20, "__touched": ["age"]}"#)?,
+            &()
+        )
+        .await
+        .is_ok());
+        assert!(matches!(
+            ValidPatch::<ProfilePatch>::from_request(
+                request(r#"{"age": 20, "__touched": ["name", "age"]}"#)?,
+                &()
+            )
+            .await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("name")
+        ));
+        Ok(())
+    }
+}
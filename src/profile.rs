@@ -0,0 +1,354 @@
+//! # Validation profiles selected by a request header
+//!
+//! ## Feature
+//!
+//! Enable the `profile` feature to use `ValidProfile<E>`.
+//!
+//! To compare validation strictness levels, e.g. in an A/B test, the same endpoint may apply different rules
+//! depending on the client. `Profiles<P>`, provided by the state, registers named profiles of any type `P`,
+//! such as the bounds of some rules, and a default profile.
+//!
+//! `ValidProfile<E>` works like `ValidEx<E>`, but the validation arguments are the profile selected by
+//! the `X-Validation-Profile` header of the request, or the default profile if the header is absent.
+//! Rules read the profile with `#[validate(context = P)]` and `use_context`.
+//! A request selecting a profile that isn't registered is rejected with `400 Bad Request`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::profile::{Profiles, ValidProfile};
+//! use serde::Deserialize;
+//! use validator::{Validate, ValidationError};
+//!
+//! pub struct Strictness {
+//!     pub min_password_length: usize,
+//! }
+//!
+//! #[derive(Validate, Deserialize)]
+//! #[validate(context = Strictness)]
+//! pub struct SignUp {
+//!     #[validate(custom(function = "validate_password", use_context))]
+//!     pub password: String,
+//! }
+//!
+//! fn validate_password(password: &str, strictness: &Strictness) -> Result<(), ValidationError> {
+//!     if password.len() >= strictness.min_password_length {
+//!         Ok(())
+//!     } else {
+//!         Err(ValidationError::new("password_length"))
+//!     }
+//! }
+//!
+//! async fn handler(ValidProfile(Json(sign_up)): ValidProfile<Json<SignUp>>) {
+//!     println!("password length = {}", sign_up.password.len());
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let profiles = Profiles::new(Strictness { min_password_length: 8 })
+//!     .profile("strict", Strictness { min_password_length: 12 });
+//! let router = Router::new()
+//!     .route("/sign_up", post(handler))
+//!     .with_state(profiles);
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::redacting;
+use crate::{HasValidateArgs, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use validator::ValidateArgs;
+
+/// Name of the header selecting the validation profile
+pub const PROFILE_HEADER: &str = "x-validation-profile";
+
+/// Named validation profiles, and the default profile used when no profile is selected
+pub struct Profiles<P> {
+    default: Arc<P>,
+    profiles: Arc<HashMap<String, Arc<P>>>,
+}
+
+impl<P> Clone for Profiles<P> {
+    fn clone(&self) -> Self {
+        Self {
+            default: self.default.clone(),
+            profiles: self.profiles.clone(),
+        }
+    }
+}
+
+impl<P: Debug> Debug for Profiles<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Profiles")
+            .field("default", &self.default)
+            .field("profiles", &self.profiles)
+            .finish()
+    }
+}
+
+impl<P> Profiles<P> {
+    /// Create profiles with the default profile
+    pub fn new(default: P) -> Self {
+        Self {
+            default: Arc::new(default),
+            profiles: Arc::default(),
+        }
+    }
+
+    /// Register a named profile
+    pub fn profile(mut self, name: impl Into<String>, profile: P) -> Self {
+        Arc::make_mut(&mut self.profiles).insert(name.into(), Arc::new(profile));
+        self
+    }
+
+    /// Get the default profile
+    pub fn default_profile(&self) -> &P {
+        &self.default
+    }
+
+    /// Get a named profile
+    pub fn get(&self, name: &str) -> Option<&P> {
+        self.profiles.get(name).map(Arc::as_ref)
+    }
+
+    /// Get the profile selected by the `X-Validation-Profile` header, the default one if the header is absent
+    pub fn select(&self, headers: &HeaderMap) -> Result<Arc<P>, UnknownProfile> {
+        let Some(name) = headers.get(PROFILE_HEADER) else {
+            return Ok(self.default.clone());
+        };
+        let name = String::from_utf8_lossy(name.as_bytes());
+        self.profiles
+            .get(name.as_ref())
+            .cloned()
+            .ok_or_else(|| UnknownProfile(name.into_owned()))
+    }
+}
+
+/// The profile selected by the `X-Validation-Profile` header isn't registered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownProfile(pub String);
+
+impl Display for UnknownProfile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown validation profile: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownProfile {}
+
+impl IntoResponse for UnknownProfile {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+    }
+}
+
+/// # `ValidProfile` data extractor
+///
+/// See the [module documentation](crate::profile) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidProfile<E>(pub E);
+
+impl<E> Deref for ValidProfile<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidProfile<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidProfile<E> {
+    /// Consumes the `ValidProfile` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+/// `ProfileRejection` is returned when the `ValidProfile` extractor fails before validation.
+#[derive(Debug)]
+pub enum ProfileRejection<E> {
+    /// The selected profile isn't registered
+    UnknownProfile(UnknownProfile),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for ProfileRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileRejection::UnknownProfile(e) => write!(f, "{e}"),
+            ProfileRejection::Inner(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for ProfileRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProfileRejection::UnknownProfile(e) => Some(e),
+            ProfileRejection::Inner(e) => Some(e),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for ProfileRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            ProfileRejection::UnknownProfile(e) => e.into_response(),
+            ProfileRejection::Inner(e) => e.into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, P> FromRequest<State> for ValidProfile<Extractor>
+where
+    State: Send + Sync,
+    P: Send + Sync + 'static,
+    Profiles<P>: FromRef<State>,
+    Extractor: for<'v> HasValidateArgs<'v> + FromRequest<State>,
+    for<'v> <Extractor as HasValidateArgs<'v>>::ValidateArgs: ValidateArgs<'v, Args = &'v P>,
+{
+    type Rejection = ValidRejection<ProfileRejection<<Extractor as FromRequest<State>>::Rejection>>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let profile = Profiles::<P>::from_ref(state)
+            .select(req.headers())
+            .map_err(|e| ValidRejection::Inner(ProfileRejection::UnknownProfile(e)))?;
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(ProfileRejection::Inner(e)))?;
+        redacting(|| inner.get_validate_args().validate_with_args(&profile))?;
+        Ok(ValidProfile(inner))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, P> FromRequestParts<State> for ValidProfile<Extractor>
+where
+    State: Send + Sync,
+    P: Send + Sync + 'static,
+    Profiles<P>: FromRef<State>,
+    Extractor: for<'v> HasValidateArgs<'v> + FromRequestParts<State>,
+    for<'v> <Extractor as HasValidateArgs<'v>>::ValidateArgs: ValidateArgs<'v, Args = &'v P>,
+{
+    type Rejection =
+        ValidRejection<ProfileRejection<<Extractor as FromRequestParts<State>>::Rejection>>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let profile = Profiles::<P>::from_ref(state)
+            .select(&parts.headers)
+            .map_err(|e| ValidRejection::Inner(ProfileRejection::UnknownProfile(e)))?;
+        let inner = Extractor::from_request_parts(parts, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(ProfileRejection::Inner(e)))?;
+        redacting(|| inner.get_validate_args().validate_with_args(&profile))?;
+        Ok(ValidProfile(inner))
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::rejection::JsonRejection;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+    use validator::{Validate, ValidationError};
+
+    struct Strictness {
+        min_password_length: usize,
+    }
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    #[validate(context = Strictness)]
+    struct SignUp {
+        #[validate(custom(function = "validate_password", use_context))]
+        password: String,
+    }
+
+    fn validate_password(password: &str, strictness: &Strictness) -> Result<(), ValidationError> {
+        if password.len() >= strictness.min_password_length {
+            Ok(())
+        } else {
+            Err(ValidationError::new("password_length"))
+        }
+    }
+
+    async fn extract(
+        profile: Option<&str>,
+    ) -> anyhow::Result<Result<SignUp, ValidRejection<ProfileRejection<JsonRejection>>>> {
+        let profiles = Profiles::new(Strictness {
+            min_password_length: 8,
+        })
+        .profile(
+            "lenient",
+            Strictness {
+                min_password_length: 4,
+            },
+        )
+        .profile(
+            "strict",
+            Strictness {
+                min_password_length: 12,
+            },
+        );
+        let mut request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(profile) = profile {
+            request = request.header(PROFILE_HEADER, profile);
+        }
+        let request = request.body(Body::from(r#"{"password": "password1"}"#))?;
+        Ok(
+            ValidProfile::<Json<SignUp>>::from_request(request, &profiles)
+                .await
+                .map(|ValidProfile(Json(sign_up))| sign_up),
+        )
+    }
+
+    #[tokio::test]
+    async fn profiles_select_rules() -> anyhow::Result<()> {
+        // the same payload passes the lenient profile and fails the strict one
+        assert_eq!(extract(Some("lenient")).await??.password, "password1");
+        assert!(matches!(
+            extract(Some("strict")).await?,
+            Err(ValidRejection::Valid(errors))
+                if errors.field_errors()["password"][0].code == "password_length"
+        ));
+
+        // the default profile applies without the header
+        assert!(extract(None).await?.is_ok());
+
+        let rejection = extract(Some("unknown"))
+            .await?
+            .expect_err("unknown profile");
+        assert!(matches!(
+            &rejection,
+            ValidRejection::Inner(ProfileRejection::UnknownProfile(UnknownProfile(name))) if name == "unknown"
+        ));
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
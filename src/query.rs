@@ -9,6 +9,36 @@
 //! 1. Implement `Deserialize` and `Validate` for your data type `T`.
 //! 2. In your handler function, use `Valid<Query<T>>` as some parameter's type.
 //!
+//! ## Optional queries
+//!
+//! List endpoints are often called without any query parameter. Add `#[serde(default)]` to the data type,
+//! and implement `Default` with valid defaults: an empty or absent query then yields the defaults,
+//! which are validated like any other query, instead of being rejected for missing fields.
+//!
+//! ```
+//! # #[cfg(feature = "validator")]
+//! #[derive(serde::Deserialize, validator::Validate)]
+//! #[serde(default)]
+//! pub struct Pagination {
+//!     #[validate(range(min = 1))]
+//!     pub page: u32,
+//!     #[validate(range(min = 1, max = 100))]
+//!     pub per_page: u32,
+//! }
+//!
+//! # #[cfg(feature = "validator")]
+//! impl Default for Pagination {
+//!     fn default() -> Self {
+//!         Self { page: 1, per_page: 20 }
+//!     }
+//! }
+//! ```
+//!
+//! ## Percent-encoded keys
+//!
+//! Keys are percent-decoded like values before deserialization, e.g. `na%6De=x` sets the `name` field,
+//! so the decoded field is validated, and its errors are reported under its decoded name.
+//!
 //! ## Example
 //!
 //! ```no_run
@@ -158,4 +188,95 @@ mod tests {
             builder.query(&T::invalid())
         }
     }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn empty_query_validates_defaults() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use axum::extract::rejection::QueryRejection;
+        use axum::extract::FromRequestParts;
+        use axum::http::Request;
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[serde(default)]
+        struct Pagination {
+            #[validate(range(min = 1))]
+            page: u32,
+            #[validate(range(min = 1, max = 100))]
+            per_page: u32,
+        }
+
+        impl Default for Pagination {
+            fn default() -> Self {
+                Self {
+                    page: 1,
+                    per_page: 20,
+                }
+            }
+        }
+
+        async fn extract(
+            uri: &str,
+        ) -> anyhow::Result<Result<Pagination, ValidRejection<QueryRejection>>> {
+            let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+            Ok(
+                Valid::<Query<Pagination>>::from_request_parts(&mut parts, &())
+                    .await
+                    .map(|Valid(Query(pagination))| pagination),
+            )
+        }
+
+        for uri in ["/items", "/items?"] {
+            let pagination = extract(uri).await?.map_err(|e| anyhow::anyhow!("{e}"))?;
+            assert_eq!((pagination.page, pagination.per_page), (1, 20));
+        }
+        let pagination = extract("/items?page=3")
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!((pagination.page, pagination.per_page), (3, 20));
+        assert!(matches!(
+            extract("/items?per_page=1000").await?,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("per_page")
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn percent_encoded_keys() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use axum::extract::FromRequestParts;
+        use axum::http::Request;
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[validate(length(min = 1, max = 5))]
+            name: String,
+            #[validate(range(max = 100))]
+            per_page: u32,
+        }
+
+        let (mut parts, _) = Request::builder()
+            .uri("/?na%6De=gteng&per%5Fpage=10")
+            .body(())?
+            .into_parts();
+        let Valid(Query(parameters)) =
+            Valid::<Query<Parameters>>::from_request_parts(&mut parts, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.name, "gteng");
+        assert_eq!(parameters.per_page, 10);
+
+        let (mut parts, _) = Request::builder()
+            .uri("/?%6E%61%6D%65=toolong&per_page=10")
+            .body(())?
+            .into_parts();
+        assert!(matches!(
+            Valid::<Query<Parameters>>::from_request_parts(&mut parts, &()).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("name")
+        ));
+        Ok(())
+    }
 }
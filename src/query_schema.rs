@@ -0,0 +1,289 @@
+//! # Support for `SchemaQuery<T>`
+//!
+//! ## Feature
+//!
+//! Enable the `query_schema` feature to use `Valid<SchemaQuery<T>>`.
+//!
+//! In a multi-tenant product, the query fields a client may use can differ per tenant,
+//! e.g. a tenant may not be allowed to filter on a field of a plan it didn't subscribe to.
+//! `SchemaQuery<T>` works like `axum::extract::Query<T>`, but first checks the query keys against
+//! the `QuerySchema` provided by the state, a set of allowed field names built at runtime.
+//! A query with a field missing from the schema is rejected with `400 Bad Request` listing the fields,
+//! before it's deserialized and validated.
+//!
+//! Provide the schema of each tenant through its state, e.g. by nesting a router per tenant
+//! or by deriving `FromRef` for a per-tenant state.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::query_schema::{QuerySchema, SchemaQuery};
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router(schema: QuerySchema) -> Router {
+//!         Router::new()
+//!             .route("/query", get(handler))
+//!             .with_state(schema)
+//!     }
+//!
+//!     async fn handler(Valid(SchemaQuery(parameter)): Valid<SchemaQuery<Parameter>>) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(range(min = 1, max = 100))]
+//!         pub limit: Option<u32>,
+//!         pub region: Option<String>,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use axum_valid::query_schema::QuerySchema;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router
+//! #         .nest("/basic", validator_example::router(QuerySchema::new(["limit"])))
+//! #         .nest("/premium", validator_example::router(QuerySchema::new(["limit", "region"])));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{FromRef, FromRequestParts, Query};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+#[cfg(feature = "validator")]
+use validator::ValidateArgs;
+
+/// Names of the query fields allowed by `SchemaQuery`
+#[derive(Debug, Clone, Default)]
+pub struct QuerySchema(Arc<HashSet<String>>);
+
+impl QuerySchema {
+    /// Create a schema from field names
+    pub fn new<I, S>(fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        QuerySchema(Arc::new(fields.into_iter().map(Into::into).collect()))
+    }
+
+    /// Check if a field is allowed
+    pub fn contains(&self, field: &str) -> bool {
+        self.0.contains(field)
+    }
+
+    /// Get the fields of `query` that are not allowed, in order of first appearance
+    pub fn not_allowed(&self, query: &str) -> Vec<String> {
+        let mut fields = Vec::<String>::new();
+        for (key, _) in form_urlencoded::parse(query.as_bytes()) {
+            if !self.contains(&key) && !fields.iter().any(|field| *field == key) {
+                fields.push(key.into_owned());
+            }
+        }
+        fields
+    }
+}
+
+/// # `SchemaQuery` extractor
+///
+/// See the [module documentation](crate::query_schema) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SchemaQuery<T>(pub T);
+
+impl<T> Deref for SchemaQuery<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for SchemaQuery<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// `SchemaQueryRejection` is returned when the `SchemaQuery` extractor fails.
+#[derive(Debug)]
+pub enum SchemaQueryRejection {
+    /// The query contains fields that are not allowed by the schema
+    NotAllowed(Vec<String>),
+    /// The query can't be deserialized
+    Query(QueryRejection),
+}
+
+impl Display for SchemaQueryRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaQueryRejection::NotAllowed(fields) => {
+                write!(f, "Query fields not allowed: {}", fields.join(", "))
+            }
+            SchemaQueryRejection::Query(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for SchemaQueryRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SchemaQueryRejection::NotAllowed(_) => None,
+            SchemaQueryRejection::Query(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for SchemaQueryRejection {
+    fn into_response(self) -> Response {
+        match self {
+            SchemaQueryRejection::NotAllowed(_) => {
+                (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+            }
+            SchemaQueryRejection::Query(e) => e.into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for SchemaQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+    QuerySchema: FromRef<S>,
+{
+    type Rejection = SchemaQueryRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let schema = QuerySchema::from_ref(state);
+        let fields = schema.not_allowed(parts.uri.query().unwrap_or_default());
+        if !fields.is_empty() {
+            return Err(SchemaQueryRejection::NotAllowed(fields));
+        }
+        let Query(value) = Query::from_request_parts(parts, state)
+            .await
+            .map_err(SchemaQueryRejection::Query)?;
+        Ok(SchemaQuery(value))
+    }
+}
+
+impl<T> HasValidate for SchemaQuery<T> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>> HasValidateArgs<'v> for SchemaQuery<T> {
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify> crate::HasModify for SchemaQuery<T> {
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T> crate::PayloadExtractor for SchemaQuery<T> {
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload> crate::HasValidify for SchemaQuery<T> {
+    type Validify = T;
+    type PayloadExtractor = SchemaQuery<T::Payload>;
+    fn from_validify(v: Self::Validify) -> Self {
+        SchemaQuery(v)
+    }
+}
+
+#[cfg(all(test, feature = "validator"))]
+mod tests {
+    use super::*;
+    use crate::{Valid, ValidRejection};
+    use axum::http::Request;
+    use validator::Validate;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Parameters {
+        #[validate(range(min = 1, max = 100))]
+        limit: Option<u32>,
+        region: Option<String>,
+    }
+
+    async fn extract(
+        uri: &str,
+        schema: &QuerySchema,
+    ) -> anyhow::Result<Result<Parameters, ValidRejection<SchemaQueryRejection>>> {
+        let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+        Ok(
+            Valid::<SchemaQuery<Parameters>>::from_request_parts(&mut parts, schema)
+                .await
+                .map(|Valid(SchemaQuery(parameters))| parameters),
+        )
+    }
+
+    #[tokio::test]
+    async fn schema_per_tenant() -> anyhow::Result<()> {
+        let basic = QuerySchema::new(["limit"]);
+        let premium = QuerySchema::new(["limit", "region"]);
+
+        let parameters = extract("/?limit=10&region=eu", &premium).await??;
+        assert_eq!(parameters.limit, Some(10));
+        assert_eq!(parameters.region.as_deref(), Some("eu"));
+
+        let rejection = extract("/?limit=10&region=eu", &basic)
+            .await?
+            .expect_err("region is not allowed");
+        assert!(matches!(
+            &rejection,
+            ValidRejection::Inner(SchemaQueryRejection::NotAllowed(fields)) if fields == &["region"]
+        ));
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+
+        // allowed fields are still validated
+        assert!(matches!(
+            extract("/?limit=1000", &basic).await?,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("limit")
+        ));
+        Ok(())
+    }
+}
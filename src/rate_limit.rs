@@ -0,0 +1,569 @@
+//! # Rate limits per field value
+//!
+//! ## Feature
+//!
+//! Enable the `rate_limit` feature to use `ValidRateLimited<E>`.
+//!
+//! Some submissions are rate-limited by the value of a field rather than by client,
+//! e.g. sign-ups or password resets per email address, so that an address can't be flooded.
+//!
+//! `RateStore`, provided by the state, counts the submissions of each value of each field within
+//! a sliding window. `rate_limit::check` records a submission of a value and fails with the code `rate_limited`
+//! once the limit is exceeded, with the `field`, the `limit` and the `window` in seconds as its parameters.
+//! Submissions over the limit aren't recorded, so the store keeps at most `limit` times per value,
+//! and the values without recent submissions are swept once per window.
+//! It's a context-based rule, applied with `#[validate(context = RateStore)]` and `custom(function = "...", use_context)`.
+//!
+//! `ValidRateLimited<E>` works like `ValidEx<E>` with the `RateStore` as arguments, but rejects the request with
+//! `429 Too Many Requests` instead of `VALIDATION_ERROR_STATUS` if any error is `rate_limited`,
+//! with the errors as an `ErrorBody`, and the window as a `Retry-After` header.
+//!
+//! With `ValidRateLimited`, a submission only counts once all the rules pass: `rate_limit::check` only checks
+//! the limit during the validation, and the extractor records the submission once the validation succeeds.
+//! Elsewhere, e.g. with `ValidEx`, `rate_limit::check` records the submission right away.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::rate_limit::{self, RateStore, ValidRateLimited};
+//! use serde::Deserialize;
+//! use std::time::Duration;
+//! use validator::{Validate, ValidationError};
+//!
+//! #[derive(Validate, Deserialize)]
+//! #[validate(context = RateStore)]
+//! pub struct PasswordReset {
+//!     #[validate(email, custom(function = "limit_email", use_context))]
+//!     pub email: String,
+//! }
+//!
+//! fn limit_email(email: &str, store: &RateStore) -> Result<(), ValidationError> {
+//!     rate_limit::check(store, "email", email)
+//! }
+//!
+//! async fn handler(ValidRateLimited(Json(reset)): ValidRateLimited<Json<PasswordReset>>) {
+//!     println!("reset password of {}", reset.email);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! // at most 3 resets per email address per hour
+//! let store = RateStore::new(3, Duration::from_secs(3600));
+//! let router = Router::new()
+//!     .route("/password_reset", post(handler))
+//!     .with_state(store);
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::redacting;
+use crate::{ErrorBody, HasValidateArgs};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, FromRequestParts, Request};
+use axum::http::header::RETRY_AFTER;
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use validator::{ValidateArgs, ValidationError, ValidationErrors};
+
+/// Error code of values submitted too often
+pub const RATE_LIMITED: &str = "rate_limited";
+
+/// Times of the recent accepted submissions, indexed by field and value
+#[derive(Debug)]
+struct Hits {
+    times: HashMap<(String, String), VecDeque<Instant>>,
+    next_sweep: Instant,
+}
+
+/// Submissions checked during the validation of a request, by field and value
+type Pending = Arc<Mutex<Vec<(&'static str, String)>>>;
+
+/// Submissions of each value of each field within a sliding window
+#[derive(Debug, Clone)]
+pub struct RateStore {
+    limit: usize,
+    window: Duration,
+    hits: Arc<Mutex<Hits>>,
+    /// Submissions checked during the validation of a request, recorded by `commit`
+    pending: Option<Pending>,
+}
+
+impl RateStore {
+    /// Allow `limit` submissions of a value within `window`
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Arc::new(Mutex::new(Hits {
+                times: HashMap::new(),
+                next_sweep: Instant::now() + window,
+            })),
+            pending: None,
+        }
+    }
+
+    /// Number of submissions allowed within the window
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Duration of the window
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Record a submission of `value` in `field`, returns whether it is within the limit
+    ///
+    /// Rejected submissions aren't recorded: a value is accepted again once its oldest
+    /// accepted submission leaves the window.
+    pub fn hit(&self, field: &str, value: &str) -> bool {
+        self.hit_at(field, value, Instant::now())
+    }
+
+    /// Record a submission of `value` in `field` at `now`, see `RateStore::hit`
+    pub fn hit_at(&self, field: &str, value: &str, now: Instant) -> bool {
+        let mut hits = self.lock(now);
+        let times = self.recent(&mut hits, now, field, value);
+        if times.len() >= self.limit {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+
+    /// Lock the submissions, dropping the values without recent submissions once per window
+    fn lock(&self, now: Instant) -> MutexGuard<'_, Hits> {
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+        if now >= hits.next_sweep {
+            hits.times.retain(|_, times| {
+                times
+                    .back()
+                    .is_some_and(|time| now.duration_since(*time) < self.window)
+            });
+            hits.next_sweep = now + self.window;
+        }
+        hits
+    }
+
+    /// Get the submissions of `value` in `field` within the window
+    fn recent<'h>(
+        &self,
+        hits: &'h mut Hits,
+        now: Instant,
+        field: &str,
+        value: &str,
+    ) -> &'h mut VecDeque<Instant> {
+        let times = hits
+            .times
+            .entry((field.to_owned(), value.to_owned()))
+            .or_default();
+        while times
+            .front()
+            .is_some_and(|time| now.duration_since(*time) >= self.window)
+        {
+            times.pop_front();
+        }
+        times
+    }
+
+    /// Share the submissions with a store whose checks are pending until `commit`
+    fn deferred(&self) -> Self {
+        Self {
+            pending: Some(Arc::default()),
+            ..self.clone()
+        }
+    }
+
+    /// Record the pending submissions, unless a value has reached the limit since it was checked
+    ///
+    /// A value checked several times, e.g. by a rule on the elements of a list, counts as several submissions.
+    fn commit(&self) -> Result<(), ValidationErrors> {
+        let Some(pending) = &self.pending else {
+            return Ok(());
+        };
+        let pending = std::mem::take(&mut *pending.lock().unwrap_or_else(|e| e.into_inner()));
+        let now = Instant::now();
+        let mut hits = self.lock(now);
+        let mut errors = ValidationErrors::new();
+        for (index, (field, value)) in pending.iter().enumerate() {
+            let earlier = pending[..index]
+                .iter()
+                .filter(|(f, v)| f == field && v == value)
+                .count();
+            if self.recent(&mut hits, now, field, value).len() + earlier >= self.limit {
+                errors.add(field, rate_limited(self, field));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        for (field, value) in &pending {
+            self.recent(&mut hits, now, field, value).push_back(now);
+        }
+        Ok(())
+    }
+}
+
+/// Check that `value` of `field` isn't submitted too often, recording the submission in `store`
+///
+/// Within `ValidRateLimited`, the submission is only recorded once the validation succeeds.
+pub fn check(store: &RateStore, field: &'static str, value: &str) -> Result<(), ValidationError> {
+    let Some(pending) = &store.pending else {
+        if store.hit(field, value) {
+            return Ok(());
+        }
+        return Err(rate_limited(store, field));
+    };
+    let now = Instant::now();
+    let mut hits = store.lock(now);
+    let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+    // the value may already be pending, e.g. checked by a rule on the elements of a list
+    let earlier = pending
+        .iter()
+        .filter(|(f, v)| *f == field && v == value)
+        .count();
+    if store.recent(&mut hits, now, field, value).len() + earlier >= store.limit {
+        return Err(rate_limited(store, field));
+    }
+    pending.push((field, value.to_owned()));
+    Ok(())
+}
+
+/// Error of a value of `field` submitted too often
+fn rate_limited(store: &RateStore, field: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(RATE_LIMITED);
+    error.message = Some(Cow::from(format!(
+        "`{field}` is submitted too often with this value"
+    )));
+    error.add_param(Cow::from("field"), &field);
+    error.add_param(Cow::from("limit"), &store.limit);
+    error.add_param(Cow::from("window"), &store.window.as_secs());
+    error
+}
+
+/// # `ValidRateLimited` data extractor
+///
+/// See the [module documentation](crate::rate_limit) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidRateLimited<E>(pub E);
+
+impl<E> Deref for ValidRateLimited<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidRateLimited<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidRateLimited<E> {
+    /// Consumes the `ValidRateLimited` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+/// `RateLimitRejection` is returned when the `ValidRateLimited` extractor fails.
+#[derive(Debug)]
+pub enum RateLimitRejection<E> {
+    /// The data is invalid, and rate-limited if any error is `rate_limited`
+    Valid(ErrorBody, Duration),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E> RateLimitRejection<E> {
+    /// Check if any error is `rate_limited`
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            RateLimitRejection::Valid(body, _) => body
+                .errors
+                .iter()
+                .any(|error| error.code.as_deref() == Some(RATE_LIMITED)),
+            RateLimitRejection::Inner(_) => false,
+        }
+    }
+}
+
+impl<E: Display> Display for RateLimitRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimitRejection::Valid(body, _) if self.is_rate_limited() => {
+                write!(f, "{} validation errors, rate limited", body.errors.len())
+            }
+            RateLimitRejection::Valid(body, _) => {
+                write!(f, "{} validation errors", body.errors.len())
+            }
+            RateLimitRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for RateLimitRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RateLimitRejection::Valid(..) => None,
+            RateLimitRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for RateLimitRejection<E> {
+    fn into_response(self) -> Response {
+        let rate_limited = self.is_rate_limited();
+        match self {
+            RateLimitRejection::Valid(body, window) => {
+                let mut response = body.into_response();
+                if rate_limited {
+                    *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+                    response
+                        .headers_mut()
+                        .insert(RETRY_AFTER, HeaderValue::from(window.as_secs()));
+                }
+                response
+            }
+            RateLimitRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidRateLimited<Extractor>
+where
+    State: Send + Sync,
+    RateStore: FromRef<State>,
+    Extractor: for<'v> HasValidateArgs<'v> + FromRequest<State>,
+    for<'v> <Extractor as HasValidateArgs<'v>>::ValidateArgs:
+        ValidateArgs<'v, Args = &'v RateStore>,
+{
+    type Rejection = RateLimitRejection<<Extractor as FromRequest<State>>::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let store = RateStore::from_ref(state).deferred();
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(RateLimitRejection::Inner)?;
+        redacting(|| inner.get_validate_args().validate_with_args(&store))
+            .and_then(|()| store.commit())
+            .map_err(|errors| RateLimitRejection::Valid(ErrorBody::from(errors), store.window))?;
+        Ok(ValidRateLimited(inner))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequestParts<State> for ValidRateLimited<Extractor>
+where
+    State: Send + Sync,
+    RateStore: FromRef<State>,
+    Extractor: for<'v> HasValidateArgs<'v> + FromRequestParts<State>,
+    for<'v> <Extractor as HasValidateArgs<'v>>::ValidateArgs:
+        ValidateArgs<'v, Args = &'v RateStore>,
+{
+    type Rejection = RateLimitRejection<<Extractor as FromRequestParts<State>>::Rejection>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let store = RateStore::from_ref(state).deferred();
+        let inner = Extractor::from_request_parts(parts, state)
+            .await
+            .map_err(RateLimitRejection::Inner)?;
+        redacting(|| inner.get_validate_args().validate_with_args(&store))
+            .and_then(|()| store.commit())
+            .map_err(|errors| RateLimitRejection::Valid(ErrorBody::from(errors), store.window))?;
+        Ok(ValidRateLimited(inner))
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+    use validator::Validate;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    #[validate(context = RateStore)]
+    struct PasswordReset {
+        #[validate(length(max = 64), custom(function = "limit_email", use_context))]
+        email: String,
+        #[serde(default)]
+        #[validate(url)]
+        redirect: Option<String>,
+    }
+
+    fn limit_email(email: &str, store: &RateStore) -> Result<(), ValidationError> {
+        check(store, "email", email)
+    }
+
+    async fn post(store: &RateStore, body: &'static str) -> anyhow::Result<Response> {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        Ok(
+            match ValidRateLimited::<Json<PasswordReset>>::from_request(request, store).await {
+                Ok(_) => StatusCode::OK.into_response(),
+                Err(rejection) => rejection.into_response(),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn exceeding_limit_per_value() -> anyhow::Result<()> {
+        let store = RateStore::new(2, Duration::from_secs(60));
+        const ALICE: &str = r#"{"email": "alice@example.com"}"#;
+        const BOB: &str = r#"{"email": "bob@example.com"}"#;
+
+        assert_eq!(post(&store, ALICE).await?.status(), StatusCode::OK);
+        assert_eq!(post(&store, ALICE).await?.status(), StatusCode::OK);
+        let response = post(&store, ALICE).await?;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "60");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let body = serde_json::from_slice::<serde_json::Value>(&body)?;
+        assert_eq!(body["errors"][0]["path"], "email");
+        assert_eq!(body["errors"][0]["code"], RATE_LIMITED);
+        assert_eq!(body["errors"][0]["params"]["limit"], 2);
+
+        // other values have their own limit
+        assert_eq!(post(&store, BOB).await?.status(), StatusCode::OK);
+
+        // other errors keep the usual status
+        let long =
+            r#"{"email": "a-very-long-email-address-that-is-longer-than-sixty-four@example.com"}"#;
+        let response = post(&store, long).await?;
+        assert_eq!(response.status(), crate::VALIDATION_ERROR_STATUS);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invalid_submissions_do_not_count() -> anyhow::Result<()> {
+        let store = RateStore::new(1, Duration::from_secs(60));
+        const INVALID: &str = r#"{"email": "alice@example.com", "redirect": "not a url"}"#;
+        const VALID: &str = r#"{"email": "alice@example.com"}"#;
+
+        for _ in 0..3 {
+            let response = post(&store, INVALID).await?;
+            assert_eq!(response.status(), crate::VALIDATION_ERROR_STATUS);
+        }
+        assert_eq!(post(&store, VALID).await?.status(), StatusCode::OK);
+        assert_eq!(
+            post(&store, VALID).await?.status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn values_checked_twice_count_twice() -> anyhow::Result<()> {
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(context = RateStore)]
+        struct Invitation {
+            #[validate(custom(function = "limit_email", use_context))]
+            email: String,
+            #[validate(custom(function = "limit_email", use_context))]
+            cc: String,
+        }
+
+        let store = RateStore::new(1, Duration::from_secs(60));
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                r#"{"email": "alice@example.com", "cc": "alice@example.com"}"#,
+            ))?;
+        let rejection = ValidRateLimited::<Json<Invitation>>::from_request(request, &store)
+            .await
+            .expect_err("the value is submitted twice");
+        assert!(rejection.is_rate_limited());
+        assert!(store.hit("email", "alice@example.com"));
+
+        // values that passed their checks before others were recorded are checked again
+        let store = RateStore::new(1, Duration::from_secs(60)).deferred();
+        store
+            .pending
+            .as_ref()
+            .expect("deferred store")
+            .lock()
+            .expect("lock pending")
+            .extend((0..2).map(|_| ("email", "bob@example.com".to_owned())));
+        let errors = store.commit().expect_err("the value is pending twice");
+        assert!(errors.field_errors().contains_key("email"));
+        assert!(store.hit("email", "bob@example.com"));
+        Ok(())
+    }
+
+    #[test]
+    fn window_expires() {
+        let store = RateStore::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(store.hit_at("email", "alice@example.com", start));
+        assert!(!store.hit_at(
+            "email",
+            "alice@example.com",
+            start + Duration::from_secs(59)
+        ));
+        assert!(store.hit_at(
+            "email",
+            "alice@example.com",
+            start + Duration::from_secs(60)
+        ));
+    }
+
+    #[test]
+    fn rejected_hits_are_not_recorded() {
+        let store = RateStore::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(store.hit_at("email", "alice@example.com", start));
+        for second in 1..60 {
+            let now = start + Duration::from_secs(second);
+            assert!(!store.hit_at("email", "alice@example.com", now));
+        }
+        // the rejected submissions don't extend the window
+        assert!(store.hit_at(
+            "email",
+            "alice@example.com",
+            start + Duration::from_secs(60)
+        ));
+        let hits = store.hits.lock().expect("lock hits");
+        assert_eq!(
+            hits.times[&("email".to_owned(), "alice@example.com".to_owned())].len(),
+            1
+        );
+    }
+
+    #[test]
+    fn stale_values_are_swept() {
+        let store = RateStore::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(store.hit_at("email", "alice@example.com", start));
+        assert!(store.hit_at("email", "bob@example.com", start));
+        assert!(store.hit_at(
+            "email",
+            "carol@example.com",
+            start + Duration::from_secs(120)
+        ));
+        let hits = store.hits.lock().expect("lock hits");
+        assert_eq!(hits.times.len(), 1);
+    }
+}
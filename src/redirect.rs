@@ -0,0 +1,467 @@
+//! # Redirect after validation failures
+//!
+//! ## Feature
+//!
+//! Enable the `redirect` feature to use `RedirectOnError<E>` and `FlashErrors`.
+//!
+//! Server-rendered forms usually answer a failed submission by redirecting back to the form,
+//! which is rendered again with the errors. `RedirectOnError<E>` wraps a validation extractor such as `Valid<E>`.
+//! When validation fails, it responds with `303 See Other` and a `Location` header pointing back to the
+//! requested path and query, and stores the errors in the `validation_errors` flash cookie.
+//! Only the path, the code and the message of each error are stored: the parameters of the failed rules are dropped,
+//! as they may include the submitted values, e.g. a password failing a `length` rule.
+//! The cookie is `HttpOnly`, and expires after `FLASH_MAX_AGE` seconds.
+//! As cookies are limited to about 4 KB, the last errors are dropped if they don't fit in `FLASH_MAX_SIZE` bytes,
+//! so this is meant for forms with a handful of fields.
+//!
+//! The handler rendering the form reads the errors back with the `FlashErrors` extractor, as an `ErrorBody`.
+//! Returning the `FlashErrors` as a response part clears the cookie, so that the errors are shown once.
+//! The messages come from a cookie, which the client can alter, so they must be escaped when rendered as HTML.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::response::Html;
+//!     use axum::routing::get;
+//!     use axum::{Form, Router};
+//!     use axum_valid::redirect::{FlashErrors, RedirectOnError};
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/signup", get(form).post(submit))
+//!     }
+//!
+//!     fn escape(text: &str) -> String {
+//!         text.replace('&', "&amp;")
+//!             .replace('<', "&lt;")
+//!             .replace('>', "&gt;")
+//!             .replace('"', "&quot;")
+//!     }
+//!
+//!     async fn form(flash: FlashErrors) -> (FlashErrors, Html<String>) {
+//!         let errors = flash
+//!             .0
+//!             .iter()
+//!             .flat_map(|body| &body.errors)
+//!             .map(|error| {
+//!                 let message = error.message.as_deref().or(error.code.as_deref()).unwrap_or_default();
+//!                 format!("<li>{}: {}</li>", escape(&error.path.to_string()), escape(message))
+//!             })
+//!             .collect::<String>();
+//!         // returning the flash errors clears the cookie
+//!         (flash, Html(format!("<ul>{errors}</ul><form method=\"post\"></form>")))
+//!     }
+//!
+//!     async fn submit(RedirectOnError(Valid(Form(parameter))): RedirectOnError<Valid<Form<Parameter>>>) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(length(min = 1, max = 32))]
+//!         pub name: String,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::{FieldError, FieldPath};
+use crate::{ErrorBody, ValidationRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, FromRequestParts, OriginalUri, Request};
+use axum::http::header::{COOKIE, LOCATION, SET_COOKIE};
+use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap, HeaderValue, StatusCode, Uri};
+use axum::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+
+/// Name of the flash cookie storing the errors
+pub const FLASH_COOKIE: &str = "validation_errors";
+
+/// Lifetime of the flash cookie in seconds
+pub const FLASH_MAX_AGE: u64 = 60;
+
+/// Maximum size of the flash cookie, its name and its value, in bytes
+pub const FLASH_MAX_SIZE: usize = 4000;
+
+/// The parts of a `FieldError` stored in the flash cookie
+#[derive(Serialize, Deserialize)]
+struct FlashError {
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+impl From<&FieldError> for FlashError {
+    fn from(error: &FieldError) -> Self {
+        FlashError {
+            path: error.path.to_string(),
+            code: error.code.clone(),
+            message: error.message.clone(),
+        }
+    }
+}
+
+impl From<FlashError> for FieldError {
+    fn from(error: FlashError) -> Self {
+        let mut field_error = FieldError::new(FieldPath::parse(&error.path), "");
+        field_error.code = error.code;
+        field_error.message = error.message;
+        field_error
+    }
+}
+
+/// Redirect back to the form, with the errors in the flash cookie
+#[derive(Debug, Clone)]
+pub struct FlashRedirect {
+    /// The `Location` of the redirect
+    pub location: String,
+    /// The errors
+    pub body: ErrorBody,
+}
+
+impl FlashRedirect {
+    /// The `Set-Cookie` header value storing the errors
+    ///
+    /// Only the paths, codes and messages are stored, the last errors are dropped to fit in `FLASH_MAX_SIZE` bytes.
+    pub fn set_cookie(&self) -> String {
+        let mut errors = self
+            .body
+            .errors
+            .iter()
+            .map(FlashError::from)
+            .collect::<Vec<_>>();
+        let value = loop {
+            let json = serde_json::to_string(&errors).unwrap_or_default();
+            let value = utf8_percent_encode(&json, NON_ALPHANUMERIC).to_string();
+            if FLASH_COOKIE.len() + 1 + value.len() <= FLASH_MAX_SIZE || errors.pop().is_none() {
+                break value;
+            }
+        };
+        format!("{FLASH_COOKIE}={value}; Path=/; Max-Age={FLASH_MAX_AGE}; HttpOnly; SameSite=Lax")
+    }
+}
+
+impl Display for FlashRedirect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} validation errors, redirecting to {}",
+            self.body.errors.len(),
+            self.location
+        )
+    }
+}
+
+impl IntoResponse for FlashRedirect {
+    fn into_response(self) -> Response {
+        let mut response = StatusCode::SEE_OTHER.into_response();
+        let headers = response.headers_mut();
+        if let Ok(location) = HeaderValue::try_from(self.location.as_str()) {
+            headers.insert(LOCATION, location);
+        }
+        if let Ok(cookie) = HeaderValue::try_from(self.set_cookie()) {
+            headers.insert(SET_COOKIE, cookie);
+        }
+        response
+    }
+}
+
+/// `RedirectRejection` is returned when the `RedirectOnError` extractor fails.
+#[derive(Debug)]
+pub enum RedirectRejection<E> {
+    /// The data is invalid
+    Valid(FlashRedirect),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for RedirectRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RedirectRejection::Valid(redirect) => write!(f, "{redirect}"),
+            RedirectRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for RedirectRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RedirectRejection::Valid(_) => None,
+            RedirectRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for RedirectRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            RedirectRejection::Valid(redirect) => redirect.into_response(),
+            RedirectRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+/// # `RedirectOnError` validation extractor
+///
+/// See the [module documentation](crate::redirect) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedirectOnError<E>(pub E);
+
+impl<E> Deref for RedirectOnError<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for RedirectOnError<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> RedirectOnError<E> {
+    /// Consumes the `RedirectOnError` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+/// The URI requested by the client, before being stripped by nested routers
+fn original_uri(extensions: &Extensions, uri: &Uri) -> Uri {
+    extensions
+        .get::<OriginalUri>()
+        .map_or_else(|| uri.clone(), |OriginalUri(uri)| uri.clone())
+}
+
+fn redirect<V, E>(rejection: ValidationRejection<V, E>, uri: &Uri) -> RedirectRejection<E>
+where
+    ErrorBody: From<V>,
+{
+    match rejection {
+        ValidationRejection::Valid(errors) => RedirectRejection::Valid(FlashRedirect {
+            location: uri
+                .path_and_query()
+                .map_or_else(|| uri.path().to_owned(), ToString::to_string),
+            body: ErrorBody::from(errors),
+        }),
+        ValidationRejection::Inner(error) => RedirectRejection::Inner(error),
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequest<State> for RedirectOnError<Extractor>
+where
+    State: Send + Sync,
+    Extractor: FromRequest<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = RedirectRejection<E>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let uri = original_uri(req.extensions(), req.uri());
+        Extractor::from_request(req, state)
+            .await
+            .map(RedirectOnError)
+            .map_err(|rejection| redirect(rejection, &uri))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequestParts<State> for RedirectOnError<Extractor>
+where
+    State: Send + Sync,
+    Extractor: FromRequestParts<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = RedirectRejection<E>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        Extractor::from_request_parts(parts, state)
+            .await
+            .map(RedirectOnError)
+            .map_err(|rejection| redirect(rejection, &original_uri(&parts.extensions, &parts.uri)))
+    }
+}
+
+/// # `FlashErrors` extractor
+///
+/// The errors stored in the flash cookie by `RedirectOnError`, `None` if there's no valid flash cookie.
+///
+/// As a response part, it clears the flash cookie if it was set, so that the errors are shown once.
+#[derive(Debug, Clone, Default)]
+pub struct FlashErrors(pub Option<ErrorBody>);
+
+impl FlashErrors {
+    /// Read the errors from the `Cookie` headers
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let errors = headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .find_map(|cookie| {
+                let (name, value) = cookie.trim().split_once('=')?;
+                (name == FLASH_COOKIE).then_some(value)
+            })
+            .and_then(|value| percent_decode_str(value).decode_utf8().ok())
+            .and_then(|json| serde_json::from_str::<Vec<FlashError>>(&json).ok())
+            .map(|errors| ErrorBody::new(errors.into_iter().map(FieldError::from).collect()));
+        FlashErrors(errors)
+    }
+
+    /// The `Set-Cookie` header value clearing the flash cookie
+    pub fn clear_cookie() -> String {
+        format!("{FLASH_COOKIE}=; Path=/; Max-Age=0; HttpOnly; SameSite=Lax")
+    }
+}
+
+impl IntoResponseParts for FlashErrors {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        if self.0.is_none() {
+            return Ok(res);
+        }
+        if let Ok(cookie) = HeaderValue::try_from(FlashErrors::clear_cookie()) {
+            res.headers_mut().append(SET_COOKIE, cookie);
+        }
+        Ok(res)
+    }
+}
+
+#[async_trait]
+impl<State: Send + Sync> FromRequestParts<State> for FlashErrors {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &State) -> Result<Self, Self::Rejection> {
+        Ok(FlashErrors::from_headers(&parts.headers))
+    }
+}
+
+#[cfg(all(test, feature = "validator", feature = "form"))]
+mod tests {
+    use super::*;
+    use crate::Valid;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Form;
+    use validator::Validate;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Parameters {
+        #[validate(length(min = 1, max = 5))]
+        name: String,
+    }
+
+    #[tokio::test]
+    async fn redirect_with_flash_cookie() -> anyhow::Result<()> {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/signup?step=2")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from("name=too+long"))?;
+        let rejection = RedirectOnError::<Valid<Form<Parameters>>>::from_request(request, &())
+            .await
+            .expect_err("name is too long");
+        let response = rejection.into_response();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()[LOCATION], "/signup?step=2");
+        let set_cookie = response.headers()[SET_COOKIE].to_str()?;
+        assert!(set_cookie.starts_with("validation_errors="));
+        assert!(set_cookie.contains("HttpOnly"));
+        // the submitted value isn't stored
+        let decoded = percent_decode_str(set_cookie).decode_utf8()?;
+        assert!(!decoded.contains("too long"));
+        assert!(!decoded.contains("params"));
+
+        // the form reads the errors back from the cookie
+        let cookie = set_cookie.split(';').next().unwrap_or_default();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            COOKIE,
+            HeaderValue::from_str(&format!("theme=dark; {cookie}"))?,
+        );
+        let flash = FlashErrors::from_headers(&headers);
+        let errors = flash
+            .0
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("no flash errors"))?;
+        assert_eq!(errors.errors[0].path.to_string(), "name");
+        assert_eq!(errors.errors[0].code.as_deref(), Some("length"));
+        assert!(errors.errors[0].params.is_empty());
+
+        // returning the flash errors clears the cookie
+        let response = (flash, "form").into_response();
+        let cleared = response.headers()[SET_COOKIE].to_str()?;
+        assert!(cleared.starts_with("validation_errors=;"));
+        assert!(cleared.contains("Max-Age=0"));
+
+        let flash = FlashErrors::from_headers(&HeaderMap::new());
+        assert!(flash.0.is_none());
+        let response = (flash, "form").into_response();
+        assert!(response.headers().get(SET_COOKIE).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn flash_cookie_size_is_capped() -> anyhow::Result<()> {
+        let errors = (0..500)
+            .map(|i| {
+                let mut error = FieldError::new(FieldPath::new().key("items").index(i), "length");
+                error.message = Some(format!("item {i} is too long"));
+                error
+            })
+            .collect();
+        let redirect = FlashRedirect {
+            location: String::from("/items"),
+            body: ErrorBody::new(errors),
+        };
+        let cookie = redirect.set_cookie();
+        let value = cookie.split(';').next().unwrap_or_default();
+        assert!(value.len() <= FLASH_MAX_SIZE);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, HeaderValue::from_str(value)?);
+        let errors = FlashErrors::from_headers(&headers)
+            .0
+            .ok_or_else(|| anyhow::anyhow!("no flash errors"))?;
+        // the first errors are kept
+        assert!(!errors.errors.is_empty() && errors.errors.len() < 500);
+        assert_eq!(errors.errors[0].path.to_string(), "items[0]");
+        assert_eq!(
+            errors.errors[0].message.as_deref(),
+            Some("item 0 is too long")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+//! # Shared cache of compiled regexes
+//!
+//! ## Feature
+//!
+//! Enable the `regex_cache` feature to use `RegexCache`.
+//!
+//! validator's `regex` rule requires a pattern known at compile time.
+//! For patterns only known at runtime (e.g. configured per tenant), compiling the pattern
+//! on every request is expensive. `RegexCache` compiles each pattern once and shares it across requests.
+//! Put it in the state and pass it as the validation arguments of `ValidEx`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::extract::FromRef;
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::regex_cache::RegexCache;
+//! use axum_valid::ValidEx;
+//! use serde::Deserialize;
+//! use validator::{Validate, ValidationError};
+//!
+//! #[derive(Clone, FromRef)]
+//! struct AppState {
+//!     regex_cache: RegexCache,
+//! }
+//!
+//! #[derive(Validate, Deserialize)]
+//! #[validate(context = RegexCache)]
+//! pub struct Parameter {
+//!     #[validate(custom(function = "validate_sku", use_context))]
+//!     pub sku: String,
+//! }
+//!
+//! fn validate_sku(sku: &str, cache: &RegexCache) -> Result<(), ValidationError> {
+//!     // e.g. loaded from the configuration
+//!     cache.validate(sku, "^[A-Z]{3}-[0-9]{4}$")
+//! }
+//!
+//! async fn handler(ValidEx(Json(parameter)): ValidEx<Json<Parameter>>) {
+//!     println!("sku = {}", parameter.sku);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new()
+//!     .route("/json", post(handler))
+//!     .with_state(AppState { regex_cache: RegexCache::default() });
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use validator::ValidationError;
+
+/// Cache of compiled regexes keyed by pattern, cheap to clone and shared between clones
+#[derive(Debug, Clone, Default)]
+pub struct RegexCache(Arc<RwLock<HashMap<String, Regex>>>);
+
+impl RegexCache {
+    /// Get the compiled regex of a pattern, compiling it on first use
+    pub fn get(&self, pattern: &str) -> Result<Regex, regex::Error> {
+        if let Some(regex) = self
+            .0
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(pattern)
+        {
+            return Ok(regex.clone());
+        }
+        let mut cache = self
+            .0
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // another request may have compiled it in the meantime
+        if let Some(regex) = cache.get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex = Regex::new(pattern)?;
+        cache.insert(pattern.to_owned(), regex.clone());
+        Ok(regex)
+    }
+
+    /// Number of compiled patterns
+    pub fn len(&self) -> usize {
+        self.0
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .len()
+    }
+
+    /// Check if no pattern has been compiled yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Validator rule checking that `value` matches `pattern`
+    ///
+    /// A mismatch is reported with the code `regex`, like validator's `regex` rule.
+    /// An invalid pattern is reported with the code `invalid_pattern`.
+    pub fn validate(&self, value: &str, pattern: &str) -> Result<(), ValidationError> {
+        let regex = self.get(pattern).map_err(|e| {
+            let mut error = ValidationError::new("invalid_pattern");
+            error.message = Some(Cow::from(e.to_string()));
+            error
+        })?;
+        if regex.is_match(value) {
+            Ok(())
+        } else {
+            let mut error = ValidationError::new("regex");
+            error.add_param(Cow::from("pattern"), &pattern);
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_pattern() {
+        let cache = RegexCache::default();
+        let error = cache.validate("abc", "(").expect_err("pattern is invalid");
+        assert_eq!(error.code, "invalid_pattern");
+        assert!(cache.is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn compile_once_across_requests() -> anyhow::Result<()> {
+        use crate::{ValidEx, ValidRejection};
+        use axum::body::Body;
+        use axum::extract::{FromRequest, Request};
+        use axum::http::header::CONTENT_TYPE;
+        use axum::Json;
+        use validator::Validate;
+
+        const PATTERN: &str = "^[A-Z]{3}-[0-9]{4}$";
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(context = RegexCache)]
+        struct Item {
+            #[validate(custom(function = "validate_sku", use_context))]
+            sku: String,
+        }
+
+        fn validate_sku(sku: &str, cache: &RegexCache) -> Result<(), ValidationError> {
+            cache.validate(sku, PATTERN)
+        }
+
+        let request = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+
+        let cache = RegexCache::default();
+        for sku in [r#"{"sku": "ABC-1234"}"#, r#"{"sku": "XYZ-0000"}"#] {
+            assert!(ValidEx::<Json<Item>>::from_request(request(sku)?, &cache)
+                .await
+                .is_ok());
+        }
+        assert!(matches!(
+            ValidEx::<Json<Item>>::from_request(request(r#"{"sku": "abc"}"#)?, &cache).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["sku"][0].code == "regex"
+        ));
+        // the pattern was compiled once for the three requests
+        assert_eq!(cache.len(), 1);
+        Ok(())
+    }
+}
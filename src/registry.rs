@@ -0,0 +1,879 @@
+//! # Validation against a schema registry
+//!
+//! ## Feature
+//!
+//! Enable the `registry` feature to use `ValidRegistry<T>`.
+//!
+//! In event-driven systems, producers and consumers agree on the shape of events through schemas
+//! published in a registry. `ValidRegistry<T>` extracts a JSON body, fetches the schema of the route
+//! from the `SchemaRegistry` provided by the state, checks the body against it, then deserializes it into `T`.
+//!
+//! The schema is chosen by the server, never by the client: it's the `SchemaId` provided by the state,
+//! e.g. a state per route or per router. Clients may still name the schema they produced in the
+//! `X-Schema-Id` header, which is then only checked against the `SchemaId`, and the request is rejected
+//! with `400 Bad Request` if they differ.
+//!
+//! `SchemaRegistry` fetches schemas with a `RegistryClient`, such as an HTTP client of the registry,
+//! and caches them by id, as published schemas are immutable. Ids the registry doesn't have are cached
+//! too, for `SchemaRegistry::with_unknown_ttl` (a minute by default), so that a misconfigured route doesn't
+//! hit the registry on every request. Failed fetches aren't cached.
+//!
+//! A body that doesn't match its schema is rejected with an `ErrorBody` and `VALIDATION_ERROR_STATUS`
+//! (`400 Bad Request` by default). A schema the registry doesn't have, or which relies on unsupported keywords,
+//! is rejected with `500 Internal Server Error`, as it's a misconfiguration, and a registry failure
+//! with `502 Bad Gateway`.
+//!
+//! ## Supported keywords
+//!
+//! `Schema` supports exactly the following subset of JSON Schema, reported with the same codes as validator's rules:
+//!
+//! | Keywords                                         | Code                    | Parameters            |
+//! |--------------------------------------------------|-------------------------|-----------------------|
+//! | `type`, a name or a list of names                | `type`                  | `expected`            |
+//! | `enum`                                           | `enum`                  | `allowed`             |
+//! | `minimum`, `maximum`                             | `range`                 | `min`, `max`, `value` |
+//! | `minLength`, `maxLength`, `minItems`, `maxItems` | `length`                | `min`, `max`, `value` |
+//! | `required`                                       | `required`              |                       |
+//! | `additionalProperties: false`                    | `additional_properties` |                       |
+//!
+//! * `type` accepts `null`, `boolean`, `integer`, `number`, `string`, `array` and `object`. Like in JSON Schema,
+//!   `integer` matches any number without a fractional part, such as `1.0`, and `number` matches integers.
+//! * `minimum` and `maximum` are inclusive, `minLength` and `maxLength` count characters.
+//! * `enum` compares values as `serde_json::Value`s, so `1` and `1.0` are different values.
+//! * `properties` and `items`, a single schema, apply schemas to fields and elements, whose errors have nested
+//!   paths such as `lines[1].sku`. Schemas of `additionalProperties` aren't applied.
+//!
+//! Annotations, which don't constrain values, are allowed too: `$schema`, `$id`, `$comment`, `title`, `description`,
+//! `default`, `examples`, `deprecated`, `readOnly` and `writeOnly`.
+//!
+//! Schemas relying on any other keyword, e.g. `$ref`, `allOf`, `const`, `pattern`, `format` or `uniqueItems`,
+//! on a list of schemas for `items`, or on a schema for `additionalProperties`, can't be checked:
+//! they are rejected when fetched with `RegistryRejection::UnsupportedSchema`, instead of accepting every body.
+//! `Schema::unsupported_keyword` finds these keywords.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::extract::FromRef;
+//! use axum::routing::post;
+//! use axum::{async_trait, BoxError, Router};
+//! use axum_valid::registry::{RegistryClient, Schema, SchemaId, SchemaRegistry, ValidRegistry};
+//! use serde::Deserialize;
+//!
+//! #[derive(Clone, FromRef)]
+//! struct EventState {
+//!     registry: SchemaRegistry,
+//!     schema: SchemaId,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! pub struct OrderPlaced {
+//!     pub order_id: String,
+//! }
+//!
+//! struct HttpRegistry;
+//!
+//! #[async_trait]
+//! impl RegistryClient for HttpRegistry {
+//!     async fn fetch(&self, id: &str) -> Result<Option<Schema>, BoxError> {
+//!         // e.g. `GET https://registry.example.com/schemas/{id}`
+//!         Ok(Some(Schema::new(serde_json::json!({
+//!             "type": "object",
+//!             "required": ["order_id"],
+//!             "properties": {"order_id": {"type": "string", "minLength": 1}}
+//!         }))))
+//!     }
+//! }
+//!
+//! async fn handler(ValidRegistry(event): ValidRegistry<OrderPlaced>) {
+//!     println!("order {} placed", event.order_id);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let registry = SchemaRegistry::new(HttpRegistry);
+//! let router = Router::new().route(
+//!     "/orders/placed",
+//!     post(handler).with_state(EventState {
+//!         registry,
+//!         schema: SchemaId::new("order-placed-v1"),
+//!     }),
+//! );
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::{FieldError, FieldPath};
+use crate::ErrorBody;
+use axum::async_trait;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{BoxError, Json};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Name of the header naming the schema of the body, checked against the `SchemaId` if present
+pub const SCHEMA_ID_HEADER: &str = "x-schema-id";
+
+/// Id of the schema of a route, provided by the state
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SchemaId(Arc<str>);
+
+impl SchemaId {
+    /// Create a schema id
+    pub fn new(id: impl Into<Arc<str>>) -> Self {
+        SchemaId(id.into())
+    }
+
+    /// The id as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for SchemaId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A JSON schema, see the [module documentation](crate::registry) for the supported keywords
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema(Value);
+
+impl Schema {
+    /// Create a schema from its JSON document
+    pub fn new(schema: Value) -> Self {
+        Schema(schema)
+    }
+
+    /// The JSON document of the schema
+    pub fn as_value(&self) -> &Value {
+        &self.0
+    }
+
+    /// Find a keyword the schema relies on that isn't supported, by its path, e.g. `properties.email.format`
+    ///
+    /// `validate` ignores unsupported keywords, `SchemaRegistry` rejects the schemas relying on them.
+    pub fn unsupported_keyword(&self) -> Option<String> {
+        unsupported_keyword(&self.0, "")
+    }
+
+    /// Check a value against the schema
+    pub fn validate(&self, value: &Value) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        check(&self.0, value, FieldPath::new(), &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Keywords supported by `Schema`
+const KEYWORDS: [&str; 12] = [
+    "type",
+    "enum",
+    "minimum",
+    "maximum",
+    "minLength",
+    "maxLength",
+    "minItems",
+    "maxItems",
+    "required",
+    "properties",
+    "items",
+    "additionalProperties",
+];
+
+/// Keywords which don't constrain values
+const ANNOTATIONS: [&str; 10] = [
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "deprecated",
+    "readOnly",
+    "writeOnly",
+];
+
+fn unsupported_keyword(schema: &Value, path: &str) -> Option<String> {
+    let Some(schema) = schema.as_object() else {
+        // `true` accepts every value, like `{}`
+        return (schema != &Value::Bool(true)).then(|| path.to_owned());
+    };
+    let join = |keyword: &str| {
+        if path.is_empty() {
+            keyword.to_owned()
+        } else {
+            format!("{path}.{keyword}")
+        }
+    };
+    for (keyword, value) in schema {
+        let keyword = keyword.as_str();
+        let unsupported = match keyword {
+            "properties" => match value.as_object() {
+                Some(properties) => properties.iter().find_map(|(name, property)| {
+                    unsupported_keyword(property, &format!("{}.{name}", join(keyword)))
+                }),
+                None => Some(join(keyword)),
+            },
+            "items" if value.is_array() => Some(join(keyword)),
+            "items" => unsupported_keyword(value, &join(keyword)),
+            "additionalProperties" if !value.is_boolean() => Some(join(keyword)),
+            keyword if KEYWORDS.contains(&keyword) || ANNOTATIONS.contains(&keyword) => None,
+            keyword => Some(join(keyword)),
+        };
+        if unsupported.is_some() {
+            return unsupported;
+        }
+    }
+    None
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number)
+            if number.is_i64()
+                || number.is_u64()
+                || number.as_f64().is_some_and(|number| number.fract() == 0.0) =>
+        {
+            "integer"
+        }
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn check(schema: &Value, value: &Value, path: FieldPath, errors: &mut Vec<FieldError>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    let error = |code: &str, params: &[(&str, Option<&Value>)]| {
+        let mut error = FieldError::new(path.clone(), code);
+        for (name, param) in params {
+            if let Some(param) = param {
+                error.params.insert(name.to_string(), (*param).clone());
+            }
+        }
+        error
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let actual = type_name(value);
+        let matches = |name: &Value| {
+            name.as_str()
+                .is_some_and(|name| name == actual || (name == "number" && actual == "integer"))
+        };
+        let matched = match expected {
+            Value::Array(names) => names.iter().any(matches),
+            name => matches(name),
+        };
+        if !matched {
+            errors.push(error("type", &[("expected", Some(expected))]));
+            return;
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(error("enum", &[("allowed", schema.get("enum"))]));
+        }
+    }
+
+    let (min, max) = match value {
+        Value::Number(_) => ("minimum", "maximum"),
+        Value::String(_) => ("minLength", "maxLength"),
+        Value::Array(_) => ("minItems", "maxItems"),
+        _ => ("", ""),
+    };
+    let (min, max) = (schema.get(min), schema.get(max));
+    let out_of_bounds = |value: f64| {
+        min.and_then(Value::as_f64).is_some_and(|min| value < min)
+            || max.and_then(Value::as_f64).is_some_and(|max| value > max)
+    };
+    let out_of_bounds = match value {
+        Value::Number(number) => out_of_bounds(number.as_f64().unwrap_or_default()),
+        Value::String(string) => out_of_bounds(string.chars().count() as f64),
+        Value::Array(items) => out_of_bounds(items.len() as f64),
+        _ => false,
+    };
+    if out_of_bounds {
+        let code = if value.is_number() { "range" } else { "length" };
+        errors.push(error(
+            code,
+            &[("min", min), ("max", max), ("value", Some(value))],
+        ));
+    }
+
+    match value {
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(item_schema, item, path.clone().index(index), errors);
+                }
+            }
+        }
+        Value::Object(object) => {
+            let required = schema.get("required").and_then(Value::as_array);
+            for name in required.into_iter().flatten().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(FieldError::new(path.clone().key(name), "required"));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let closed = schema.get("additionalProperties") == Some(&Value::Bool(false));
+            for (name, value) in object {
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(property) => check(property, value, path.clone().key(name), errors),
+                    None if closed => errors.push(FieldError::new(
+                        path.clone().key(name),
+                        "additional_properties",
+                    )),
+                    None => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Client of a schema registry
+#[async_trait]
+pub trait RegistryClient: Send + Sync + 'static {
+    /// Fetch a schema by id, `None` if the registry doesn't have it
+    async fn fetch(&self, id: &str) -> Result<Option<Schema>, BoxError>;
+}
+
+/// A cached schema, an id the registry didn't have, or a schema relying on an unsupported keyword
+#[derive(Debug, Clone)]
+enum Cached {
+    Schema(Arc<Schema>),
+    Unknown(Instant),
+    Unsupported(String),
+}
+
+/// Schema registry with cached schemas, provided by the state
+#[derive(Clone)]
+pub struct SchemaRegistry {
+    client: Arc<dyn RegistryClient>,
+    cache: Arc<RwLock<HashMap<String, Cached>>>,
+    unknown_ttl: Duration,
+}
+
+impl Debug for SchemaRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchemaRegistry")
+            .field("cache", &self.cache)
+            .field("unknown_ttl", &self.unknown_ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SchemaRegistry {
+    /// Create a registry fetching schemas with `client`
+    pub fn new(client: impl RegistryClient) -> Self {
+        Self {
+            client: Arc::new(client),
+            cache: Arc::default(),
+            unknown_ttl: Duration::from_secs(60),
+        }
+    }
+
+    /// How long an id the registry doesn't have is remembered before it's fetched again, a minute by default
+    pub fn with_unknown_ttl(mut self, unknown_ttl: Duration) -> Self {
+        self.unknown_ttl = unknown_ttl;
+        self
+    }
+
+    /// Get a schema by id, from the cache or else from the registry
+    ///
+    /// Schemas relying on unsupported keywords are rejected, see `Schema::unsupported_keyword`.
+    pub async fn schema(&self, id: &str) -> Result<Arc<Schema>, RegistryRejection> {
+        let cached = self
+            .cache
+            .read()
+            .ok()
+            .and_then(|cache| cache.get(id).cloned());
+        match cached {
+            Some(Cached::Schema(schema)) => return Ok(schema),
+            Some(Cached::Unknown(since)) if since.elapsed() < self.unknown_ttl => {
+                return Err(RegistryRejection::UnknownSchema(id.to_owned()))
+            }
+            Some(Cached::Unsupported(keyword)) => {
+                return Err(RegistryRejection::UnsupportedSchema {
+                    id: id.to_owned(),
+                    keyword,
+                })
+            }
+            _ => {}
+        }
+        let fetched = self
+            .client
+            .fetch(id)
+            .await
+            .map_err(RegistryRejection::Registry)?;
+        let cached = match fetched {
+            Some(schema) => match schema.unsupported_keyword() {
+                Some(keyword) => Cached::Unsupported(keyword),
+                None => Cached::Schema(Arc::new(schema)),
+            },
+            None => Cached::Unknown(Instant::now()),
+        };
+        if let Ok(mut cache) = self.cache.write() {
+            cache.insert(id.to_owned(), cached.clone());
+        }
+        match cached {
+            Cached::Schema(schema) => Ok(schema),
+            Cached::Unknown(_) => Err(RegistryRejection::UnknownSchema(id.to_owned())),
+            Cached::Unsupported(keyword) => Err(RegistryRejection::UnsupportedSchema {
+                id: id.to_owned(),
+                keyword,
+            }),
+        }
+    }
+}
+
+/// # `ValidRegistry` data extractor
+///
+/// See the [module documentation](crate::registry) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidRegistry<T>(pub T);
+
+impl<T> Deref for ValidRegistry<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ValidRegistry<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> ValidRegistry<T> {
+    /// Consumes the `ValidRegistry` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// `RegistryRejection` is returned when the `ValidRegistry` extractor fails.
+#[derive(Debug)]
+pub enum RegistryRejection {
+    /// The `X-Schema-Id` header names another schema than the one of the route
+    SchemaMismatch {
+        /// The id of the schema of the route
+        expected: SchemaId,
+        /// The id named by the header
+        found: String,
+    },
+    /// The registry doesn't have the schema of the route
+    UnknownSchema(String),
+    /// The schema of the route relies on a keyword that isn't supported
+    UnsupportedSchema {
+        /// The id of the schema
+        id: String,
+        /// The path of the keyword in the schema, e.g. `properties.email.format`
+        keyword: String,
+    },
+    /// The schema can't be fetched
+    Registry(BoxError),
+    /// The body is rejected by `Json`
+    Json(JsonRejection),
+    /// The body doesn't match the schema
+    Invalid(ErrorBody),
+    /// The body matches the schema but can't be deserialized
+    Deserialize(serde_json::Error),
+}
+
+impl Display for RegistryRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryRejection::SchemaMismatch { expected, found } => {
+                write!(f, "Expected schema {expected}, found {found}")
+            }
+            RegistryRejection::UnknownSchema(id) => write!(f, "Unknown schema: {id}"),
+            RegistryRejection::UnsupportedSchema { id, keyword } => {
+                write!(f, "Schema {id} relies on an unsupported keyword: {keyword}")
+            }
+            RegistryRejection::Registry(e) => write!(f, "Failed to fetch the schema: {e}"),
+            RegistryRejection::Json(e) => write!(f, "{e}"),
+            RegistryRejection::Invalid(body) => {
+                write!(
+                    f,
+                    "The body doesn't match its schema: {} errors",
+                    body.errors.len()
+                )
+            }
+            RegistryRejection::Deserialize(e) => {
+                write!(f, "Failed to deserialize the body: {e}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RegistryRejection::Registry(e) => Some(e.as_ref()),
+            RegistryRejection::Json(e) => Some(e),
+            RegistryRejection::Deserialize(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for RegistryRejection {
+    fn into_response(self) -> Response {
+        match self {
+            RegistryRejection::Json(e) => e.into_response(),
+            RegistryRejection::Invalid(body) => body.into_response(),
+            e @ RegistryRejection::Registry(_) => {
+                (StatusCode::BAD_GATEWAY, e.to_string()).into_response()
+            }
+            // the schema of the route is misconfigured
+            e @ (RegistryRejection::UnknownSchema(_)
+            | RegistryRejection::UnsupportedSchema { .. }) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+            }
+            e => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidRegistry<T>
+where
+    State: Send + Sync,
+    SchemaRegistry: FromRef<State>,
+    SchemaId: FromRef<State>,
+    T: DeserializeOwned,
+{
+    type Rejection = RegistryRejection;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let expected = SchemaId::from_ref(state);
+        if let Some(found) = req.headers().get(SCHEMA_ID_HEADER) {
+            if found.as_bytes() != expected.as_str().as_bytes() {
+                return Err(RegistryRejection::SchemaMismatch {
+                    expected,
+                    found: String::from_utf8_lossy(found.as_bytes()).into_owned(),
+                });
+            }
+        }
+        let schema = SchemaRegistry::from_ref(state)
+            .schema(expected.as_str())
+            .await?;
+        let Json(value) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(RegistryRejection::Json)?;
+        schema
+            .validate(&value)
+            .map_err(|errors| RegistryRejection::Invalid(ErrorBody::new(errors)))?;
+        T::deserialize(value)
+            .map(ValidRegistry)
+            .map_err(RegistryRejection::Deserialize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VALIDATION_ERROR_STATUS;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug, serde::Deserialize)]
+    struct OrderPlaced {
+        order_id: String,
+        quantity: u32,
+    }
+
+    /// Registry with a single schema, counting fetches
+    #[derive(Default)]
+    struct MockRegistry(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl RegistryClient for MockRegistry {
+        async fn fetch(&self, id: &str) -> Result<Option<Schema>, BoxError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            match id {
+                "order-placed-v1" => Ok(Some(Schema::new(json!({
+                    "type": "object",
+                    "required": ["order_id", "quantity"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "order_id": {"type": "string", "minLength": 1, "maxLength": 16},
+                        "quantity": {"type": "integer", "minimum": 1, "maximum": 100},
+                        "channel": {"enum": ["web", "store"]}
+                    }
+                })))),
+                "order-placed-v2" => Ok(Some(Schema::new(json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "title": "Order placed",
+                    "type": "object",
+                    "properties": {
+                        "order_id": {"type": "string", "pattern": "^[A-Z][0-9]+$"},
+                        "quantity": {"type": "integer", "minimum": 1}
+                    }
+                })))),
+                "unavailable" => Err("registry unavailable".into()),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    #[derive(Clone, axum::extract::FromRef)]
+    struct EventState {
+        registry: SchemaRegistry,
+        schema: SchemaId,
+    }
+
+    async fn extract(
+        registry: &SchemaRegistry,
+        schema: &str,
+        header: Option<&str>,
+        body: Value,
+    ) -> anyhow::Result<Result<OrderPlaced, RegistryRejection>> {
+        let mut request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(header) = header {
+            request = request.header(SCHEMA_ID_HEADER, header);
+        }
+        let request = request.body(Body::from(body.to_string()))?;
+        let state = EventState {
+            registry: registry.clone(),
+            schema: SchemaId::new(schema),
+        };
+        Ok(ValidRegistry::<OrderPlaced>::from_request(request, &state)
+            .await
+            .map(ValidRegistry::into_inner))
+    }
+
+    #[tokio::test]
+    async fn validate_against_fetched_schema() -> anyhow::Result<()> {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let registry = SchemaRegistry::new(MockRegistry(fetches.clone()));
+        let id = "order-placed-v1";
+
+        let order = extract(
+            &registry,
+            id,
+            None,
+            json!({"order_id": "A1", "quantity": 2}),
+        )
+        .await??;
+        assert_eq!((order.order_id.as_str(), order.quantity), ("A1", 2));
+        // an integer may have a zero fractional part
+        let integer = Schema::new(json!({"type": "integer"}));
+        assert!(integer.validate(&json!(2.0)).is_ok());
+        assert!(integer.validate(&json!(2.5)).is_err());
+
+        let rejection = extract(
+            &registry,
+            id,
+            None,
+            json!({"order_id": "", "quantity": 0, "channel": "phone", "note": "x"}),
+        )
+        .await?
+        .expect_err("doesn't match the schema");
+        let RegistryRejection::Invalid(body) = &rejection else {
+            panic!("expected schema errors");
+        };
+        let errors = body
+            .errors
+            .iter()
+            .map(|error| {
+                (
+                    error.path.to_string(),
+                    error.code.as_deref().unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let errors = errors
+            .iter()
+            .map(|(path, code)| (path.as_str(), *code))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                ("channel", "enum"),
+                ("note", "additional_properties"),
+                ("order_id", "length"),
+                ("quantity", "range"),
+            ]
+        );
+        assert_eq!(rejection.into_response().status(), VALIDATION_ERROR_STATUS);
+
+        let rejection = extract(
+            &registry,
+            id,
+            None,
+            json!({"order_id": "A1", "quantity": "2"}),
+        )
+        .await?
+        .expect_err("wrong type");
+        assert!(matches!(
+            rejection,
+            RegistryRejection::Invalid(body)
+                if body.errors[0].code.as_deref() == Some("type")
+                    && body.errors[0].params["expected"] == "integer"
+        ));
+        let rejection = extract(&registry, id, None, json!({"order_id": "A1"}))
+            .await?
+            .expect_err("missing field");
+        assert!(matches!(
+            rejection,
+            RegistryRejection::Invalid(body)
+                if body.errors[0].path.to_string() == "quantity"
+                    && body.errors[0].code.as_deref() == Some("required")
+        ));
+
+        // the schema is fetched once
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn schema_id_header_is_checked() -> anyhow::Result<()> {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let registry = SchemaRegistry::new(MockRegistry(fetches.clone()));
+        let body = json!({"order_id": "A1", "quantity": 2});
+
+        assert!(extract(
+            &registry,
+            "order-placed-v1",
+            Some("order-placed-v1"),
+            body.clone()
+        )
+        .await?
+        .is_ok());
+        // the client can't choose another schema, even a known one
+        let rejection = extract(&registry, "order-placed-v1", Some("order-placed-v0"), body)
+            .await?
+            .expect_err("another schema");
+        assert!(matches!(
+            &rejection,
+            RegistryRejection::SchemaMismatch { expected, found }
+                if expected.as_str() == "order-placed-v1" && found == "order-placed-v0"
+        ));
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unknown_and_unavailable_schemas() -> anyhow::Result<()> {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let registry = SchemaRegistry::new(MockRegistry(fetches.clone()));
+        let body = json!({"order_id": "A1", "quantity": 2});
+
+        let rejection = extract(&registry, "unknown", None, body.clone())
+            .await?
+            .expect_err("unknown schema");
+        assert!(matches!(&rejection, RegistryRejection::UnknownSchema(id) if id == "unknown"));
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        // unknown ids are cached
+        assert!(extract(&registry, "unknown", None, body.clone())
+            .await?
+            .is_err());
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        // until they expire
+        let registry = registry.with_unknown_ttl(Duration::ZERO);
+        assert!(extract(&registry, "unknown", None, body.clone())
+            .await?
+            .is_err());
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+
+        let rejection = extract(&registry, "unavailable", None, body.clone())
+            .await?
+            .expect_err("registry failure");
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_GATEWAY);
+
+        // failures aren't cached
+        assert!(extract(&registry, "unavailable", None, body)
+            .await?
+            .is_err());
+        assert_eq!(fetches.load(Ordering::SeqCst), 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unsupported_keywords_are_rejected() -> anyhow::Result<()> {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let registry = SchemaRegistry::new(MockRegistry(fetches.clone()));
+        let body = json!({"order_id": "A1", "quantity": 2});
+
+        for _ in 0..2 {
+            let rejection = extract(&registry, "order-placed-v2", None, body.clone())
+                .await?
+                .expect_err("unsupported schema");
+            assert!(matches!(
+                &rejection,
+                RegistryRejection::UnsupportedSchema { id, keyword }
+                    if id == "order-placed-v2" && keyword == "properties.order_id.pattern"
+            ));
+            assert_eq!(
+                rejection.into_response().status(),
+                StatusCode::INTERNAL_SERVER_ERROR
+            );
+        }
+        // schemas are immutable, so the rejection is cached
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        let unsupported = |schema: Value| Schema::new(schema).unsupported_keyword();
+        assert_eq!(unsupported(json!({"type": "string", "title": "Id"})), None);
+        assert_eq!(unsupported(json!({"items": true})), None);
+        assert_eq!(
+            unsupported(json!({"oneOf": [{"type": "string"}]})).as_deref(),
+            Some("oneOf")
+        );
+        assert_eq!(
+            unsupported(json!({"items": {"properties": {"id": {"const": 1}}}})).as_deref(),
+            Some("items.properties.id.const")
+        );
+        assert_eq!(
+            unsupported(json!({"items": [{"type": "string"}]})).as_deref(),
+            Some("items")
+        );
+        assert_eq!(
+            unsupported(json!({"additionalProperties": {"type": "string"}})).as_deref(),
+            Some("additionalProperties")
+        );
+        assert_eq!(
+            unsupported(json!({"properties": {"id": false}})).as_deref(),
+            Some("properties.id")
+        );
+        Ok(())
+    }
+}
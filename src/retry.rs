@@ -0,0 +1,320 @@
+//! # Retry hints for transient validation errors
+//!
+//! ## Feature
+//!
+//! Enable the `retry` feature to use `WithRetryHints<E>`.
+//!
+//! Some validation rules depend on a context that changes over time, e.g. a rate limit, a quota,
+//! or a resource that is still being provisioned. Their failures are transient: the same request may succeed later.
+//!
+//! `WithRetryHints<E>` wraps a validation extractor such as `Valid<E>`. When validation fails,
+//! the codes of the errors are looked up in the `RetryHints` provided by the state, which map retryable codes
+//! to suggested delays. If all the errors are retryable, they are returned as an `ErrorBody` with `retryable: true`
+//! and the longest of their delays in seconds as `retry_after`, which is also sent as a `Retry-After` header.
+//! Otherwise, retrying wouldn't help, and they are returned as a usual `ErrorBody`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::extract::Query;
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::retry::{RetryHints, WithRetryHints};
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use std::time::Duration;
+//!     use validator::{Validate, ValidationError};
+//!
+//!     pub fn router() -> Router {
+//!         let hints = RetryHints::new().retryable("not_ready", Duration::from_secs(5));
+//!         Router::new()
+//!             .route("/query", get(handler))
+//!             .with_state(hints)
+//!     }
+//!
+//!     async fn handler(WithRetryHints(Valid(Query(parameter))): WithRetryHints<Valid<Query<Parameter>>>) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(custom(function = "validate_report"))]
+//!         pub report: u64,
+//!     }
+//!
+//!     fn validate_report(report: &u64) -> Result<(), ValidationError> {
+//!         // e.g. the report is still being generated
+//!         if *report < 1000 {
+//!             Ok(())
+//!         } else {
+//!             Err(ValidationError::new("not_ready"))
+//!         }
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::{ErrorBody, ValidationRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, FromRequestParts, Request};
+use axum::http::header::RETRY_AFTER;
+use axum::http::request::Parts;
+use axum::http::HeaderValue;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Suggested retry delays, indexed by the codes of transient errors
+#[derive(Debug, Clone, Default)]
+pub struct RetryHints(Arc<HashMap<String, Duration>>);
+
+impl RetryHints {
+    /// Create an empty mapping, where no error is retryable
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark an error code as retryable after `delay`
+    pub fn retryable(mut self, code: impl Into<String>, delay: Duration) -> Self {
+        Arc::make_mut(&mut self.0).insert(code.into(), delay);
+        self
+    }
+
+    /// Get the retry delay of an error code, `None` if it isn't retryable
+    pub fn get(&self, code: &str) -> Option<Duration> {
+        self.0.get(code).copied()
+    }
+
+    /// Get the retry delay of the errors in `body`
+    ///
+    /// This is the longest delay of the errors, `None` if any error isn't retryable or if there's no error.
+    pub fn hint_of(&self, body: &ErrorBody) -> Option<Duration> {
+        body.errors
+            .iter()
+            .try_fold(None, |delay: Option<Duration>, error| {
+                let hint = self.get(error.code.as_deref()?)?;
+                Some(Some(delay.map_or(hint, |delay| delay.max(hint))))
+            })?
+    }
+
+    /// Add the retry hint of the errors to `body`, if they are all retryable
+    pub fn apply(&self, body: ErrorBody) -> ErrorBody {
+        match self.hint_of(&body) {
+            Some(delay) => body.with_retry_hint(delay),
+            None => body,
+        }
+    }
+}
+
+/// `RetryHintRejection` is returned when the `WithRetryHints` extractor fails.
+#[derive(Debug)]
+pub enum RetryHintRejection<E> {
+    /// The data is invalid, the errors include a retry hint if they are transient
+    Valid(ErrorBody),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for RetryHintRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetryHintRejection::Valid(body) => match body.retry_after {
+                Some(delay) => write!(
+                    f,
+                    "{} validation errors, retry after {delay} seconds",
+                    body.errors.len()
+                ),
+                None => write!(f, "{} validation errors", body.errors.len()),
+            },
+            RetryHintRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for RetryHintRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RetryHintRejection::Valid(_) => None,
+            RetryHintRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for RetryHintRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            RetryHintRejection::Valid(body) => {
+                let retry_after = body.retry_after;
+                let mut response = body.into_response();
+                if let Some(delay) = retry_after {
+                    response
+                        .headers_mut()
+                        .insert(RETRY_AFTER, HeaderValue::from(delay));
+                }
+                response
+            }
+            RetryHintRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+/// # `WithRetryHints` validation extractor
+///
+/// See the [module documentation](crate::retry) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WithRetryHints<E>(pub E);
+
+impl<E> Deref for WithRetryHints<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for WithRetryHints<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> WithRetryHints<E> {
+    /// Consumes the `WithRetryHints` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+fn hint<V, E>(rejection: ValidationRejection<V, E>, hints: &RetryHints) -> RetryHintRejection<E>
+where
+    ErrorBody: From<V>,
+{
+    match rejection {
+        ValidationRejection::Valid(errors) => {
+            RetryHintRejection::Valid(hints.apply(ErrorBody::from(errors)))
+        }
+        ValidationRejection::Inner(error) => RetryHintRejection::Inner(error),
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequest<State> for WithRetryHints<Extractor>
+where
+    State: Send + Sync,
+    RetryHints: FromRef<State>,
+    Extractor: FromRequest<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = RetryHintRejection<E>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let hints = RetryHints::from_ref(state);
+        Extractor::from_request(req, state)
+            .await
+            .map(WithRetryHints)
+            .map_err(|rejection| hint(rejection, &hints))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequestParts<State> for WithRetryHints<Extractor>
+where
+    State: Send + Sync,
+    RetryHints: FromRef<State>,
+    Extractor: FromRequestParts<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = RetryHintRejection<E>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let hints = RetryHints::from_ref(state);
+        Extractor::from_request_parts(parts, state)
+            .await
+            .map(WithRetryHints)
+            .map_err(|rejection| hint(rejection, &hints))
+    }
+}
+
+#[cfg(all(test, feature = "validator", feature = "query"))]
+mod tests {
+    use super::*;
+    use crate::Valid;
+    use axum::extract::Query;
+    use axum::http::Request;
+    use validator::{Validate, ValidationError};
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Parameters {
+        #[validate(custom(function = "validate_report"))]
+        report: u64,
+        #[validate(range(max = 100))]
+        limit: u32,
+    }
+
+    fn validate_report(report: &u64) -> Result<(), ValidationError> {
+        if *report < 1000 {
+            Ok(())
+        } else {
+            Err(ValidationError::new("not_ready"))
+        }
+    }
+
+    async fn extract(uri: &str) -> anyhow::Result<Response> {
+        let hints = RetryHints::new()
+            .retryable("not_ready", Duration::from_millis(1500))
+            .retryable("rate_limited", Duration::from_secs(1));
+        let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+        let rejection =
+            WithRetryHints::<Valid<Query<Parameters>>>::from_request_parts(&mut parts, &hints)
+                .await
+                .expect_err("invalid parameters");
+        Ok(rejection.into_response())
+    }
+
+    async fn json(response: Response) -> anyhow::Result<serde_json::Value> {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    #[tokio::test]
+    async fn retryable_errors_have_hint() -> anyhow::Result<()> {
+        let response = extract("/?report=1000&limit=10").await?;
+        assert_eq!(response.status(), crate::VALIDATION_ERROR_STATUS);
+        assert_eq!(response.headers()[RETRY_AFTER], "2");
+        let body = json(response).await?;
+        assert_eq!(body["retryable"], true);
+        assert_eq!(body["retry_after"], 2);
+
+        let response = extract("/?report=1&limit=1000").await?;
+        assert!(!response.headers().contains_key(RETRY_AFTER));
+        let body = json(response).await?;
+        assert!(body.get("retryable").is_none());
+        assert!(body.get("retry_after").is_none());
+
+        // retrying can't fix the limit
+        let body = json(extract("/?report=1000&limit=1000").await?).await?;
+        assert!(body.get("retryable").is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+//! # Cached rule detection
+//!
+//! ## Feature
+//!
+//! Enable the `rule_cache` feature to use `ValidCached<E>`.
+//!
+//! Some types derive `Validate` without declaring any rule, e.g. because their fields are generated
+//! from a schema which may or may not constrain them, yet `Valid<E>` validates them on every request.
+//!
+//! Derive `HasRules` next to `Validate` to detect whether a type has any rule: it has rules if the type
+//! or one of its fields has a `#[validate]` attribute, which includes schema, custom and nested rules.
+//! `ValidCached<E>` evaluates `HasRules::has_rules` once per type, on its first request, and caches the result
+//! in a map keyed by the `TypeId` of the type, shared by all threads. Data of a type without rules
+//! is then passed to the handler without calling `validate`, data of other types is validated like with `Valid<E>`.
+//! Debug and release builds behave the same.
+//!
+//! The derive only reads the attributes of validator: for a type implementing `Validate` by hand,
+//! implement `HasRules` by hand too, and only return `false` if its `validate` never fails.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::rule_cache::{HasRules, ValidCached};
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize, HasRules)]
+//! pub struct Note {
+//!     pub text: String,
+//! }
+//!
+//! async fn handler(ValidCached(Json(note)): ValidCached<Json<Note>>) -> String {
+//!     note.text
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/notes", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::{HasValidate, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{OnceLock, RwLock};
+use validator::Validate;
+
+/// Detection of the validation rules of a type
+///
+/// Derive it from the `#[validate]` attributes of the type, see the [module documentation](crate::rule_cache).
+pub trait HasRules {
+    /// Check if the type has any validation rule
+    ///
+    /// It's evaluated once per type by `ValidCached`.
+    fn has_rules() -> bool;
+}
+
+pub use axum_valid_derive::HasRules;
+
+/// `HasRules::has_rules` of the types checked so far
+static RULES: OnceLock<RwLock<HashMap<TypeId, bool>>> = OnceLock::new();
+
+/// Check if `T` has any validation rule, evaluating `HasRules::has_rules` on the first call only
+pub fn has_rules<T: HasRules + 'static>() -> bool {
+    let rules = RULES.get_or_init(Default::default);
+    let type_id = TypeId::of::<T>();
+    let cached = rules
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&type_id)
+        .copied();
+    if let Some(has_rules) = cached {
+        return has_rules;
+    }
+    // evaluated under the write lock, so that concurrent first requests evaluate it once
+    *rules
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .entry(type_id)
+        .or_insert_with(T::has_rules)
+}
+
+/// # `ValidCached` data extractor
+///
+/// See the [module documentation](crate::rule_cache) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidCached<E>(pub E);
+
+impl<E> Deref for ValidCached<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidCached<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidCached<E> {
+    /// Consumes the `ValidCached` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+fn validate<T: Validate + HasRules + 'static>(data: &T) -> Result<(), validator::ValidationErrors> {
+    if has_rules::<T>() {
+        validate_redacted(data)
+    } else {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidCached<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequest<State>,
+    Extractor::Validate: Validate + HasRules + 'static,
+{
+    type Rejection = ValidRejection<<Extractor as FromRequest<State>>::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(ValidRejection::Inner)?;
+        validate(inner.get_validate())?;
+        Ok(ValidCached(inner))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequestParts<State> for ValidCached<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequestParts<State>,
+    Extractor::Validate: Validate + HasRules + 'static,
+{
+    type Rejection = ValidRejection<<Extractor as FromRequestParts<State>>::Rejection>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request_parts(parts, state)
+            .await
+            .map_err(ValidRejection::Inner)?;
+        validate(inner.get_validate())?;
+        Ok(ValidCached(inner))
+    }
+}
+
+#[cfg(all(test, feature = "query"))]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use axum::http::Request;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use validator::{ValidationError, ValidationErrors};
+
+    static VALIDATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, serde::Deserialize, HasRules)]
+    struct Plain {
+        text: String,
+    }
+
+    impl Validate for Plain {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            VALIDATIONS.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, serde::Deserialize, Validate, HasRules)]
+    struct Ruled {
+        #[validate(length(min = 1, max = 5))]
+        text: String,
+    }
+
+    #[tokio::test]
+    async fn rule_less_types_short_circuit() -> anyhow::Result<()> {
+        for _ in 0..3 {
+            let (mut parts, _) = Request::builder().uri("/?text=").body(())?.into_parts();
+            let ValidCached(Query(plain)) =
+                ValidCached::<Query<Plain>>::from_request_parts(&mut parts, &())
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{e}"))?;
+            assert_eq!(plain.text, "");
+        }
+        assert_eq!(VALIDATIONS.load(Ordering::SeqCst), 0);
+
+        // types with rules are still validated on every request
+        for _ in 0..2 {
+            let (mut parts, _) = Request::builder()
+                .uri("/?text=too%20long")
+                .body(())?
+                .into_parts();
+            let rejection = ValidCached::<Query<Ruled>>::from_request_parts(&mut parts, &())
+                .await
+                .expect_err("text is too long");
+            assert!(
+                matches!(rejection, ValidRejection::Valid(errors) if errors.field_errors().contains_key("text"))
+            );
+        }
+        Ok(())
+    }
+
+    static DETECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl HasRules for Counted {
+        fn has_rules() -> bool {
+            DETECTIONS.fetch_add(1, Ordering::SeqCst);
+            false
+        }
+    }
+
+    #[test]
+    fn rules_are_detected_once() {
+        // the first requests are concurrent, yet the rules are detected once
+        let threads = (0..8)
+            .map(|_| std::thread::spawn(has_rules::<Counted>))
+            .collect::<Vec<_>>();
+        for thread in threads {
+            assert!(!thread.join().expect("thread panicked"));
+        }
+        assert!(!has_rules::<Counted>());
+        assert_eq!(DETECTIONS.load(Ordering::SeqCst), 1);
+    }
+
+    fn validate_schema(_: &Schema) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    #[derive(Validate, HasRules)]
+    #[validate(schema(function = "validate_schema"))]
+    struct Schema {
+        _text: String,
+    }
+
+    #[derive(Validate)]
+    #[validate(nested)]
+    struct Inner {
+        #[validate(length(min = 1))]
+        _text: String,
+    }
+
+    #[derive(Validate, HasRules)]
+    struct Nested {
+        #[validate(nested)]
+        _inner: Inner,
+    }
+
+    #[derive(HasRules)]
+    struct Generic<T> {
+        _value: T,
+    }
+
+    #[test]
+    fn derived_rules() {
+        assert!(!has_rules::<Plain>());
+        assert!(has_rules::<Ruled>());
+        assert!(has_rules::<Schema>());
+        assert!(has_rules::<Nested>());
+        assert!(!has_rules::<Generic<u8>>());
+    }
+
+    /// Each instance of a generic type has its own entry
+    struct Sized<T>(T);
+
+    impl<T> HasRules for Sized<T> {
+        fn has_rules() -> bool {
+            std::mem::size_of::<T>() > 1
+        }
+    }
+
+    #[test]
+    fn generic_types_are_cached_separately() {
+        assert!(!has_rules::<Sized<u8>>());
+        assert!(has_rules::<Sized<u16>>());
+        assert!(!has_rules::<Sized<u8>>());
+    }
+}
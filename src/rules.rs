@@ -0,0 +1,1564 @@
+//! # Rule templates
+//!
+//! ## Feature
+//!
+//! Enable the `rules` feature to use the rule templates in this module.
+//!
+//! validator only supports cross-field rules through custom functions.
+//! This module provides ready-made building blocks for such functions, so that common rules
+//! report consistent error codes and parameters across handlers.
+//!
+//! Cross-field rules such as `mutually_exclusive`, `required_if`, `required_with` and `ordered` are applied with
+//! `#[validate(schema(function = "..."))]`, their errors are reported under the `__all__` key of `ValidationErrors`.
+//! `RequiredFields` configures the fields required for each value of a discriminator, e.g. per account type,
+//! and is provided by the state as the validation arguments of `ValidEx`, like `max_span` maximums.
+//! `max_span` computes the span between two fields, such as `end - start`, and checks it against a maximum
+//! that may depend on the request: with `#[validate(schema(function = "...", use_context))]` and `ValidEx`,
+//! the maximum is read from the validation arguments provided by the state.
+//!
+//! Single-field rules such as `private_ip` are applied with `#[validate(custom(function = "..."))]`.
+//! `max_decimal_places` bounds the precision of amounts, e.g. cents for prices, after they are deserialized.
+//! `isbn13` and `luhn` check formatted identifiers, such as book numbers and card numbers, by their check digit.
+//! `each` applies a single-field rule to every element of a list, alongside a `length` rule on the list:
+//! a wrong element count is reported as `length`, invalid elements as `elements` with their indices.
+//! `IpAddr` fields are parsed by serde before validation, enable the `ipnet` feature to also
+//! accept CIDR notation with `ipnet::IpNet` fields and to use `ip_in_networks`.
+//! Enable the `chrono` feature to parse timestamps with `de::rfc3339` and check them with `timestamp_window`:
+//! malformed timestamps are rejected during deserialization, out-of-window ones fail validation with `timestamp_window`.
+//! Enable the `timezone` feature to check timezone names such as `Europe/Paris` against the IANA database
+//! of `chrono-tz` with `timezone`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::extract::Query;
+//! use axum::routing::get;
+//! use axum::Router;
+//! use axum_valid::rules;
+//! use axum_valid::Valid;
+//! use serde::Deserialize;
+//! use validator::{Validate, ValidationError};
+//!
+//! #[derive(Validate, Deserialize)]
+//! #[validate(schema(function = "validate_lookup"))]
+//! pub struct Lookup {
+//!     pub id: Option<u64>,
+//!     pub name: Option<String>,
+//! }
+//!
+//! fn validate_lookup(lookup: &Lookup) -> Result<(), ValidationError> {
+//!     rules::mutually_exclusive(&[("id", lookup.id.is_some()), ("name", lookup.name.is_some())])
+//! }
+//!
+//! async fn handler(Valid(Query(lookup)): Valid<Query<Lookup>>) {
+//!     assert!(lookup.validate().is_ok());
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/lookup", get(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+#[cfg(feature = "timezone")]
+use chrono_tz::Tz;
+#[cfg(feature = "ipnet")]
+use ipnet::IpNet;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::ops::{Bound, RangeBounds, Sub};
+use std::sync::Arc;
+use std::time::Duration;
+use validator::ValidationError;
+
+/// Check that at most one of the fields is provided
+///
+/// `fields` are pairs of field names and whether the field is provided.
+/// The error has the code `mutually_exclusive` and the provided field names as its `fields` parameter.
+pub fn mutually_exclusive(fields: &[(&'static str, bool)]) -> Result<(), ValidationError> {
+    let provided = fields
+        .iter()
+        .filter(|(_, provided)| *provided)
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>();
+    if provided.len() <= 1 {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("mutually_exclusive");
+    error.message = Some(Cow::from(format!(
+        "only one of `{}` may be provided",
+        provided.join("`, `")
+    )));
+    error.add_param(Cow::from("fields"), &provided);
+    Err(error)
+}
+
+/// Check that a field is provided when another field has a given value
+///
+/// `field` is the dependent field name and whether it is provided, `other` is the name and the value
+/// of the field it depends on, e.g. `reason` is required if `status` is `value`.
+/// The error has the code `required_if`, and the field names and `value` as its `field`, `other` and `value` parameters.
+pub fn required_if<T>(
+    (field, provided): (&'static str, bool),
+    (other, other_value): (&'static str, &T),
+    value: &T,
+) -> Result<(), ValidationError>
+where
+    T: PartialEq + Serialize,
+{
+    if provided || other_value != value {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("required_if");
+    error.add_param(Cow::from("field"), &field);
+    error.add_param(Cow::from("other"), &other);
+    error.add_param(Cow::from("value"), value);
+    let value = &error.params["value"];
+    let value = value
+        .as_str()
+        .map_or_else(|| value.to_string(), str::to_owned);
+    error.message = Some(Cow::from(format!(
+        "`{field}` is required when `{other}` is {value}"
+    )));
+    Err(error)
+}
+
+/// Check that a field is provided when another field is provided
+///
+/// `field` and `other` are field names and whether the field is provided, e.g. `order` is required with `sort`.
+/// Only `field` depends on `other`: `field` alone is accepted.
+/// The error has the code `required_with`, and the field names as its `field` and `other` parameters.
+pub fn required_with(
+    (field, provided): (&'static str, bool),
+    (other, other_provided): (&'static str, bool),
+) -> Result<(), ValidationError> {
+    if provided || !other_provided {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("required_with");
+    error.add_param(Cow::from("field"), &field);
+    error.add_param(Cow::from("other"), &other);
+    error.message = Some(Cow::from(format!(
+        "`{field}` is required when `{other}` is provided"
+    )));
+    Err(error)
+}
+
+/// Fields required for each value of a discriminator, e.g. per account type, cheap to clone
+///
+/// Put it in the state and pass it as the validation arguments of `ValidEx`, so that the required fields
+/// can be configured at runtime, then call `check` from a `schema` rule.
+#[derive(Debug, Clone, Default)]
+pub struct RequiredFields(Arc<HashMap<String, Vec<String>>>);
+
+impl RequiredFields {
+    /// Create an empty configuration, which requires no field
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require `fields` when the discriminator is `value`, replacing the previously required fields
+    pub fn require<I>(mut self, value: impl Into<String>, fields: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Arc::make_mut(&mut self.0)
+            .insert(value.into(), fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Get the fields required when the discriminator is `value`
+    pub fn get(&self, value: &str) -> &[String] {
+        self.0.get(value).map_or(&[], Vec::as_slice)
+    }
+
+    /// Check that the fields required by the value of the discriminator are provided
+    ///
+    /// `discriminator` is the name and the value of the discriminator field, `fields` are pairs of field names
+    /// and whether the field is provided. A required field missing from `fields` is considered not provided.
+    /// The error has the code `required_fields`, the discriminator name and value as its `discriminator`
+    /// and `value` parameters, and the missing field names as its `fields` parameter.
+    pub fn check(
+        &self,
+        (discriminator, value): (&'static str, &str),
+        fields: &[(&'static str, bool)],
+    ) -> Result<(), ValidationError> {
+        let missing = self
+            .get(value)
+            .iter()
+            .filter(|required| {
+                !fields
+                    .iter()
+                    .any(|(name, provided)| name == required && *provided)
+            })
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let mut error = ValidationError::new("required_fields");
+        error.message = Some(Cow::from(format!(
+            "`{}` {} required when `{discriminator}` is {value}",
+            missing.join("`, `"),
+            if missing.len() == 1 { "is" } else { "are" }
+        )));
+        error.add_param(Cow::from("discriminator"), &discriminator);
+        error.add_param(Cow::from("value"), &value);
+        error.add_param(Cow::from("fields"), &missing);
+        Err(error)
+    }
+}
+
+/// Check that a field is not greater than another field
+///
+/// `lower` and `upper` are field names and values, e.g. `("min", 10)` and `("max", 40)` for a range query.
+/// Equal values are accepted. The error has the code `ordered`, and the field names as its `lower` and `upper` parameters.
+pub fn ordered<T: PartialOrd>(
+    (lower, lower_value): (&'static str, T),
+    (upper, upper_value): (&'static str, T),
+) -> Result<(), ValidationError> {
+    if lower_value <= upper_value {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("ordered");
+    error.add_param(Cow::from("lower"), &lower);
+    error.add_param(Cow::from("upper"), &upper);
+    error.message = Some(Cow::from(format!(
+        "`{lower}` must not be greater than `{upper}`"
+    )));
+    Err(error)
+}
+
+/// Check that the span between two fields is at most `max`
+///
+/// `start` and `end` are field names and values, e.g. `("start", 10)` and `("end", 40)` span 30.
+/// The error has the code `max_span`, the field names and `max` as its `start`, `end` and `max` parameters,
+/// and the computed span as its `span` parameter. An `end` before `start` fails with the same code but without `span`.
+pub fn max_span<T, S>(
+    (start, start_value): (&'static str, T),
+    (end, end_value): (&'static str, T),
+    max: S,
+) -> Result<(), ValidationError>
+where
+    T: PartialOrd + Sub<Output = S>,
+    S: PartialOrd + Serialize + Display,
+{
+    let mut error = ValidationError::new("max_span");
+    error.add_param(Cow::from("start"), &start);
+    error.add_param(Cow::from("end"), &end);
+    error.add_param(Cow::from("max"), &max);
+    if end_value < start_value {
+        error.message = Some(Cow::from(format!("`{end}` must not be before `{start}`")));
+        return Err(error);
+    }
+    let span = end_value - start_value;
+    if span <= max {
+        return Ok(());
+    }
+    error.message = Some(Cow::from(format!(
+        "`{end}` must be at most {max} after `{start}`"
+    )));
+    error.add_param(Cow::from("span"), &span);
+    Err(error)
+}
+
+/// Check that a duration is within `min..=max`
+///
+/// The error has the code `range`, like validator's `range` rule,
+/// and the bounds and the value as `min`, `max` and `value` parameters in seconds.
+/// With `#[validate(custom(function = "..."))]`, validator replaces `value` with the field itself.
+pub fn duration_range(
+    value: &Duration,
+    min: Duration,
+    max: Duration,
+) -> Result<(), ValidationError> {
+    if (min..=max).contains(value) {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("range");
+    error.add_param(Cow::from("min"), &min.as_secs_f64());
+    error.add_param(Cow::from("max"), &max.as_secs_f64());
+    error.add_param(Cow::from("value"), &value.as_secs_f64());
+    Err(error)
+}
+
+/// Check that a value is within bounds that are inclusive or exclusive
+///
+/// validator's `range` rule takes its bounds from attributes. This rule takes any `RangeBounds`,
+/// such as `0.0..1.0` or `(Bound::Excluded(0.0), Bound::Included(1.0))`, so that whether each bound
+/// is inclusive can be chosen per rule, e.g. from a configuration.
+///
+/// The error has the code `range`, like validator's `range` rule, and the bounds as `min`, `max`,
+/// `exclusive_min` or `exclusive_max` parameters, with a message such as `must be greater than 0 and at most 1`.
+pub fn range_bounds<T, R>(value: &T, bounds: R) -> Result<(), ValidationError>
+where
+    T: PartialOrd + Serialize + Display,
+    R: RangeBounds<T>,
+{
+    if bounds.contains(value) {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("range");
+    let mut conditions = Vec::with_capacity(2);
+    match bounds.start_bound() {
+        Bound::Included(min) => {
+            error.add_param(Cow::from("min"), min);
+            conditions.push(format!("at least {min}"));
+        }
+        Bound::Excluded(min) => {
+            error.add_param(Cow::from("exclusive_min"), min);
+            conditions.push(format!("greater than {min}"));
+        }
+        Bound::Unbounded => {}
+    }
+    match bounds.end_bound() {
+        Bound::Included(max) => {
+            error.add_param(Cow::from("max"), max);
+            conditions.push(format!("at most {max}"));
+        }
+        Bound::Excluded(max) => {
+            error.add_param(Cow::from("exclusive_max"), max);
+            conditions.push(format!("less than {max}"));
+        }
+        Bound::Unbounded => {}
+    }
+    error.message = Some(Cow::from(format!("must be {}", conditions.join(" and "))));
+    Err(error)
+}
+
+/// Check that a string contains a substring, ignoring case
+///
+/// This is a case-insensitive version of validator's `contains` rule, both strings are compared in lowercase.
+/// The error has the code `contains`, like validator's `contains` rule, and the substring as its `needle` parameter.
+pub fn contains_ignore_case(value: &str, needle: &str) -> Result<(), ValidationError> {
+    if value.to_lowercase().contains(&needle.to_lowercase()) {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("contains");
+    error.add_param(Cow::from("needle"), &needle);
+    Err(error)
+}
+
+/// Check that a number has at most `max` decimal places
+///
+/// Decimal places are counted in the shortest representation of the value, e.g. `12.5` has one decimal place,
+/// so a `f64` has as many decimal places as the JSON number it was deserialized from, such as `12.345`.
+/// Decimal types formatted with their scale, e.g. `12.50`, are counted with their trailing zeros.
+/// The error has the code `decimal_places` and `max` as its parameter, with a message such as
+/// `must have at most 2 decimal places`.
+pub fn max_decimal_places<T: Display>(value: &T, max: usize) -> Result<(), ValidationError> {
+    let value = value.to_string();
+    let places = value.split_once('.').map_or(0, |(_, fraction)| {
+        fraction.bytes().take_while(u8::is_ascii_digit).count()
+    });
+    if places <= max {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("decimal_places");
+    error.add_param(Cow::from("max"), &max);
+    error.message = Some(Cow::from(format!(
+        "must have at most {max} decimal place{}",
+        if max == 1 { "" } else { "s" }
+    )));
+    Err(error)
+}
+
+/// Check that a string is a valid ISBN-13, e.g. `978-0-306-40615-7`
+///
+/// Hyphens and spaces between the digits are ignored. The ISBN must have 13 digits, the prefix `978` or `979`
+/// and a valid check digit. The error has the code `isbn13`.
+pub fn isbn13(value: &str) -> Result<(), ValidationError> {
+    let digits = separated_digits(value);
+    let valid = digits.as_ref().is_some_and(|digits| {
+        digits.len() == 13
+            && (digits.starts_with(&[9, 7, 8]) || digits.starts_with(&[9, 7, 9]))
+            && digits
+                .iter()
+                .enumerate()
+                .map(|(i, digit)| if i % 2 == 0 { *digit } else { digit * 3 })
+                .sum::<u32>()
+                % 10
+                == 0
+    });
+    if valid {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("isbn13");
+    error.message = Some(Cow::from("must be a valid ISBN-13"));
+    Err(error)
+}
+
+/// Check that a string of digits passes the Luhn checksum, e.g. a card number such as `4111 1111 1111 1111`
+///
+/// Hyphens and spaces between the digits are ignored. At least two digits are required,
+/// check the number of digits of a card number with a `length` rule or a pattern.
+/// The error has the code `luhn`.
+pub fn luhn(value: &str) -> Result<(), ValidationError> {
+    let digits = separated_digits(value);
+    let valid = digits.as_ref().is_some_and(|digits| {
+        digits.len() >= 2
+            && digits
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(i, digit)| match (i % 2 == 1, digit * 2) {
+                    (false, _) => *digit,
+                    (true, doubled) if doubled > 9 => doubled - 9,
+                    (true, doubled) => doubled,
+                })
+                .sum::<u32>()
+                % 10
+                == 0
+    });
+    if valid {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("luhn");
+    error.message = Some(Cow::from("must have a valid check digit"));
+    Err(error)
+}
+
+/// Digits of a string, ignoring hyphens and spaces, or `None` if it contains other characters
+fn separated_digits(value: &str) -> Option<Vec<u32>> {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .map(|c| c.to_digit(10))
+        .collect()
+}
+
+/// Check that the keys of the elements of a sequence are unique
+///
+/// `key` extracts the key of an element, such as its SKU, which must not be shared with other elements.
+/// The error has the code `unique` and the indices of all elements sharing their key
+/// with another element, in ascending order, as its `indices` parameter.
+pub fn unique_by<'a, T, K, F>(items: &'a [T], key: F) -> Result<(), ValidationError>
+where
+    K: Hash + Eq,
+    F: Fn(&'a T) -> K,
+{
+    let mut indices_by_key = HashMap::<K, Vec<usize>>::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        indices_by_key.entry(key(item)).or_default().push(index);
+    }
+    let mut duplicates = indices_by_key
+        .into_values()
+        .filter(|indices| indices.len() > 1)
+        .flatten()
+        .collect::<Vec<_>>();
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+    duplicates.sort_unstable();
+    let mut error = ValidationError::new("unique");
+    error.message = Some(Cow::from(format!(
+        "elements at indices {duplicates:?} are not unique"
+    )));
+    error.add_param(Cow::from("indices"), &duplicates);
+    Err(error)
+}
+
+/// Check each element of a sequence with `rule`
+///
+/// Combine it with a `length` rule on the same field, e.g. `#[validate(length(min = 1, max = 3), custom(function = "..."))]`,
+/// to check both the element count and the elements: validator reports both errors under the field,
+/// a wrong count with the code `length` and invalid elements with the code `elements`.
+/// The `elements` error has the indices of the invalid elements, in ascending order, as its `indices` parameter,
+/// and the error of each invalid element (its `index`, `code`, `message` and `params`) as its `errors` parameter.
+pub fn each<'a, T, F>(items: &'a [T], rule: F) -> Result<(), ValidationError>
+where
+    F: Fn(&'a T) -> Result<(), ValidationError>,
+{
+    #[derive(Serialize)]
+    struct ElementError {
+        index: usize,
+        #[serde(flatten)]
+        error: ValidationError,
+    }
+
+    let errors = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| rule(item).err().map(|error| ElementError { index, error }))
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let indices = errors.iter().map(|error| error.index).collect::<Vec<_>>();
+    let mut error = ValidationError::new("elements");
+    error.message = Some(Cow::from(match indices.as_slice() {
+        [index] => format!("element at index {index} is invalid"),
+        indices => format!("elements at indices {indices:?} are invalid"),
+    }));
+    error.add_param(Cow::from("indices"), &indices);
+    error.add_param(Cow::from("errors"), &errors);
+    Err(error)
+}
+
+/// Check that an IP address is private
+///
+/// Private addresses are IPv4 private (RFC 1918), loopback and link-local addresses,
+/// and IPv6 unique local (`fc00::/7`), loopback and link-local (`fe80::/10`) addresses.
+/// IPv4-mapped IPv6 addresses are checked as IPv4 addresses.
+/// The error has the code `private_ip`.
+pub fn private_ip(value: &IpAddr) -> Result<(), ValidationError> {
+    let private = match value.to_canonical() {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_loopback() || ip.is_link_local(),
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            ip.is_loopback() || first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80
+        }
+    };
+    if private {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("private_ip");
+    error.add_param(Cow::from("value"), &value.to_string());
+    Err(error)
+}
+
+/// Check that an IP address is contained in one of the networks
+///
+/// The error has the code `ip_in_networks` and the networks in CIDR notation as its `networks` parameter.
+#[cfg(feature = "ipnet")]
+pub fn ip_in_networks(value: &IpAddr, networks: &[IpNet]) -> Result<(), ValidationError> {
+    if networks.iter().any(|network| network.contains(value)) {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("ip_in_networks");
+    error.add_param(
+        Cow::from("networks"),
+        &networks.iter().map(IpNet::to_string).collect::<Vec<_>>(),
+    );
+    Err(error)
+}
+
+/// Check that a timestamp is between `earliest` and `latest`, inclusive
+///
+/// The error has the code `timestamp_window`, distinct from the deserialization error of malformed timestamps,
+/// and the bounds as RFC 3339 `earliest` and `latest` parameters.
+#[cfg(feature = "chrono")]
+pub fn timestamp_between(
+    value: &DateTime<Utc>,
+    earliest: DateTime<Utc>,
+    latest: DateTime<Utc>,
+) -> Result<(), ValidationError> {
+    if (earliest..=latest).contains(value) {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("timestamp_window");
+    error.add_param(
+        Cow::from("earliest"),
+        &earliest.to_rfc3339_opts(SecondsFormat::Secs, true),
+    );
+    error.add_param(
+        Cow::from("latest"),
+        &latest.to_rfc3339_opts(SecondsFormat::Secs, true),
+    );
+    Err(error)
+}
+
+/// Check that a timestamp is at most `past` before and `future` after the current time
+///
+/// This is the usual window of client-provided timestamps, such as the time of an event,
+/// e.g. at most a day old and one minute ahead to tolerate clock skew. See `timestamp_between` for the error.
+#[cfg(feature = "chrono")]
+pub fn timestamp_window(
+    value: &DateTime<Utc>,
+    past: TimeDelta,
+    future: TimeDelta,
+) -> Result<(), ValidationError> {
+    let now = Utc::now();
+    timestamp_between(value, now - past, now + future)
+}
+
+/// Check that a string is a timezone name of the IANA database, such as `Europe/Paris` or `UTC`
+///
+/// Names are case-sensitive, and include the links of the database, such as `US/Eastern`.
+/// The error has the code `timezone` and the name as its `value` parameter.
+#[cfg(feature = "timezone")]
+pub fn timezone(value: &str) -> Result<(), ValidationError> {
+    if value.parse::<Tz>().is_ok() {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("timezone");
+    error.add_param(Cow::from("value"), &value);
+    Err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(any(feature = "query", feature = "json"))]
+    use crate::{Valid, ValidEx, ValidRejection};
+    #[cfg(any(feature = "query", feature = "json"))]
+    use axum::http::Request;
+    #[cfg(any(feature = "query", feature = "json"))]
+    use serde::de::DeserializeOwned;
+    #[cfg(any(feature = "query", feature = "json"))]
+    use validator::{Validate, ValidateArgs};
+
+    /// Extract `T` from the query of `uri` with `Valid`
+    #[cfg(feature = "query")]
+    async fn query<T: DeserializeOwned + Validate>(
+        uri: &str,
+    ) -> anyhow::Result<Result<T, ValidRejection<axum::extract::rejection::QueryRejection>>> {
+        use axum::extract::{FromRequestParts, Query};
+
+        let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+        Ok(Valid::<Query<T>>::from_request_parts(&mut parts, &())
+            .await
+            .map(|Valid(Query(value))| value))
+    }
+
+    /// Extract `T` from the query of `uri` with `ValidEx`, validating it against `args`
+    #[cfg(feature = "query")]
+    async fn query_ex<T, Args>(
+        uri: &str,
+        args: &Args,
+    ) -> anyhow::Result<Result<T, ValidRejection<axum::extract::rejection::QueryRejection>>>
+    where
+        T: DeserializeOwned + for<'v> ValidateArgs<'v, Args = &'v Args>,
+        Args: Clone + Send + Sync,
+    {
+        use axum::extract::{FromRequestParts, Query};
+
+        let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+        Ok(ValidEx::<Query<T>>::from_request_parts(&mut parts, args)
+            .await
+            .map(|ValidEx(Query(value))| value))
+    }
+
+    /// Build a `POST` request with a JSON `body`
+    #[cfg(feature = "json")]
+    fn json_request(body: impl Into<axum::body::Body>) -> anyhow::Result<axum::extract::Request> {
+        Ok(Request::builder()
+            .method("POST")
+            .header(axum::http::header::CONTENT_TYPE, "application/json")
+            .body(body.into())?)
+    }
+
+    /// Extract `T` from a JSON `body` with `Valid`
+    #[cfg(feature = "json")]
+    async fn json<T: DeserializeOwned + Validate>(
+        body: impl Into<axum::body::Body>,
+    ) -> anyhow::Result<Result<T, ValidRejection<axum::extract::rejection::JsonRejection>>> {
+        use axum::extract::FromRequest;
+        use axum::Json;
+
+        Ok(Valid::<Json<T>>::from_request(json_request(body)?, &())
+            .await
+            .map(|Valid(Json(value))| value))
+    }
+
+    /// Extract `T` from a JSON `body` with `ValidEx`, validating it against `args`
+    #[cfg(feature = "json")]
+    async fn json_ex<T, Args>(
+        body: impl Into<axum::body::Body>,
+        args: &Args,
+    ) -> anyhow::Result<Result<T, ValidRejection<axum::extract::rejection::JsonRejection>>>
+    where
+        T: DeserializeOwned + for<'v> ValidateArgs<'v, Args = &'v Args>,
+        Args: Clone + Send + Sync,
+    {
+        use axum::extract::FromRequest;
+        use axum::Json;
+
+        Ok(ValidEx::<Json<T>>::from_request(json_request(body)?, args)
+            .await
+            .map(|ValidEx(Json(value))| value))
+    }
+
+    #[test]
+    fn mutually_exclusive_fields() {
+        assert!(mutually_exclusive(&[("a", false), ("b", false)]).is_ok());
+        assert!(mutually_exclusive(&[("a", true), ("b", false)]).is_ok());
+        assert!(mutually_exclusive(&[("a", false), ("b", true)]).is_ok());
+
+        let error =
+            mutually_exclusive(&[("a", true), ("b", true), ("c", false)]).expect_err("a and b");
+        assert_eq!(error.code, "mutually_exclusive");
+        assert_eq!(error.params["fields"], serde_json::json!(["a", "b"]));
+        assert_eq!(
+            error.message.as_deref(),
+            Some("only one of `a`, `b` may be provided")
+        );
+    }
+
+    #[test]
+    fn required_if_other_value() {
+        assert!(required_if(("reason", false), ("status", &"approved"), &"rejected").is_ok());
+        assert!(required_if(("reason", true), ("status", &"rejected"), &"rejected").is_ok());
+        let error = required_if(("reason", false), ("status", &"rejected"), &"rejected")
+            .expect_err("reason is required");
+        assert_eq!(error.code, "required_if");
+        assert_eq!(error.params["field"], serde_json::json!("reason"));
+        assert_eq!(error.params["other"], serde_json::json!("status"));
+        assert_eq!(error.params["value"], serde_json::json!("rejected"));
+        assert_eq!(
+            error.message.as_deref(),
+            Some("`reason` is required when `status` is rejected")
+        );
+        let error = required_if(("limit", false), ("paged", &true), &true).expect_err("limit");
+        assert_eq!(
+            error.message.as_deref(),
+            Some("`limit` is required when `paged` is true")
+        );
+    }
+
+    #[test]
+    fn required_with_other_field() {
+        assert!(required_with(("order", false), ("sort", false)).is_ok());
+        assert!(required_with(("order", true), ("sort", true)).is_ok());
+        assert!(required_with(("order", true), ("sort", false)).is_ok());
+        let error = required_with(("order", false), ("sort", true)).expect_err("order");
+        assert_eq!(error.code, "required_with");
+        assert_eq!(error.params["field"], serde_json::json!("order"));
+        assert_eq!(error.params["other"], serde_json::json!("sort"));
+        assert_eq!(
+            error.message.as_deref(),
+            Some("`order` is required when `sort` is provided")
+        );
+    }
+
+    #[test]
+    fn required_fields_per_value() {
+        let required = RequiredFields::new()
+            .require("business", ["company", "vat"])
+            .require("personal", ["birth_date"]);
+        assert!(required
+            .check(("kind", "business"), &[("company", true), ("vat", true)])
+            .is_ok());
+        assert!(required.check(("kind", "other"), &[]).is_ok());
+
+        let error = required
+            .check(("kind", "business"), &[("company", true), ("vat", false)])
+            .expect_err("vat is required");
+        assert_eq!(error.code, "required_fields");
+        assert_eq!(error.params["discriminator"], serde_json::json!("kind"));
+        assert_eq!(error.params["value"], serde_json::json!("business"));
+        assert_eq!(error.params["fields"], serde_json::json!(["vat"]));
+        assert_eq!(
+            error.message.as_deref(),
+            Some("`vat` is required when `kind` is business")
+        );
+
+        let error = required
+            .check(("kind", "business"), &[])
+            .expect_err("company and vat are required");
+        assert_eq!(
+            error.params["fields"],
+            serde_json::json!(["company", "vat"])
+        );
+        assert_eq!(
+            error.message.as_deref(),
+            Some("`company`, `vat` are required when `kind` is business")
+        );
+    }
+
+    #[test]
+    fn span_between_fields() {
+        assert!(max_span(("start", 10), ("end", 40), 30).is_ok());
+        assert!(max_span(("start", 10), ("end", 10), 0).is_ok());
+        let error = max_span(("start", 10), ("end", 41), 30).expect_err("too wide");
+        assert_eq!(error.code, "max_span");
+        assert_eq!(error.params["start"], serde_json::json!("start"));
+        assert_eq!(error.params["end"], serde_json::json!("end"));
+        assert_eq!(error.params["max"], serde_json::json!(30));
+        assert_eq!(error.params["span"], serde_json::json!(31));
+        assert_eq!(
+            error.message.as_deref(),
+            Some("`end` must be at most 30 after `start`")
+        );
+        let error = max_span(("from", 2u32), ("to", 1u32), 30).expect_err("reversed");
+        assert!(!error.params.contains_key("span"));
+        assert_eq!(
+            error.message.as_deref(),
+            Some("`to` must not be before `from`")
+        );
+    }
+
+    #[test]
+    fn duration_within_range() {
+        let (min, max) = (Duration::from_secs(1), Duration::from_secs(60));
+        assert!(duration_range(&Duration::from_secs(1), min, max).is_ok());
+        assert!(duration_range(&Duration::from_secs(60), min, max).is_ok());
+        let error = duration_range(&Duration::from_millis(500), min, max).expect_err("too short");
+        assert_eq!(error.code, "range");
+        assert_eq!(error.params["max"], serde_json::json!(60.0));
+        assert_eq!(error.params["value"], serde_json::json!(0.5));
+    }
+
+    #[test]
+    fn inclusive_and_exclusive_bounds() {
+        assert!(range_bounds(&1, 1..=10).is_ok());
+        assert!(range_bounds(&10, 1..=10).is_ok());
+        assert!(range_bounds(&10, 1..10).is_err());
+        assert!(range_bounds(&1, (Bound::Excluded(1), Bound::Unbounded)).is_err());
+
+        let error = range_bounds(&0.0, (Bound::Excluded(0.0), Bound::Included(1.0)))
+            .expect_err("0 is excluded");
+        assert_eq!(error.code, "range");
+        assert_eq!(error.params["exclusive_min"], serde_json::json!(0.0));
+        assert_eq!(error.params["max"], serde_json::json!(1.0));
+        assert!(!error.params.contains_key("min"));
+        assert_eq!(
+            error.message.as_deref(),
+            Some("must be greater than 0 and at most 1")
+        );
+        let error = range_bounds(&5, ..5).expect_err("5 is excluded");
+        assert_eq!(error.message.as_deref(), Some("must be less than 5"));
+    }
+
+    #[test]
+    fn contains_ignoring_case() {
+        assert!(contains_ignore_case("Hello", "hello").is_ok());
+        assert!(contains_ignore_case("say HELLO world", "Hello").is_ok());
+        assert!(contains_ignore_case("ÉCOLE", "école").is_ok());
+        let error = contains_ignore_case("Help", "hello").expect_err("no hello");
+        assert_eq!(error.code, "contains");
+        assert_eq!(error.params["needle"], serde_json::json!("hello"));
+    }
+
+    #[test]
+    fn decimal_places() {
+        assert!(max_decimal_places(&12.0, 2).is_ok());
+        assert!(max_decimal_places(&12.5, 2).is_ok());
+        assert!(max_decimal_places(&-0.01, 2).is_ok());
+        assert!(max_decimal_places(&"12.50", 2).is_ok());
+        assert!(max_decimal_places(&7u32, 0).is_ok());
+        let error = max_decimal_places(&12.345, 2).expect_err("three decimal places");
+        assert_eq!(error.code, "decimal_places");
+        assert_eq!(error.params["max"], serde_json::json!(2));
+        assert_eq!(
+            error.message.as_deref(),
+            Some("must have at most 2 decimal places")
+        );
+        assert!(max_decimal_places(&0.1f32, 0).is_err());
+    }
+
+    #[test]
+    fn check_digits() {
+        assert!(isbn13("9780306406157").is_ok());
+        assert!(isbn13("978-0-306-40615-7").is_ok());
+        assert!(isbn13("979 10 90636 07 1").is_ok());
+        assert_eq!(
+            isbn13("978-0-306-40615-8").map_err(|e| e.code),
+            Err("isbn13".into())
+        );
+        assert!(isbn13("0-306-40615-2").is_err());
+        assert!(isbn13("977-0-306-40615-8").is_err());
+        assert!(isbn13("978030640615x").is_err());
+
+        assert!(luhn("4111111111111111").is_ok());
+        assert!(luhn("4111 1111 1111 1111").is_ok());
+        assert!(luhn("5500-0000-0000-0004").is_ok());
+        assert!(luhn("79927398713").is_ok());
+        let error = luhn("4111111111111112").expect_err("invalid check digit");
+        assert_eq!(error.code, "luhn");
+        assert!(luhn("0").is_err());
+        assert!(luhn("").is_err());
+        assert!(luhn("4111-1111-1111-111a").is_err());
+    }
+
+    #[test]
+    fn unique_keys() {
+        assert!(unique_by(&["a", "b", "c"], |item| *item).is_ok());
+        assert!(unique_by::<&str, &str, _>(&[], |item| *item).is_ok());
+        let error =
+            unique_by(&["a", "b", "a", "c", "b", "a"], |item| *item).expect_err("duplicates");
+        assert_eq!(error.code, "unique");
+        assert_eq!(error.params["indices"], serde_json::json!([0, 1, 2, 4, 5]));
+    }
+
+    #[test]
+    fn each_element() {
+        let short = |tag: &&str| {
+            if tag.len() <= 3 {
+                Ok(())
+            } else {
+                Err(ValidationError::new("tag_length"))
+            }
+        };
+        assert!(each(&["a", "bc"], short).is_ok());
+        let error = each(&["a", "long", "bc", "longer"], short).expect_err("long tags");
+        assert_eq!(error.code, "elements");
+        assert_eq!(error.params["indices"], serde_json::json!([1, 3]));
+        assert_eq!(error.params["errors"][0]["index"], 1);
+        assert_eq!(error.params["errors"][0]["code"], "tag_length");
+        assert_eq!(error.params["errors"][1]["index"], 3);
+    }
+
+    #[test]
+    fn private_ip_addresses() -> anyhow::Result<()> {
+        for ip in [
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "127.0.0.1",
+            "fd00::1",
+            "fe80::1",
+            "::1",
+        ] {
+            assert!(private_ip(&ip.parse()?).is_ok(), "{ip}");
+        }
+        for ip in ["8.8.8.8", "172.32.0.1", "2001:4860::8888"] {
+            let error = private_ip(&ip.parse()?).expect_err(ip);
+            assert_eq!(error.code, "private_ip");
+        }
+        assert!(private_ip(&"::ffff:192.168.1.1".parse()?).is_ok());
+        Ok(())
+    }
+
+    #[cfg(feature = "query")]
+    #[tokio::test]
+    async fn mutually_exclusive_query() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(schema(function = "validate_exclusive"))]
+        struct Parameters {
+            a: Option<u32>,
+            b: Option<u32>,
+        }
+
+        fn validate_exclusive(parameters: &Parameters) -> Result<(), ValidationError> {
+            mutually_exclusive(&[("a", parameters.a.is_some()), ("b", parameters.b.is_some())])
+        }
+
+        assert!(query::<Parameters>("/?a=1").await?.is_ok());
+        assert!(query::<Parameters>("/?b=2").await?.is_ok());
+        assert!(query::<Parameters>("/").await?.is_ok());
+        assert!(matches!(
+            query::<Parameters>("/?a=1&b=2").await?,
+            Err(ValidRejection::Valid(errors)) if errors.errors().contains_key("__all__")
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn ordered_fields() {
+        assert!(ordered(("min", 1), ("max", 2)).is_ok());
+        assert!(ordered(("min", 2), ("max", 2)).is_ok());
+        let error = ordered(("min", 3), ("max", 2)).expect_err("min > max");
+        assert_eq!(error.code, "ordered");
+        assert_eq!(error.params["lower"], serde_json::json!("min"));
+        assert_eq!(error.params["upper"], serde_json::json!("max"));
+        assert_eq!(
+            error.message.as_deref(),
+            Some("`min` must not be greater than `max`")
+        );
+    }
+
+    #[cfg(feature = "query")]
+    #[tokio::test]
+    async fn ordered_query() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(schema(function = "validate_price_range"))]
+        struct PriceRange {
+            min: f64,
+            max: f64,
+        }
+
+        fn validate_price_range(range: &PriceRange) -> Result<(), ValidationError> {
+            ordered(("min", range.min), ("max", range.max))
+        }
+
+        assert!(query::<PriceRange>("/?min=10&max=20").await?.is_ok());
+        assert!(query::<PriceRange>("/?min=10&max=10").await?.is_ok());
+        let Err(ValidRejection::Valid(errors)) = query::<PriceRange>("/?min=20&max=10").await?
+        else {
+            panic!("expected min > max to fail");
+        };
+        let validator::ValidationErrorsKind::Field(errors) = &errors.errors()["__all__"] else {
+            panic!("expected a schema error");
+        };
+        assert_eq!(errors[0].code, "ordered");
+        Ok(())
+    }
+
+    #[cfg(feature = "query")]
+    #[tokio::test]
+    async fn card_number_query() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Payment {
+            #[validate(length(min = 12, max = 23), custom(function = "luhn"))]
+            card: String,
+            #[validate(custom(function = "isbn13"))]
+            isbn: String,
+        }
+
+        assert!(
+            query::<Payment>("/?card=4111111111111111&isbn=9780306406157")
+                .await?
+                .is_ok()
+        );
+        assert!(
+            query::<Payment>("/?card=4111-1111-1111-1111&isbn=978-0-306-40615-7")
+                .await?
+                .is_ok()
+        );
+        let Err(ValidRejection::Valid(errors)) =
+            query::<Payment>("/?card=4111111111111112&isbn=9780306406157").await?
+        else {
+            panic!("expected a Luhn-invalid card number to fail");
+        };
+        assert_eq!(errors.field_errors()["card"][0].code, "luhn");
+        let Err(ValidRejection::Valid(errors)) =
+            query::<Payment>("/?card=4111111111111111&isbn=9780306406158").await?
+        else {
+            panic!("expected an invalid ISBN to fail");
+        };
+        assert_eq!(errors.field_errors()["isbn"][0].code, "isbn13");
+        Ok(())
+    }
+
+    #[cfg(feature = "query")]
+    #[tokio::test]
+    async fn required_with_query() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(schema(function = "validate_sort"))]
+        struct Parameters {
+            sort: Option<String>,
+            order: Option<String>,
+        }
+
+        fn validate_sort(parameters: &Parameters) -> Result<(), ValidationError> {
+            required_with(
+                ("order", parameters.order.is_some()),
+                ("sort", parameters.sort.is_some()),
+            )
+        }
+
+        assert!(query::<Parameters>("/?sort=name&order=asc").await?.is_ok());
+        assert!(query::<Parameters>("/").await?.is_ok());
+        assert!(matches!(
+            query::<Parameters>("/?sort=name").await?,
+            Err(ValidRejection::Valid(errors))
+                if errors.errors().contains_key("__all__")
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "query")]
+    #[tokio::test]
+    async fn max_span_from_state_query() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Clone)]
+        struct RangeLimits {
+            max_range: u64,
+        }
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(context = RangeLimits)]
+        #[validate(schema(function = "validate_range", use_context))]
+        struct Range {
+            start: u64,
+            end: u64,
+        }
+
+        fn validate_range(range: &Range, limits: &RangeLimits) -> Result<(), ValidationError> {
+            max_span(("start", range.start), ("end", range.end), limits.max_range)
+        }
+
+        // the same range passes or fails depending on the maximum provided by the state
+        assert!(
+            query_ex::<Range, _>("/?start=10&end=110", &RangeLimits { max_range: 100 })
+                .await?
+                .is_ok()
+        );
+        assert!(matches!(
+            query_ex::<Range, _>("/?start=10&end=110", &RangeLimits { max_range: 50 }).await?,
+            Err(ValidRejection::Valid(errors))
+                if errors.errors().contains_key("__all__")
+        ));
+        assert!(
+            query_ex::<Range, _>("/?start=10&end=60", &RangeLimits { max_range: 50 })
+                .await?
+                .is_ok()
+        );
+        assert!(
+            query_ex::<Range, _>("/?start=60&end=10", &RangeLimits { max_range: 100 })
+                .await?
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn required_if_json() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, PartialEq, serde::Deserialize, Serialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Status {
+            Approved,
+            Rejected,
+        }
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(schema(function = "validate_review"))]
+        struct Review {
+            status: Status,
+            reason: Option<String>,
+        }
+
+        fn validate_review(review: &Review) -> Result<(), ValidationError> {
+            required_if(
+                ("reason", review.reason.is_some()),
+                ("status", &review.status),
+                &Status::Rejected,
+            )
+        }
+
+        assert!(json::<Review>(r#"{"status": "approved"}"#).await?.is_ok());
+        assert!(
+            json::<Review>(r#"{"status": "rejected", "reason": "spam"}"#)
+                .await?
+                .is_ok()
+        );
+        let Err(ValidRejection::Valid(errors)) =
+            json::<Review>(r#"{"status": "rejected"}"#).await?
+        else {
+            panic!("expected validation errors");
+        };
+        let validator::ValidationErrorsKind::Field(errors) = &errors.errors()["__all__"] else {
+            panic!("expected schema errors");
+        };
+        assert_eq!(errors[0].code, "required_if");
+        assert_eq!(
+            errors[0].message.as_deref(),
+            Some("`reason` is required when `status` is rejected")
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn required_fields_from_state_json() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(context = RequiredFields)]
+        #[validate(schema(function = "validate_account", use_context))]
+        struct Account {
+            kind: String,
+            company: Option<String>,
+            vat: Option<String>,
+            birth_date: Option<String>,
+        }
+
+        fn validate_account(
+            account: &Account,
+            required: &RequiredFields,
+        ) -> Result<(), ValidationError> {
+            required.check(
+                ("kind", &account.kind),
+                &[
+                    ("company", account.company.is_some()),
+                    ("vat", account.vat.is_some()),
+                    ("birth_date", account.birth_date.is_some()),
+                ],
+            )
+        }
+
+        let required = RequiredFields::new()
+            .require("business", ["company", "vat"])
+            .require("personal", ["birth_date"]);
+        // the required fields vary with the discriminator
+        assert!(json_ex::<Account, _>(
+            r#"{"kind": "business", "company": "Acme", "vat": "FR1"}"#,
+            &required
+        )
+        .await?
+        .is_ok());
+        assert!(matches!(
+            json_ex::<Account, _>(r#"{"kind": "business", "company": "Acme"}"#, &required).await?,
+            Err(ValidRejection::Valid(errors))
+                if errors.errors().contains_key("__all__")
+        ));
+        assert!(json_ex::<Account, _>(
+            r#"{"kind": "personal", "birth_date": "2000-01-01"}"#,
+            &required
+        )
+        .await?
+        .is_ok());
+        assert!(
+            json_ex::<Account, _>(r#"{"kind": "personal", "company": "Acme"}"#, &required)
+                .await?
+                .is_err()
+        );
+
+        // and with the configuration provided by the state
+        let required = RequiredFields::new().require("business", ["company"]);
+        assert!(
+            json_ex::<Account, _>(r#"{"kind": "business", "company": "Acme"}"#, &required)
+                .await?
+                .is_ok()
+        );
+        assert!(json_ex::<Account, _>(r#"{"kind": "personal"}"#, &required)
+            .await?
+            .is_ok());
+        Ok(())
+    }
+
+    #[cfg(feature = "query")]
+    #[tokio::test]
+    async fn exclusive_bounds_query() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[validate(custom(function = "validate_inclusive"))]
+            inclusive: f64,
+            #[validate(custom(function = "validate_exclusive"))]
+            exclusive: f64,
+        }
+
+        fn validate_inclusive(value: &f64) -> Result<(), ValidationError> {
+            range_bounds(value, 0.0..=1.0)
+        }
+
+        fn validate_exclusive(value: &f64) -> Result<(), ValidationError> {
+            range_bounds(value, (Bound::Excluded(0.0), Bound::Excluded(1.0)))
+        }
+
+        assert!(query::<Parameters>("/?inclusive=0&exclusive=0.5")
+            .await?
+            .is_ok());
+        assert!(query::<Parameters>("/?inclusive=1&exclusive=0.5")
+            .await?
+            .is_ok());
+        assert!(matches!(
+            query::<Parameters>("/?inclusive=1&exclusive=1").await?,
+            Err(ValidRejection::Valid(errors))
+                if !errors.field_errors().contains_key("inclusive")
+                    && errors.field_errors()["exclusive"][0].params.contains_key("exclusive_max")
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "query")]
+    #[tokio::test]
+    async fn contains_ignore_case_query() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[validate(contains(pattern = "hello"))]
+            sensitive: String,
+            #[validate(custom(function = "contains_hello"))]
+            insensitive: String,
+        }
+
+        fn contains_hello(value: &str) -> Result<(), ValidationError> {
+            contains_ignore_case(value, "hello")
+        }
+
+        assert!(query::<Parameters>("/?sensitive=hello&insensitive=Hello")
+            .await?
+            .is_ok());
+        assert!(query::<Parameters>("/?sensitive=Hello&insensitive=Hello")
+            .await?
+            .is_err());
+        assert!(query::<Parameters>("/?sensitive=hello&insensitive=Help")
+            .await?
+            .is_err());
+        Ok(())
+    }
+
+    #[cfg(all(feature = "query", feature = "ipnet"))]
+    #[tokio::test]
+    async fn private_ip_query() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        #[validate(schema(function = "validate_network"))]
+        struct Parameters {
+            #[validate(custom(function = "private_ip"))]
+            ip: IpAddr,
+            network: IpNet,
+        }
+
+        fn validate_network(parameters: &Parameters) -> Result<(), ValidationError> {
+            ip_in_networks(&parameters.ip, &[parameters.network])
+        }
+
+        let parameters = query::<Parameters>("/?ip=192.168.1.10&network=192.168.1.0/24").await??;
+        assert_eq!(parameters.network.prefix_len(), 24);
+        assert!(matches!(
+            query::<Parameters>("/?ip=8.8.8.8&network=8.8.8.0/24").await?,
+            Err(ValidRejection::Valid(errors))
+                if errors.field_errors()["ip"][0].code == "private_ip"
+        ));
+        assert!(matches!(
+            query::<Parameters>("/?ip=192.168.2.10&network=192.168.1.0/24").await?,
+            Err(ValidRejection::Valid(errors)) if errors.errors().contains_key("__all__")
+        ));
+        assert!(matches!(
+            query::<Parameters>("/?ip=192.168.1.10&network=192.168.1.0/33").await?,
+            Err(ValidRejection::Inner(_))
+        ));
+        Ok(())
+    }
+    #[cfg(all(feature = "query", feature = "de"))]
+    #[tokio::test]
+    async fn element_count_and_elements_query() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use axum::extract::{FromRequestParts, Query};
+        use axum::http::Request;
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[serde(deserialize_with = "crate::de::comma_separated")]
+            #[validate(length(min = 2, max = 4), custom(function = "validate_tags"))]
+            tags: Vec<String>,
+        }
+
+        fn validate_tags(tags: &[String]) -> Result<(), ValidationError> {
+            each(tags, |tag| {
+                if (1..=5).contains(&tag.len()) {
+                    Ok(())
+                } else {
+                    Err(ValidationError::new("tag_length"))
+                }
+            })
+        }
+
+        async fn tag_errors(uri: &str) -> anyhow::Result<Vec<ValidationError>> {
+            let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+            match Valid::<Query<Parameters>>::from_request_parts(&mut parts, &()).await {
+                Ok(_) => Ok(Vec::new()),
+                Err(ValidRejection::Valid(errors)) => Ok(errors.field_errors()["tags"].clone()),
+                Err(ValidRejection::Inner(e)) => Err(e.into()),
+            }
+        }
+
+        assert!(tag_errors("/?tags=a,bc,def").await?.is_empty());
+
+        // too few elements
+        let errors = tag_errors("/?tags=a").await?;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "length");
+
+        // element 2 invalid
+        let errors = tag_errors("/?tags=a,bc,toolong").await?;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "elements");
+        assert_eq!(errors[0].params["indices"], serde_json::json!([2]));
+        assert_eq!(errors[0].params["errors"][0]["code"], "tag_length");
+
+        // both, reported distinctly
+        let errors = tag_errors("/?tags=toolong").await?;
+        let codes = errors
+            .iter()
+            .map(|error| error.code.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(codes, ["length", "elements"]);
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn unique_json_elements() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use axum::body::Body;
+        use axum::extract::{FromRequest, Request};
+        use axum::http::header::CONTENT_TYPE;
+        use axum::Json;
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, serde::Serialize)]
+        struct Item {
+            sku: String,
+            quantity: u32,
+        }
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Order {
+            #[validate(custom(function = "unique_skus"))]
+            items: Vec<Item>,
+        }
+
+        fn unique_skus(items: &[Item]) -> Result<(), ValidationError> {
+            unique_by(items, |item| item.sku.as_str())
+        }
+
+        let request = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+
+        let Valid(Json(order)) = Valid::<Json<Order>>::from_request(
+            request(r#"{"items": [{"sku": "a", "quantity": 1}, {"sku": "b", "quantity": 2}]}"#)?,
+            &(),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(order.items[1].quantity, 2);
+
+        let rejection = Valid::<Json<Order>>::from_request(
+            request(
+                r#"{"items": [{"sku": "a", "quantity": 1}, {"sku": "b", "quantity": 2}, {"sku": "a", "quantity": 3}]}"#,
+            )?,
+            &(),
+        )
+        .await
+        .expect_err("duplicate SKUs");
+        let ValidRejection::Valid(errors) = rejection else {
+            panic!("expected validation errors");
+        };
+        let validator::ValidationErrorsKind::Field(errors) = &errors.errors()["items"] else {
+            panic!("expected field errors of `items`");
+        };
+        assert_eq!(errors[0].code, "unique");
+        assert_eq!(errors[0].params["indices"], serde_json::json!([0, 2]));
+        Ok(())
+    }
+
+    #[cfg(all(feature = "json", feature = "chrono"))]
+    #[tokio::test]
+    async fn timestamp_json() -> anyhow::Result<()> {
+        use axum::extract::rejection::JsonRejection;
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Event {
+            #[serde(deserialize_with = "crate::de::rfc3339")]
+            #[validate(custom(function = "recent"))]
+            at: DateTime<Utc>,
+        }
+
+        fn recent(at: &DateTime<Utc>) -> Result<(), ValidationError> {
+            timestamp_window(at, TimeDelta::days(1), TimeDelta::minutes(1))
+        }
+
+        fn body(at: &str) -> String {
+            format!(r#"{{"at": "{at}"}}"#)
+        }
+
+        let now = Utc::now();
+        let event = json::<Event>(body(&now.to_rfc3339()))
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(event.at, now);
+        let an_hour_ago = (now - TimeDelta::hours(1)).to_rfc3339_opts(SecondsFormat::Secs, false);
+        assert!(json::<Event>(body(&an_hour_ago)).await?.is_ok());
+
+        // malformed timestamps are rejected by `Json`
+        let rejection = json::<Event>(body("2024-13-01T00:00:00Z"))
+            .await?
+            .expect_err("month 13");
+        assert!(
+            matches!(
+                &rejection,
+                ValidRejection::Inner(JsonRejection::JsonDataError(_))
+            ),
+            "{rejection:?}"
+        );
+        assert!(json::<Event>(body("yesterday")).await?.is_err());
+
+        // well-formed timestamps out of the window fail validation
+        let last_week = (now - TimeDelta::weeks(1)).to_rfc3339();
+        let ValidRejection::Valid(errors) =
+            json::<Event>(body(&last_week)).await?.expect_err("too old")
+        else {
+            panic!("expected validation errors");
+        };
+        let error = &errors.field_errors()["at"][0];
+        assert_eq!(error.code, "timestamp_window");
+        assert!(error.params.contains_key("earliest"));
+        let tomorrow = (now + TimeDelta::days(1)).to_rfc3339();
+        assert!(matches!(
+            json::<Event>(body(&tomorrow)).await?,
+            Err(ValidRejection::Valid(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn decimal_places_json() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Payment {
+            #[validate(custom(function = "cents"))]
+            amount: f64,
+        }
+
+        fn cents(amount: &f64) -> Result<(), ValidationError> {
+            max_decimal_places(amount, 2)
+        }
+
+        assert!(json::<Payment>(r#"{"amount": 19.99}"#).await?.is_ok());
+        assert!(json::<Payment>(r#"{"amount": 20}"#).await?.is_ok());
+        let Err(ValidRejection::Valid(errors)) = json::<Payment>(r#"{"amount": 19.999}"#).await?
+        else {
+            panic!("expected a validation error");
+        };
+        assert_eq!(errors.field_errors()["amount"][0].code, "decimal_places");
+        Ok(())
+    }
+
+    #[cfg(all(feature = "json", feature = "timezone"))]
+    #[tokio::test]
+    async fn timezone_json() -> anyhow::Result<()> {
+        use validator::Validate;
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Meeting {
+            #[validate(custom(function = "timezone"))]
+            timezone: String,
+        }
+
+        for name in [
+            "Europe/Paris",
+            "America/Argentina/Buenos_Aires",
+            "UTC",
+            "US/Eastern",
+        ] {
+            assert!(timezone(name).is_ok(), "{name}");
+        }
+        let meeting = json::<Meeting>(r#"{"timezone": "Asia/Tokyo"}"#).await??;
+        assert_eq!(meeting.timezone, "Asia/Tokyo");
+
+        for name in ["Mars/Olympus_Mons", "europe/paris", "CET+1", ""] {
+            assert!(timezone(name).is_err(), "{name}");
+        }
+        let rejection = json::<Meeting>(r#"{"timezone": "Europe/Atlantis"}"#)
+            .await?
+            .expect_err("unknown timezone");
+        let ValidRejection::Valid(errors) = rejection else {
+            panic!("expected validation errors");
+        };
+        let error = &errors.field_errors()["timezone"][0];
+        assert_eq!(error.code, "timezone");
+        assert_eq!(error.params["value"], "Europe/Atlantis");
+        Ok(())
+    }
+}
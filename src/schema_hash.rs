@@ -0,0 +1,305 @@
+//! # Error schema hashes
+//!
+//! ## Feature
+//!
+//! Enable the `schema_hash` feature to use `ValidSchemaHashed<E>`.
+//!
+//! Clients often build their error handling from the shape of validation errors, e.g. which fields
+//! of a search form can fail with which codes, and may cache it as long as that shape doesn't change.
+//! `ValidSchemaHashed<E>` validates the inner data like `Valid<E>`, and adds the `x-error-schema-hash` header
+//! to validation-failure responses: the `ErrorSchemaHash` of the errors, 64 hexadecimal digits.
+//!
+//! The hash only depends on the structure of the errors: the paths of the invalid fields, with array indices
+//! left out, the codes of the failed rules and the names of their parameters. It's stable across requests
+//! failing the same rules, whatever their invalid values, messages or order.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::extract::Query;
+//! use axum::routing::get;
+//! use axum::Router;
+//! use axum_valid::schema_hash::ValidSchemaHashed;
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Search {
+//!     #[validate(length(min = 1, max = 64))]
+//!     pub q: String,
+//!     #[validate(range(min = 1, max = 100))]
+//!     pub limit: u32,
+//! }
+//!
+//! async fn handler(ValidSchemaHashed(Query(search)): ValidSchemaHashed<Query<Search>>) -> String {
+//!     search.q
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/search", get(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::PathSegment;
+use crate::hashed::ContentHash;
+use crate::validator::validate_redacted;
+use crate::{ErrorBody, HasValidate, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::{HeaderName, HeaderValue};
+use axum::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use validator::{Validate, ValidationErrors};
+
+/// Name of the header holding the hash of the error schema
+pub const ERROR_SCHEMA_HASH_HEADER: HeaderName = HeaderName::from_static("x-error-schema-hash");
+
+/// Hash of the structure of validation errors
+///
+/// As a response part, it sets the `x-error-schema-hash` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorSchemaHash(pub ContentHash);
+
+impl ErrorSchemaHash {
+    /// Hash the structure of an error body: the paths without array indices, the codes and the parameter names
+    pub fn of(body: &ErrorBody) -> Self {
+        // a sorted set, so that the order and repetitions of the errors don't matter
+        let schema = body
+            .errors
+            .iter()
+            .map(|error| {
+                let path = error
+                    .path
+                    .segments()
+                    .iter()
+                    .map(|segment| match segment {
+                        PathSegment::Key(key) => format!(".{key}"),
+                        PathSegment::Index(_) => String::from("[]"),
+                    })
+                    .collect::<String>();
+                let params = error.params.keys().cloned().collect::<Vec<_>>().join(",");
+                let code = error.code.as_deref().unwrap_or_default();
+                format!("{path}\t{code}\t{params}")
+            })
+            .collect::<BTreeSet<_>>();
+        let schema = schema.into_iter().collect::<Vec<_>>().join("\n");
+        ErrorSchemaHash(ContentHash::of_bytes(schema.as_bytes()))
+    }
+}
+
+/// Formats the hash as 64 lowercase hexadecimal digits
+impl Display for ErrorSchemaHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl IntoResponseParts for ErrorSchemaHash {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        if let Ok(value) = HeaderValue::from_str(&self.to_string()) {
+            res.headers_mut().insert(ERROR_SCHEMA_HASH_HEADER, value);
+        }
+        Ok(res)
+    }
+}
+
+/// # `ValidSchemaHashed` data extractor
+///
+/// See the [module documentation](crate::schema_hash) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidSchemaHashed<E>(pub E);
+
+impl<E> Deref for ValidSchemaHashed<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidSchemaHashed<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidSchemaHashed<E> {
+    /// Consumes the `ValidSchemaHashed` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+/// `SchemaHashRejection` is returned when the `ValidSchemaHashed` extractor fails.
+#[derive(Debug)]
+pub enum SchemaHashRejection<E> {
+    /// The data is invalid
+    Valid(ValidationErrors),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E> SchemaHashRejection<E> {
+    /// The hash of the error schema, if the data is invalid
+    pub fn schema_hash(&self) -> Option<ErrorSchemaHash> {
+        match self {
+            SchemaHashRejection::Valid(errors) => Some(ErrorSchemaHash::of(&errors.into())),
+            SchemaHashRejection::Inner(_) => None,
+        }
+    }
+}
+
+impl<E: Display> Display for SchemaHashRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaHashRejection::Valid(errors) => write!(f, "{errors}"),
+            SchemaHashRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for SchemaHashRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SchemaHashRejection::Valid(errors) => Some(errors),
+            SchemaHashRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for SchemaHashRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            SchemaHashRejection::Valid(errors) => {
+                let hash = ErrorSchemaHash::of(&(&errors).into());
+                (hash, ValidRejection::<E>::Valid(errors)).into_response()
+            }
+            SchemaHashRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidSchemaHashed<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequest<State>,
+    Extractor::Validate: Validate,
+{
+    type Rejection = SchemaHashRejection<<Extractor as FromRequest<State>>::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(SchemaHashRejection::Inner)?;
+        validate_redacted(inner.get_validate()).map_err(SchemaHashRejection::Valid)?;
+        Ok(ValidSchemaHashed(inner))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequestParts<State> for ValidSchemaHashed<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequestParts<State>,
+    Extractor::Validate: Validate,
+{
+    type Rejection = SchemaHashRejection<<Extractor as FromRequestParts<State>>::Rejection>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request_parts(parts, state)
+            .await
+            .map_err(SchemaHashRejection::Inner)?;
+        validate_redacted(inner.get_validate()).map_err(SchemaHashRejection::Valid)?;
+        Ok(ValidSchemaHashed(inner))
+    }
+}
+
+#[cfg(all(test, feature = "query"))]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use axum::http::{Request, StatusCode};
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Search {
+        #[validate(length(min = 1, max = 8))]
+        q: String,
+        #[validate(range(min = 1, max = 100))]
+        limit: u32,
+    }
+
+    async fn schema_hash(uri: &str) -> anyhow::Result<Option<String>> {
+        let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+        let Err(rejection) =
+            ValidSchemaHashed::<Query<Search>>::from_request_parts(&mut parts, &()).await
+        else {
+            return Ok(None);
+        };
+        let response = rejection.into_response();
+        Ok(response
+            .headers()
+            .get(ERROR_SCHEMA_HASH_HEADER)
+            .map(|hash| hash.to_str())
+            .transpose()?
+            .map(str::to_owned))
+    }
+
+    #[tokio::test]
+    async fn schema_hash_is_stable() -> anyhow::Result<()> {
+        assert_eq!(schema_hash("/?q=shoes&limit=10").await?, None);
+
+        let hash = schema_hash("/?q=&limit=500").await?.expect("schema hash");
+        assert_eq!(hash.len(), 64);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+        // same failing rules with other values
+        assert_eq!(
+            schema_hash("/?q=a%20long%20query&limit=0").await?,
+            Some(hash.clone())
+        );
+        // other failing rules
+        let limit_only = schema_hash("/?q=shoes&limit=0")
+            .await?
+            .expect("schema hash");
+        assert_ne!(limit_only, hash);
+        assert_eq!(schema_hash("/?q=boots&limit=1000").await?, Some(limit_only));
+
+        // no hash for rejections of the inner extractor
+        let (mut parts, _) = Request::builder().uri("/?q=shoes").body(())?.into_parts();
+        let rejection = ValidSchemaHashed::<Query<Search>>::from_request_parts(&mut parts, &())
+            .await
+            .expect_err("limit is missing");
+        assert!(rejection.schema_hash().is_none());
+        let response = rejection.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response.headers().get(ERROR_SCHEMA_HASH_HEADER).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn array_indices_are_ignored() {
+        use crate::error_body::{FieldError, FieldPath};
+        let body = |index: usize| {
+            ErrorBody::new(vec![FieldError::new(
+                FieldPath::new().key("items").index(index).key("name"),
+                "length",
+            )])
+        };
+        assert_eq!(ErrorSchemaHash::of(&body(0)), ErrorSchemaHash::of(&body(3)));
+    }
+}
@@ -0,0 +1,428 @@
+//! # Secret field values
+//!
+//! ## Feature
+//!
+//! Enable the `secret` feature to use `Secret<T>`.
+//!
+//! Validation errors may carry the value of the invalid field, e.g. validator adds a `value` parameter
+//! to `length` and `range` errors, which ends up in the HTTP response when `into_json` is enabled.
+//! Wrap fields that must never be echoed back (tokens, passwords, ...) in `Secret<T>`.
+//! `Secret<T>` deserializes, serializes and validates like `T`, so the value survives serialization round-trips,
+//! hashes and signatures, but it prints as `[REDACTED]`.
+//!
+//! The extractors of this crate validating with validator, such as `Valid`, `ValidEx` or `ValidHashed`,
+//! validate the data within `redacting`, where `Secret<T>` serializes as `[REDACTED]`,
+//! so the parameters validator serializes from a `Secret<T>` field, such as `value`, don't carry its value.
+//! Call `redacting` to do the same when validating the data yourself, e.g. in a custom extractor.
+//!
+//! This only covers what is serialized from the field: custom rules may still copy the value into their messages.
+//! Mark such fields with `#[redact]` and derive `SensitiveFields` to redact everything their errors carry,
+//! see `ErrorBody::redact_sensitive`.
+//!
+//! The `length`, `range`, `email`, `url`, `contains`, `does_not_contain` and `ip` rules of validator
+//! can be applied to `Secret<T>`. Other rules, e.g. `regex` (validator doesn't export the trait it takes)
+//! or `credit_card`, need to be applied to the inner value with a custom function.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(all(feature = "validator", feature = "query"))]
+//! mod validator_example {
+//!     use axum::extract::Query;
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::secret::Secret;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/query", get(handler))
+//!     }
+//!
+//!     // A token of the wrong length is rejected without echoing it back
+//!     async fn handler(Valid(Query(parameter)): Valid<Query<Parameter>>) -> String {
+//!         // Secret values are only exposed explicitly
+//!         format!("token length = {}", parameter.token.expose().len())
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(length(min = 32, max = 64))]
+//!         pub token: Secret<String>,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(all(feature = "validator", feature = "query"))]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::REDACTED;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::Cell;
+use std::fmt::{Debug, Formatter};
+
+thread_local! {
+    /// Whether `Secret` values are serialized as `[REDACTED]`, see `redacting`
+    static REDACTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f`, serializing `Secret` values as `[REDACTED]` meanwhile
+///
+/// Validate the data within `redacting`, so that validation errors don't carry secret values,
+/// e.g. the `value` parameter of `length` and `range` errors.
+pub fn redacting<R>(f: impl FnOnce() -> R) -> R {
+    /// Restores the previous state, even if `f` panics
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            REDACTING.with(|redacting| redacting.set(self.0));
+        }
+    }
+
+    let _restore = Restore(REDACTING.with(|redacting| redacting.replace(true)));
+    f()
+}
+
+/// # Secret field value
+///
+/// See the [module documentation](crate::secret) for details.
+///
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// Wrap a secret value
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    /// Get a reference to the secret value
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Consumes the `Secret` and returns the secret value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Secret(value)
+    }
+}
+
+impl<T> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: Serialize> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if REDACTING.with(Cell::get) {
+            serializer.serialize_str(REDACTED)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Secret)
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<T: validator::ValidateLength<u64>> validator::ValidateLength<u64> for Secret<T> {
+    fn length(&self) -> Option<u64> {
+        self.0.length()
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<T: validator::ValidateRange<R>, R> validator::ValidateRange<R> for Secret<T> {
+    fn greater_than(&self, max: R) -> Option<bool> {
+        self.0.greater_than(max)
+    }
+
+    fn less_than(&self, min: R) -> Option<bool> {
+        self.0.less_than(min)
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<T: validator::ValidateEmail> validator::ValidateEmail for Secret<T> {
+    fn as_email_string(&self) -> Option<std::borrow::Cow<'_, str>> {
+        self.0.as_email_string()
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<T: validator::ValidateUrl> validator::ValidateUrl for Secret<T> {
+    fn as_url_string(&self) -> Option<std::borrow::Cow<'_, str>> {
+        self.0.as_url_string()
+    }
+}
+
+/// `does_not_contain` is also available, through the blanket implementation of validator
+#[cfg(feature = "validator")]
+impl<T: validator::ValidateContains> validator::ValidateContains for Secret<T> {
+    fn validate_contains(&self, needle: &str) -> bool {
+        self.0.validate_contains(needle)
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<T: validator::ValidateIp> validator::ValidateIp for Secret<T> {
+    fn validate_ipv4(&self) -> bool {
+        self.0.validate_ipv4()
+    }
+
+    fn validate_ipv6(&self) -> bool {
+        self.0.validate_ipv6()
+    }
+
+    fn validate_ip(&self) -> bool {
+        self.0.validate_ip()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_is_only_redacted_when_printed_or_redacting() {
+        let secret = Secret::new(String::from("password"));
+        assert_eq!(format!("{secret:?}"), REDACTED);
+        let redacted = redacting(|| serde_json::to_value(&secret)).expect("serialize secret");
+        assert_eq!(redacted, REDACTED);
+        let json = serde_json::to_string(&secret).expect("serialize secret");
+        assert_eq!(json, "\"password\"");
+        let secret = serde_json::from_str::<Secret<String>>(&json).expect("deserialize secret");
+        assert_eq!(secret.expose(), "password");
+        assert_eq!(secret.into_inner(), "password");
+    }
+
+    #[cfg(feature = "validator")]
+    #[test]
+    fn secret_string_rules() {
+        use validator::Validate;
+
+        #[derive(Validate)]
+        struct Contact {
+            #[validate(email)]
+            email: Secret<String>,
+            #[validate(url)]
+            callback: Secret<String>,
+            #[validate(contains(pattern = "-"), does_not_contain(pattern = " "))]
+            token: Secret<String>,
+            #[validate(ip)]
+            address: Secret<String>,
+        }
+
+        let contact = |email: &str, callback: &str, token: &str, address: &str| Contact {
+            email: Secret::from(email.to_owned()),
+            callback: Secret::from(callback.to_owned()),
+            token: Secret::from(token.to_owned()),
+            address: Secret::from(address.to_owned()),
+        };
+        assert!(
+            contact("a@example.com", "https://example.com", "a-b", "10.0.0.1")
+                .validate()
+                .is_ok()
+        );
+        let errors = contact("example.com", "example", "a b", "10.0.0")
+            .validate()
+            .expect_err("contact is invalid");
+        let codes = errors
+            .field_errors()
+            .into_iter()
+            .map(|(field, errors)| (field, errors.len()))
+            .collect::<std::collections::BTreeMap<_, _>>();
+        assert_eq!(
+            codes,
+            [("address", 1), ("callback", 1), ("email", 1), ("token", 2)].into()
+        );
+    }
+
+    #[cfg(all(feature = "validator", feature = "query"))]
+    #[tokio::test]
+    async fn secret_query_field_is_not_leaked() -> anyhow::Result<()> {
+        use crate::error_body::SensitiveFields;
+        use crate::{ErrorBody, Valid, ValidRejection};
+        use axum::extract::{FromRequestParts, Query};
+        use axum::http::Request;
+        use axum::response::IntoResponse;
+        use validator::{Validate, ValidationError};
+
+        fn validate_code(code: &Option<Secret<String>>) -> Result<(), ValidationError> {
+            let Some(code) = code else {
+                return Ok(());
+            };
+            let mut error = ValidationError::new("code");
+            error.message = Some(format!("{} is not a code", code.expose()).into());
+            Err(error)
+        }
+
+        #[derive(Deserialize, Validate, SensitiveFields)]
+        struct Credentials {
+            #[validate(length(min = 8))]
+            token: Secret<String>,
+            #[validate(range(min = 1000, max = 9999))]
+            pin: Secret<u32>,
+            #[validate(range(max = 10))]
+            attempts: u32,
+            #[serde(default)]
+            #[validate(custom(function = "validate_code"))]
+            #[redact]
+            code: Option<Secret<String>>,
+        }
+
+        let (mut parts, _) = Request::builder()
+            .uri("/?token=leaked&pin=12345&attempts=11")
+            .body(())?
+            .into_parts();
+        let rejection = Valid::<Query<Credentials>>::from_request_parts(&mut parts, &())
+            .await
+            .err();
+        let Some(ValidRejection::Valid(errors)) = rejection else {
+            panic!("credentials should be invalid");
+        };
+        // the default rejection doesn't carry the secret values
+        let response =
+            ValidRejection::<std::convert::Infallible>::Valid(errors.clone()).into_response();
+        let output = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let output = String::from_utf8(output.to_vec())?;
+        assert!(!output.contains("leaked"));
+        assert!(!output.contains("12345"));
+        #[cfg(feature = "into_json")]
+        assert!(output.contains(REDACTED));
+        let body = ErrorBody::from(&errors);
+        let paths = body
+            .errors
+            .iter()
+            .map(|error| error.path.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["attempts", "pin", "token"]);
+        let output = serde_json::to_string(&body)?;
+        assert!(!output.contains("leaked"));
+        assert!(!output.contains("12345"));
+        assert_eq!(
+            body.errors[2].param::<String>("value").as_deref(),
+            Some(REDACTED)
+        );
+        // the bounds are kept, and other fields aren't redacted
+        assert_eq!(body.errors[1].param::<u32>("max"), Some(9999));
+        assert_eq!(body.errors[0].param::<u32>("value"), Some(11));
+
+        // messages of custom rules are redacted for the `SensitiveFields`
+        let (mut parts, _) = Request::builder()
+            .uri("/?token=0123456789&pin=1234&attempts=1&code=leaked")
+            .body(())?
+            .into_parts();
+        let Some(ValidRejection::Valid(errors)) =
+            Valid::<Query<Credentials>>::from_request_parts(&mut parts, &())
+                .await
+                .err()
+        else {
+            panic!("code should be invalid");
+        };
+        assert_eq!(Credentials::SENSITIVE_FIELDS, ["code"]);
+        let body = ErrorBody::from(&errors).redact_sensitive::<Credentials>();
+        assert_eq!(body.errors[0].message.as_deref(), Some(REDACTED));
+        assert!(!serde_json::to_string(&body)?.contains("leaked"));
+
+        let (mut parts, _) = Request::builder()
+            .uri("/?token=0123456789&pin=1234&attempts=1")
+            .body(())?
+            .into_parts();
+        let Valid(Query(credentials)) =
+            Valid::<Query<Credentials>>::from_request_parts(&mut parts, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(credentials.token.expose(), "0123456789");
+        assert_eq!(*credentials.pin.expose(), 1234);
+        Ok(())
+    }
+
+    #[cfg(feature = "hashed")]
+    #[tokio::test]
+    async fn secret_is_redacted_by_other_extractors() -> anyhow::Result<()> {
+        use crate::hashed::ValidHashed;
+        use crate::ValidRejection;
+        use axum::body::Body;
+        use axum::extract::FromRequest;
+        use axum::http::header::CONTENT_TYPE;
+        use axum::http::Request;
+        use validator::Validate;
+
+        #[derive(Serialize, Deserialize, Validate)]
+        struct Login {
+            #[validate(length(min = 8))]
+            token: Secret<String>,
+        }
+
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"token": "leaked"}"#))?;
+        let Some(ValidRejection::Valid(errors)) =
+            ValidHashed::<Login>::from_request(request, &()).await.err()
+        else {
+            panic!("login should be invalid");
+        };
+        let output = serde_json::to_string(&errors)?;
+        assert!(!output.contains("leaked"));
+        assert!(output.contains(REDACTED));
+        Ok(())
+    }
+
+    #[cfg(all(feature = "strip", feature = "json"))]
+    #[tokio::test]
+    async fn secret_survives_stripping() -> anyhow::Result<()> {
+        use crate::strip::{AllowedFields, ValidStripped};
+        use axum::body::Body;
+        use axum::extract::FromRequest;
+        use axum::http::header::CONTENT_TYPE;
+        use axum::http::Request;
+        use axum::Json;
+        use validator::Validate;
+
+        #[derive(Serialize, Deserialize, Validate)]
+        struct Login {
+            #[validate(length(min = 8))]
+            token: Secret<String>,
+            #[serde(default)]
+            role: Option<String>,
+        }
+
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"token": "0123456789", "role": "admin"}"#))?;
+        let ValidStripped(Json(login)) =
+            ValidStripped::<Json<Login>>::from_request(request, &AllowedFields::new(["token"]))
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(login.token.expose(), "0123456789");
+        assert_eq!(login.role, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+//! Rejection of JSON extractors serializing the data again after validation, to hash, sign or compare it
+
+use axum::extract::rejection::JsonRejection;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::fmt::{Display, Formatter};
+
+/// `SerializedRejection` is returned when a JSON extractor serializing its data fails.
+#[derive(Debug)]
+pub enum SerializedRejection {
+    /// The body is rejected by `Json`
+    Json(JsonRejection),
+    /// The body can't be deserialized into the target type, for extractors reading it as a `Value` first
+    Deserialize(serde_json::Error),
+    /// The data can't be serialized
+    Serialize(serde_json::Error),
+}
+
+impl Display for SerializedRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SerializedRejection::Json(e) => write!(f, "{e}"),
+            SerializedRejection::Deserialize(e) => {
+                write!(
+                    f,
+                    "Failed to deserialize the JSON body into the target type: {e}"
+                )
+            }
+            SerializedRejection::Serialize(e) => write!(f, "Failed to serialize the data: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SerializedRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SerializedRejection::Json(e) => Some(e),
+            SerializedRejection::Deserialize(e) => Some(e),
+            SerializedRejection::Serialize(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for SerializedRejection {
+    fn into_response(self) -> Response {
+        match self {
+            SerializedRejection::Json(e) => e.into_response(),
+            // Same status code as `JsonDataError`
+            e @ SerializedRejection::Deserialize(_) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response()
+            }
+            // The data type can't be serialized, which is a server-side error
+            e @ SerializedRejection::Serialize(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+            }
+        }
+    }
+}
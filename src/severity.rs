@@ -0,0 +1,406 @@
+//! # Validation errors with severity levels
+//!
+//! ## Feature
+//!
+//! Enable the `severity` feature to use `ValidWithWarnings<E>`.
+//!
+//! Some rules are hard errors, others are only warnings, e.g. a password that is valid but weak,
+//! or a date that is unusually far in the future. Warnings shouldn't block the request,
+//! but the client should still be told about them.
+//!
+//! Derive `Warnings` on the validated type and mark the error codes that are warnings with `#[warning("code", ...)]`:
+//! on a field, the codes are warnings for the field and its nested fields, e.g. `#[warning("weak_password")]`
+//! on `password`, and on the type, they're warnings for all fields. Other errors are hard errors.
+//! The annotations are collected into `Severities`, which can also be built by hand to sort an `ErrorBody`.
+//!
+//! `ValidWithWarnings<E>` validates the inner data like `Valid<E>`, then separates the errors by severity:
+//!
+//! * If there are hard errors, the request is rejected with an `ErrorBody` listing the hard errors as `errors`
+//!   and the warnings as `warnings`, with the `VALIDATION_ERROR_STATUS` (`400 Bad Request` by default).
+//! * Otherwise, the data is handed to the handler together with the warnings, which it can include in its response.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::error_body::FieldError;
+//! use axum_valid::severity::{ValidWithWarnings, Warnings};
+//! use serde::Deserialize;
+//! use validator::{Validate, ValidationError};
+//!
+//! #[derive(Validate, Deserialize, Warnings)]
+//! pub struct SignUp {
+//!     #[validate(length(min = 1, max = 32))]
+//!     pub name: String,
+//!     #[validate(length(min = 8), custom(function = "validate_strength"))]
+//!     #[warning("weak_password")]
+//!     pub password: String,
+//! }
+//!
+//! fn validate_strength(password: &str) -> Result<(), ValidationError> {
+//!     if password.chars().any(|c| c.is_ascii_digit()) {
+//!         Ok(())
+//!     } else {
+//!         Err(ValidationError::new("weak_password"))
+//!     }
+//! }
+//!
+//! async fn handler(
+//!     ValidWithWarnings(Json(sign_up), warnings): ValidWithWarnings<Json<SignUp>>,
+//! ) -> Json<Vec<FieldError>> {
+//!     println!("name = {}", sign_up.name);
+//!     Json(warnings)
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/sign_up", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::FieldError;
+use crate::validator::validate_redacted;
+use crate::{ErrorBody, HasValidate};
+use axum::async_trait;
+use axum::extract::{FromRequest, Request};
+use axum::response::{IntoResponse, Response};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use validator::Validate;
+
+/// Severity of a validation error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// Blocking error, the request is rejected
+    Error,
+    /// Non-blocking error, the request is accepted
+    Warning,
+}
+
+/// Error codes that are warnings for a type, marked with `#[warning("code", ...)]`
+///
+/// Derive it, the codes marked on the type are warnings for all fields,
+/// and the codes marked on a field are warnings for the field and its nested fields.
+pub trait Warnings {
+    /// Error codes that are warnings, for all fields (`None`) or for a field path
+    const WARNINGS: &'static [(Option<&'static str>, &'static str)];
+}
+
+pub use axum_valid_derive::Warnings;
+
+/// Error codes that are warnings, for all fields or for a field path
+#[derive(Debug, Clone, Default)]
+pub struct Severities(Arc<HashSet<(Option<String>, String)>>);
+
+impl Severities {
+    /// Create an empty mapping, where all errors are hard errors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the mapping of the `Warnings` of `T`
+    pub fn of<T: Warnings>() -> Self {
+        T::WARNINGS
+            .iter()
+            .fold(Self::new(), |severities, (path, code)| match path {
+                Some(path) => severities.field_warning(*path, *code),
+                None => severities.warning(*code),
+            })
+    }
+
+    /// Mark an error code as a warning for all fields
+    pub fn warning(mut self, code: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.0).insert((None, code.into()));
+        self
+    }
+
+    /// Mark an error code as a warning for the field at `path` and its nested fields, e.g. `address.zip`
+    pub fn field_warning(mut self, path: impl Into<String>, code: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.0).insert((Some(path.into()), code.into()));
+        self
+    }
+
+    /// Get the severity of an error
+    pub fn severity_of(&self, error: &FieldError) -> Severity {
+        let Some(code) = &error.code else {
+            return Severity::Error;
+        };
+        let is_warning = self.0.iter().any(|(path, warning)| {
+            warning == code
+                && path
+                    .as_deref()
+                    .is_none_or(|path| error.path.is_within(path))
+        });
+        if is_warning {
+            Severity::Warning
+        } else {
+            Severity::Error
+        }
+    }
+
+    /// Move the warnings of `body` from its `errors` to its `warnings`
+    pub fn apply(&self, mut body: ErrorBody) -> ErrorBody {
+        let (warnings, errors) = body
+            .errors
+            .into_iter()
+            .partition(|error| self.severity_of(error) == Severity::Warning);
+        body.errors = errors;
+        body.warnings.extend(warnings);
+        body
+    }
+}
+
+/// # `ValidWithWarnings` data extractor
+///
+/// See the [module documentation](crate::severity) for details.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ValidWithWarnings<E>(pub E, pub Vec<FieldError>);
+
+impl<E> Deref for ValidWithWarnings<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidWithWarnings<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidWithWarnings<E> {
+    /// Consumes the `ValidWithWarnings` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+
+    /// Consumes the `ValidWithWarnings` and returns the validated data and the warnings.
+    pub fn into_parts(self) -> (E, Vec<FieldError>) {
+        (self.0, self.1)
+    }
+
+    /// Returns the warnings, empty if the data is fully valid.
+    pub fn warnings(&self) -> &[FieldError] {
+        &self.1
+    }
+}
+
+/// `SeverityRejection` is returned when the `ValidWithWarnings` extractor fails.
+#[derive(Debug)]
+pub enum SeverityRejection<E> {
+    /// The data has hard errors, the body also lists the warnings
+    Valid(ErrorBody),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for SeverityRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SeverityRejection::Valid(body) => write!(
+                f,
+                "{} validation errors, {} warnings",
+                body.errors.len(),
+                body.warnings.len()
+            ),
+            SeverityRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for SeverityRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SeverityRejection::Valid(_) => None,
+            SeverityRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for SeverityRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            SeverityRejection::Valid(body) => body.into_response(),
+            SeverityRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidWithWarnings<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequest<State>,
+    Extractor::Validate: Validate + Warnings,
+{
+    type Rejection = SeverityRejection<<Extractor as FromRequest<State>>::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(SeverityRejection::Inner)?;
+        let Err(errors) = validate_redacted(inner.get_validate()) else {
+            return Ok(ValidWithWarnings(inner, Vec::new()));
+        };
+        let body = Severities::of::<Extractor::Validate>().apply(ErrorBody::from(errors));
+        if body.errors.is_empty() {
+            Ok(ValidWithWarnings(inner, body.warnings))
+        } else {
+            Err(SeverityRejection::Valid(body))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error_body::FieldPath;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::http::StatusCode;
+    use axum::Json;
+    use validator::ValidationError;
+
+    #[derive(Debug, serde::Deserialize, Validate, Warnings)]
+    struct SignUp {
+        #[validate(length(min = 1, max = 32))]
+        name: String,
+        #[validate(custom(function = "validate_strength"))]
+        #[warning("weak_password")]
+        password: String,
+    }
+
+    fn validate_strength(password: &str) -> Result<(), ValidationError> {
+        if password.chars().any(|c| c.is_ascii_digit()) {
+            Ok(())
+        } else {
+            Err(ValidationError::new("weak_password"))
+        }
+    }
+
+    async fn handler(
+        ValidWithWarnings(Json(sign_up), warnings): ValidWithWarnings<Json<SignUp>>,
+    ) -> Json<serde_json::Value> {
+        Json(serde_json::json!({"name": sign_up.name, "warnings": warnings}))
+    }
+
+    async fn post_json(body: &'static str) -> anyhow::Result<(StatusCode, serde_json::Value)> {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        let response = match ValidWithWarnings::from_request(request, &()).await {
+            Ok(sign_up) => handler(sign_up).await.into_response(),
+            Err(rejection) => rejection.into_response(),
+        };
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        Ok((status, serde_json::from_slice(&body)?))
+    }
+
+    #[tokio::test]
+    async fn warnings_do_not_block() -> anyhow::Result<()> {
+        let (status, body) = post_json(r#"{"name": "gteng", "password": "password1"}"#).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["warnings"], serde_json::json!([]));
+
+        let (status, body) = post_json(r#"{"name": "gteng", "password": "password"}"#).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["name"], "gteng");
+        assert_eq!(body["warnings"][0]["path"], "password");
+        assert_eq!(body["warnings"][0]["code"], "weak_password");
+
+        let (status, body) = post_json(r#"{"name": "", "password": "password"}"#).await?;
+        assert_eq!(status, crate::VALIDATION_ERROR_STATUS);
+        assert_eq!(body["errors"][0]["path"], "name");
+        assert_eq!(body["errors"].as_array().map(Vec::len), Some(1));
+        assert_eq!(body["warnings"][0]["code"], "weak_password");
+        Ok(())
+    }
+
+    #[cfg(feature = "secret")]
+    #[tokio::test]
+    async fn secret_values_are_not_returned_as_warnings() -> anyhow::Result<()> {
+        use crate::secret::Secret;
+
+        #[derive(serde::Deserialize, Validate, Warnings)]
+        struct Login {
+            #[validate(length(min = 8))]
+            #[warning("length")]
+            token: Secret<String>,
+        }
+
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"token": "leaked"}"#))?;
+        let ValidWithWarnings(Json(login), warnings) =
+            ValidWithWarnings::<Json<Login>>::from_request(request, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(login.token.expose(), "leaked");
+        assert_eq!(warnings.len(), 1);
+        let output = serde_json::to_string(&warnings)?;
+        assert!(!output.contains("leaked"));
+        assert!(output.contains(crate::error_body::REDACTED));
+        Ok(())
+    }
+
+    #[test]
+    fn derive_warnings() {
+        #[derive(Warnings)]
+        #[warning("unusual")]
+        #[allow(dead_code)]
+        struct Order {
+            #[warning("far_future", "weekend")]
+            delivery: Delivery,
+            quantity: u32,
+        }
+
+        #[allow(dead_code)]
+        struct Delivery {
+            date: String,
+        }
+
+        assert_eq!(
+            Order::WARNINGS,
+            [
+                (None, "unusual"),
+                (Some("delivery"), "far_future"),
+                (Some("delivery"), "weekend")
+            ]
+        );
+        let severities = Severities::of::<Order>();
+        let error = |path: FieldPath, code: &str| FieldError::new(path, code);
+        let date = FieldPath::new().key("delivery").key("date");
+        let quantity = FieldPath::new().key("quantity");
+        assert_eq!(
+            severities.severity_of(&error(date.clone(), "far_future")),
+            Severity::Warning
+        );
+        assert_eq!(
+            severities.severity_of(&error(quantity.clone(), "far_future")),
+            Severity::Error
+        );
+        assert_eq!(
+            severities.severity_of(&error(quantity.clone(), "unusual")),
+            Severity::Warning
+        );
+        assert_eq!(
+            severities.severity_of(&error(quantity, "range")),
+            Severity::Error
+        );
+    }
+}
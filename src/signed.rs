@@ -0,0 +1,326 @@
+//! # Signatures of validated data
+//!
+//! ## Feature
+//!
+//! Enable the `signed` feature to use `ValidSigned<T>`.
+//!
+//! In service-to-service calls, a service validating a payload may forward it to internal services,
+//! which shouldn't have to validate it again, but need to trust that it was validated.
+//! `ValidSigned<T>` extracts a JSON body like `Json<T>`, validates it, and signs the validated data
+//! with the `SigningKey` provided by the state. The handler forwards the data with its `Signature`,
+//! e.g. in a header, and the downstream service checks it with `SigningKey::verify_value`
+//! and the same key.
+//!
+//! The signature is an HMAC-SHA256 of the canonical JSON serialization of the data, with the keys of objects sorted,
+//! so it doesn't depend on the whitespace or key order of the request body.
+//! It's formatted as 64 lowercase hexadecimal digits.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::Router;
+//! use axum_valid::signed::{SigningKey, ValidSigned};
+//! use serde::{Deserialize, Serialize};
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Serialize, Deserialize)]
+//! pub struct Order {
+//!     #[validate(length(min = 1, max = 64))]
+//!     pub item: String,
+//!     #[validate(range(min = 1))]
+//!     pub quantity: u32,
+//! }
+//!
+//! async fn handler(ValidSigned(order, signature): ValidSigned<Order>) {
+//!     // e.g. forward the order to an internal service with an `X-Signature: {signature}` header
+//!     println!("order of {} signed {signature}", order.item);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let key = SigningKey::new(b"shared secret between services")?;
+//! let router = Router::new()
+//!     .route("/orders", post(handler))
+//!     .with_state(key);
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::canonical;
+use crate::validator::validate_redacted;
+use crate::{SerializedRejection, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::Json;
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::Sha256;
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+use validator::Validate;
+
+/// HMAC-SHA256 signature of validated data
+///
+/// It doesn't implement `PartialEq`, as comparing signatures must be done in constant time
+/// with [`SigningKey::verify`].
+#[derive(Clone, Copy)]
+pub struct Signature([u8; 32]);
+
+impl Signature {
+    /// The signature as bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Debug for Signature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Signature({self})")
+    }
+}
+
+/// Formats the signature as 64 lowercase hexadecimal digits
+impl Display for Signature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+/// Error returned when parsing a signature that isn't 64 hexadecimal digits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseSignatureError;
+
+impl Display for ParseSignatureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a signature must be 64 hexadecimal digits")
+    }
+}
+
+impl std::error::Error for ParseSignatureError {}
+
+/// Parses a signature from 64 hexadecimal digits, e.g. received in a header
+impl FromStr for Signature {
+    type Err = ParseSignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 64 || !s.is_ascii() {
+            return Err(ParseSignatureError);
+        }
+        let mut bytes = [0u8; 32];
+        for (byte, digits) in bytes.iter_mut().zip(s.as_bytes().chunks_exact(2)) {
+            let digits = std::str::from_utf8(digits).map_err(|_| ParseSignatureError)?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| ParseSignatureError)?;
+        }
+        Ok(Signature(bytes))
+    }
+}
+
+/// Error returned when creating a signing key of a length unsupported by HMAC-SHA256
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidKeyLength;
+
+impl Display for InvalidKeyLength {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid length of the signing key")
+    }
+}
+
+impl std::error::Error for InvalidKeyLength {}
+
+/// Secret key of HMAC-SHA256 signatures, shared by the signing and the verifying services
+#[derive(Clone)]
+pub struct SigningKey(Arc<Hmac<Sha256>>);
+
+/// Hides the key
+impl Debug for SigningKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SigningKey(..)")
+    }
+}
+
+impl SigningKey {
+    /// Create a signing key, it should be at least 32 random bytes
+    pub fn new(key: impl AsRef<[u8]>) -> Result<Self, InvalidKeyLength> {
+        let mac = Hmac::<Sha256>::new_from_slice(key.as_ref()).map_err(|_| InvalidKeyLength)?;
+        Ok(SigningKey(Arc::new(mac)))
+    }
+
+    /// Sign bytes with HMAC-SHA256 (RFC 2104)
+    pub fn sign(&self, payload: &[u8]) -> Signature {
+        let mut mac = Hmac::clone(&self.0);
+        mac.update(payload);
+        Signature(mac.finalize().into_bytes().into())
+    }
+
+    /// Check the signature of bytes, in constant time
+    pub fn verify(&self, payload: &[u8], signature: &Signature) -> bool {
+        let mut mac = Hmac::clone(&self.0);
+        mac.update(payload);
+        mac.verify_slice(&signature.0).is_ok()
+    }
+
+    /// Sign the canonical JSON serialization of a value
+    pub fn sign_value<T: Serialize>(&self, value: &T) -> Result<Signature, serde_json::Error> {
+        Ok(self.sign(&canonical::to_vec(value)?))
+    }
+
+    /// Check the signature of the canonical JSON serialization of a value, in constant time
+    pub fn verify_value<T: Serialize>(
+        &self,
+        value: &T,
+        signature: &Signature,
+    ) -> Result<bool, serde_json::Error> {
+        Ok(self.verify(&canonical::to_vec(value)?, signature))
+    }
+}
+
+/// # `ValidSigned` data extractor
+///
+/// See the [module documentation](crate::signed) for details.
+///
+#[derive(Debug, Clone)]
+pub struct ValidSigned<T>(pub T, pub Signature);
+
+impl<T> ValidSigned<T> {
+    /// Consumes the `ValidSigned` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidSigned`
+pub type SignedRejection = SerializedRejection;
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidSigned<T>
+where
+    State: Send + Sync,
+    SigningKey: FromRef<State>,
+    T: DeserializeOwned + Serialize + Validate,
+{
+    type Rejection = ValidRejection<SignedRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let key = SigningKey::from_ref(state);
+        let Json(data) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Json(e)))?;
+        validate_redacted(&data).map_err(ValidRejection::Valid)?;
+        let signature = key
+            .sign_value(&data)
+            .map_err(|e| ValidRejection::Inner(SerializedRejection::Serialize(e)))?;
+        Ok(ValidSigned(data, signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[derive(Debug, serde::Deserialize, Serialize, Validate)]
+    struct Order {
+        #[validate(length(min = 1, max = 64))]
+        item: String,
+        #[validate(range(min = 1))]
+        quantity: u32,
+    }
+
+    #[test]
+    fn hmac_test_vectors() -> anyhow::Result<()> {
+        // RFC 4231, test cases 2 and 6
+        assert_eq!(
+            SigningKey::new(b"Jefe")?
+                .sign(b"what do ya want for nothing?")
+                .to_string(),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            SigningKey::new([0xaa; 131])?
+                .sign(b"Test Using Larger Than Block-Size Key - Hash Key First")
+                .to_string(),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn signature_verifies_against_payload_and_key() -> anyhow::Result<()> {
+        let key = SigningKey::new(b"0123456789abcdef0123456789abcdef")?;
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"quantity": 2, "item": "book"}"#))?;
+        let ValidSigned(order, signature) = ValidSigned::<Order>::from_request(request, &key)
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+        // e.g. forwarded in a header and checked downstream
+        let forwarded = signature.to_string().parse::<Signature>()?;
+        assert_eq!(forwarded.as_bytes(), signature.as_bytes());
+        assert!(key.verify_value(&order, &forwarded)?);
+        assert!(key.verify(br#"{"item":"book","quantity":2}"#, &forwarded));
+
+        let tampered = Order {
+            quantity: 20,
+            ..order
+        };
+        assert!(!key.verify_value(&tampered, &signature)?);
+        let other_key = SigningKey::new(b"another key")?;
+        assert!(!other_key.verify_value(
+            &Order {
+                quantity: 2,
+                ..tampered
+            },
+            &signature
+        )?);
+
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"quantity": 0, "item": "book"}"#))?;
+        assert!(matches!(
+            ValidSigned::<Order>::from_request(request, &key).await,
+            Err(ValidRejection::Valid(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn canonical_sorts_keys() -> anyhow::Result<()> {
+        // declared out of order, which `serde_json`'s `preserve_order` feature would keep
+        #[derive(Serialize)]
+        struct Shipment {
+            zone: u8,
+            address: Address,
+        }
+
+        #[derive(Serialize)]
+        struct Address {
+            street: &'static str,
+            city: &'static str,
+        }
+
+        let shipment = Shipment {
+            zone: 3,
+            address: Address {
+                street: "Main",
+                city: "Springfield",
+            },
+        };
+        assert_eq!(
+            canonical::to_vec(&shipment)?,
+            br#"{"address":{"city":"Springfield","street":"Main"},"zone":3}"#
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,384 @@
+//! # Status codes of validation errors
+//!
+//! ## Feature
+//!
+//! Enable the `status_map` feature to use `WithStatusMap<E>`.
+//!
+//! Some validation failures aren't malformed requests: a username that's already taken, checked against
+//! the existing users passed as the validation context, is a conflict with the current state of the server,
+//! which should be answered with `409 Conflict` (RFC 9110) rather than with the usual validation error status.
+//!
+//! `WithStatusMap<E>` wraps a validation extractor such as `ValidEx<E>`. When validation fails,
+//! the codes of the errors are looked up in the `StatusMap` provided by the state, in the order of the errors.
+//! The errors are returned as an `ErrorBody` with the status code of the first mapped error,
+//! or with the usual validation error status if no error code is mapped.
+//!
+//! `StatusMap::new()` maps the `unique` code (`UNIQUE_CODE`) to `409 Conflict`.
+//! Map other codes with `StatusMap::status`, or start from `StatusMap::empty()` to drop the default entry.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::extract::FromRef;
+//!     use axum::routing::post;
+//!     use axum::{Json, Router};
+//!     use axum_valid::status_map::{StatusMap, WithStatusMap, UNIQUE_CODE};
+//!     use axum_valid::ValidEx;
+//!     use serde::Deserialize;
+//!     use std::collections::HashSet;
+//!     use std::sync::Arc;
+//!     use validator::{Validate, ValidationError};
+//!
+//!     #[derive(Clone)]
+//!     pub struct Usernames(Arc<HashSet<String>>);
+//!
+//!     #[derive(Clone, FromRef)]
+//!     pub struct AppState {
+//!         statuses: StatusMap,
+//!         usernames: Usernames,
+//!     }
+//!
+//!     pub fn router() -> Router {
+//!         // e.g. loaded from the database
+//!         let usernames = Usernames(Arc::new(HashSet::from(["admin".to_owned()])));
+//!         Router::new()
+//!             .route("/users", post(handler))
+//!             .with_state(AppState { statuses: StatusMap::new(), usernames })
+//!     }
+//!
+//!     async fn handler(WithStatusMap(ValidEx(Json(sign_up))): WithStatusMap<ValidEx<Json<SignUp>>>) {
+//!         println!("welcome {}", sign_up.username);
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     #[validate(context = Usernames)]
+//!     pub struct SignUp {
+//!         #[validate(custom(function = "validate_username", use_context))]
+//!         pub username: String,
+//!     }
+//!
+//!     fn validate_username(username: &str, usernames: &Usernames) -> Result<(), ValidationError> {
+//!         if usernames.0.contains(username) {
+//!             Err(ValidationError::new(UNIQUE_CODE))
+//!         } else {
+//!             Ok(())
+//!         }
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::{ErrorBody, ValidationRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// Error code of uniqueness violations, mapped to `409 Conflict` by default
+pub const UNIQUE_CODE: &str = "unique";
+
+/// Status codes of validation errors, indexed by error code
+#[derive(Debug, Clone)]
+pub struct StatusMap(Arc<HashMap<String, StatusCode>>);
+
+impl Default for StatusMap {
+    fn default() -> Self {
+        Self::empty().status(UNIQUE_CODE, StatusCode::CONFLICT)
+    }
+}
+
+impl StatusMap {
+    /// Create a mapping of `unique` to `409 Conflict`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty mapping
+    pub fn empty() -> Self {
+        StatusMap(Arc::default())
+    }
+
+    /// Map an error code to a status code, replacing its previous status code
+    pub fn status(mut self, code: impl Into<String>, status: StatusCode) -> Self {
+        Arc::make_mut(&mut self.0).insert(code.into(), status);
+        self
+    }
+
+    /// Remove the status code of an error code
+    pub fn remove(mut self, code: &str) -> Self {
+        Arc::make_mut(&mut self.0).remove(code);
+        self
+    }
+
+    /// Get the status code of an error code
+    pub fn get(&self, code: &str) -> Option<StatusCode> {
+        self.0.get(code).copied()
+    }
+
+    /// Get the status code of the first mapped error in `body`
+    pub fn status_of(&self, body: &ErrorBody) -> Option<StatusCode> {
+        body.errors
+            .iter()
+            .filter_map(|error| error.code.as_deref())
+            .find_map(|code| self.get(code))
+    }
+}
+
+/// Validation errors, returned with the status code of their first mapped error
+#[derive(Debug, Clone)]
+pub struct MappedErrors {
+    /// The status code of the errors, `None` for the usual validation error status
+    pub status: Option<StatusCode>,
+    /// The errors
+    pub body: ErrorBody,
+}
+
+impl Display for MappedErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.body, f)
+    }
+}
+
+impl IntoResponse for MappedErrors {
+    fn into_response(self) -> Response {
+        let mut response = self.body.into_response();
+        if let Some(status) = self.status {
+            *response.status_mut() = status;
+        }
+        response
+    }
+}
+
+/// `StatusMapRejection` is returned when the `WithStatusMap` extractor fails.
+#[derive(Debug)]
+pub enum StatusMapRejection<E> {
+    /// The data is invalid
+    Valid(MappedErrors),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for StatusMapRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusMapRejection::Valid(errors) => write!(f, "{errors}"),
+            StatusMapRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for StatusMapRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StatusMapRejection::Valid(_) => None,
+            StatusMapRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for StatusMapRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            StatusMapRejection::Valid(errors) => errors.into_response(),
+            StatusMapRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+/// # `WithStatusMap` validation extractor
+///
+/// See the [module documentation](crate::status_map) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WithStatusMap<E>(pub E);
+
+impl<E> Deref for WithStatusMap<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for WithStatusMap<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> WithStatusMap<E> {
+    /// Consumes the `WithStatusMap` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+fn map_status<V, E>(
+    rejection: ValidationRejection<V, E>,
+    statuses: &StatusMap,
+) -> StatusMapRejection<E>
+where
+    ErrorBody: From<V>,
+{
+    match rejection {
+        ValidationRejection::Valid(errors) => {
+            let body = ErrorBody::from(errors);
+            StatusMapRejection::Valid(MappedErrors {
+                status: statuses.status_of(&body),
+                body,
+            })
+        }
+        ValidationRejection::Inner(error) => StatusMapRejection::Inner(error),
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequest<State> for WithStatusMap<Extractor>
+where
+    State: Send + Sync,
+    StatusMap: FromRef<State>,
+    Extractor: FromRequest<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = StatusMapRejection<E>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let statuses = StatusMap::from_ref(state);
+        Extractor::from_request(req, state)
+            .await
+            .map(WithStatusMap)
+            .map_err(|rejection| map_status(rejection, &statuses))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor, V, E> FromRequestParts<State> for WithStatusMap<Extractor>
+where
+    State: Send + Sync,
+    StatusMap: FromRef<State>,
+    Extractor: FromRequestParts<State, Rejection = ValidationRejection<V, E>>,
+    ErrorBody: From<V>,
+    E: IntoResponse,
+{
+    type Rejection = StatusMapRejection<E>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let statuses = StatusMap::from_ref(state);
+        Extractor::from_request_parts(parts, state)
+            .await
+            .map(WithStatusMap)
+            .map_err(|rejection| map_status(rejection, &statuses))
+    }
+}
+
+#[cfg(all(test, feature = "validator", feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::ValidEx;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+    use std::collections::HashSet;
+    use validator::{Validate, ValidationError};
+
+    #[derive(Clone)]
+    struct Usernames(Arc<HashSet<String>>);
+
+    #[derive(Clone, FromRef)]
+    struct AppState {
+        statuses: StatusMap,
+        usernames: Usernames,
+    }
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    #[validate(context = Usernames)]
+    struct SignUp {
+        #[validate(custom(function = "validate_username", use_context))]
+        username: String,
+        #[validate(length(min = 8))]
+        password: String,
+    }
+
+    fn validate_username(username: &str, usernames: &Usernames) -> Result<(), ValidationError> {
+        if usernames.0.contains(username) {
+            Err(ValidationError::new(UNIQUE_CODE))
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn extract(body: &'static str, statuses: StatusMap) -> anyhow::Result<Response> {
+        let state = AppState {
+            statuses,
+            usernames: Usernames(Arc::new(HashSet::from(["admin".to_owned()]))),
+        };
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        let rejection = WithStatusMap::<ValidEx<Json<SignUp>>>::from_request(request, &state)
+            .await
+            .expect_err("invalid sign up");
+        Ok(rejection.into_response())
+    }
+
+    #[tokio::test]
+    async fn uniqueness_failure_is_conflict() -> anyhow::Result<()> {
+        let response = extract(
+            r#"{"username": "admin", "password": "correct horse"}"#,
+            StatusMap::new(),
+        )
+        .await?;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = extract(
+            r#"{"username": "alice", "password": "short"}"#,
+            StatusMap::new(),
+        )
+        .await?;
+        assert_eq!(response.status(), crate::VALIDATION_ERROR_STATUS);
+
+        // the mapping is configurable
+        let response = extract(
+            r#"{"username": "admin", "password": "short"}"#,
+            StatusMap::new().status("length", StatusCode::PAYLOAD_TOO_LARGE),
+        )
+        .await?;
+        // the first mapped error, in the order of the paths, gives the status code
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let response = extract(
+            r#"{"username": "admin", "password": "correct horse"}"#,
+            StatusMap::new().remove(UNIQUE_CODE),
+        )
+        .await?;
+        assert_eq!(response.status(), crate::VALIDATION_ERROR_STATUS);
+        let response = extract(
+            r#"{"username": "admin", "password": "correct horse"}"#,
+            StatusMap::empty().status(UNIQUE_CODE, StatusCode::UNPROCESSABLE_ENTITY),
+        )
+        .await?;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        Ok(())
+    }
+}
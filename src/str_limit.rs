@@ -0,0 +1,334 @@
+//! # Support for `StrLimitedJson<T, MAX_LEN>`
+//!
+//! ## Feature
+//!
+//! Enable the `str_limit` feature to use `Valid<StrLimitedJson<T, MAX_LEN>>`.
+//!
+//! `length` rules bound the strings of the fields they're applied to, but they run after deserialization,
+//! once every string of the body has been allocated, including those of fields without rules
+//! and of maps such as `HashMap<String, String>`. `StrLimitedJson<T, MAX_LEN>` scans the whole body
+//! before deserializing it, and rejects bodies containing a string, key or value, longer than `MAX_LEN` characters
+//! with an `ErrorBody` with the code `string_too_long`, the path of the string and the limit as its `max` parameter.
+//! Field rules only run on bodies within the limit, so it complements them as a global cap.
+//! The limit defaults to `DEFAULT_MAX_LEN`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::Router;
+//!     use axum_valid::str_limit::StrLimitedJson;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use std::collections::HashMap;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/str_limit", post(handler))
+//!     }
+//!
+//!     async fn handler(
+//!         Valid(StrLimitedJson(parameter)): Valid<StrLimitedJson<Parameter, 256>>,
+//!     ) {
+//!         assert!(parameter.validate().is_ok());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(length(min = 1, max = 10))]
+//!         pub name: String,
+//!         pub labels: HashMap<String, String>,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::buffered::BufferedRequest;
+use crate::error_body::{ErrorBody, FieldError, FieldPath};
+use crate::json_scan::{unescaped_len, Scanner, Token};
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::extract::rejection::{BytesRejection, JsonRejection};
+use axum::extract::{FromRequest, Request};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "validator")]
+use validator::ValidateArgs;
+
+/// Error code of bodies with a too long string
+pub const STRING_TOO_LONG: &str = "string_too_long";
+
+/// Default maximum string length of `StrLimitedJson`, in characters
+pub const DEFAULT_MAX_LEN: usize = 65536;
+
+/// # `StrLimitedJson` extractor
+///
+/// See the [module documentation](crate::str_limit) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StrLimitedJson<T, const MAX_LEN: usize = DEFAULT_MAX_LEN>(pub T);
+
+impl<T, const MAX_LEN: usize> Deref for StrLimitedJson<T, MAX_LEN> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, const MAX_LEN: usize> DerefMut for StrLimitedJson<T, MAX_LEN> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// `StrLimitedJsonRejection` is returned when the `StrLimitedJson` extractor fails.
+#[derive(Debug)]
+pub enum StrLimitedJsonRejection {
+    /// The body can't be read
+    Bytes(BytesRejection),
+    /// The body contains a too long string
+    StringTooLong(ErrorBody),
+    /// The body is rejected by `Json`
+    Json(JsonRejection),
+}
+
+impl Display for StrLimitedJsonRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StrLimitedJsonRejection::Bytes(e) => write!(f, "{e}"),
+            StrLimitedJsonRejection::StringTooLong(body) => {
+                let error = body.errors.first();
+                let limit = error
+                    .and_then(|error| error.params.get("max"))
+                    .unwrap_or(&Value::Null);
+                let path = error
+                    .map(|error| error.path.to_string())
+                    .unwrap_or_default();
+                write!(
+                    f,
+                    "The body contains a string longer than {limit} characters at `{path}`"
+                )
+            }
+            StrLimitedJsonRejection::Json(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for StrLimitedJsonRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StrLimitedJsonRejection::Bytes(e) => Some(e),
+            StrLimitedJsonRejection::StringTooLong(_) => None,
+            StrLimitedJsonRejection::Json(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for StrLimitedJsonRejection {
+    fn into_response(self) -> Response {
+        match self {
+            StrLimitedJsonRejection::Bytes(e) => e.into_response(),
+            StrLimitedJsonRejection::StringTooLong(body) => body.into_response(),
+            StrLimitedJsonRejection::Json(e) => e.into_response(),
+        }
+    }
+}
+
+/// Find the first string of a JSON document longer than `max` characters, and return its path
+///
+/// Too long keys are reported with the path of their object. Syntax errors are ignored,
+/// they are reported by the JSON deserializer afterwards.
+fn find_long_string(json: &[u8], max: usize) -> Option<FieldPath> {
+    let mut scanner = Scanner::new(json);
+    while let Some(token) = scanner.next() {
+        if let Token::Key(raw) | Token::String(raw) = token {
+            if unescaped_len(raw) > max {
+                return Some(scanner.path());
+            }
+        }
+    }
+    None
+}
+
+#[async_trait]
+impl<T, S, const MAX_LEN: usize> FromRequest<S> for StrLimitedJson<T, MAX_LEN>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = StrLimitedJsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let buffered = BufferedRequest::read(req, state)
+            .await
+            .map_err(StrLimitedJsonRejection::Bytes)?;
+        if let Some(path) = find_long_string(&buffered.bytes, MAX_LEN) {
+            let mut error = FieldError::new(path, STRING_TOO_LONG);
+            error
+                .params
+                .insert(String::from("max"), Value::from(MAX_LEN));
+            return Err(StrLimitedJsonRejection::StringTooLong(ErrorBody::new(
+                vec![error],
+            )));
+        }
+        let Json(value) = Json::from_request(buffered.into_request(), state)
+            .await
+            .map_err(StrLimitedJsonRejection::Json)?;
+        Ok(StrLimitedJson(value))
+    }
+}
+
+impl<T, const MAX_LEN: usize> HasValidate for StrLimitedJson<T, MAX_LEN> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>, const MAX_LEN: usize> HasValidateArgs<'v>
+    for StrLimitedJson<T, MAX_LEN>
+{
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify, const MAX_LEN: usize> crate::HasModify for StrLimitedJson<T, MAX_LEN> {
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T, const MAX_LEN: usize> crate::PayloadExtractor for StrLimitedJson<T, MAX_LEN> {
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload, const MAX_LEN: usize> crate::HasValidify
+    for StrLimitedJson<T, MAX_LEN>
+{
+    type Validify = T;
+    type PayloadExtractor = StrLimitedJson<T::Payload, MAX_LEN>;
+    fn from_validify(v: Self::Validify) -> Self {
+        StrLimitedJson(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[test]
+    fn find_long_strings() {
+        let json = r#"{"a": {"b": "xyz", "c": ["d", "éèà\"", "éx"]}, "e": 1}"#;
+        assert_eq!(find_long_string(json.as_bytes(), 4), None);
+        assert_eq!(
+            find_long_string(json.as_bytes(), 3).map(|path| path.to_string()),
+            Some(String::from("a.c[1]"))
+        );
+        assert_eq!(
+            find_long_string(br#"{"a": {"long_key": 1}}"#, 4).map(|path| path.to_string()),
+            Some(String::from("a"))
+        );
+        assert_eq!(find_long_string(br#""toolong""#, 4), Some(FieldPath::new()));
+        // escapes count as the character they stand for
+        assert_eq!(find_long_string(br#"["\u00e9\"ab"]"#, 4), None);
+        assert_eq!(
+            find_long_string(br#"["\u00e9\"abc"]"#, 4).map(|path| path.to_string()),
+            Some(String::from("[0]"))
+        );
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn reject_before_field_rules() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use validator::{Validate, ValidationError};
+
+        static VALIDATIONS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Parameters {
+            #[validate(custom(function = "validate_name"))]
+            name: String,
+            notes: Vec<String>,
+        }
+
+        fn validate_name(name: &str) -> Result<(), ValidationError> {
+            VALIDATIONS.fetch_add(1, Ordering::SeqCst);
+            if name.len() <= 5 {
+                Ok(())
+            } else {
+                Err(ValidationError::new("length"))
+            }
+        }
+
+        let request = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+
+        let Valid(StrLimitedJson(parameters)) =
+            Valid::<StrLimitedJson<Parameters, 8>>::from_request(
+                request(r#"{"name": "n", "notes": ["12345678"]}"#)?,
+                &(),
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.notes, ["12345678"]);
+        assert_eq!(VALIDATIONS.load(Ordering::SeqCst), 1);
+
+        let rejection = Valid::<StrLimitedJson<Parameters, 8>>::from_request(
+            request(r#"{"name": "much too long", "notes": []}"#)?,
+            &(),
+        )
+        .await
+        .expect_err("name is too long");
+        let ValidRejection::Inner(StrLimitedJsonRejection::StringTooLong(body)) = rejection else {
+            panic!("expected a too long string rejection");
+        };
+        assert_eq!(body.errors[0].path.to_string(), "name");
+        assert_eq!(body.errors[0].code.as_deref(), Some(STRING_TOO_LONG));
+        assert_eq!(body.errors[0].params["max"], 8);
+        // the field rules didn't run
+        assert_eq!(VALIDATIONS.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,751 @@
+//! # Memory-bounded JSON deserialization with `StreamingJson<T>`
+//!
+//! ## Feature
+//!
+//! Enable the `streaming_json` feature to use `Valid<StreamingJson<T>>`.
+//!
+//! `axum::Json<T>` buffers the whole request body before deserializing it,
+//! so a large JSON object occupies memory twice: once as raw bytes and once as the deserialized value.
+//! `StreamingJson<T>` deserializes the body while it's being received, chunk by chunk,
+//! keeping at most a couple of body chunks and a fixed read buffer in memory besides the value itself.
+//! Malformed bodies are rejected as soon as the error is encountered, without reading the rest of the body.
+//! Like `Json<T>`, the body is limited by `DefaultBodyLimit`, bodies exceeding it are rejected with `413 Payload Too Large`.
+//!
+//! `Valid<StreamingJson<T>>` validates the value once it's fully deserialized. To validate parts of it
+//! during deserialization, e.g. the items of a large array, wrap them in `ValidatedField<T>`: each of them
+//! is validated as soon as it's deserialized, and the first invalid one stops the deserialization,
+//! without reading the rest of the body. The request is then rejected with the validation errors of that part,
+//! like `Valid<E>` would, but the position of the part in the body isn't reported.
+//!
+//! ## Limits
+//!
+//! The body is deserialized on a blocking thread of the tokio runtime, for as long as the client sends it.
+//! `StreamingJsonLimits`, provided by the state, bounds the number of bodies deserialized at once,
+//! further requests wait for a slot, and the time a request may wait and take to send its body,
+//! after which it's rejected with `408 Request Timeout`, see `StreamingJsonLimits::new`.
+//! Routers without state, i.e. with `()` as their state, share process-wide default limits.
+//!
+//! ## Usage
+//!
+//! 1. Implement `Deserialize` and `Validate` for your data type `T`.
+//! 2. In your handler function, use `Valid<StreamingJson<T>>` as some parameter's type.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::Router;
+//!     use axum_valid::streaming_json::{StreamingJson, ValidatedField};
+//!     use axum_valid::Valid;
+//!     use serde::{Deserialize, Serialize};
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/streaming_json", post(handler))
+//!     }
+//!
+//!     async fn handler(Valid(StreamingJson(batch)): Valid<StreamingJson<Batch>>) {
+//!         assert!(batch.validate().is_ok());
+//!         println!("{} items", batch.items.len());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Batch {
+//!         #[validate(length(max = 100000))]
+//!         pub items: Vec<ValidatedField<Item>>,
+//!     }
+//!
+//!     // Validated as soon as it's deserialized
+//!     #[derive(Validate, Deserialize, Serialize)]
+//!     pub struct Item {
+//!         #[validate(range(max = 1000))]
+//!         pub quantity: u64,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "validator")]
+use crate::validator::validate_redacted;
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::RequestExt;
+use http_body_util::{BodyExt, LengthLimitError};
+use serde::de::DeserializeOwned;
+#[cfg(feature = "validator")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "validator")]
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{BufReader, Read};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::time::{timeout_at, Instant};
+#[cfg(feature = "validator")]
+use validator::{Validate, ValidateArgs, ValidationErrors};
+
+/// # `StreamingJson` extractor
+///
+/// See the [module documentation](crate::streaming_json) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamingJson<T>(pub T);
+
+impl<T> Deref for StreamingJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for StreamingJson<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Limits of the deserialization of `StreamingJson` bodies, provided by the state
+///
+/// See the [module documentation](crate::streaming_json) for details.
+#[derive(Debug, Clone)]
+pub struct StreamingJsonLimits {
+    permits: Arc<Semaphore>,
+    timeout: Duration,
+}
+
+impl StreamingJsonLimits {
+    /// Default number of bodies deserialized at once, well below the 512 blocking threads of tokio by default
+    pub const DEFAULT_MAX_CONCURRENT: usize = 64;
+    /// Default time a request may wait for a slot and take to send its body
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Deserialize at most `max_concurrent` bodies at once, each request may wait for a slot and send its body
+    /// for `timeout` in total
+    pub fn new(max_concurrent: usize, timeout: Duration) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            timeout,
+        }
+    }
+}
+
+impl Default for StreamingJsonLimits {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_CONCURRENT, Self::DEFAULT_TIMEOUT)
+    }
+}
+
+/// Without a state, the default limits are shared by the whole process
+impl FromRef<()> for StreamingJsonLimits {
+    fn from_ref(_: &()) -> Self {
+        static LIMITS: OnceLock<StreamingJsonLimits> = OnceLock::new();
+        LIMITS.get_or_init(StreamingJsonLimits::default).clone()
+    }
+}
+
+/// `StreamingJsonRejection` is returned when the `StreamingJson` extractor fails.
+#[derive(Debug)]
+pub enum StreamingJsonRejection {
+    /// The request doesn't have a JSON content type
+    MissingJsonContentType,
+    /// The request body can't be read, or exceeds `DefaultBodyLimit`
+    Body(axum::Error),
+    /// The request body can't be deserialized
+    Json(serde_json::Error),
+    /// A `ValidatedField` is invalid, the rest of the body wasn't read
+    #[cfg(feature = "validator")]
+    Field(ValidationErrors),
+    /// The request waited for a slot and sent its body for longer than the timeout of `StreamingJsonLimits`
+    Timeout,
+    /// The deserialization task failed
+    Task(tokio::task::JoinError),
+}
+
+impl StreamingJsonRejection {
+    fn status(&self) -> StatusCode {
+        // Same status codes as `axum::Json`
+        match self {
+            StreamingJsonRejection::MissingJsonContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            StreamingJsonRejection::Body(e) if is_length_limit_error(e) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            StreamingJsonRejection::Body(_) => StatusCode::BAD_REQUEST,
+            StreamingJsonRejection::Json(e) if e.is_data() => StatusCode::UNPROCESSABLE_ENTITY,
+            StreamingJsonRejection::Json(_) => StatusCode::BAD_REQUEST,
+            #[cfg(feature = "validator")]
+            StreamingJsonRejection::Field(_) => crate::VALIDATION_ERROR_STATUS,
+            StreamingJsonRejection::Timeout => StatusCode::REQUEST_TIMEOUT,
+            StreamingJsonRejection::Task(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl Display for StreamingJsonRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamingJsonRejection::MissingJsonContentType => {
+                write!(f, "Expected request with `Content-Type: application/json`")
+            }
+            StreamingJsonRejection::Body(e) => write!(f, "Failed to read request body: {e}"),
+            StreamingJsonRejection::Json(e) => {
+                write!(f, "Failed to deserialize the JSON body: {e}")
+            }
+            #[cfg(feature = "validator")]
+            StreamingJsonRejection::Field(errors) => write!(f, "{errors}"),
+            StreamingJsonRejection::Timeout => write!(f, "Timed out receiving the request body"),
+            StreamingJsonRejection::Task(e) => write!(f, "Failed to deserialize: {e}"),
+        }
+    }
+}
+
+impl Error for StreamingJsonRejection {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StreamingJsonRejection::MissingJsonContentType => None,
+            StreamingJsonRejection::Body(e) => Some(e),
+            StreamingJsonRejection::Json(e) => Some(e),
+            #[cfg(feature = "validator")]
+            StreamingJsonRejection::Field(errors) => Some(errors),
+            StreamingJsonRejection::Timeout => None,
+            StreamingJsonRejection::Task(e) => Some(e),
+        }
+    }
+}
+
+impl IntoResponse for StreamingJsonRejection {
+    fn into_response(self) -> Response {
+        match self {
+            // Same response as the validation errors of `Valid`
+            #[cfg(feature = "validator")]
+            StreamingJsonRejection::Field(errors) => {
+                crate::ValidRejection::<std::convert::Infallible>::Valid(errors).into_response()
+            }
+            rejection => (rejection.status(), rejection.to_string()).into_response(),
+        }
+    }
+}
+
+/// Check if the body was rejected by `DefaultBodyLimit`
+fn is_length_limit_error(error: &axum::Error) -> bool {
+    let mut source = error.source();
+    while let Some(error) = source {
+        if error.is::<LengthLimitError>() {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Blocking reader over the body chunks sent by the async side
+struct BodyReader {
+    receiver: mpsc::Receiver<Bytes>,
+    chunk: Bytes,
+}
+
+impl BodyReader {
+    fn new(receiver: mpsc::Receiver<Bytes>) -> Self {
+        Self {
+            receiver,
+            chunk: Bytes::new(),
+        }
+    }
+}
+
+impl Read for BodyReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => self.chunk = chunk,
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk.split_to(len));
+        Ok(len)
+    }
+}
+
+fn is_json_content_type(req: &Request) -> bool {
+    req.headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(str::trim)
+        .is_some_and(|mime| {
+            mime == "application/json"
+                || (mime.starts_with("application/") && mime.ends_with("+json"))
+        })
+}
+
+#[cfg(feature = "validator")]
+thread_local! {
+    /// Errors of the first invalid `ValidatedField`, `Some` while `StreamingJson` deserializes on this thread
+    static FIELD_ERRORS: RefCell<Option<Option<ValidationErrors>>> = const { RefCell::new(None) };
+}
+
+/// Deserialize the body, reporting the errors of the first invalid `ValidatedField`
+fn deserialize<T: DeserializeOwned>(reader: BodyReader) -> Result<T, StreamingJsonRejection> {
+    // serde_json reads byte by byte, so the reader is buffered
+    let deserialize = || {
+        serde_json::from_reader::<_, T>(BufReader::new(reader))
+            .map_err(StreamingJsonRejection::Json)
+    };
+    #[cfg(feature = "validator")]
+    {
+        FIELD_ERRORS.set(Some(None));
+        let result = deserialize();
+        match FIELD_ERRORS.take().flatten() {
+            Some(errors) if result.is_err() => Err(StreamingJsonRejection::Field(errors)),
+            _ => result,
+        }
+    }
+    #[cfg(not(feature = "validator"))]
+    deserialize()
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for StreamingJson<T>
+where
+    T: DeserializeOwned + Send + 'static,
+    S: Send + Sync,
+    StreamingJsonLimits: FromRef<S>,
+{
+    type Rejection = StreamingJsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_json_content_type(&req) {
+            return Err(StreamingJsonRejection::MissingJsonContentType);
+        }
+
+        let limits = StreamingJsonLimits::from_ref(state);
+        let deadline = Instant::now() + limits.timeout;
+        // The semaphore is never closed
+        let Ok(Ok(permit)) = timeout_at(deadline, limits.permits.acquire_owned()).await else {
+            return Err(StreamingJsonRejection::Timeout);
+        };
+
+        // Capacity 1: the body is only read as fast as it's deserialized
+        let (sender, receiver) = mpsc::channel(1);
+        let reader = BodyReader::new(receiver);
+        let task = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            deserialize::<T>(reader)
+        });
+
+        // Limited like `Bytes::from_request`, by `DefaultBodyLimit`
+        let mut body = req.into_limited_body();
+        loop {
+            // On early returns, the sender is dropped, which ends the deserialization
+            let frame = match timeout_at(deadline, body.frame()).await {
+                Ok(Some(frame)) => frame.map_err(StreamingJsonRejection::Body)?,
+                Ok(None) => break,
+                Err(_) => return Err(StreamingJsonRejection::Timeout),
+            };
+            if let Ok(data) = frame.into_data() {
+                if sender.send(data).await.is_err() {
+                    // The deserializer stopped early, its result tells why
+                    break;
+                }
+            }
+        }
+        drop(sender);
+
+        task.await
+            .map_err(StreamingJsonRejection::Task)?
+            .map(StreamingJson)
+    }
+}
+
+/// # Field validated during deserialization
+///
+/// `ValidatedField<T>` deserializes like `T`, then validates it right away, and fails to deserialize if it's invalid.
+/// `StreamingJson` then stops reading the body, and reports the validation errors with `StreamingJsonRejection::Field`.
+/// See the [module documentation](crate::streaming_json) for details.
+#[cfg(feature = "validator")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidatedField<T>(pub T);
+
+#[cfg(feature = "validator")]
+impl<T> Deref for ValidatedField<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<T> DerefMut for ValidatedField<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<T> ValidatedField<T> {
+    /// Consumes the `ValidatedField` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Serializes like `T`, so that rules like `length` apply to collections of `ValidatedField`
+#[cfg(feature = "validator")]
+impl<T: Serialize> Serialize for ValidatedField<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'de, T: Deserialize<'de> + Validate> Deserialize<'de> for ValidatedField<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = T::deserialize(deserializer)?;
+        if let Err(errors) = validate_redacted(&value) {
+            let message = errors.to_string();
+            FIELD_ERRORS.with_borrow_mut(|collected| {
+                if let Some(collected @ None) = collected {
+                    *collected = Some(errors);
+                }
+            });
+            return Err(serde::de::Error::custom(message));
+        }
+        Ok(ValidatedField(value))
+    }
+}
+
+impl<T> HasValidate for StreamingJson<T> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>> HasValidateArgs<'v> for StreamingJson<T> {
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify> crate::HasModify for StreamingJson<T> {
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T> crate::PayloadExtractor for StreamingJson<T> {
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload> crate::HasValidify for StreamingJson<T> {
+    type Validify = T;
+    type PayloadExtractor = StreamingJson<T::Payload>;
+    fn from_validify(v: Self::Validify) -> Self {
+        StreamingJson(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use futures_util::StreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug, serde::Deserialize)]
+    #[cfg_attr(feature = "validator", derive(validator::Validate))]
+    struct Batch {
+        #[cfg_attr(feature = "validator", validate(length(max = 100000)))]
+        items: Vec<u64>,
+    }
+
+    fn large_body(items: usize) -> String {
+        let items = (0..items).map(|i| i.to_string()).collect::<Vec<_>>();
+        format!(r#"{{"items": [{}]}}"#, items.join(","))
+    }
+
+    /// Number of frames of the body pulled by the extractor
+    static PULLED: AtomicUsize = AtomicUsize::new(0);
+    /// Largest number of frames pulled by the extractor ahead of the deserializer
+    static AHEAD: AtomicUsize = AtomicUsize::new(0);
+
+    /// An item of a body sent one item per frame, recording how far the extractor is ahead when it's deserialized
+    #[derive(Debug)]
+    struct Item;
+
+    impl<'de> serde::Deserialize<'de> for Item {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let index = usize::deserialize(deserializer)?;
+            // the frames of the opening bracket and of the items up to this one have been read
+            let read = index + 2;
+            AHEAD.fetch_max(
+                PULLED.load(Ordering::SeqCst).saturating_sub(read),
+                Ordering::SeqCst,
+            );
+            Ok(Item)
+        }
+    }
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Items {
+        items: Vec<Item>,
+    }
+
+    #[tokio::test]
+    async fn memory_stays_bounded() -> anyhow::Result<()> {
+        const ITEMS: usize = 10000;
+        let frames = std::iter::once(String::from(r#"{"items": ["#))
+            .chain((0..ITEMS).map(|i| match i {
+                i if i == ITEMS - 1 => format!("{i}]}}"),
+                i => format!("{i},"),
+            }))
+            .collect::<Vec<_>>();
+        let stream = futures_util::stream::iter(frames).map(|frame| {
+            PULLED.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, std::convert::Infallible>(frame)
+        });
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from_stream(stream))?;
+
+        let StreamingJson(items) = StreamingJson::<Items>::from_request(request, &()).await?;
+        assert_eq!(items.items.len(), ITEMS);
+        assert_eq!(PULLED.load(Ordering::SeqCst), ITEMS + 1);
+        // the body is pulled as it's deserialized, a few frames ahead at most, not buffered
+        assert!(AHEAD.load(Ordering::SeqCst) <= 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn limits_are_respected() -> anyhow::Result<()> {
+        let limits = StreamingJsonLimits::new(1, Duration::from_millis(200));
+        let request = |body: Body| {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .body(body)
+        };
+
+        // a client that never finishes sending its body
+        let pending = request(Body::from_stream(
+            futures_util::stream::once(async {
+                Ok::<_, std::convert::Infallible>(String::from(r#"{"items": ["#))
+            })
+            .chain(futures_util::stream::pending()),
+        ))?;
+        let slow = tokio::spawn({
+            // sharing the slot, but timing out after the other requests
+            let limits = StreamingJsonLimits {
+                timeout: Duration::from_millis(500),
+                ..limits.clone()
+            };
+            async move { StreamingJson::<Batch>::from_request(pending, &limits).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(limits.permits.available_permits(), 0);
+
+        // the only slot is taken until the slow request times out
+        let rejection =
+            StreamingJson::<Batch>::from_request(request(Body::from("{\"items\": [1]}"))?, &limits)
+                .await
+                .expect_err("no slot is available");
+        assert!(matches!(rejection, StreamingJsonRejection::Timeout));
+
+        let rejection = slow.await?.expect_err("body is never completed");
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::REQUEST_TIMEOUT
+        );
+
+        // the slot is released with the blocking thread
+        let StreamingJson(batch) =
+            StreamingJson::<Batch>::from_request(request(Body::from("{\"items\": [1]}"))?, &limits)
+                .await?;
+        assert_eq!(batch.items, [1]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn streaming_json_rejections() -> anyhow::Result<()> {
+        let request = |content_type: &'static str, body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, content_type)
+                .body(Body::from(body))
+        };
+
+        let StreamingJson(batch) = StreamingJson::<Batch>::from_request(
+            request("application/json", "{\"items\": [1, 2]}")?,
+            &(),
+        )
+        .await?;
+        assert_eq!(batch.items, [1, 2]);
+
+        let rejection = StreamingJson::<Batch>::from_request(request("text/plain", "{}")?, &())
+            .await
+            .expect_err("content type is not json");
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+
+        let rejection = StreamingJson::<Batch>::from_request(
+            request("application/json", "{\"items\": [")?,
+            &(),
+        )
+        .await
+        .expect_err("body is truncated");
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+
+        let rejection = StreamingJson::<Batch>::from_request(
+            request("application/json", "{\"items\": [\"1\"]}")?,
+            &(),
+        )
+        .await
+        .expect_err("items are not numbers");
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn body_limit_is_respected() -> anyhow::Result<()> {
+        // larger than the default limit of `DefaultBodyLimit`, 2 MB
+        let body = large_body(400000);
+        assert!(body.len() > 2 * 1024 * 1024);
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        let rejection = StreamingJson::<Batch>::from_request(request, &())
+            .await
+            .expect_err("body is too large");
+        assert!(matches!(&rejection, StreamingJsonRejection::Body(_)));
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn validate_streaming_json() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+
+        // one item too many for `length(max = 100000)`
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(large_body(100001)))?;
+        assert!(matches!(
+            Valid::<StreamingJson<Batch>>::from_request(request, &()).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("items")
+        ));
+        Ok(())
+    }
+
+    /// Number of frames of the body pulled by the extractor
+    #[cfg(feature = "validator")]
+    static FIELD_PULLED: AtomicUsize = AtomicUsize::new(0);
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn fields_are_validated_while_streaming() -> anyhow::Result<()> {
+        use crate::ValidRejection;
+
+        #[derive(Debug, serde::Deserialize, Serialize, Validate)]
+        struct Item {
+            #[validate(range(max = 1000))]
+            quantity: u64,
+        }
+
+        #[derive(Debug, serde::Deserialize, Validate)]
+        struct Items {
+            #[validate(length(max = 100000))]
+            items: Vec<ValidatedField<Item>>,
+        }
+
+        const ITEMS: usize = 10000;
+        const INVALID: usize = 100;
+        let frames = std::iter::once(String::from(r#"{"items": ["#))
+            .chain((0..ITEMS).map(|i| {
+                let quantity = if i == INVALID { 1001 } else { i % 1000 };
+                let separator = if i == ITEMS - 1 { "]}" } else { "," };
+                format!(r#"{{"quantity": {quantity}}}{separator}"#)
+            }))
+            .collect::<Vec<_>>();
+        let stream = futures_util::stream::iter(frames).map(|frame| {
+            FIELD_PULLED.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, std::convert::Infallible>(frame)
+        });
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from_stream(stream))?;
+
+        let rejection = crate::Valid::<StreamingJson<Items>>::from_request(request, &())
+            .await
+            .expect_err("an item is invalid");
+        let ValidRejection::Inner(StreamingJsonRejection::Field(errors)) = &rejection else {
+            panic!("unexpected rejection: {rejection:?}");
+        };
+        assert!(errors.field_errors().contains_key("quantity"));
+        // the rest of the body isn't read past the invalid item
+        assert!(FIELD_PULLED.load(Ordering::SeqCst) <= INVALID + 5);
+        assert_eq!(
+            rejection.into_response().status(),
+            crate::VALIDATION_ERROR_STATUS
+        );
+
+        // valid items are deserialized as usual
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"items": [{"quantity": 1}]}"#))?;
+        let StreamingJson(items) = StreamingJson::<Items>::from_request(request, &()).await?;
+        assert_eq!(items.items[0].quantity, 1);
+        Ok(())
+    }
+}
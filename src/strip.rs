@@ -0,0 +1,256 @@
+//! # Field stripping after validation
+//!
+//! ## Feature
+//!
+//! Enable the `strip` feature to use `ValidStripped<E>`.
+//!
+//! `ValidStripped<E>` validates the inner data like `Valid<E>`, then removes every top-level field
+//! that is not contained in the `AllowedFields` provided by the state, before handing the data to the handler.
+//! This is useful for field-level authorization, e.g. ignoring fields a client is not allowed to update.
+//!
+//! The data is stripped via a `serde_json::Value` round-trip, so its type must implement `Serialize` and `Deserialize`,
+//! and fields that may be stripped must be deserializable when missing (e.g. `Option<T>` or `#[serde(default)]`).
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::strip::{AllowedFields, ValidStripped};
+//! use serde::{Deserialize, Serialize};
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Serialize, Deserialize)]
+//! pub struct Profile {
+//!     #[validate(length(min = 1, max = 10))]
+//!     pub name: String,
+//!     #[serde(default)]
+//!     pub role: Option<String>,
+//! }
+//!
+//! async fn handler(ValidStripped(Json(profile)): ValidStripped<Json<Profile>>) {
+//!     assert!(profile.role.is_none());
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new()
+//!     .route("/profile", post(handler))
+//!     .with_state(AllowedFields::new(["name"]));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::{HasValidate, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use validator::Validate;
+
+/// Names of the top-level fields that are kept by `ValidStripped`
+#[derive(Debug, Clone, Default)]
+pub struct AllowedFields(Arc<HashSet<String>>);
+
+impl AllowedFields {
+    /// Create an allow-list from field names
+    pub fn new<I, S>(fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        AllowedFields(Arc::new(fields.into_iter().map(Into::into).collect()))
+    }
+
+    /// Check if a field is allowed
+    pub fn contains(&self, field: &str) -> bool {
+        self.0.contains(field)
+    }
+
+    /// Remove the fields that are not allowed from `value`
+    pub fn strip<T: Serialize + DeserializeOwned>(&self, value: &T) -> serde_json::Result<T> {
+        let mut json = serde_json::to_value(value)?;
+        if let serde_json::Value::Object(map) = &mut json {
+            map.retain(|field, _| self.contains(field));
+        }
+        serde_json::from_value(json)
+    }
+}
+
+/// # `ValidStripped` data extractor
+///
+/// See the [module documentation](crate::strip) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidStripped<E>(pub E);
+
+impl<E> Deref for ValidStripped<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidStripped<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidStripped<E> {
+    /// Consumes the `ValidStripped` and returns the validated and stripped data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidStripped`
+#[derive(Debug)]
+pub enum StripRejection<E> {
+    /// The inner extractor failed
+    Inner(E),
+    /// The stripped data can't be deserialized, which means that a required field is not allowed
+    Strip(serde_json::Error),
+}
+
+impl<E: Display> Display for StripRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StripRejection::Inner(e) => write!(f, "{e}"),
+            StripRejection::Strip(e) => write!(f, "Failed to strip fields: {e}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for StripRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StripRejection::Inner(e) => Some(e),
+            StripRejection::Strip(e) => Some(e),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for StripRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            StripRejection::Inner(e) => e.into_response(),
+            StripRejection::Strip(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to strip fields: {e}"),
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidStripped<Extractor>
+where
+    State: Send + Sync,
+    AllowedFields: FromRef<State>,
+    Extractor: HasValidate + FromRequest<State> + DerefMut<Target = Extractor::Validate>,
+    Extractor::Validate: Validate + Serialize + DeserializeOwned,
+{
+    type Rejection = ValidRejection<StripRejection<<Extractor as FromRequest<State>>::Rejection>>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let allowed = AllowedFields::from_ref(state);
+        let mut inner = Extractor::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(StripRejection::Inner(e)))?;
+        validate_redacted(inner.get_validate())?;
+        let stripped = allowed
+            .strip(inner.get_validate())
+            .map_err(|e| ValidRejection::Inner(StripRejection::Strip(e)))?;
+        *inner = stripped;
+        Ok(ValidStripped(inner))
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+    use serde::Deserialize;
+
+    #[derive(Debug, Validate, Serialize, Deserialize)]
+    struct Profile {
+        #[validate(length(min = 1, max = 10))]
+        name: String,
+        #[serde(default)]
+        role: Option<String>,
+        #[serde(default)]
+        #[validate(range(max = 150))]
+        age: u8,
+    }
+
+    fn request(body: &'static str) -> anyhow::Result<Request> {
+        Ok(Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?)
+    }
+
+    #[tokio::test]
+    async fn strip_disallowed_fields() -> anyhow::Result<()> {
+        let allowed = AllowedFields::new(["name", "age"]);
+        let ValidStripped(Json(profile)) = ValidStripped::<Json<Profile>>::from_request(
+            request(r#"{"name": "gteng", "role": "admin", "age": 18}"#)?,
+            &allowed,
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(profile.name, "gteng");
+        assert_eq!(profile.age, 18);
+        assert_eq!(profile.role, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn validate_before_stripping() -> anyhow::Result<()> {
+        // `age` is validated even if it's not allowed
+        let allowed = AllowedFields::new(["name"]);
+        let rejection = ValidStripped::<Json<Profile>>::from_request(
+            request(r#"{"name": "gteng", "age": 200}"#)?,
+            &allowed,
+        )
+        .await
+        .expect_err("age is out of range");
+        assert!(
+            matches!(rejection, ValidRejection::Valid(errors) if errors.field_errors().contains_key("age"))
+        );
+
+        // `name` is required, so it can't be stripped
+        let rejection = ValidStripped::<Json<Profile>>::from_request(
+            request(r#"{"name": "gteng"}"#)?,
+            &AllowedFields::new(["age"]),
+        )
+        .await
+        .expect_err("name can't be stripped");
+        assert!(matches!(
+            rejection,
+            ValidRejection::Inner(StripRejection::Strip(_))
+        ));
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,373 @@
+//! # Support for `TextForm<T>`
+//!
+//! ## Feature
+//!
+//! Enable the `text_form` feature to use `Valid<TextForm<T>>`.
+//!
+//! `TextForm<T>` works like `axum::Form<T>`, but also accepts `multipart/form-data` bodies
+//! that only contain text fields. The text fields are deserialized exactly as an
+//! `application/x-www-form-urlencoded` body with the same fields would be,
+//! so one handler can serve both kinds of clients. Multipart bodies containing files are rejected.
+//!
+//! ## Usage
+//!
+//! 1. Implement `Deserialize` and `Validate` for your data type `T`.
+//! 2. In your handler function, use `Valid<TextForm<T>>` as some parameter's type.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::Router;
+//!     use axum_valid::text_form::TextForm;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/text_form", post(handler))
+//!     }
+//!
+//!     async fn handler(Valid(TextForm(parameter)): Valid<TextForm<Parameter>>) {
+//!         assert!(parameter.validate().is_ok());
+//!         // Support automatic dereferencing
+//!         println!("v0 = {}, v1 = {}", parameter.v0, parameter.v1);
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(range(min = 5, max = 10))]
+//!         pub v0: i32,
+//!         #[validate(length(min = 1, max = 10))]
+//!         pub v1: String,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::HasValidate;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use axum::async_trait;
+use axum::body::Body;
+use axum::extract::multipart::{MultipartError, MultipartRejection};
+use axum::extract::rejection::FormRejection;
+use axum::extract::{FromRequest, Multipart, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Form;
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "validator")]
+use validator::ValidateArgs;
+
+/// # `TextForm` extractor
+///
+/// Extracts `T` from an `application/x-www-form-urlencoded` body
+/// or a `multipart/form-data` body with text fields only.
+///
+/// See the [module documentation](crate::text_form) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextForm<T>(pub T);
+
+impl<T> Deref for TextForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for TextForm<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// `TextFormRejection` is returned when the `TextForm` extractor fails.
+#[derive(Debug)]
+pub enum TextFormRejection {
+    /// The body is not a valid urlencoded form, or it can't be deserialized
+    Form(FormRejection),
+    /// The multipart body can't be parsed
+    Multipart(MultipartRejection),
+    /// A field of the multipart body can't be read
+    MultipartField(MultipartError),
+    /// The multipart body contains a file field
+    FileField(String),
+}
+
+impl Display for TextFormRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextFormRejection::Form(e) => write!(f, "{e}"),
+            TextFormRejection::Multipart(e) => write!(f, "{e}"),
+            TextFormRejection::MultipartField(e) => write!(f, "{e}"),
+            TextFormRejection::FileField(name) => {
+                write!(f, "File field `{name}` is not supported")
+            }
+        }
+    }
+}
+
+impl Error for TextFormRejection {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TextFormRejection::Form(e) => Some(e),
+            TextFormRejection::Multipart(e) => Some(e),
+            TextFormRejection::MultipartField(e) => Some(e),
+            TextFormRejection::FileField(_) => None,
+        }
+    }
+}
+
+impl IntoResponse for TextFormRejection {
+    fn into_response(self) -> Response {
+        match self {
+            TextFormRejection::Form(e) => e.into_response(),
+            TextFormRejection::Multipart(e) => e.into_response(),
+            TextFormRejection::MultipartField(e) => e.into_response(),
+            TextFormRejection::FileField(_) => {
+                (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for TextForm<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = TextFormRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_multipart = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("multipart/form-data"));
+        if !is_multipart {
+            let Form(value) = Form::from_request(req, state)
+                .await
+                .map_err(TextFormRejection::Form)?;
+            return Ok(TextForm(value));
+        }
+
+        let method = req.method().clone();
+        let uri = req.uri().clone();
+        let mut multipart = Multipart::from_request(req, state)
+            .await
+            .map_err(TextFormRejection::Multipart)?;
+        let mut fields = Vec::new();
+        while let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(TextFormRejection::MultipartField)?
+        {
+            let name = field.name().unwrap_or_default().to_string();
+            if field.file_name().is_some() {
+                return Err(TextFormRejection::FileField(name));
+            }
+            let text = field
+                .text()
+                .await
+                .map_err(TextFormRejection::MultipartField)?;
+            fields.push((name, text));
+        }
+
+        // Re-encode the text fields so that they are deserialized and rejected like a urlencoded form
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .finish();
+        let mut req = Request::new(Body::from(body));
+        *req.method_mut() = method;
+        *req.uri_mut() = uri;
+        req.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        let Form(value) = Form::from_request(req, state)
+            .await
+            .map_err(TextFormRejection::Form)?;
+        Ok(TextForm(value))
+    }
+}
+
+impl<T> HasValidate for TextForm<T> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>> HasValidateArgs<'v> for TextForm<T> {
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify> crate::HasModify for TextForm<T> {
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T> crate::PayloadExtractor for TextForm<T> {
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload> crate::HasValidify for TextForm<T> {
+    type Validify = T;
+    type PayloadExtractor = TextForm<T::Payload>;
+    fn from_validify(v: Self::Validify) -> Self {
+        TextForm(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{ValidTest, ValidTestParameter};
+    use reqwest::RequestBuilder;
+    use serde::Serialize;
+
+    impl<T: ValidTestParameter + Serialize> ValidTest for TextForm<T> {
+        const ERROR_STATUS_CODE: StatusCode = StatusCode::UNPROCESSABLE_ENTITY;
+
+        fn set_valid_request(builder: RequestBuilder) -> RequestBuilder {
+            builder.form(T::valid())
+        }
+
+        fn set_error_request(builder: RequestBuilder) -> RequestBuilder {
+            builder.form(T::error())
+        }
+
+        fn set_invalid_request(builder: RequestBuilder) -> RequestBuilder {
+            builder.form(T::invalid())
+        }
+    }
+
+    fn multipart_request(fields: &[(&str, Option<&str>, &str)]) -> anyhow::Result<Request> {
+        const BOUNDARY: &str = "axum-valid-boundary";
+        let mut body = String::new();
+        for (name, file_name, value) in fields {
+            body.push_str(&format!("--{BOUNDARY}\r\n"));
+            match file_name {
+                Some(file_name) => body.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{name}\"; filename=\"{file_name}\"\r\n\r\n"
+                )),
+                None => body.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{name}\"\r\n\r\n"
+                )),
+            }
+            body.push_str(value);
+            body.push_str("\r\n");
+        }
+        body.push_str(&format!("--{BOUNDARY}--\r\n"));
+        Ok(Request::builder()
+            .method("POST")
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(body))?)
+    }
+
+    #[derive(Debug, serde::Deserialize, PartialEq)]
+    struct Data {
+        v0: i32,
+        v1: String,
+    }
+
+    #[tokio::test]
+    async fn text_only_multipart_like_urlencoded() -> anyhow::Result<()> {
+        let urlencoded = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, mime::APPLICATION_WWW_FORM_URLENCODED.as_ref())
+            .body(Body::from("v0=5&v1=a%26b"))?;
+        let TextForm(from_urlencoded) = TextForm::<Data>::from_request(urlencoded, &()).await?;
+
+        let multipart = multipart_request(&[("v0", None, "5"), ("v1", None, "a&b")])?;
+        let TextForm(from_multipart) = TextForm::<Data>::from_request(multipart, &()).await?;
+
+        assert_eq!(from_urlencoded, from_multipart);
+        assert_eq!(
+            from_multipart,
+            Data {
+                v0: 5,
+                v1: String::from("a&b")
+            }
+        );
+
+        let error = multipart_request(&[("v0", None, "not_i32"), ("v1", None, "a")])?;
+        assert!(matches!(
+            TextForm::<Data>::from_request(error, &()).await,
+            Err(TextFormRejection::Form(_))
+        ));
+
+        let file = multipart_request(&[("v0", None, "5"), ("v1", Some("v1.txt"), "a")])?;
+        let rejection = TextForm::<Data>::from_request(file, &())
+            .await
+            .expect_err("file fields are rejected");
+        assert!(matches!(rejection, TextFormRejection::FileField(ref name) if name == "v1"));
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn validate_text_only_multipart() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::Validate;
+
+        #[derive(serde::Deserialize, Validate)]
+        struct Parameters {
+            #[validate(range(min = 5, max = 10))]
+            v0: i32,
+        }
+
+        let valid = multipart_request(&[("v0", None, "5")])?;
+        assert!(Valid::<TextForm<Parameters>>::from_request(valid, &())
+            .await
+            .is_ok());
+
+        let invalid = multipart_request(&[("v0", None, "11")])?;
+        assert!(matches!(
+            Valid::<TextForm<Parameters>>::from_request(invalid, &()).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("v0")
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+//! # Signed timestamps against stale requests
+//!
+//! ## Feature
+//!
+//! Enable the `timestamped` feature to use `ValidTimestamped<T>`.
+//!
+//! A captured request signed with a shared key stays valid forever, unless it carries a timestamp covered
+//! by the signature. `ValidTimestamped<T>` reads a JSON body with a `timestamp` member, in seconds since
+//! the Unix epoch, and its HMAC-SHA256 signature in the `X-Signature` header (`SIGNATURE_HEADER`),
+//! computed over the raw body with the `SigningKey` provided by the state (see `signed`).
+//!
+//! The signature is checked first, so that a tampered body or timestamp is rejected as
+//! `TimestampedRejection::Tampered`. The timestamp is then checked against the `ReplayWindow` provided by
+//! the state: a timestamp older than `max_age` is rejected as `TimestampedRejection::Expired`, one further than
+//! `max_skew` in the future as `TimestampedRejection::Future`. Both are answered with `401 Unauthorized`,
+//! and distinct messages. Finally, the body is deserialized into `T`, which may include the `timestamp` field,
+//! and validated.
+//!
+//! This only limits how long a captured request can be replayed: no nonces are tracked, so a request
+//! replayed within the window is accepted again. Handlers that must not run twice still need their own
+//! idempotency keys.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::extract::FromRef;
+//! use axum::routing::post;
+//! use axum::Router;
+//! use axum_valid::signed::SigningKey;
+//! use axum_valid::timestamped::{ReplayWindow, ValidTimestamped};
+//! use serde::Deserialize;
+//! use std::time::Duration;
+//! use validator::Validate;
+//!
+//! #[derive(Clone, FromRef)]
+//! struct AppState {
+//!     key: SigningKey,
+//!     window: ReplayWindow,
+//! }
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Transfer {
+//!     #[validate(range(min = 1))]
+//!     pub amount: u64,
+//! }
+//!
+//! async fn handler(ValidTimestamped(transfer): ValidTimestamped<Transfer>) {
+//!     println!("transfer of {}", transfer.amount);
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let state = AppState {
+//!     key: SigningKey::new(b"shared secret between services")?,
+//!     window: ReplayWindow::new(Duration::from_secs(300)),
+//! };
+//! let router = Router::new()
+//!     .route("/transfers", post(handler))
+//!     .with_state(state);
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::signed::{Signature, SigningKey};
+use crate::validator::validate_redacted;
+use crate::ValidRejection;
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use validator::Validate;
+
+/// Name of the header carrying the hex-encoded signature of the body
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Name of the member of the body holding the timestamp, in seconds since the Unix epoch
+pub const TIMESTAMP: &str = "timestamp";
+
+/// Accepted age of signed timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayWindow {
+    /// Maximum age of a timestamp
+    pub max_age: Duration,
+    /// Maximum time a timestamp may be ahead of the server clock, to tolerate clock skew
+    pub max_skew: Duration,
+}
+
+impl ReplayWindow {
+    /// Create a window accepting timestamps at most `max_age` old, and 30 seconds ahead
+    pub fn new(max_age: Duration) -> Self {
+        ReplayWindow {
+            max_age,
+            max_skew: Duration::from_secs(30),
+        }
+    }
+
+    /// Set the maximum time a timestamp may be ahead of the server clock
+    pub fn max_skew(mut self, max_skew: Duration) -> Self {
+        self.max_skew = max_skew;
+        self
+    }
+
+    /// Check a timestamp, in seconds since the Unix epoch, against the window at `now`
+    pub fn check(&self, timestamp: u64, now: SystemTime) -> Result<(), TimestampedRejection> {
+        let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        if timestamp > now.saturating_add(self.max_skew.as_secs()) {
+            return Err(TimestampedRejection::Future {
+                ahead: timestamp - now,
+            });
+        }
+        let age = now.saturating_sub(timestamp);
+        if age > self.max_age.as_secs() {
+            return Err(TimestampedRejection::Expired { age });
+        }
+        Ok(())
+    }
+}
+
+/// # `ValidTimestamped` data extractor
+///
+/// See the [module documentation](crate::timestamped) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidTimestamped<T>(pub T);
+
+impl<T> Deref for ValidTimestamped<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ValidTimestamped<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> ValidTimestamped<T> {
+    /// Consumes the `ValidTimestamped` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidTimestamped`
+#[derive(Debug)]
+pub enum TimestampedRejection {
+    /// The body can't be read
+    Bytes(BytesRejection),
+    /// The signature header is missing or isn't 64 hexadecimal digits
+    MissingSignature,
+    /// The signature doesn't match the body
+    Tampered,
+    /// The body isn't a JSON object with a `timestamp` in seconds since the Unix epoch
+    MissingTimestamp,
+    /// The timestamp is older than the window
+    Expired {
+        /// Age of the timestamp in seconds
+        age: u64,
+    },
+    /// The timestamp is ahead of the server clock by more than the window allows
+    Future {
+        /// Seconds the timestamp is ahead of the server clock
+        ahead: u64,
+    },
+    /// The data can't be deserialized
+    Deserialize(serde_json::Error),
+}
+
+impl Display for TimestampedRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampedRejection::Bytes(e) => write!(f, "{e}"),
+            TimestampedRejection::MissingSignature => {
+                write!(f, "Missing or malformed `{SIGNATURE_HEADER}` header")
+            }
+            TimestampedRejection::Tampered => {
+                write!(f, "Body doesn't match the `{SIGNATURE_HEADER}` header")
+            }
+            TimestampedRejection::MissingTimestamp => {
+                write!(f, "Missing `{TIMESTAMP}` in seconds since the Unix epoch")
+            }
+            TimestampedRejection::Expired { age } => {
+                write!(f, "Request expired, its timestamp is {age} seconds old")
+            }
+            TimestampedRejection::Future { ahead } => {
+                write!(f, "Request timestamp is {ahead} seconds in the future")
+            }
+            TimestampedRejection::Deserialize(e) => {
+                write!(
+                    f,
+                    "Failed to deserialize the JSON body into the target type: {e}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TimestampedRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TimestampedRejection::Bytes(e) => Some(e),
+            TimestampedRejection::Deserialize(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for TimestampedRejection {
+    fn into_response(self) -> Response {
+        let status = match self {
+            TimestampedRejection::Bytes(e) => return e.into_response(),
+            TimestampedRejection::MissingSignature
+            | TimestampedRejection::Tampered
+            | TimestampedRejection::Expired { .. }
+            | TimestampedRejection::Future { .. } => StatusCode::UNAUTHORIZED,
+            TimestampedRejection::MissingTimestamp => StatusCode::BAD_REQUEST,
+            // Same status code as `JsonDataError`
+            TimestampedRejection::Deserialize(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidTimestamped<T>
+where
+    State: Send + Sync,
+    SigningKey: FromRef<State>,
+    ReplayWindow: FromRef<State>,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ValidRejection<TimestampedRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let key = SigningKey::from_ref(state);
+        let window = ReplayWindow::from_ref(state);
+        let signature = req
+            .headers()
+            .get(SIGNATURE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Signature>().ok())
+            .ok_or(ValidRejection::Inner(
+                TimestampedRejection::MissingSignature,
+            ))?;
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(TimestampedRejection::Bytes(e)))?;
+        if !key.verify(&body, &signature) {
+            return Err(ValidRejection::Inner(TimestampedRejection::Tampered));
+        }
+        let value = serde_json::from_slice::<Value>(&body)
+            .map_err(|e| ValidRejection::Inner(TimestampedRejection::Deserialize(e)))?;
+        let timestamp =
+            value
+                .get(TIMESTAMP)
+                .and_then(Value::as_u64)
+                .ok_or(ValidRejection::Inner(
+                    TimestampedRejection::MissingTimestamp,
+                ))?;
+        window
+            .check(timestamp, SystemTime::now())
+            .map_err(ValidRejection::Inner)?;
+        let data = serde_json::from_value::<T>(value)
+            .map_err(|e| ValidRejection::Inner(TimestampedRejection::Deserialize(e)))?;
+        validate_redacted(&data).map_err(ValidRejection::Valid)?;
+        Ok(ValidTimestamped(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[derive(Clone, FromRef)]
+    struct AppState {
+        key: SigningKey,
+        window: ReplayWindow,
+    }
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Transfer {
+        #[validate(range(min = 1))]
+        amount: u64,
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    async fn extract(
+        body: String,
+        signature: Signature,
+    ) -> Result<ValidTimestamped<Transfer>, ValidRejection<TimestampedRejection>> {
+        let state = AppState {
+            key: SigningKey::new(b"0123456789abcdef0123456789abcdef").expect("valid key"),
+            window: ReplayWindow::new(Duration::from_secs(300)),
+        };
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature.to_string())
+            .body(Body::from(body))
+            .expect("valid request");
+        ValidTimestamped::<Transfer>::from_request(request, &state).await
+    }
+
+    fn signed(body: &str) -> Signature {
+        SigningKey::new(b"0123456789abcdef0123456789abcdef")
+            .expect("valid key")
+            .sign(body.as_bytes())
+    }
+
+    #[tokio::test]
+    async fn expired_tampered_and_valid() -> anyhow::Result<()> {
+        let body = format!(r#"{{"amount": 10, "timestamp": {}}}"#, now() - 10);
+        let ValidTimestamped(transfer) = extract(body.clone(), signed(&body))
+            .await
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(transfer.amount, 10);
+
+        // the amount is changed after signing
+        let tampered = body.replace("10,", "1000,");
+        assert!(matches!(
+            extract(tampered, signed(&body)).await,
+            Err(ValidRejection::Inner(TimestampedRejection::Tampered))
+        ));
+
+        let expired = format!(r#"{{"amount": 10, "timestamp": {}}}"#, now() - 600);
+        let rejection = extract(expired.clone(), signed(&expired))
+            .await
+            .expect_err("expired");
+        assert!(matches!(
+            rejection,
+            ValidRejection::Inner(TimestampedRejection::Expired { age }) if age >= 600
+        ));
+        let ValidRejection::Inner(rejection) = rejection else {
+            unreachable!()
+        };
+        assert_eq!(rejection.into_response().status(), StatusCode::UNAUTHORIZED);
+
+        let future = format!(r#"{{"amount": 10, "timestamp": {}}}"#, now() + 600);
+        assert!(matches!(
+            extract(future.clone(), signed(&future)).await,
+            Err(ValidRejection::Inner(TimestampedRejection::Future { .. }))
+        ));
+
+        let invalid = format!(r#"{{"amount": 0, "timestamp": {}}}"#, now());
+        assert!(matches!(
+            extract(invalid.clone(), signed(&invalid)).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("amount")
+        ));
+        Ok(())
+    }
+}
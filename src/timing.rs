@@ -0,0 +1,263 @@
+//! # Validation timing
+//!
+//! ## Feature
+//!
+//! Enable the `timing` feature to use `ValidTimed<E>`.
+//!
+//! `ValidTimed<E>` validates the inner data like `Valid<E>`, and measures how long the validation took,
+//! excluding the inner extractor. The measurement is returned as a `ValidationTiming`,
+//! which can be added to the response as a `Server-Timing` header (e.g. `Server-Timing: validation;dur=0.125`)
+//! to identify expensive validators in production.
+//!
+//! When the validation fails, the `TimedRejection` adds the header to the error response,
+//! so that the duration of the validation phase is reported for both successful and failed requests.
+//! Measuring only takes two readings of a monotonic clock, the header value is formatted when the response is built.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::timing::{ValidTimed, ValidationTiming};
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Parameter {
+//!     #[validate(range(min = 5, max = 10))]
+//!     pub v0: i32,
+//! }
+//!
+//! async fn handler(ValidTimed(Json(parameter), timing): ValidTimed<Json<Parameter>>) -> (ValidationTiming, String) {
+//!     (timing, parameter.v0.to_string())
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/json", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::{HasValidate, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::header::HeaderValue;
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
+use validator::{Validate, ValidationErrors};
+
+/// Name of the metric in the `Server-Timing` header
+pub const SERVER_TIMING_METRIC: &str = "validation";
+
+/// Duration of the validation step
+///
+/// As a response part, it appends a `Server-Timing` header with the duration in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ValidationTiming(pub Duration);
+
+impl ValidationTiming {
+    /// The `Server-Timing` header value, e.g. `validation;dur=0.125`
+    pub fn server_timing(&self) -> String {
+        format!(
+            "{SERVER_TIMING_METRIC};dur={}",
+            self.0.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+impl IntoResponseParts for ValidationTiming {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        if let Ok(value) = HeaderValue::from_str(&self.server_timing()) {
+            res.headers_mut().append("server-timing", value);
+        }
+        Ok(res)
+    }
+}
+
+/// # `ValidTimed` data extractor
+///
+/// See the [module documentation](crate::timing) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidTimed<E>(pub E, pub ValidationTiming);
+
+impl<E> Deref for ValidTimed<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidTimed<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidTimed<E> {
+    /// Consumes the `ValidTimed` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+
+    /// The duration of the validation
+    pub fn timing(&self) -> ValidationTiming {
+        self.1
+    }
+}
+
+/// `TimedRejection` is returned when the `ValidTimed` extractor fails.
+#[derive(Debug)]
+pub enum TimedRejection<E> {
+    /// The data is invalid, with the duration of its validation
+    Valid(ValidationErrors, ValidationTiming),
+    /// The inner extractor failed
+    Inner(E),
+}
+
+impl<E: Display> Display for TimedRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimedRejection::Valid(errors, _) => write!(f, "{errors}"),
+            TimedRejection::Inner(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for TimedRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TimedRejection::Valid(errors, _) => Some(errors),
+            TimedRejection::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for TimedRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            TimedRejection::Valid(errors, timing) => {
+                (timing, ValidRejection::<E>::Valid(errors)).into_response()
+            }
+            TimedRejection::Inner(error) => error.into_response(),
+        }
+    }
+}
+
+fn validate_timed<T: Validate, E>(data: &T) -> Result<ValidationTiming, TimedRejection<E>> {
+    let start = Instant::now();
+    let result = validate_redacted(data);
+    let timing = ValidationTiming(start.elapsed());
+    result
+        .map(|_| timing)
+        .map_err(|errors| TimedRejection::Valid(errors, timing))
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidTimed<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequest<State>,
+    Extractor::Validate: Validate,
+{
+    type Rejection = TimedRejection<<Extractor as FromRequest<State>>::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request(req, state)
+            .await
+            .map_err(TimedRejection::Inner)?;
+        let timing = validate_timed(inner.get_validate())?;
+        Ok(ValidTimed(inner, timing))
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequestParts<State> for ValidTimed<Extractor>
+where
+    State: Send + Sync,
+    Extractor: HasValidate + FromRequestParts<State>,
+    Extractor::Validate: Validate,
+{
+    type Rejection = TimedRejection<<Extractor as FromRequestParts<State>>::Rejection>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let inner = Extractor::from_request_parts(parts, state)
+            .await
+            .map_err(TimedRejection::Inner)?;
+        let timing = validate_timed(inner.get_validate())?;
+        Ok(ValidTimed(inner, timing))
+    }
+}
+
+#[cfg(all(test, feature = "query"))]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use axum::http::Request;
+    use axum::response::IntoResponse;
+    use validator::ValidationError;
+
+    const DELAY: Duration = Duration::from_millis(2);
+
+    fn slow_rule(_: &i32) -> Result<(), ValidationError> {
+        std::thread::sleep(DELAY);
+        Ok(())
+    }
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Parameters {
+        #[validate(range(min = 5, max = 10), custom(function = "slow_rule"))]
+        v0: i32,
+    }
+
+    #[tokio::test]
+    async fn timing_is_recorded() -> anyhow::Result<()> {
+        let (mut parts, _) = Request::builder().uri("/?v0=6").body(())?.into_parts();
+        let ValidTimed(Query(parameters), timing) =
+            ValidTimed::<Query<Parameters>>::from_request_parts(&mut parts, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.v0, 6);
+        assert!(timing.0 >= DELAY);
+
+        let response = (timing, "ok").into_response();
+        let header = response.headers()["server-timing"].to_str()?;
+        let duration = header
+            .strip_prefix("validation;dur=")
+            .expect("validation metric")
+            .parse::<f64>()?;
+        assert!(duration > 0.0);
+
+        // failed validations are timed too
+        let (mut parts, _) = Request::builder().uri("/?v0=11").body(())?.into_parts();
+        let Err(rejection @ TimedRejection::Valid(_, timing)) =
+            ValidTimed::<Query<Parameters>>::from_request_parts(&mut parts, &()).await
+        else {
+            panic!("expected validation errors");
+        };
+        assert!(timing.0 >= DELAY);
+        let response = rejection.into_response();
+        assert_eq!(response.status(), crate::VALIDATION_ERROR_STATUS);
+        let header = response.headers()["server-timing"].to_str()?;
+        let duration = header
+            .strip_prefix("validation;dur=")
+            .expect("validation metric")
+            .parse::<f64>()?;
+        assert!(duration >= DELAY.as_secs_f64() * 1000.0);
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+//! # Validation errors in response trailers
+//!
+//! ## Feature
+//!
+//! Enable the `trailer` feature to use `ErrorTrailer`.
+//!
+//! Handlers streaming a response, e.g. items validated one by one while they are exported,
+//! may find invalid data after the status code and the headers were sent, so the errors can't be
+//! reported with `VALIDATION_ERROR_STATUS` anymore. Over HTTP/2, such errors can be delivered in the trailers
+//! of the response, sent after its body.
+//!
+//! An `ErrorTrailer` collects the errors reported while the body is produced, with `report` or `report_at`.
+//! `ErrorTrailer::response` wraps the body of a response, announces the trailer with a `Trailer` header,
+//! and, once the body ends, sends the summary of the errors as the `x-validation-errors` trailer,
+//! e.g. `2 validation errors: items[1].name (length), items[4].price (range)`.
+//! No trailer is sent if no error was reported.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::body::Body;
+//! use axum::response::Response;
+//! use axum::routing::get;
+//! use axum::Router;
+//! use axum_valid::error_body::FieldPath;
+//! use axum_valid::trailer::ErrorTrailer;
+//! use futures_util::StreamExt;
+//! use serde::Serialize;
+//! use std::convert::Infallible;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Serialize)]
+//! pub struct Item {
+//!     #[validate(length(min = 1, max = 32))]
+//!     pub name: String,
+//! }
+//!
+//! async fn handler() -> Response {
+//!     let trailer = ErrorTrailer::new();
+//!     let items = vec![Item { name: "a".to_owned() }, Item { name: String::new() }];
+//!     let reporter = trailer.clone();
+//!     let stream = futures_util::stream::iter(items.into_iter().enumerate()).map(move |(i, item)| {
+//!         if let Err(errors) = item.validate() {
+//!             reporter.report_at(FieldPath::new().key("items").index(i), errors);
+//!         }
+//!         Ok::<_, Infallible>(format!("{}\n", item.name))
+//!     });
+//!     trailer.response(Body::from_stream(stream))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/export", get(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::{FieldError, FieldPath, PathSegment};
+use crate::ErrorBody;
+use axum::body::{Body, Bytes, HttpBody};
+use axum::http::header::TRAILER;
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use http_body::{Frame, SizeHint};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+
+/// Name of the trailer holding the summary of the validation errors
+pub const VALIDATION_ERRORS_TRAILER: HeaderName = HeaderName::from_static("x-validation-errors");
+
+/// Validation errors reported while a response body is produced
+#[derive(Debug, Clone, Default)]
+pub struct ErrorTrailer(Arc<Mutex<Vec<FieldError>>>);
+
+impl ErrorTrailer {
+    /// Create a trailer without errors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report validation errors
+    pub fn report(&self, errors: impl Into<ErrorBody>) {
+        self.report_at(FieldPath::new(), errors);
+    }
+
+    /// Report validation errors of a part of the response, prefixing their paths with `prefix`, e.g. `items[3]`
+    pub fn report_at(&self, prefix: FieldPath, errors: impl Into<ErrorBody>) {
+        let errors = errors.into().errors.into_iter().map(|mut error| {
+            error.path = error
+                .path
+                .segments()
+                .iter()
+                .fold(prefix.clone(), |path, segment| match segment {
+                    PathSegment::Key(key) => path.key(key.clone()),
+                    PathSegment::Index(index) => path.index(*index),
+                });
+            error
+        });
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(errors);
+    }
+
+    /// Get the errors reported so far, sorted by their paths
+    pub fn errors(&self) -> ErrorBody {
+        ErrorBody::new(self.0.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    /// Summary of the errors reported so far, e.g. `1 validation error: name (length)`, or `None` without errors
+    pub fn summary(&self) -> Option<String> {
+        let body = self.errors();
+        if body.errors.is_empty() {
+            return None;
+        }
+        let fields = body
+            .errors
+            .iter()
+            .map(|error| match &error.code {
+                Some(code) => format!("{} ({code})", error.path),
+                None => error.path.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let count = body.errors.len();
+        let plural = if count == 1 { "" } else { "s" };
+        Some(format!("{count} validation error{plural}: {fields}"))
+    }
+
+    /// Wrap a body, sending the summary of the errors reported while it's produced as a trailer
+    pub fn body(&self, body: Body) -> Body {
+        Body::new(TrailerBody {
+            inner: body,
+            trailer: self.clone(),
+            trailers: None,
+            done: false,
+        })
+    }
+
+    /// Build a response with the wrapped body, announcing the trailer with a `Trailer` header
+    pub fn response(&self, body: Body) -> Response {
+        let mut response = self.body(body).into_response();
+        response
+            .headers_mut()
+            .insert(TRAILER, HeaderValue::from_static("x-validation-errors"));
+        response
+    }
+
+    fn trailers(&self, trailers: Option<HeaderMap>) -> Option<HeaderMap> {
+        let Some(summary) = self.summary() else {
+            return trailers;
+        };
+        // header values are limited to visible ASCII
+        let summary = summary
+            .chars()
+            .map(|c| {
+                if c == ' ' || c.is_ascii_graphic() {
+                    c
+                } else {
+                    '?'
+                }
+            })
+            .collect::<String>();
+        let mut trailers = trailers.unwrap_or_default();
+        if let Ok(value) = HeaderValue::from_str(&summary) {
+            trailers.insert(VALIDATION_ERRORS_TRAILER, value);
+        }
+        Some(trailers)
+    }
+}
+
+/// Body sending the summary of the reported errors as a trailer
+struct TrailerBody {
+    inner: Body,
+    trailer: ErrorTrailer,
+    /// Trailers of the inner body, merged with the summary
+    trailers: Option<HeaderMap>,
+    done: bool,
+}
+
+impl HttpBody for TrailerBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        if this.done {
+            return Poll::Ready(None);
+        }
+        loop {
+            match ready!(Pin::new(&mut this.inner).poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_trailers() {
+                    Ok(trailers) => this
+                        .trailers
+                        .get_or_insert_with(HeaderMap::new)
+                        .extend(trailers),
+                    Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                },
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => {
+                    this.done = true;
+                    let trailers = this.trailer.trailers(this.trailers.take());
+                    return Poll::Ready(trailers.map(|trailers| Ok(Frame::trailers(trailers))));
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(all(test, feature = "validator"))]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use std::convert::Infallible;
+    use validator::Validate;
+
+    #[derive(Debug, Validate)]
+    struct Item {
+        #[validate(length(min = 1, max = 5))]
+        name: String,
+    }
+
+    async fn frames(mut body: Body) -> anyhow::Result<(String, Option<HeaderMap>)> {
+        let mut data = String::new();
+        let mut trailers = None;
+        while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await
+        {
+            match frame?.into_data() {
+                Ok(chunk) => data.push_str(std::str::from_utf8(&chunk)?),
+                Err(frame) => trailers = frame.into_trailers().ok(),
+            }
+        }
+        Ok((data, trailers))
+    }
+
+    fn export(names: &[&str]) -> (ErrorTrailer, Response) {
+        let items = names
+            .iter()
+            .map(|name| Item {
+                name: name.to_string(),
+            })
+            .collect::<Vec<_>>();
+        let trailer = ErrorTrailer::new();
+        let reporter = trailer.clone();
+        // items are validated while the body is streamed, after the response has begun
+        let stream =
+            futures_util::stream::iter(items.into_iter().enumerate()).map(move |(i, item)| {
+                if let Err(errors) = item.validate() {
+                    reporter.report_at(FieldPath::new().key("items").index(i), errors);
+                }
+                Ok::<_, Infallible>(format!("{}\n", item.name))
+            });
+        let response = trailer.response(Body::from_stream(stream));
+        (trailer, response)
+    }
+
+    #[tokio::test]
+    async fn trailer_contains_error_summary() -> anyhow::Result<()> {
+        let (trailer, response) = export(&["a", "", "bb", "too long"]);
+        assert!(trailer.summary().is_none());
+        assert_eq!(response.headers()[TRAILER], "x-validation-errors");
+
+        let (data, trailers) = frames(response.into_body()).await?;
+        assert_eq!(data, "a\n\nbb\ntoo long\n");
+        let trailers = trailers.expect("trailers");
+        assert_eq!(
+            trailers[VALIDATION_ERRORS_TRAILER],
+            "2 validation errors: items[1].name (length), items[3].name (length)"
+        );
+        assert_eq!(trailer.errors().errors.len(), 2);
+
+        // no trailer without errors
+        let (_, response) = export(&["a", "bb"]);
+        let (data, trailers) = frames(response.into_body()).await?;
+        assert_eq!(data, "a\nbb\n");
+        assert!(trailers.is_none());
+        Ok(())
+    }
+}
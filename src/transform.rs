@@ -0,0 +1,309 @@
+//! # Pre-validation transform pipelines
+//!
+//! ## Feature
+//!
+//! Enable the `transform` feature to use `ValidTransformed<E>`.
+//!
+//! Input often needs to be normalized before it's validated, e.g. an email address should be trimmed
+//! and lowercased before the `email` rule is applied. A `Pipeline` is an ordered list of string transforms,
+//! built with `trim`, `lowercase`, `uppercase` or any function with `then`.
+//! `Transforms` maps top-level field names to pipelines.
+//!
+//! `ValidTransformed<E>` applies the `Transforms` provided by the state to the inner data,
+//! then validates it like `Valid<E>`, so the handler receives the transformed data.
+//! Pipelines are applied to string fields and to the strings of array fields, other fields are left unchanged.
+//!
+//! The data is transformed via a `serde_json::Value` round-trip, so its type must implement `Serialize` and `Deserialize`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::{Json, Router};
+//! use axum_valid::transform::{Pipeline, Transforms, ValidTransformed};
+//! use serde::{Deserialize, Serialize};
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Serialize, Deserialize)]
+//! pub struct Subscription {
+//!     #[validate(email)]
+//!     pub email: String,
+//! }
+//!
+//! async fn handler(ValidTransformed(Json(subscription)): ValidTransformed<Json<Subscription>>) {
+//!     assert_eq!(subscription.email, subscription.email.trim().to_lowercase());
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let transforms = Transforms::new().field("email", Pipeline::new().trim().lowercase());
+//! let router = Router::new()
+//!     .route("/subscribe", post(handler))
+//!     .with_state(transforms);
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::{HasValidate, ValidRejection};
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use validator::Validate;
+
+type Step = Arc<dyn Fn(String) -> String + Send + Sync>;
+
+/// Ordered list of string transforms
+#[derive(Clone, Default)]
+pub struct Pipeline(Vec<Step>);
+
+impl Debug for Pipeline {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("steps", &self.0.len())
+            .finish()
+    }
+}
+
+impl Pipeline {
+    /// Create an empty pipeline, which leaves strings unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a transform to the pipeline
+    pub fn then<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(String) -> String + Send + Sync + 'static,
+    {
+        self.0.push(Arc::new(transform));
+        self
+    }
+
+    /// Append a transform removing leading and trailing whitespace
+    pub fn trim(self) -> Self {
+        self.then(|value| match value.trim() {
+            trimmed if trimmed.len() == value.len() => value,
+            trimmed => trimmed.to_owned(),
+        })
+    }
+
+    /// Append a transform converting to lowercase
+    pub fn lowercase(self) -> Self {
+        self.then(|value| value.to_lowercase())
+    }
+
+    /// Append a transform converting to uppercase
+    pub fn uppercase(self) -> Self {
+        self.then(|value| value.to_uppercase())
+    }
+
+    /// Apply the transforms to `value`, in order
+    pub fn apply(&self, value: String) -> String {
+        self.0.iter().fold(value, |value, step| step(value))
+    }
+}
+
+/// Pipelines applied by `ValidTransformed`, indexed by top-level field names
+#[derive(Debug, Clone, Default)]
+pub struct Transforms(Arc<HashMap<String, Pipeline>>);
+
+impl Transforms {
+    /// Create an empty mapping, where no field is transformed
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Transform a field with `pipeline`
+    pub fn field(mut self, name: impl Into<String>, pipeline: Pipeline) -> Self {
+        Arc::make_mut(&mut self.0).insert(name.into(), pipeline);
+        self
+    }
+
+    /// Get the pipeline of a field
+    pub fn get(&self, name: &str) -> Option<&Pipeline> {
+        self.0.get(name)
+    }
+
+    /// Apply the pipelines to the fields of `value`
+    pub fn apply<T: Serialize + DeserializeOwned>(&self, value: &T) -> serde_json::Result<T> {
+        let mut json = serde_json::to_value(value)?;
+        if let Value::Object(map) = &mut json {
+            for (name, value) in map.iter_mut() {
+                if let Some(pipeline) = self.get(name) {
+                    transform(value, pipeline);
+                }
+            }
+        }
+        serde_json::from_value(json)
+    }
+}
+
+fn transform(value: &mut Value, pipeline: &Pipeline) {
+    match value {
+        Value::String(string) => *string = pipeline.apply(std::mem::take(string)),
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| transform(value, pipeline)),
+        _ => {}
+    }
+}
+
+/// # `ValidTransformed` data extractor
+///
+/// See the [module documentation](crate::transform) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidTransformed<E>(pub E);
+
+impl<E> Deref for ValidTransformed<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidTransformed<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidTransformed<E> {
+    /// Consumes the `ValidTransformed` and returns the transformed and validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidTransformed`
+#[derive(Debug)]
+pub enum TransformRejection<E> {
+    /// The inner extractor failed
+    Inner(E),
+    /// The transformed data can't be deserialized
+    Transform(serde_json::Error),
+}
+
+impl<E: Display> Display for TransformRejection<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransformRejection::Inner(e) => write!(f, "{e}"),
+            TransformRejection::Transform(e) => write!(f, "Failed to transform fields: {e}"),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for TransformRejection<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TransformRejection::Inner(e) => Some(e),
+            TransformRejection::Transform(e) => Some(e),
+        }
+    }
+}
+
+impl<E: IntoResponse> IntoResponse for TransformRejection<E> {
+    fn into_response(self) -> Response {
+        match self {
+            TransformRejection::Inner(e) => e.into_response(),
+            TransformRejection::Transform(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to transform fields: {e}"),
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidTransformed<Extractor>
+where
+    State: Send + Sync,
+    Transforms: FromRef<State>,
+    Extractor: HasValidate + FromRequest<State> + DerefMut<Target = Extractor::Validate>,
+    Extractor::Validate: Validate + Serialize + DeserializeOwned,
+{
+    type Rejection =
+        ValidRejection<TransformRejection<<Extractor as FromRequest<State>>::Rejection>>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let transforms = Transforms::from_ref(state);
+        let mut inner = Extractor::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(TransformRejection::Inner(e)))?;
+        let transformed = transforms
+            .apply(inner.get_validate())
+            .map_err(|e| ValidRejection::Inner(TransformRejection::Transform(e)))?;
+        *inner = transformed;
+        validate_redacted(inner.get_validate())?;
+        Ok(ValidTransformed(inner))
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+    use serde::Deserialize;
+
+    #[derive(Debug, Validate, Serialize, Deserialize)]
+    struct Subscription {
+        #[validate(email)]
+        email: String,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    fn request(body: &'static str) -> anyhow::Result<Request> {
+        Ok(Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?)
+    }
+
+    #[test]
+    fn apply_in_order() {
+        let pipeline = Pipeline::new().trim().then(|value| format!("<{value}>"));
+        assert_eq!(pipeline.apply(String::from("  a ")), "<a>");
+        let pipeline = Pipeline::new().then(|value| format!("<{value}>")).trim();
+        assert_eq!(pipeline.apply(String::from("  a ")), "<  a >");
+    }
+
+    #[tokio::test]
+    async fn transform_before_validation() -> anyhow::Result<()> {
+        let body = r#"{"email": "  Gteng@Example.COM ", "tags": [" Rust "]}"#;
+        assert!(matches!(
+            ValidTransformed::<Json<Subscription>>::from_request(request(body)?, &Transforms::new())
+                .await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["email"][0].code == "email"
+        ));
+
+        let transforms = Transforms::new()
+            .field("email", Pipeline::new().trim().lowercase())
+            .field("tags", Pipeline::new().trim());
+        let ValidTransformed(Json(subscription)) =
+            ValidTransformed::<Json<Subscription>>::from_request(request(body)?, &transforms)
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(subscription.email, "gteng@example.com");
+        assert_eq!(subscription.tags, ["Rust"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+//! # Support for `TypedQuery<T>`
+//!
+//! ## Feature
+//!
+//! Enable the `typed_query` feature to use `Valid<TypedQuery<T>>`.
+//!
+//! `axum::extract::Query<T>` reports the values that can't be converted to the type of their field
+//! without the field nor the type, e.g. `invalid digit found in string` for `?age=abc`.
+//! `TypedQuery<T>` works like `Query<T>`, but names both: the value of `age` is rejected with
+//! ``age: invalid type: string "abc", expected i32``, and the rejection carries the error
+//! as an `ErrorBody`, converted like the deserialization errors of `i18n::FullyLocalized`:
+//!
+//! ```json
+//! {"errors": [{"path": "age", "code": "invalid_type", "message": "invalid type: string \"abc\", expected i32",
+//!              "params": {"unexpected": "string \"abc\"", "expected": "i32"}}]}
+//! ```
+//!
+//! The expected type is named for integers, floats, `bool` and `char`. Other errors, such as missing fields
+//! or unknown enum variants, are prefixed with the name of their field too. Like `Query<T>`,
+//! `TypedQuery<T>` supports flat structs of scalar fields, and rejections are returned with `400 Bad Request`.
+//!
+//! ## Usage
+//!
+//! 1. Implement `Deserialize` and `Validate` for your data type `T`.
+//! 2. In your handler function, use `Valid<TypedQuery<T>>` as some parameter's type.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::get;
+//!     use axum::Router;
+//!     use axum_valid::typed_query::TypedQuery;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/typed_query", get(handler))
+//!     }
+//!
+//!     async fn handler(Valid(TypedQuery(parameter)): Valid<TypedQuery<Parameter>>) {
+//!         assert!(parameter.validate().is_ok());
+//!         // Support automatic dereferencing
+//!         println!("v0 = {}, v1 = {}", parameter.v0, parameter.v1);
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(range(min = 5, max = 10))]
+//!         pub v0: i32,
+//!         #[validate(length(min = 1, max = 10))]
+//!         pub v1: String,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::i18n::deserialize_error;
+#[cfg(feature = "validator")]
+use crate::HasValidateArgs;
+use crate::{ErrorBody, HasValidate};
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::de::value::{Error, MapDeserializer};
+use serde::de::{DeserializeOwned, Error as _, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+#[cfg(feature = "validator")]
+use validator::ValidateArgs;
+
+/// # `TypedQuery` extractor
+///
+/// Extracts `T` from the query string, naming the field and the expected type of invalid values.
+///
+/// See the [module documentation](crate::typed_query) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TypedQuery<T>(pub T);
+
+impl<T> Deref for TypedQuery<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for TypedQuery<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// `TypedQueryRejection` is returned when the `TypedQuery` extractor fails.
+#[derive(Debug, Clone)]
+pub struct TypedQueryRejection {
+    /// The message of the deserialization error
+    pub message: String,
+    /// The deserialization error, as a structured error
+    pub body: ErrorBody,
+}
+
+impl Display for TypedQueryRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to deserialize query string: {}", self.message)
+    }
+}
+
+impl std::error::Error for TypedQueryRejection {}
+
+impl IntoResponse for TypedQueryRejection {
+    fn into_response(self) -> Response {
+        let mut response = self.body.into_response();
+        // Same status code as `FailedToDeserializeQueryString`
+        *response.status_mut() = StatusCode::BAD_REQUEST;
+        response
+    }
+}
+
+/// Value of a query string parameter, parsed according to the type requested by its field
+struct Part {
+    key: String,
+    value: String,
+}
+
+impl Part {
+    /// Prefix the errors of the field with its name, so that they are reported with its path
+    fn keyed<T>(key: &str, result: Result<T, Error>) -> Result<T, Error> {
+        result.map_err(|e| Error::custom(format_args!("{key}: {e}")))
+    }
+
+    fn invalid_type(&self, expected: &str) -> Error {
+        Error::custom(format_args!(
+            "{}: invalid type: string {:?}, expected {expected}",
+            self.key, self.value
+        ))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Part {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident: $ty:ty,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.value.parse::<$ty>() {
+                    Ok(value) => Self::keyed(&self.key, visitor.$visit(value)),
+                    Err(_) => Err(self.invalid_type(stringify!($ty))),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> serde::Deserializer<'de> for Part {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        Self::keyed(&self.key, visitor.visit_string(self.value))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Self::keyed(
+            &self.key,
+            visitor.visit_enum(IntoDeserializer::<Error>::into_deserializer(self.value)),
+        )
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool: bool,
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_i128 => visit_i128: i128,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_u128 => visit_u128: u128,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+        deserialize_char => visit_char: char,
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Deserialize `T` from a query string, with the errors of the fields prefixed by their names
+fn from_query<T: DeserializeOwned>(query: &str) -> Result<T, Error> {
+    let pairs = form_urlencoded::parse(query.as_bytes()).map(|(key, value)| {
+        let key = key.into_owned();
+        let part = Part {
+            key: key.clone(),
+            value: value.into_owned(),
+        };
+        (key, part)
+    });
+    T::deserialize(MapDeserializer::new(pairs))
+}
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for TypedQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = TypedQueryRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        from_query(parts.uri.query().unwrap_or_default())
+            .map(TypedQuery)
+            .map_err(|e| {
+                let message = e.to_string();
+                let body = ErrorBody::new(vec![deserialize_error(&message)]);
+                TypedQueryRejection { message, body }
+            })
+    }
+}
+
+impl<T> HasValidate for TypedQuery<T> {
+    type Validate = T;
+    fn get_validate(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validator")]
+impl<'v, T: ValidateArgs<'v>> HasValidateArgs<'v> for TypedQuery<T> {
+    type ValidateArgs = T;
+    fn get_validate_args(&self) -> &Self::ValidateArgs {
+        &self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Modify> crate::HasModify for TypedQuery<T> {
+    type Modify = T;
+
+    fn get_modify(&mut self) -> &mut Self::Modify {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T> crate::PayloadExtractor for TypedQuery<T> {
+    type Payload = T;
+
+    fn get_payload(self) -> Self::Payload {
+        self.0
+    }
+}
+
+#[cfg(feature = "validify")]
+impl<T: validify::Validify + validify::ValidifyPayload> crate::HasValidify for TypedQuery<T> {
+    type Validify = T;
+    type PayloadExtractor = TypedQuery<T::Payload>;
+    fn from_validify(v: Self::Validify) -> Self {
+        TypedQuery(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    enum Order {
+        Asc,
+        Desc,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Parameters {
+        age: i32,
+        ratio: Option<f64>,
+        active: Option<bool>,
+        name: Option<String>,
+        order: Option<Order>,
+    }
+
+    async fn extract(uri: &str) -> anyhow::Result<Result<Parameters, TypedQueryRejection>> {
+        let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+        Ok(
+            TypedQuery::<Parameters>::from_request_parts(&mut parts, &())
+                .await
+                .map(|TypedQuery(parameters)| parameters),
+        )
+    }
+
+    #[tokio::test]
+    async fn coercion_error_names_field_and_type() -> anyhow::Result<()> {
+        let parameters = extract("/?age=30&ratio=0.5&active=true&name=a%20b&order=desc")
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(
+            parameters,
+            Parameters {
+                age: 30,
+                ratio: Some(0.5),
+                active: Some(true),
+                name: Some("a b".to_owned()),
+                order: Some(Order::Desc),
+            }
+        );
+
+        let rejection = extract("/?age=abc").await?.expect_err("age is not an i32");
+        assert_eq!(
+            rejection.to_string(),
+            r#"Failed to deserialize query string: age: invalid type: string "abc", expected i32"#
+        );
+        let error = &rejection.body.errors[0];
+        assert_eq!(error.path.to_string(), "age");
+        assert_eq!(error.code.as_deref(), Some("invalid_type"));
+        assert_eq!(error.params["expected"], "i32");
+        assert_eq!(error.params["unexpected"], r#"string "abc""#);
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+
+        let rejection = extract("/?age=1&ratio=half").await?.expect_err("ratio");
+        assert_eq!(rejection.body.errors[0].path.to_string(), "ratio");
+        assert_eq!(rejection.body.errors[0].params["expected"], "f64");
+
+        let rejection = extract("/?age=1&active=yes").await?.expect_err("active");
+        assert_eq!(rejection.body.errors[0].params["expected"], "bool");
+
+        let rejection = extract("/?age=1&order=up").await?.expect_err("order");
+        assert_eq!(rejection.body.errors[0].path.to_string(), "order");
+        assert_eq!(
+            rejection.body.errors[0].code.as_deref(),
+            Some("unknown_variant")
+        );
+
+        let rejection = extract("/").await?.expect_err("missing age");
+        assert_eq!(rejection.body.errors[0].path.to_string(), "age");
+        assert_eq!(
+            rejection.body.errors[0].code.as_deref(),
+            Some("missing_field")
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "validator")]
+    #[tokio::test]
+    async fn validate_typed_query() -> anyhow::Result<()> {
+        use crate::{Valid, ValidRejection};
+        use validator::Validate;
+
+        #[derive(Debug, Deserialize, Validate)]
+        struct Parameters {
+            #[validate(range(min = 18))]
+            age: i32,
+        }
+
+        async fn extract(uri: &str) -> anyhow::Result<Option<ValidRejection<TypedQueryRejection>>> {
+            let (mut parts, _) = Request::builder().uri(uri).body(())?.into_parts();
+            Ok(
+                Valid::<TypedQuery<Parameters>>::from_request_parts(&mut parts, &())
+                    .await
+                    .err(),
+            )
+        }
+
+        assert!(extract("/?age=30").await?.is_none());
+        assert!(matches!(
+            extract("/?age=10").await?,
+            Some(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("age")
+        ));
+        assert!(matches!(
+            extract("/?age=ten").await?,
+            Some(ValidRejection::Inner(rejection)) if rejection.body.errors[0].params["expected"] == "i32"
+        ));
+        Ok(())
+    }
+}
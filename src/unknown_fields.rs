@@ -0,0 +1,360 @@
+//! # Unknown field policy
+//!
+//! ## Feature
+//!
+//! Enable the `unknown_fields` feature to use `ValidKnownJson<T>`.
+//!
+//! With serde, unknown fields are either ignored, or rejected by `#[serde(deny_unknown_fields)]` on every route.
+//! `ValidKnownJson<T>` extracts a JSON body like `Json<T>` and validates it, applying the `UnknownFieldPolicy`
+//! provided by the state, so that each router, given its own state, can choose how to treat fields `T` doesn't know:
+//!
+//! * `Ignore`, the default, drops them like serde does.
+//! * `Warn` accepts the request, and reports them as `UnknownFields`, which can be returned as a response part
+//!   to list them in the `x-unknown-fields` header of a successful response.
+//! * `Reject` rejects the request with `422 Unprocessable Entity`, like `#[serde(deny_unknown_fields)]`,
+//!   before the data is validated.
+//!
+//! Unknown fields are the fields ignored while deserializing the body, including in nested objects and arrays,
+//! so fields sent under a `#[serde(alias = "...")]` are known.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::Router;
+//! use axum_valid::unknown_fields::{UnknownFieldPolicy, UnknownFields, ValidKnownJson};
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Profile {
+//!     #[validate(length(min = 1, max = 32))]
+//!     pub name: String,
+//! }
+//!
+//! async fn handler(ValidKnownJson(profile, unknown): ValidKnownJson<Profile>) -> (UnknownFields, String) {
+//!     (unknown, profile.name)
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new()
+//!     .route("/v2/profile", post(handler))
+//!     .with_state(UnknownFieldPolicy::Reject)
+//!     .merge(
+//!         Router::new()
+//!             .route("/v1/profile", post(handler))
+//!             .with_state(UnknownFieldPolicy::Warn),
+//!     );
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::validator::validate_redacted;
+use crate::ValidRejection;
+use axum::async_trait;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRef, FromRequest, Request};
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde_ignored::Path;
+use serde_json::Value;
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use validator::Validate;
+
+/// Name of the header listing the unknown fields of a request
+pub const UNKNOWN_FIELDS_HEADER: HeaderName = HeaderName::from_static("x-unknown-fields");
+
+/// How `ValidKnownJson` treats unknown fields
+///
+/// It's provided by the state with `FromRef`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownFieldPolicy {
+    /// Drop unknown fields, like serde
+    #[default]
+    Ignore,
+    /// Accept the request, and report the unknown fields
+    Warn,
+    /// Reject the request
+    Reject,
+}
+
+/// Paths of the unknown fields of a request, e.g. `address.zip` or `items[1].color`
+///
+/// As a response part, it lists the fields in the `x-unknown-fields` header, if there is any.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnknownFields(Vec<String>);
+
+impl UnknownFields {
+    /// Check if a field is unknown
+    pub fn contains(&self, path: &str) -> bool {
+        self.0.iter().any(|field| field == path)
+    }
+
+    /// Check if there is no unknown field
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the paths of the unknown fields
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+}
+
+impl Display for UnknownFields {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.join(", "))
+    }
+}
+
+impl IntoResponseParts for UnknownFields {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        if self.is_empty() {
+            return Ok(res);
+        }
+        if let Ok(value) = HeaderValue::from_str(&self.to_string()) {
+            res.headers_mut().insert(UNKNOWN_FIELDS_HEADER, value);
+        }
+        Ok(res)
+    }
+}
+
+/// Format the path of an ignored field like the paths of validation errors, e.g. `tags[1].color`
+fn field_path(path: &Path) -> String {
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, index } => format!("{}[{index}]", field_path(parent)),
+        Path::Map { parent, key } => match field_path(parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{parent}.{key}"),
+        },
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => field_path(parent),
+    }
+}
+
+/// # `ValidKnownJson` data extractor
+///
+/// See the [module documentation](crate::unknown_fields) for details.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ValidKnownJson<T>(pub T, pub UnknownFields);
+
+impl<T> ValidKnownJson<T> {
+    /// Consumes the `ValidKnownJson` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    /// The unknown fields of the request, always empty with `UnknownFieldPolicy::Ignore`
+    pub fn unknown_fields(&self) -> &UnknownFields {
+        &self.1
+    }
+}
+
+/// Inner rejection of `ValidKnownJson`
+#[derive(Debug)]
+pub enum UnknownFieldsRejection {
+    /// The body is not valid JSON
+    Json(JsonRejection),
+    /// The body can't be deserialized into the target type
+    Deserialize(serde_json::Error),
+    /// The body has unknown fields, with `UnknownFieldPolicy::Reject`
+    Unknown(UnknownFields),
+}
+
+impl Display for UnknownFieldsRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnknownFieldsRejection::Json(e) => write!(f, "{e}"),
+            UnknownFieldsRejection::Deserialize(e) => {
+                write!(
+                    f,
+                    "Failed to deserialize the JSON body into the target type: {e}"
+                )
+            }
+            UnknownFieldsRejection::Unknown(fields) => write!(f, "Unknown fields: {fields}"),
+        }
+    }
+}
+
+impl std::error::Error for UnknownFieldsRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UnknownFieldsRejection::Json(e) => Some(e),
+            UnknownFieldsRejection::Deserialize(e) => Some(e),
+            UnknownFieldsRejection::Unknown(_) => None,
+        }
+    }
+}
+
+impl IntoResponse for UnknownFieldsRejection {
+    fn into_response(self) -> Response {
+        match self {
+            UnknownFieldsRejection::Json(e) => e.into_response(),
+            // Same status code as `JsonDataError`, returned for `#[serde(deny_unknown_fields)]`
+            e @ (UnknownFieldsRejection::Deserialize(_) | UnknownFieldsRejection::Unknown(_)) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response()
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidKnownJson<T>
+where
+    State: Send + Sync,
+    UnknownFieldPolicy: FromRef<State>,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ValidRejection<UnknownFieldsRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let policy = UnknownFieldPolicy::from_ref(state);
+        let Json(body) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(UnknownFieldsRejection::Json(e)))?;
+        let mut unknown = UnknownFields::default();
+        let data = match policy {
+            UnknownFieldPolicy::Ignore => T::deserialize(&body),
+            UnknownFieldPolicy::Warn | UnknownFieldPolicy::Reject => {
+                serde_ignored::deserialize(&body, |path| unknown.0.push(field_path(&path)))
+            }
+        }
+        .map_err(|e| ValidRejection::Inner(UnknownFieldsRejection::Deserialize(e)))?;
+        if policy == UnknownFieldPolicy::Reject && !unknown.is_empty() {
+            return Err(ValidRejection::Inner(UnknownFieldsRejection::Unknown(
+                unknown,
+            )));
+        }
+        validate_redacted(&data)?;
+        Ok(ValidKnownJson(data, unknown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[derive(Debug, serde::Deserialize, serde::Serialize, Validate)]
+    struct Profile {
+        #[validate(length(min = 1, max = 10))]
+        #[serde(alias = "username")]
+        name: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<Tag>,
+        #[serde(default, skip_serializing)]
+        password: Option<String>,
+    }
+
+    #[derive(Debug, serde::Deserialize, serde::Serialize, Validate)]
+    struct Tag {
+        label: String,
+    }
+
+    async fn extract(
+        policy: UnknownFieldPolicy,
+        body: &'static str,
+    ) -> anyhow::Result<Result<ValidKnownJson<Profile>, ValidRejection<UnknownFieldsRejection>>>
+    {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        Ok(ValidKnownJson::<Profile>::from_request(request, &policy).await)
+    }
+
+    const UNKNOWN: &str = r#"{"name": "gteng", "admin": true, "tags": [{"label": "a"}, {"label": "b", "color": "red"}]}"#;
+
+    #[tokio::test]
+    async fn ignore_unknown_fields() -> anyhow::Result<()> {
+        let ValidKnownJson(profile, unknown) = extract(UnknownFieldPolicy::Ignore, UNKNOWN)
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(profile.name, "gteng");
+        assert_eq!(profile.tags.len(), 2);
+        assert!(unknown.is_empty());
+        let response = (unknown, "ok").into_response();
+        assert!(response.headers().get(UNKNOWN_FIELDS_HEADER).is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn warn_about_unknown_fields() -> anyhow::Result<()> {
+        let ValidKnownJson(profile, unknown) = extract(UnknownFieldPolicy::Warn, UNKNOWN)
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(profile.name, "gteng");
+        assert_eq!(
+            unknown.iter().collect::<Vec<_>>(),
+            ["admin", "tags[1].color"]
+        );
+
+        let response = (unknown, "ok").into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[UNKNOWN_FIELDS_HEADER],
+            "admin, tags[1].color"
+        );
+
+        // the data is still validated
+        assert!(matches!(
+            extract(UnknownFieldPolicy::Warn, r#"{"name": "", "admin": true}"#).await?,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("name")
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reject_unknown_fields() -> anyhow::Result<()> {
+        let Err(rejection) = extract(UnknownFieldPolicy::Reject, UNKNOWN).await? else {
+            panic!("expected unknown fields");
+        };
+        let ValidRejection::Inner(UnknownFieldsRejection::Unknown(unknown)) = &rejection else {
+            panic!("expected unknown fields");
+        };
+        assert!(unknown.contains("admin"));
+        assert!(unknown.contains("tags[1].color"));
+        assert_eq!(
+            rejection.into_response().status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        // unknown fields are rejected before validation
+        assert!(matches!(
+            extract(UnknownFieldPolicy::Reject, r#"{"name": "", "admin": true}"#).await?,
+            Err(ValidRejection::Inner(UnknownFieldsRejection::Unknown(_)))
+        ));
+        let ValidKnownJson(_, unknown) =
+            extract(UnknownFieldPolicy::Reject, r#"{"name": "gteng"}"#)
+                .await?
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert!(unknown.is_empty());
+
+        // aliases and fields skipped when serializing are known
+        let ValidKnownJson(profile, unknown) = extract(
+            UnknownFieldPolicy::Reject,
+            r#"{"username": "gteng", "tags": [], "password": "secret"}"#,
+        )
+        .await?
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(profile.name, "gteng");
+        assert_eq!(profile.password.as_deref(), Some("secret"));
+        assert!(unknown.is_empty());
+        Ok(())
+    }
+}
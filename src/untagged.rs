@@ -0,0 +1,326 @@
+//! # Validation of untagged enums
+//!
+//! ## Feature
+//!
+//! Enable the `untagged` feature to use `ValidUntagged<T>`.
+//!
+//! With `#[serde(untagged)]`, serde tries each variant in turn and only reports
+//! `data did not match any variant of untagged enum` when none matches, without telling why.
+//! Validating the matched variant afterwards doesn't help either: a body that deserializes as a variant
+//! but breaks its rules may have been meant for another variant.
+//!
+//! `ValidUntagged<T>` extracts a JSON body and tries the variants listed by `UntaggedVariants::variants`
+//! in order, deserializing and validating each of them. The first variant that is both well-formed and valid is
+//! handed to the handler. If none is, the request is rejected with `VALIDATION_ERROR_STATUS`
+//! (`400 Bad Request` by default) and a JSON body listing the errors of each variant as `FieldError`s:
+//! `{"variants": [{"variant": "card", "errors": [...]}, {"variant": "bank", "errors": [...]}]}`.
+//! A variant that can't be deserialized has a single error with the code `parse`, like in `ValidBatch`.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::routing::post;
+//! use axum::Router;
+//! use axum_valid::untagged::{UntaggedVariants, ValidUntagged, Variants};
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Card {
+//!     #[validate(length(min = 12, max = 19))]
+//!     pub number: String,
+//! }
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Bank {
+//!     #[validate(length(min = 15, max = 34))]
+//!     pub iban: String,
+//! }
+//!
+//! pub enum Payment {
+//!     Card(Card),
+//!     Bank(Bank),
+//! }
+//!
+//! impl UntaggedVariants for Payment {
+//!     fn variants() -> Variants<Self> {
+//!         Variants::new()
+//!             .variant("card", Payment::Card)
+//!             .variant("bank", Payment::Bank)
+//!     }
+//! }
+//!
+//! async fn handler(ValidUntagged(payment): ValidUntagged<Payment>) {
+//!     match payment {
+//!         Payment::Card(card) => println!("card {}", card.number),
+//!         Payment::Bank(bank) => println!("bank {}", bank.iban),
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! let router = Router::new().route("/payments", post(handler));
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::{FieldError, FieldPath};
+use crate::validator::validate_redacted;
+use crate::{ErrorBody, VALIDATION_ERROR_STATUS};
+use axum::async_trait;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, Request};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use validator::Validate;
+
+type Attempt<T> = Box<dyn Fn(&Value) -> Result<T, Vec<FieldError>> + Send + Sync>;
+
+/// Variants of an untagged enum, tried in order
+pub struct Variants<T>(Vec<(&'static str, Attempt<T>)>);
+
+impl<T> Default for Variants<T> {
+    fn default() -> Self {
+        Variants(Vec::new())
+    }
+}
+
+impl<T> Variants<T> {
+    /// Create an empty list of variants
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a variant named `name`, with the data type `V` wrapped into the enum by `into`
+    pub fn variant<V, F>(mut self, name: &'static str, into: F) -> Self
+    where
+        V: DeserializeOwned + Validate,
+        F: Fn(V) -> T + Send + Sync + 'static,
+    {
+        self.0.push((
+            name,
+            Box::new(move |value| {
+                let data = V::deserialize(value).map_err(|e| {
+                    let mut error = FieldError::new(FieldPath::new(), "parse");
+                    error.message = Some(e.to_string());
+                    vec![error]
+                })?;
+                validate_redacted(&data).map_err(|errors| ErrorBody::from(errors).errors)?;
+                Ok(into(data))
+            }),
+        ));
+        self
+    }
+
+    /// Get the first well-formed and valid variant, or the errors of all variants
+    pub fn resolve(&self, value: &Value) -> Result<T, Vec<VariantErrors>> {
+        let mut variants = Vec::with_capacity(self.0.len());
+        for (variant, attempt) in &self.0 {
+            match attempt(value) {
+                Ok(data) => return Ok(data),
+                Err(errors) => variants.push(VariantErrors { variant, errors }),
+            }
+        }
+        Err(variants)
+    }
+}
+
+/// Trait for untagged enums that can be extracted with `ValidUntagged`
+pub trait UntaggedVariants: Sized {
+    /// The variants of the enum, in the order they are tried
+    fn variants() -> Variants<Self>;
+}
+
+/// Errors of a variant that didn't match
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VariantErrors {
+    /// Name of the variant
+    pub variant: &'static str,
+    /// Deserialization or validation errors of the variant
+    pub errors: Vec<FieldError>,
+}
+
+/// # `ValidUntagged` data extractor
+///
+/// See the [module documentation](crate::untagged) for details.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidUntagged<T>(pub T);
+
+impl<T> Deref for ValidUntagged<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ValidUntagged<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> ValidUntagged<T> {
+    /// Consumes the `ValidUntagged` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// `UntaggedRejection` is returned when the `ValidUntagged` extractor fails.
+#[derive(Debug)]
+pub enum UntaggedRejection {
+    /// The body is rejected by `Json`
+    Json(JsonRejection),
+    /// No variant is well-formed and valid
+    NoMatch(Vec<VariantErrors>),
+}
+
+impl Display for UntaggedRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UntaggedRejection::Json(e) => write!(f, "{e}"),
+            UntaggedRejection::NoMatch(variants) => {
+                write!(f, "Data did not match any variant: ")?;
+                for (i, variant) in variants.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} ({} errors)", variant.variant, variant.errors.len())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for UntaggedRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UntaggedRejection::Json(e) => Some(e),
+            UntaggedRejection::NoMatch(_) => None,
+        }
+    }
+}
+
+impl IntoResponse for UntaggedRejection {
+    fn into_response(self) -> Response {
+        match self {
+            UntaggedRejection::Json(e) => e.into_response(),
+            UntaggedRejection::NoMatch(variants) => (
+                VALIDATION_ERROR_STATUS,
+                Json(serde_json::json!({ "variants": variants })),
+            )
+                .into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidUntagged<T>
+where
+    State: Send + Sync,
+    T: UntaggedVariants,
+{
+    type Rejection = UntaggedRejection;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(UntaggedRejection::Json)?;
+        T::variants()
+            .resolve(&value)
+            .map(ValidUntagged)
+            .map_err(UntaggedRejection::NoMatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Card {
+        #[validate(length(equal = 16))]
+        number: String,
+    }
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Bank {
+        #[validate(length(min = 15, max = 34))]
+        iban: String,
+    }
+
+    #[derive(Debug)]
+    enum Payment {
+        Card(Card),
+        Bank(Bank),
+    }
+
+    impl UntaggedVariants for Payment {
+        fn variants() -> Variants<Self> {
+            Variants::new()
+                .variant("card", Payment::Card)
+                .variant("bank", Payment::Bank)
+        }
+    }
+
+    async fn extract(body: &'static str) -> anyhow::Result<Result<Payment, UntaggedRejection>> {
+        let request = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        Ok(ValidUntagged::<Payment>::from_request(request, &())
+            .await
+            .map(ValidUntagged::into_inner))
+    }
+
+    #[tokio::test]
+    async fn per_variant_diagnostics() -> anyhow::Result<()> {
+        assert!(matches!(
+            extract(r#"{"number": "4111111111111111"}"#).await?,
+            Ok(Payment::Card(card)) if card.number.len() == 16
+        ));
+        // a body that isn't a valid card is tried as a bank account
+        assert!(matches!(
+            extract(r#"{"number": "4111", "iban": "DE89370400440532013000"}"#).await?,
+            Ok(Payment::Bank(bank)) if bank.iban.starts_with("DE")
+        ));
+
+        let rejection = extract(r#"{"number": "4111"}"#)
+            .await?
+            .expect_err("no variant matches");
+        let UntaggedRejection::NoMatch(variants) = &rejection else {
+            panic!("expected per-variant errors");
+        };
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[0].variant, "card");
+        assert_eq!(variants[0].errors[0].path.to_string(), "number");
+        assert_eq!(variants[0].errors[0].code.as_deref(), Some("length"));
+        assert_eq!(variants[1].variant, "bank");
+        assert_eq!(variants[1].errors[0].code.as_deref(), Some("parse"));
+
+        let response = rejection.into_response();
+        assert_eq!(response.status(), VALIDATION_ERROR_STATUS);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        let body = serde_json::from_slice::<Value>(&body)?;
+        assert_eq!(body["variants"][0]["variant"], "card");
+        assert_eq!(body["variants"][0]["errors"][0]["code"], "length");
+        assert_eq!(body["variants"][1]["variant"], "bank");
+        assert_eq!(body["variants"][1]["errors"][0]["code"], "parse");
+        Ok(())
+    }
+}
@@ -4,10 +4,26 @@
 //!
 //! Enable the `validator` feature (enabled by default) to use `Valid<E>` and `ValidEx<E, A>`.
 //!
+//! ## Skipping validation
+//!
+//! For latency-critical services, validation can be removed at compile time by building with
+//! `RUSTFLAGS="--cfg axum_valid_skip_validation"`. `Valid<E>` and `ValidEx<E>` then only run the inner extractor,
+//! and data reaches the handler unvalidated. The data types still need to implement `Validate`,
+//! so the same code builds with and without the flag.
+//!
+//! This is a `cfg` flag rather than a Cargo feature because features are additive:
+//! a dependency enabling it would silently disable validation for the whole dependency graph.
+//!
+//! The flag only applies to `Valid<E>` and `ValidEx<E>`. Every other extractor still validates,
+//! including `Garde<E>`, `Validated<E>`, `Modified<E>` and `Validified<E>`, and the extractors of the
+//! optional modules of this crate, e.g. `ValidSchemaHashed<E>` or `ValidCached<E>`.
+//!
 
 #[cfg(test)]
-pub mod test;
+mod test;
 
+#[cfg(feature = "secret")]
+pub(crate) use crate::secret::redacting;
 use crate::{HasValidate, ValidationRejection};
 use axum::async_trait;
 use axum::extract::{FromRef, FromRequest, FromRequestParts, Request};
@@ -80,6 +96,10 @@ where
 ///
 /// Although current module documentation predominantly showcases `Valid` examples, the usage of `ValidEx` is analogous.
 ///
+/// The arguments are obtained from the state with `FromRef`. Since axum implements `FromRef<S>` for any `S: Clone`,
+/// the whole application state can be used as the validation context directly, without a dedicated arguments type:
+/// declare `#[validate(context = AppState)]` on the data type and use `ValidEx<Json<T>>` in a router with `AppState` as its state.
+///
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ValidEx<E>(pub E);
 
@@ -133,6 +153,21 @@ impl<E> From<ValidationErrors> for ValidRejection<E> {
     }
 }
 
+/// Without the `secret` feature, there are no secret values to redact from the errors
+#[cfg(not(feature = "secret"))]
+#[cfg_attr(axum_valid_skip_validation, allow(dead_code))]
+pub(crate) fn redacting<R>(f: impl FnOnce() -> R) -> R {
+    f()
+}
+
+/// Validate `data`, with the values of `Secret` fields redacted from the errors, see `secret::redacting`
+///
+/// Extractors of this crate validate through it, or through `redacting` when validating with arguments.
+#[cfg_attr(axum_valid_skip_validation, allow(dead_code))]
+pub(crate) fn validate_redacted<T: Validate + ?Sized>(data: &T) -> Result<(), ValidationErrors> {
+    redacting(|| data.validate())
+}
+
 /// Trait for types that can supply a reference that can be validated using arguments.
 ///
 /// Extractor types `T` that implement this trait can be used with `ValidEx`.
@@ -157,7 +192,8 @@ where
         let inner = Extractor::from_request(req, state)
             .await
             .map_err(ValidRejection::Inner)?;
-        inner.get_validate().validate()?;
+        #[cfg(not(axum_valid_skip_validation))]
+        validate_redacted(inner.get_validate())?;
         Ok(Valid(inner))
     }
 }
@@ -175,7 +211,8 @@ where
         let inner = Extractor::from_request_parts(parts, state)
             .await
             .map_err(ValidRejection::Inner)?;
-        inner.get_validate().validate()?;
+        #[cfg(not(axum_valid_skip_validation))]
+        validate_redacted(inner.get_validate())?;
         Ok(Valid(inner))
     }
 }
@@ -191,12 +228,15 @@ where
     type Rejection = ValidRejection<<Extractor as FromRequest<State>>::Rejection>;
 
     async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
-        let arguments: Args = FromRef::from_ref(state);
         let inner = Extractor::from_request(req, state)
             .await
             .map_err(ValidRejection::Inner)?;
-
-        inner.get_validate_args().validate_with_args(&arguments)?;
+        #[cfg(not(axum_valid_skip_validation))]
+        redacting(|| {
+            inner
+                .get_validate_args()
+                .validate_with_args(&Args::from_ref(state))
+        })?;
         Ok(ValidEx(inner))
     }
 }
@@ -212,17 +252,21 @@ where
     type Rejection = ValidRejection<<Extractor as FromRequestParts<State>>::Rejection>;
 
     async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
-        let arguments: Args = FromRef::from_ref(state);
         let inner = Extractor::from_request_parts(parts, state)
             .await
             .map_err(ValidRejection::Inner)?;
-        inner.get_validate_args().validate_with_args(&arguments)?;
+        #[cfg(not(axum_valid_skip_validation))]
+        redacting(|| {
+            inner
+                .get_validate_args()
+                .validate_with_args(&Args::from_ref(state))
+        })?;
         Ok(ValidEx(inner))
     }
 }
 
 #[cfg(test)]
-pub mod tests {
+mod tests {
     use super::*;
     use std::error::Error;
     use std::fmt::Formatter;
@@ -305,9 +349,230 @@ pub mod tests {
         );
 
         // ValidRejection::Valid Error
-        let vr = ValidRejection::<io::Error>::Inner(io::Error::new(io::ErrorKind::Other, TEST));
+        let vr = ValidRejection::<io::Error>::Inner(io::Error::other(TEST));
         assert!(
             matches!(vr.source(), Some(source) if source.downcast_ref::<io::Error>().is_some())
         );
     }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn into_app_error() -> anyhow::Result<()> {
+        use axum::body::Body;
+        use axum::extract::rejection::JsonRejection;
+        use axum::http::{header, StatusCode};
+        use axum::response::{IntoResponse, Response};
+        use axum::Json;
+
+        #[derive(Debug, Validate, serde::Deserialize)]
+        struct Data {
+            #[validate(range(min = 5, max = 10))]
+            v0: i32,
+        }
+
+        #[derive(Debug)]
+        enum AppError {
+            Validation(ValidationErrors),
+            Json(JsonRejection),
+        }
+
+        impl From<ValidationErrors> for AppError {
+            fn from(value: ValidationErrors) -> Self {
+                AppError::Validation(value)
+            }
+        }
+
+        impl From<JsonRejection> for AppError {
+            fn from(value: JsonRejection) -> Self {
+                AppError::Json(value)
+            }
+        }
+
+        impl IntoResponse for AppError {
+            fn into_response(self) -> Response {
+                match self {
+                    AppError::Validation(errors) => {
+                        (StatusCode::IM_A_TEAPOT, errors.to_string()).into_response()
+                    }
+                    AppError::Json(rejection) => {
+                        (StatusCode::NOT_ACCEPTABLE, rejection.body_text()).into_response()
+                    }
+                }
+            }
+        }
+
+        async fn extract(body: &'static str) -> anyhow::Result<AppError> {
+            let request = Request::builder()
+                .method("POST")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))?;
+            Ok(Valid::<Json<Data>>::from_request(request, &())
+                .await
+                .map(|_| ())
+                .map_err(ValidRejection::into_app_error::<AppError>)
+                .expect_err("request should be rejected"))
+        }
+
+        let invalid = extract(r#"{"v0": 11}"#).await?;
+        assert!(
+            matches!(invalid, AppError::Validation(ref errors) if errors.field_errors().contains_key("v0"))
+        );
+        assert_eq!(invalid.into_response().status(), StatusCode::IM_A_TEAPOT);
+
+        let error = extract("not json").await?;
+        assert!(matches!(error, AppError::Json(_)));
+        assert_eq!(error.into_response().status(), StatusCode::NOT_ACCEPTABLE);
+        Ok(())
+    }
+
+    #[cfg(feature = "query")]
+    #[tokio::test]
+    async fn head_rejection_has_no_body() -> anyhow::Result<()> {
+        use crate::VALIDATION_ERROR_STATUS;
+        use axum::extract::Query;
+        use axum::routing::get;
+        use axum::Router;
+        use std::net::SocketAddr;
+        use tokio::net::TcpListener;
+
+        #[derive(Validate, serde::Deserialize)]
+        struct Parameters {
+            #[validate(range(min = 5, max = 10))]
+            v0: i32,
+        }
+
+        async fn handler(_: Valid<Query<Parameters>>) {}
+
+        let router = Router::new().route("/", get(handler));
+        let listener = TcpListener::bind(&SocketAddr::from(([127u8, 0, 0, 1], 0u16))).await?;
+        let server_addr = listener.local_addr()?;
+        let server =
+            tokio::spawn(async move { axum::serve(listener, router.into_make_service()).await });
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{server_addr}/?v0=11");
+        let get_response = client.get(&url).send().await?;
+        assert_eq!(
+            get_response.status().as_u16(),
+            VALIDATION_ERROR_STATUS.as_u16()
+        );
+        let get_body = get_response.bytes().await?;
+        assert!(!get_body.is_empty());
+
+        let head_response = client.head(&url).send().await?;
+        assert_eq!(
+            head_response.status().as_u16(),
+            VALIDATION_ERROR_STATUS.as_u16()
+        );
+        assert_eq!(
+            head_response.headers()[reqwest::header::CONTENT_LENGTH],
+            get_body.len().to_string()
+        );
+        assert!(head_response.bytes().await?.is_empty());
+
+        server.abort();
+        Ok(())
+    }
+
+    #[cfg(all(feature = "into_json", feature = "query"))]
+    #[tokio::test]
+    async fn typed_params_in_json() -> anyhow::Result<()> {
+        use axum::extract::{FromRequestParts, Query};
+        use axum::http::Request;
+        use axum::response::IntoResponse;
+
+        #[derive(Debug, Validate, serde::Deserialize)]
+        struct Parameters {
+            #[validate(length(min = 2, max = 5))]
+            name: String,
+            #[validate(range(min = 1, max = 100))]
+            page: u32,
+        }
+
+        let (mut parts, _) = Request::builder()
+            .uri("/?name=a&page=0")
+            .body(())?
+            .into_parts();
+        let rejection = Valid::<Query<Parameters>>::from_request_parts(&mut parts, &())
+            .await
+            .expect_err("parameters are invalid");
+        let body = axum::body::to_bytes(rejection.into_response().into_body(), usize::MAX).await?;
+        let body = serde_json::from_slice::<serde_json::Value>(&body)?;
+        for field in ["name", "page"] {
+            let params = &body[field][0]["params"];
+            assert!(params["min"].is_u64(), "{field}: {params}");
+            assert!(params["max"].is_u64(), "{field}: {params}");
+        }
+        assert_eq!(body["name"][0]["params"]["max"], 5);
+        assert_eq!(body["page"][0]["params"]["value"], 0);
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[tokio::test]
+    async fn state_as_validation_context() -> anyhow::Result<()> {
+        use axum::body::Body;
+        use axum::http::header::CONTENT_TYPE;
+        use axum::Json;
+
+        #[derive(Debug, Clone)]
+        struct AppState {
+            max_items: usize,
+        }
+
+        fn validate_items(items: &[u32], state: &AppState) -> Result<(), ValidationError> {
+            if items.len() <= state.max_items {
+                Ok(())
+            } else {
+                Err(ValidationError::new("too_many_items"))
+            }
+        }
+
+        #[derive(Debug, Validate, serde::Deserialize)]
+        #[validate(context = AppState)]
+        struct Order {
+            #[validate(custom(function = "validate_items", use_context))]
+            items: Vec<u32>,
+        }
+
+        let request = || {
+            Request::builder()
+                .method("POST")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"items": [1, 2, 3]}"#))
+        };
+
+        let ValidEx(Json(order)) =
+            ValidEx::<Json<Order>>::from_request(request()?, &AppState { max_items: 3 })
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(order.items, [1, 2, 3]);
+
+        assert!(matches!(
+            ValidEx::<Json<Order>>::from_request(request()?, &AppState { max_items: 2 }).await,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors()["items"][0].code == "too_many_items"
+        ));
+        Ok(())
+    }
+
+    #[cfg(all(axum_valid_skip_validation, feature = "query"))]
+    #[tokio::test]
+    async fn skip_validation() -> anyhow::Result<()> {
+        use axum::extract::Query;
+
+        #[derive(Debug, Validate, serde::Deserialize)]
+        struct Parameters {
+            #[validate(range(min = 5, max = 10))]
+            v0: i32,
+        }
+
+        let (mut parts, _) = Request::builder().uri("/?v0=11").body(())?.into_parts();
+        let Valid(Query(parameters)) =
+            Valid::<Query<Parameters>>::from_request_parts(&mut parts, &())
+                .await
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(parameters.v0, 11);
+        assert!(parameters.validate().is_err());
+        Ok(())
+    }
 }
@@ -289,6 +289,17 @@ async fn test_main() -> anyhow::Result<()> {
         .route(sonic::route::SONIC, post(sonic::extract_sonic))
         .route(sonic::route::SONIC_EX, post(sonic::extract_sonic_ex));
 
+    #[cfg(feature = "text_form")]
+    let router = router
+        .route(
+            text_form::route::TEXT_FORM,
+            post(text_form::extract_text_form),
+        )
+        .route(
+            text_form::route::TEXT_FORM_EX,
+            post(text_form::extract_text_form_ex),
+        );
+
     let router = router.with_state(state);
 
     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
@@ -644,6 +655,17 @@ async fn test_main() -> anyhow::Result<()> {
             .await?;
     }
 
+    #[cfg(feature = "text_form")]
+    {
+        use crate::text_form::TextForm;
+        test_executor
+            .execute::<TextForm<Parameters>>(Method::POST, text_form::route::TEXT_FORM)
+            .await?;
+        test_executor
+            .execute::<TextForm<Parameters>>(Method::POST, text_form::route::TEXT_FORM_EX)
+            .await?;
+    }
+
     Ok(())
 }
 
@@ -1514,3 +1536,33 @@ mod sonic {
         validate_again_ex(parameters, &arguments)
     }
 }
+
+#[cfg(feature = "text_form")]
+mod text_form {
+    use super::{
+        validate_again, validate_again_ex, Parameters, ParametersEx,
+        ParametersExValidationArguments,
+    };
+    use crate::text_form::TextForm;
+    use crate::{Valid, ValidEx};
+    use axum::extract::State;
+    use axum::http::StatusCode;
+
+    pub mod route {
+        pub const TEXT_FORM: &str = "/text_form";
+        pub const TEXT_FORM_EX: &str = "/text_form_ex";
+    }
+
+    pub async fn extract_text_form(
+        Valid(TextForm(parameters)): Valid<TextForm<Parameters>>,
+    ) -> StatusCode {
+        validate_again(parameters)
+    }
+
+    pub async fn extract_text_form_ex(
+        State(arguments): State<ParametersExValidationArguments>,
+        ValidEx(TextForm(parameters)): ValidEx<TextForm<ParametersEx>>,
+    ) -> StatusCode {
+        validate_again_ex(parameters, &arguments)
+    }
+}
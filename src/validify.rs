@@ -6,7 +6,7 @@
 //!
 
 #[cfg(test)]
-pub mod test;
+mod test;
 
 use crate::{HasValidate, ValidationRejection};
 use axum::async_trait;
@@ -517,8 +517,7 @@ mod tests {
         );
 
         // ValidifyRejection::Valid Error
-        let vr =
-            ValidifyRejection::<io::Error>::Inner(io::Error::new(io::ErrorKind::Other, VALIDIFY));
+        let vr = ValidifyRejection::<io::Error>::Inner(io::Error::other(VALIDIFY));
         assert!(
             matches!(vr.source(), Some(source) if source.downcast_ref::<io::Error>().is_some())
         );
@@ -0,0 +1,360 @@
+//! # Verified uploads
+//!
+//! ## Feature
+//!
+//! Enable the `verified_upload` feature to use `ValidUpload<T>`.
+//!
+//! Some uploads are only accepted if their content is known in advance, e.g. firmware images or documents
+//! approved by a reviewer, identified by their SHA-256 digests. `ValidUpload<T>` extracts a `multipart/form-data`
+//! body, hashes each file while it's streamed, and rejects the request as soon as a file whose digest isn't in
+//! the `HashAllowList` provided by the state is read, without reading the rest of the body.
+//!
+//! The handler receives the verified files as `UploadedFile`s, with their content and digest.
+//! The content is collected while it's hashed, within the `DefaultBodyLimit` of the route like any multipart body,
+//! and dropped as soon as its digest is rejected.
+//! The text fields are deserialized into `T` like a `TextForm<T>` and validated.
+//!
+//! A disallowed file is rejected with an `ErrorBody` and `VALIDATION_ERROR_STATUS`: an error at the path
+//! of its field, with the code `disallowed_file` and its digest as the `sha256` parameter.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use axum::extract::FromRef;
+//! use axum::routing::post;
+//! use axum::Router;
+//! use axum_valid::verified_upload::{HashAllowList, ValidUpload};
+//! use serde::Deserialize;
+//! use validator::Validate;
+//!
+//! #[derive(Clone, FromRef)]
+//! struct AppState {
+//!     firmwares: HashAllowList,
+//! }
+//!
+//! #[derive(Validate, Deserialize)]
+//! pub struct Install {
+//!     #[validate(length(min = 1, max = 32))]
+//!     pub device: String,
+//! }
+//!
+//! async fn handler(ValidUpload(install, files): ValidUpload<Install>) {
+//!     for file in files {
+//!         println!("install {} ({} bytes) on {}", file.sha256, file.content.len(), install.device);
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use tokio::net::TcpListener;
+//! // e.g. the digests of the released firmwares
+//! let firmwares = HashAllowList::new()
+//!     .allow("782eb224a85e5ac7bdae147eb82c6d71930e37bb1eacab5b21e26b11451cf6b7");
+//! let router = Router::new()
+//!     .route("/install", post(handler))
+//!     .with_state(AppState { firmwares });
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::error_body::{FieldError, FieldPath};
+use crate::validator::validate_redacted;
+use crate::{ErrorBody, ValidRejection};
+use axum::async_trait;
+use axum::body::{Body, Bytes};
+use axum::extract::multipart::{MultipartError, MultipartRejection};
+use axum::extract::rejection::FormRejection;
+use axum::extract::{FromRef, FromRequest, Multipart, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::HeaderValue;
+use axum::response::{IntoResponse, Response};
+use axum::Form;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter, Write};
+use std::sync::Arc;
+use validator::Validate;
+
+/// Code of the error of a file whose digest isn't allowed
+pub const DISALLOWED_FILE: &str = "disallowed_file";
+
+/// Set of allowed SHA-256 digests, cheap to clone
+#[derive(Debug, Clone, Default)]
+pub struct HashAllowList(Arc<HashSet<String>>);
+
+impl HashAllowList {
+    /// Create an empty allow-list, which rejects every file
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow the content with a hex-encoded SHA-256 digest, in lowercase or uppercase
+    pub fn allow(mut self, sha256: &str) -> Self {
+        Arc::make_mut(&mut self.0).insert(sha256.to_ascii_lowercase());
+        self
+    }
+
+    /// Check if the content with a lowercase hex-encoded SHA-256 digest is allowed
+    pub fn contains(&self, sha256: &str) -> bool {
+        self.0.contains(sha256)
+    }
+}
+
+/// A file uploaded with an allowed digest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedFile {
+    /// The name of the field
+    pub name: String,
+    /// The file name sent by the client
+    pub file_name: String,
+    /// The content type sent by the client
+    pub content_type: Option<String>,
+    /// The content
+    pub content: Bytes,
+    /// The lowercase hex-encoded SHA-256 digest of the content
+    pub sha256: String,
+}
+
+/// # `ValidUpload` data extractor
+///
+/// See the [module documentation](crate::verified_upload) for details.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ValidUpload<T>(pub T, pub Vec<UploadedFile>);
+
+impl<T> ValidUpload<T> {
+    /// Consumes the `ValidUpload` and returns the validated data within.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// Inner rejection of `ValidUpload`
+#[derive(Debug)]
+pub enum UploadRejection {
+    /// The multipart body can't be parsed
+    Multipart(MultipartRejection),
+    /// A field of the multipart body can't be read
+    MultipartField(MultipartError),
+    /// The text fields can't be deserialized
+    Form(FormRejection),
+    /// The digest of a file isn't allowed, reported as a `disallowed_file` error
+    Disallowed(ErrorBody),
+}
+
+impl Display for UploadRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploadRejection::Multipart(e) => write!(f, "{e}"),
+            UploadRejection::MultipartField(e) => write!(f, "{e}"),
+            UploadRejection::Form(e) => write!(f, "{e}"),
+            UploadRejection::Disallowed(body) => {
+                let Some(error) = body.errors.first() else {
+                    return write!(f, "File is not allowed");
+                };
+                let sha256 = error.param::<String>("sha256").unwrap_or_default();
+                write!(
+                    f,
+                    "File `{}` with SHA-256 digest {sha256} is not allowed",
+                    error.path
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for UploadRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UploadRejection::Multipart(e) => Some(e),
+            UploadRejection::MultipartField(e) => Some(e),
+            UploadRejection::Form(e) => Some(e),
+            UploadRejection::Disallowed(_) => None,
+        }
+    }
+}
+
+impl IntoResponse for UploadRejection {
+    fn into_response(self) -> Response {
+        match self {
+            UploadRejection::Multipart(e) => e.into_response(),
+            UploadRejection::MultipartField(e) => e.into_response(),
+            UploadRejection::Form(e) => e.into_response(),
+            UploadRejection::Disallowed(body) => body.into_response(),
+        }
+    }
+}
+
+#[async_trait]
+impl<State, T> FromRequest<State> for ValidUpload<T>
+where
+    State: Send + Sync,
+    HashAllowList: FromRef<State>,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = ValidRejection<UploadRejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let allow_list = HashAllowList::from_ref(state);
+        let method = req.method().clone();
+        let uri = req.uri().clone();
+        let mut multipart = Multipart::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(UploadRejection::Multipart(e)))?;
+        let field_error = |e| ValidRejection::Inner(UploadRejection::MultipartField(e));
+        let mut fields = Vec::new();
+        let mut files = Vec::new();
+        while let Some(mut field) = multipart.next_field().await.map_err(field_error)? {
+            let name = field.name().unwrap_or_default().to_string();
+            let Some(file_name) = field.file_name().map(ToOwned::to_owned) else {
+                fields.push((name, field.text().await.map_err(field_error)?));
+                continue;
+            };
+            let content_type = field.content_type().map(ToOwned::to_owned);
+            let mut hasher = Sha256::new();
+            let mut content = Vec::new();
+            while let Some(chunk) = field.chunk().await.map_err(field_error)? {
+                hasher.update(&chunk[..]);
+                content.extend_from_slice(&chunk);
+            }
+            let sha256 =
+                hasher
+                    .finalize()
+                    .iter()
+                    .fold(String::with_capacity(64), |mut hex, byte| {
+                        let _ = write!(hex, "{byte:02x}");
+                        hex
+                    });
+            if !allow_list.contains(&sha256) {
+                let error = FieldError::new(FieldPath::new().key(name), DISALLOWED_FILE)
+                    .with_param("sha256", sha256);
+                return Err(ValidRejection::Inner(UploadRejection::Disallowed(
+                    ErrorBody::new(vec![error]),
+                )));
+            }
+            files.push(UploadedFile {
+                name,
+                file_name,
+                content_type,
+                content: Bytes::from(content),
+                sha256,
+            });
+        }
+
+        // Re-encode the text fields so that they are deserialized and rejected like a urlencoded form
+        let body = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .finish();
+        let mut req = Request::new(Body::from(body));
+        *req.method_mut() = method;
+        *req.uri_mut() = uri;
+        req.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        let Form(data) = Form::<T>::from_request(req, state)
+            .await
+            .map_err(|e| ValidRejection::Inner(UploadRejection::Form(e)))?;
+        validate_redacted(&data).map_err(ValidRejection::Valid)?;
+        Ok(ValidUpload(data, files))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SHA-256 of `firmware v1`
+    const ALLOWED: &str = "782eb224a85e5ac7bdae147eb82c6d71930e37bb1eacab5b21e26b11451cf6b7";
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Install {
+        #[validate(length(min = 1, max = 32))]
+        device: String,
+    }
+
+    fn multipart_request(device: &str, firmware: &str) -> anyhow::Result<Request> {
+        const BOUNDARY: &str = "axum-valid-boundary";
+        let body = format!(
+            "--{BOUNDARY}\r\n\
+             Content-Disposition: form-data; name=\"device\"\r\n\r\n\
+             {device}\r\n\
+             --{BOUNDARY}\r\n\
+             Content-Disposition: form-data; name=\"firmware\"; filename=\"firmware.bin\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n\
+             {firmware}\r\n\
+             --{BOUNDARY}--\r\n"
+        );
+        Ok(Request::builder()
+            .method("POST")
+            .header(
+                CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(body))?)
+    }
+
+    async fn extract(
+        device: &str,
+        firmware: &str,
+    ) -> anyhow::Result<Result<ValidUpload<Install>, ValidRejection<UploadRejection>>> {
+        let allow_list = HashAllowList::new().allow(&ALLOWED.to_ascii_uppercase());
+        Ok(
+            ValidUpload::<Install>::from_request(multipart_request(device, firmware)?, &allow_list)
+                .await,
+        )
+    }
+
+    #[tokio::test]
+    async fn allowed_and_disallowed_hashes() -> anyhow::Result<()> {
+        let ValidUpload(install, files) = extract("router-1", "firmware v1")
+            .await?
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        assert_eq!(install.device, "router-1");
+        assert_eq!(
+            files,
+            [UploadedFile {
+                name: "firmware".to_owned(),
+                file_name: "firmware.bin".to_owned(),
+                content_type: Some("application/octet-stream".to_owned()),
+                content: Bytes::from_static(b"firmware v1"),
+                sha256: ALLOWED.to_owned(),
+            }]
+        );
+
+        let rejection = extract("router-1", "firmware v2")
+            .await?
+            .expect_err("disallowed firmware");
+        let ValidRejection::Inner(rejection) = rejection else {
+            panic!("expected a disallowed file");
+        };
+        let UploadRejection::Disallowed(body) = &rejection else {
+            panic!("expected a disallowed file");
+        };
+        let error = &body.errors[0];
+        assert_eq!(error.path.to_string(), "firmware");
+        assert_eq!(error.code.as_deref(), Some(DISALLOWED_FILE));
+        let sha256 = error.param::<String>("sha256").expect("sha256 param");
+        assert_eq!(sha256.len(), 64);
+        assert_ne!(sha256, ALLOWED);
+        assert_eq!(
+            rejection.to_string(),
+            format!("File `firmware` with SHA-256 digest {sha256} is not allowed")
+        );
+        assert_eq!(
+            rejection.into_response().status(),
+            crate::VALIDATION_ERROR_STATUS
+        );
+
+        assert!(matches!(
+            extract("", "firmware v1").await?,
+            Err(ValidRejection::Valid(errors)) if errors.field_errors().contains_key("device")
+        ));
+        Ok(())
+    }
+}
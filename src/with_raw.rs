@@ -0,0 +1,164 @@
+//! # Raw request body of validated data
+//!
+//! ## Feature
+//!
+//! Enable the `with_raw` feature to use `ValidWithRaw<E>`.
+//!
+//! `ValidWithRaw<E>` wraps an extractor that consumes the request body, such as `Valid<Json<T>>`,
+//! and returns the extracted data together with the exact bytes of the request body it was extracted from.
+//! This allows signing, auditing or persisting the input that passed validation as it was received.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! #[cfg(feature = "validator")]
+//! mod validator_example {
+//!     use axum::routing::post;
+//!     use axum::{Json, Router};
+//!     use axum_valid::with_raw::ValidWithRaw;
+//!     use axum_valid::Valid;
+//!     use serde::Deserialize;
+//!     use validator::Validate;
+//!
+//!     pub fn router() -> Router {
+//!         Router::new().route("/json", post(handler))
+//!     }
+//!
+//!     async fn handler(ValidWithRaw(Valid(Json(parameter)), raw): ValidWithRaw<Valid<Json<Parameter>>>) {
+//!         assert!(parameter.validate().is_ok());
+//!         assert!(!raw.is_empty());
+//!     }
+//!
+//!     #[derive(Validate, Deserialize)]
+//!     pub struct Parameter {
+//!         #[validate(length(min = 1, max = 100))]
+//!         pub message: String,
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! #     use std::net::SocketAddr;
+//! #     use axum::Router;
+//! #     use tokio::net::TcpListener;
+//! #     let router = Router::new();
+//! #     #[cfg(feature = "validator")]
+//! #     let router = router.nest("/validator", validator_example::router());
+//! #     let listener = TcpListener::bind(&SocketAddr::from(([0u8, 0, 0, 0], 0u16))).await?;
+//! #     axum::serve(listener, router.into_make_service())
+//! #         .await?;
+//! #     Ok(())
+//! # }
+//! ```
+
+use crate::buffered::{BufferedRejection, BufferedRequest};
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, Request};
+use std::ops::{Deref, DerefMut};
+
+/// # `ValidWithRaw` extractor
+///
+/// See the [module documentation](crate::with_raw) for details.
+///
+#[derive(Debug, Clone, Default)]
+pub struct ValidWithRaw<E>(pub E, pub Bytes);
+
+impl<E> Deref for ValidWithRaw<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E> DerefMut for ValidWithRaw<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<E> ValidWithRaw<E> {
+    /// Consumes the `ValidWithRaw` and returns the validated data within.
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+
+    /// Consumes the `ValidWithRaw` and returns the validated data and the raw request body.
+    pub fn into_parts(self) -> (E, Bytes) {
+        (self.0, self.1)
+    }
+
+    /// Returns the raw request body.
+    pub fn raw(&self) -> &Bytes {
+        &self.1
+    }
+}
+
+/// `WithRawRejection` is returned when the `ValidWithRaw` extractor fails.
+pub type WithRawRejection<E> = BufferedRejection<E>;
+
+#[async_trait]
+impl<State, Extractor> FromRequest<State> for ValidWithRaw<Extractor>
+where
+    State: Send + Sync,
+    Extractor: FromRequest<State>,
+{
+    type Rejection = WithRawRejection<Extractor::Rejection>;
+
+    async fn from_request(req: Request, state: &State) -> Result<Self, Self::Rejection> {
+        let buffered = BufferedRequest::read(req, state)
+            .await
+            .map_err(BufferedRejection::Bytes)?;
+        let bytes = buffered.bytes.clone();
+        let inner = Extractor::from_request(buffered.into_request(), state)
+            .await
+            .map_err(BufferedRejection::Inner)?;
+        Ok(ValidWithRaw(inner, bytes))
+    }
+}
+
+#[cfg(all(test, feature = "validator"))]
+mod tests {
+    use super::*;
+    use crate::{Valid, ValidationRejection};
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use axum::Json;
+    use validator::Validate;
+
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Parameters {
+        #[validate(length(min = 1, max = 10))]
+        message: String,
+    }
+
+    fn request(body: &'static str) -> anyhow::Result<Request> {
+        Ok(Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?)
+    }
+
+    #[tokio::test]
+    async fn raw_body_matches_request() -> anyhow::Result<()> {
+        // whitespace and key order are kept as received
+        const BODY: &str = "{ \"message\" :\n\"hello\" }";
+        let ValidWithRaw(Valid(Json(parameters)), raw) =
+            ValidWithRaw::<Valid<Json<Parameters>>>::from_request(request(BODY)?, &()).await?;
+        assert_eq!(parameters.message, "hello");
+        assert_eq!(raw, BODY.as_bytes());
+
+        let rejection = ValidWithRaw::<Valid<Json<Parameters>>>::from_request(
+            request(r#"{"message": ""}"#)?,
+            &(),
+        )
+        .await
+        .expect_err("empty message");
+        assert!(matches!(
+            rejection,
+            WithRawRejection::Inner(ValidationRejection::Valid(_))
+        ));
+        Ok(())
+    }
+}